@@ -1,16 +1,47 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use conduwuit::Result;
 
 use crate::{
 	appservice, appservice::AppserviceCommand, check, check::CheckCommand, command::Command,
 	debug, debug::DebugCommand, federation, federation::FederationCommand, media,
-	media::MediaCommand, query, query::QueryCommand, room, room::RoomCommand, server,
-	server::ServerCommand, user, user::UserCommand,
+	media::MediaCommand, policy, policy::PolicyCommand, query, query::QueryCommand, report,
+	report::ReportCommand, room, room::RoomCommand, server, server::ServerCommand, user,
+	user::UserCommand,
 };
 
 #[derive(Debug, Parser)]
 #[command(name = "conduwuit", version = conduwuit::version())]
-pub(super) enum AdminCommand {
+pub(super) struct AdminCommand {
+	/// - Output format of the command. With `json`, the reply is a JSON object
+	///   holding the structured result of the command, for use in scripts.
+	#[arg(long, global = true, value_enum, default_value_t)]
+	pub(super) format: OutputFormat,
+
+	#[command(subcommand)]
+	pub(super) command: AdminSubcommand,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum OutputFormat {
+	/// Human readable markdown
+	#[default]
+	Markdown,
+
+	/// JSON object with the structured result of the command
+	Json,
+}
+
+impl From<OutputFormat> for service::admin::OutputFormat {
+	fn from(format: OutputFormat) -> Self {
+		match format {
+			| OutputFormat::Markdown => Self::Markdown,
+			| OutputFormat::Json => Self::Json,
+		}
+	}
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum AdminSubcommand {
 	#[command(subcommand)]
 	/// - Commands for managing appservices
 	Appservices(AppserviceCommand),
@@ -35,6 +66,14 @@ pub(super) enum AdminCommand {
 	/// - Commands for managing media
 	Media(MediaCommand),
 
+	#[command(subcommand)]
+	/// - Commands for handling room and event reports made by users
+	Reports(ReportCommand),
+
+	#[command(subcommand)]
+	/// - Commands for following moderation policy lists
+	Policy(PolicyCommand),
+
 	#[command(subcommand)]
 	/// - Commands for checking integrity
 	Check(CheckCommand),
@@ -49,8 +88,8 @@ pub(super) enum AdminCommand {
 }
 
 #[tracing::instrument(skip_all, name = "command")]
-pub(super) async fn process(command: AdminCommand, context: &Command<'_>) -> Result {
-	use AdminCommand::*;
+pub(super) async fn process(command: AdminSubcommand, context: &Command<'_>) -> Result {
+	use AdminSubcommand::*;
 
 	match command {
 		| Appservices(command) => appservice::process(command, context).await?,
@@ -62,6 +101,8 @@ pub(super) async fn process(command: AdminCommand, context: &Command<'_>) -> Res
 		| Debug(command) => debug::process(command, context).await?,
 		| Query(command) => query::process(command, context).await?,
 		| Check(command) => check::process(command, context).await?,
+		| Policy(command) => policy::process(command, context).await?,
+		| Reports(command) => report::process(command, context).await?,
 	}
 
 	Ok(())
@@ -79,6 +79,7 @@ pub(super) async fn show_appservice_config(
 #[admin_command]
 pub(super) async fn list_registered(&self) -> Result<RoomMessageEventContent> {
 	let appservices = self.services.appservice.iter_ids().await;
+	self.write_data(&appservices).await?;
 	let output = format!("Appservices ({}): {}", appservices.len(), appservices.join(", "));
 	Ok(RoomMessageEventContent::text_plain(output))
 }
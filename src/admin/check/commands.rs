@@ -1,28 +1,616 @@
-use conduwuit::Result;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt::Write,
+	mem::size_of,
+	str,
+	sync::Arc,
+};
+
+use conduwuit::{
+	Result,
+	utils::{
+		stream::{ReadyExt, TryIgnore},
+		u64_from_bytes,
+	},
+};
+use conduwuit_database::{Ignore, Interfix, Map};
 use conduwuit_macros::implement;
 use futures::StreamExt;
-use ruma::events::room::message::RoomMessageEventContent;
+use ruma::{
+	OwnedRoomId, OwnedUserId, UserId,
+	events::{
+		TimelineEventType,
+		room::{
+			member::{MembershipState, RoomMemberEventContent},
+			message::RoomMessageEventContent,
+		},
+	},
+};
+use tokio::time::Instant;
 
 use crate::Command;
 
-/// Uses the iterator in `src/database/key_value/users.rs` to iterator over
-/// every user in our database (remote and local). Reports total count, any
-/// errors if there were any, etc
+/// Maximum number of individual problems listed in the output of a check.
+const MAX_LISTED_PROBLEMS: usize = 50;
+
+/// Outcome of a single consistency check.
+struct Report {
+	name: &'static str,
+	timer: Instant,
+	checked: usize,
+	problems: usize,
+	repaired: usize,
+	listed: Vec<String>,
+}
+
+impl Report {
+	fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			timer: Instant::now(),
+			checked: 0,
+			problems: 0,
+			repaired: 0,
+			listed: Vec::new(),
+		}
+	}
+
+	fn checked(&mut self) { self.checked = self.checked.saturating_add(1); }
+
+	fn repaired(&mut self) { self.repaired = self.repaired.saturating_add(1); }
+
+	fn problem(&mut self, problem: String) {
+		self.problems = self.problems.saturating_add(1);
+		if self.listed.len() < MAX_LISTED_PROBLEMS {
+			self.listed.push(problem);
+		}
+	}
+
+	fn message(&self, repair: bool) -> String {
+		let Self { name, checked, problems, repaired, .. } = self;
+		let elapsed = self.timer.elapsed();
+
+		let mut out = format!(
+			"**{name}**: checked {checked} entries in {elapsed:?}, found {problems} problems"
+		);
+
+		if repair {
+			write!(out, ", repaired {repaired}").expect("writes to string");
+		}
+
+		out.push_str(".\n");
+		if !self.listed.is_empty() {
+			out.push_str("```\n");
+			for problem in &self.listed {
+				writeln!(out, "{problem}").expect("writes to string");
+			}
+
+			let unlisted = problems.saturating_sub(self.listed.len());
+			if unlisted > 0 {
+				writeln!(out, "... and {unlisted} more").expect("writes to string");
+			}
+
+			out.push_str("```\n");
+		}
+
+		out
+	}
+}
+
 #[implement(Command, params = "<'_>")]
 pub(super) async fn check_all_users(&self) -> Result<RoomMessageEventContent> {
-	let timer = tokio::time::Instant::now();
-	let users = self.services.users.iter().collect::<Vec<_>>().await;
-	let query_time = timer.elapsed();
+	let report = self.check_users().await?;
 
-	let total = users.len();
-	let err_count = users.iter().filter(|_user| false).count();
-	let ok_count = users.iter().filter(|_user| true).count();
+	Ok(RoomMessageEventContent::notice_markdown(report.message(false)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn pdus(&self, repair: bool) -> Result<RoomMessageEventContent> {
+	let report = self.check_pdus(repair).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(report.message(repair)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn short_ids(&self, repair: bool) -> Result<RoomMessageEventContent> {
+	let report = self.check_short_ids(repair).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(report.message(repair)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn state_diffs(&self, repair: bool) -> Result<RoomMessageEventContent> {
+	let report = self.check_state_diffs(repair).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(report.message(repair)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn memberships(&self, repair: bool) -> Result<RoomMessageEventContent> {
+	let report = self.check_memberships(repair).await?;
 
-	let message = format!(
-		"Database query completed in {query_time:?}:\n\n```\nTotal entries: \
-		 {total:?}\nFailure/Invalid user count: {err_count:?}\nSuccess/Valid user count: \
-		 {ok_count:?}\n```"
-	);
+	Ok(RoomMessageEventContent::notice_markdown(report.message(repair)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn media(&self, repair: bool) -> Result<RoomMessageEventContent> {
+	let report = self.check_media(repair).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(report.message(repair)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn all(&self, repair: bool) -> Result<RoomMessageEventContent> {
+	let reports = [
+		self.check_users().await?,
+		self.check_pdus(repair).await?,
+		self.check_short_ids(repair).await?,
+		self.check_state_diffs(repair).await?,
+		self.check_memberships(repair).await?,
+		self.check_media(repair).await?,
+	];
+
+	let message = reports
+		.iter()
+		.map(|report| report.message(repair))
+		.collect::<Vec<_>>()
+		.join("\n");
 
 	Ok(RoomMessageEventContent::notice_markdown(message))
 }
+
+/// Every user ID in `userid_password` must parse.
+#[implement(Command, params = "<'_>")]
+async fn check_users(&self) -> Result<Report> {
+	let userid_password = self.services.db.get("userid_password")?;
+
+	let mut report = Report::new("Users");
+	userid_password
+		.raw_keys()
+		.ignore_err()
+		.ready_for_each(|key| {
+			report.checked();
+			let valid = str::from_utf8(key)
+				.ok()
+				.and_then(|user_id| UserId::parse(user_id).ok())
+				.is_some();
+
+			if !valid {
+				report.problem(format!("Invalid user ID {}", display_key(key)));
+			}
+		})
+		.await;
+
+	Ok(report)
+}
+
+/// Every `eventid_pduid` entry must resolve in `pduid_pdu`.
+#[implement(Command, params = "<'_>")]
+async fn check_pdus(&self, repair: bool) -> Result<Report> {
+	let eventid_pduid = self.services.db.get("eventid_pduid")?;
+	let pduid_pdu = self.services.db.get("pduid_pdu")?;
+
+	let mut report = Report::new("PDUs");
+	let mut entries = eventid_pduid
+		.raw_stream()
+		.ignore_err()
+		.map(|(key, val)| (key.to_vec(), val.to_vec()))
+		.boxed();
+
+	while let Some((event_id, pdu_id)) = entries.next().await {
+		report.checked();
+		if pduid_pdu.exists(&pdu_id).await.is_ok() {
+			continue;
+		}
+
+		report.problem(format!(
+			"{} maps to missing PDU {}",
+			display_key(&event_id),
+			display_key(&pdu_id)
+		));
+
+		if repair {
+			eventid_pduid.remove(&event_id);
+			report.repaired();
+		}
+	}
+
+	Ok(report)
+}
+
+/// Short event IDs and short state keys must map back to what they were
+/// created from.
+#[implement(Command, params = "<'_>")]
+async fn check_short_ids(&self, repair: bool) -> Result<Report> {
+	const PAIRS: [(&str, &str); 2] = [
+		("eventid_shorteventid", "shorteventid_eventid"),
+		("statekey_shortstatekey", "shortstatekey_statekey"),
+	];
+
+	let mut report = Report::new("Short IDs");
+	for (forward, reverse) in PAIRS {
+		let forward = self.services.db.get(forward)?;
+		let reverse = self.services.db.get(reverse)?;
+
+		check_round_trip(forward, reverse, repair, &mut report).await;
+		check_round_trip(reverse, forward, repair, &mut report).await;
+	}
+
+	Ok(report)
+}
+
+async fn check_round_trip(from: &Arc<Map>, to: &Arc<Map>, repair: bool, report: &mut Report) {
+	let mut entries = from
+		.raw_stream()
+		.ignore_err()
+		.map(|(key, val)| (key.to_vec(), val.to_vec()))
+		.boxed();
+
+	while let Some((key, val)) = entries.next().await {
+		report.checked();
+		match to.get(&val).await {
+			| Ok(back) if *back == *key => {},
+			| Ok(back) => report.problem(format!(
+				"{from} {} -> {} conflicts with {to} {} -> {}",
+				display_key(&key),
+				display_key(&val),
+				display_key(&val),
+				display_key(&back),
+			)),
+			| Err(_) => {
+				report.problem(format!(
+					"{from} {} -> {} is missing from {to}",
+					display_key(&key),
+					display_key(&val),
+				));
+
+				if repair {
+					to.insert(&val, &key);
+					report.repaired();
+				}
+			},
+		}
+	}
+}
+
+/// Every state diff must decode and its parent, if any, must exist.
+#[implement(Command, params = "<'_>")]
+async fn check_state_diffs(&self, repair: bool) -> Result<Report> {
+	let statediffs = self.services.db.get("shortstatehash_statediff")?;
+
+	let mut report = Report::new("State diffs");
+	let mut entries = statediffs
+		.raw_stream()
+		.ignore_err()
+		.map(|(key, val)| (key.to_vec(), val.to_vec()))
+		.boxed();
+
+	while let Some((key, val)) = entries.next().await {
+		report.checked();
+		let Ok(shortstatehash) = u64_from_bytes(&key) else {
+			report.problem(format!("Invalid shortstatehash {}", display_key(&key)));
+			continue;
+		};
+
+		let diff = StateDiff::decode(&val);
+		let parent_missing = match diff.parent {
+			| Some(parent) if parent == shortstatehash => true,
+			| Some(parent) => statediffs.exists(&parent.to_be_bytes()).await.is_err(),
+			| None => false,
+		};
+
+		if parent_missing {
+			report.problem(format!(
+				"{shortstatehash} refers to missing parent {}",
+				diff.parent.unwrap_or_default()
+			));
+		} else if diff.malformed {
+			report.problem(format!("{shortstatehash} has a malformed diff"));
+		} else {
+			continue;
+		}
+
+		// Dropping the parent would silently truncate the state, so such diffs
+		// are only reported; the trailing bytes of a malformed diff can't be
+		// decoded and are safe to drop.
+		if repair && !parent_missing {
+			statediffs.insert(&key, diff.encode());
+			report.repaired();
+		}
+	}
+
+	if report.repaired > 0 {
+		self.services
+			.rooms
+			.state_compressor
+			.stateinfo_cache
+			.lock()
+			.expect("locked")
+			.clear();
+	}
+
+	Ok(report)
+}
+
+/// Decoded form of a `shortstatehash_statediff` value: the parent
+/// shortstatehash followed by added and removed compressed state events,
+/// separated by a zero word.
+struct StateDiff<'a> {
+	parent: Option<u64>,
+	added: Vec<&'a [u8]>,
+	removed: Vec<&'a [u8]>,
+	malformed: bool,
+}
+
+impl<'a> StateDiff<'a> {
+	const STRIDE: usize = size_of::<u64>();
+
+	fn decode(value: &'a [u8]) -> Self {
+		let mut diff = Self {
+			parent: None,
+			added: Vec::new(),
+			removed: Vec::new(),
+			malformed: false,
+		};
+
+		let Some(parent) = value.get(..Self::STRIDE) else {
+			diff.malformed = true;
+			return diff;
+		};
+
+		diff.parent = u64_from_bytes(parent).ok().take_if(|parent| *parent != 0);
+
+		let mut add_mode = true;
+		let mut i = Self::STRIDE;
+		while let Some(v) = value.get(i..i.saturating_add(2 * Self::STRIDE)) {
+			if add_mode && v.starts_with(&0_u64.to_be_bytes()) {
+				add_mode = false;
+				i = i.saturating_add(Self::STRIDE);
+				continue;
+			}
+
+			if add_mode {
+				diff.added.push(v);
+			} else {
+				diff.removed.push(v);
+			}
+
+			i = i.saturating_add(2 * Self::STRIDE);
+		}
+
+		diff.malformed = i != value.len();
+		diff
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut value = Vec::new();
+		value.extend_from_slice(&self.parent.unwrap_or(0).to_be_bytes());
+		for added in &self.added {
+			value.extend_from_slice(added);
+		}
+
+		if !self.removed.is_empty() {
+			value.extend_from_slice(&0_u64.to_be_bytes());
+			for removed in &self.removed {
+				value.extend_from_slice(removed);
+			}
+		}
+
+		value
+	}
+}
+
+/// The membership caches of every room (joined, invited, knocked and left)
+/// must agree with its current state.
+#[implement(Command, params = "<'_>")]
+async fn check_memberships(&self, repair: bool) -> Result<Report> {
+	let rooms = &self.services.rooms;
+	let roomuserid_leftcount = self.services.db.get("roomuserid_leftcount")?;
+
+	let mut report = Report::new("Memberships");
+	let room_ids: Vec<OwnedRoomId> = rooms
+		.metadata
+		.iter_ids()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for room_id in &room_ids {
+		if rooms.state.get_room_shortstatehash(room_id).await.is_err() {
+			continue;
+		}
+
+		report.checked();
+		let in_state: BTreeMap<OwnedUserId, MembershipState> = rooms
+			.state_accessor
+			.room_state_full_pdus(room_id)
+			.ignore_err()
+			.ready_filter_map(|pdu| {
+				if pdu.kind != TimelineEventType::RoomMember {
+					return None;
+				}
+
+				let content = pdu.get_content::<RoomMemberEventContent>().ok()?;
+				let user_id = UserId::parse(pdu.state_key.as_deref()?).ok()?;
+
+				Some((user_id, content.membership))
+			})
+			.collect()
+			.await;
+
+		let joined: BTreeSet<OwnedUserId> = rooms
+			.state_cache
+			.room_members(room_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		let invited: BTreeSet<OwnedUserId> = rooms
+			.state_cache
+			.room_members_invited(room_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		let knocked: BTreeSet<OwnedUserId> = rooms
+			.state_cache
+			.room_members_knocked(room_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		let left: BTreeSet<OwnedUserId> = roomuserid_leftcount
+			.keys_prefix(&(room_id, Interfix))
+			.ignore_err()
+			.map(|(_, user_id): (Ignore, &UserId)| user_id.to_owned())
+			.collect()
+			.await;
+
+		// Left entries may have been forgotten, so only cached memberships which
+		// the state contradicts are problems for them.
+		let cached = [
+			(MembershipState::Join, &joined, true),
+			(MembershipState::Invite, &invited, true),
+			(MembershipState::Knock, &knocked, true),
+			(MembershipState::Leave, &left, false),
+		];
+
+		let mut wrong = BTreeSet::new();
+		for (membership, cache, required) in cached {
+			let expected: BTreeSet<&OwnedUserId> = in_state
+				.iter()
+				.filter(|(_, state)| same_membership(state, &membership))
+				.map(|(user_id, _)| user_id)
+				.collect();
+
+			if required {
+				for user_id in expected.iter().filter(|user_id| !cache.contains(**user_id)) {
+					report.problem(format!(
+						"{user_id} is {membership} in {room_id} but not cached as such"
+					));
+					wrong.insert((*user_id).clone());
+				}
+			}
+
+			for user_id in cache.iter().filter(|user_id| !expected.contains(user_id)) {
+				let actual = in_state
+					.get(user_id)
+					.map_or("not a member", MembershipState::as_str);
+
+				report.problem(format!(
+					"{user_id} is cached as {membership} in {room_id} but is {actual}"
+				));
+				wrong.insert(user_id.clone());
+			}
+		}
+
+		let joined_count = rooms
+			.state_cache
+			.room_joined_count(room_id)
+			.await
+			.unwrap_or(0);
+
+		let joined_in_state = in_state
+			.values()
+			.filter(|membership| **membership == MembershipState::Join)
+			.count();
+
+		let count_wrong = usize::try_from(joined_count).ok() != Some(joined_in_state);
+		if count_wrong {
+			report.problem(format!(
+				"{room_id} has a cached joined count of {joined_count} but {joined_in_state} \
+				 joined members"
+			));
+		}
+
+		if !repair || (wrong.is_empty() && !count_wrong) {
+			continue;
+		}
+
+		for user_id in &wrong {
+			match in_state.get(user_id) {
+				| Some(MembershipState::Join) =>
+					rooms.state_cache.mark_as_joined(user_id, room_id),
+				| Some(MembershipState::Invite) =>
+					rooms
+						.state_cache
+						.mark_as_invited(user_id, room_id, None, None)
+						.await,
+				| Some(MembershipState::Knock) =>
+					rooms.state_cache.mark_as_knocked(user_id, room_id, None),
+				| _ => rooms.state_cache.mark_as_left(user_id, room_id),
+			}
+
+			report.repaired();
+		}
+
+		if count_wrong {
+			report.repaired();
+		}
+
+		rooms.state_cache.update_joined_count(room_id).await;
+	}
+
+	Ok(report)
+}
+
+/// Bans are cached the same way as leaves.
+fn same_membership(state: &MembershipState, cached: &MembershipState) -> bool {
+	match cached {
+		| MembershipState::Leave =>
+			matches!(state, MembershipState::Leave | MembershipState::Ban),
+		| cached => state == cached,
+	}
+}
+
+/// Every `mediaid_file` entry must have its file in the media directory.
+#[implement(Command, params = "<'_>")]
+async fn check_media(&self, repair: bool) -> Result<Report> {
+	let mediaid_file = self.services.db.get("mediaid_file")?;
+
+	let mut report = Report::new("Media");
+	let keys: Vec<Vec<u8>> = mediaid_file
+		.raw_keys()
+		.ignore_err()
+		.map(<[u8]>::to_vec)
+		.collect()
+		.await;
+
+	for key in keys {
+		report.checked();
+		let mxc = key.split(|&b| b == 0xFF).next().unwrap_or_default();
+		let path = self.services.media.get_media_file(&key);
+		match tokio::fs::try_exists(&path).await {
+			| Ok(true) => {},
+			| Ok(false) => {
+				report.problem(format!("{} has no file at {}", display_key(mxc), path.display()));
+
+				if repair {
+					mediaid_file.remove(&key);
+					report.repaired();
+				}
+			},
+			| Err(e) => {
+				report.problem(format!("{} could not be checked: {e}", display_key(mxc)));
+			},
+		}
+	}
+
+	Ok(report)
+}
+
+/// Formats a raw database key or value for display: as a string if it is
+/// valid UTF-8, as a number if it is a single word, otherwise as bytes.
+fn display_key(bytes: &[u8]) -> String {
+	if let Ok(s) = str::from_utf8(bytes) {
+		if !s.contains(char::is_control) {
+			return s.to_owned();
+		}
+	}
+
+	if let Ok(n) = u64_from_bytes(bytes) {
+		return n.to_string();
+	}
+
+	format!("{bytes:?}")
+}
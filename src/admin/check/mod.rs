@@ -8,5 +8,56 @@
 #[admin_command_dispatch]
 #[derive(Debug, Subcommand)]
 pub(super) enum CheckCommand {
+	/// - Checks that every user ID in the database is valid
 	CheckAllUsers,
+
+	/// - Checks that every event ID mapped to a PDU ID resolves to a PDU
+	///
+	/// With --repair, mappings to missing PDUs are removed.
+	Pdus {
+		#[arg(long)]
+		repair: bool,
+	},
+
+	/// - Checks that short event IDs and short state keys round-trip
+	///
+	/// With --repair, missing reverse mappings are restored. Conflicting
+	/// mappings are only reported.
+	ShortIds {
+		#[arg(long)]
+		repair: bool,
+	},
+
+	/// - Checks that every state diff decodes and refers to an existing parent
+	///
+	/// With --repair, trailing garbage is cut off malformed diffs. Diffs with
+	/// a missing parent are only reported, as the state they build on is lost;
+	/// the affected rooms need their state fetched again.
+	StateDiffs {
+		#[arg(long)]
+		repair: bool,
+	},
+
+	/// - Checks that the joined, invited, knocked and left members caches agree
+	///   with the state of every room
+	///
+	/// With --repair, the cache is updated to match the room state.
+	Memberships {
+		#[arg(long)]
+		repair: bool,
+	},
+
+	/// - Checks that every media entry in the database has a file on disk
+	///
+	/// With --repair, entries without a file are removed from the database.
+	Media {
+		#[arg(long)]
+		repair: bool,
+	},
+
+	/// - Runs all of the above checks
+	All {
+		#[arg(long)]
+		repair: bool,
+	},
 }
@@ -1,4 +1,4 @@
-use std::{fmt, time::SystemTime};
+use std::{fmt, sync::Arc, time::SystemTime};
 
 use conduwuit::Result;
 use conduwuit_service::Services;
@@ -7,17 +7,48 @@
 	io::{AsyncWriteExt, BufWriter},
 	lock::Mutex,
 };
-use ruma::EventId;
+use ruma::{EventId, OwnedUserId};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::admin::OutputFormat;
 
 pub(crate) struct Command<'a> {
-	pub(crate) services: &'a Services,
+	pub(crate) services: &'a Arc<Services>,
 	pub(crate) body: &'a [&'a str],
 	pub(crate) timer: SystemTime,
 	pub(crate) reply_id: Option<&'a EventId>,
+	pub(crate) format: OutputFormat,
 	pub(crate) output: Mutex<BufWriter<Vec<u8>>>,
+	pub(crate) data: Mutex<Option<Value>>,
 }
 
 impl Command<'_> {
+	/// The admin who sent the command, or the server user for commands from
+	/// the console, which have no sender.
+	pub(crate) async fn sender_user(&self) -> Result<OwnedUserId> {
+		match self.reply_id {
+			| Some(reply_id) => Ok(self.services.rooms.timeline.get_pdu(reply_id).await?.sender),
+			| None => Ok(self.services.globals.server_user.clone()),
+		}
+	}
+
+	/// Sets the structured result of the command, which is returned as the
+	/// `data` of the reply when the command is run with `--format json`.
+	pub(crate) fn write_data<T: Serialize + ?Sized>(
+		&self,
+		data: &T,
+	) -> impl Future<Output = Result> + Send + '_ + use<'_, T> {
+		let data = (self.format == OutputFormat::Json)
+			.then(|| serde_json::to_value(data))
+			.transpose();
+
+		self.data.lock().map(move |mut output| {
+			*output = data?;
+			Ok(())
+		})
+	}
+
 	pub(crate) fn write_fmt(
 		&self,
 		arguments: fmt::Arguments<'_>,
@@ -2,7 +2,7 @@
 	collections::HashMap,
 	fmt::Write,
 	iter::once,
-	time::{Instant, SystemTime},
+	time::{Duration, Instant, SystemTime},
 };
 
 use conduwuit::{
@@ -141,6 +141,29 @@ pub(super) async fn get_pdu(&self, event_id: Box<EventId>) -> Result<RoomMessage
 	}
 }
 
+#[admin_command]
+pub(super) async fn get_unredacted_content(
+	&self,
+	event_id: Box<EventId>,
+) -> Result<RoomMessageEventContent> {
+	let Ok(unredacted) = self.services.rooms.unredacted.get(&event_id).await else {
+		return Ok(RoomMessageEventContent::text_plain(
+			"No unredacted content is kept for this event.",
+		));
+	};
+
+	let content: serde_json::Value = serde_json::from_str(unredacted.content.get())?;
+	let redacted = SystemTime::UNIX_EPOCH
+		.checked_add(Duration::from_millis(unredacted.redacted_ts))
+		.map(|ts| utils::time::format(ts, "%+"))
+		.unwrap_or_default();
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Content of {event_id} before it was redacted at {redacted}:\n```json\n{}\n```",
+		serde_json::to_string_pretty(&content)?
+	)))
+}
+
 #[admin_command]
 pub(super) async fn get_short_pdu(
 	&self,
@@ -38,6 +38,13 @@ pub(super) enum DebugCommand {
 		event_id: Box<EventId>,
 	},
 
+	/// - Print the content a redacted event had before it was redacted, if it
+	///   is still kept (see `redacted_content_retention`)
+	GetUnredactedContent {
+		/// An event ID (a $ followed by the base64 reference hash)
+		event_id: Box<EventId>,
+	},
+
 	/// - Retrieve and print a PDU by PduId from the conduwuit database
 	GetShortPdu {
 		/// Shortroomid integer
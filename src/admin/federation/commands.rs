@@ -1,12 +1,13 @@
-use std::fmt::Write;
+use std::{fmt::Write, sync::atomic::Ordering, time::Instant};
 
-use conduwuit::Result;
+use conduwuit::{Result, utils::time};
 use futures::StreamExt;
 use ruma::{
 	OwnedRoomId, RoomId, ServerName, UserId, events::room::message::RoomMessageEventContent,
 };
+use serde_json::json;
 
-use crate::{admin_command, get_room_info};
+use crate::{admin_command, get_room_info, rooms_json};
 
 #[admin_command]
 pub(super) async fn disable_room(&self, room_id: Box<RoomId>) -> Result<RoomMessageEventContent> {
@@ -30,12 +31,21 @@ pub(super) async fn incoming_federation(&self) -> Result<RoomMessageEventContent
 		.read()
 		.expect("locked");
 	let mut msg = format!("Handling {} incoming pdus:\n", map.len());
+	let mut data = Vec::with_capacity(map.len());
 
 	for (r, (e, i)) in map.iter() {
 		let elapsed = i.elapsed();
 		writeln!(msg, "{} {}: {}m{}s", r, e, elapsed.as_secs() / 60, elapsed.as_secs() % 60)?;
+		data.push(json!({
+			"room_id": r,
+			"event_id": e,
+			"elapsed_secs": elapsed.as_secs(),
+		}));
 	}
 
+	drop(map);
+	self.write_data(&data).await?;
+
 	Ok(RoomMessageEventContent::text_plain(&msg))
 }
 
@@ -114,13 +124,15 @@ pub(super) async fn remote_user_in_rooms(
 		.collect()
 		.await;
 
+	rooms.sort_by_key(|r| r.1);
+	rooms.reverse();
+
+	self.write_data(&rooms_json(&rooms)).await?;
+
 	if rooms.is_empty() {
 		return Ok(RoomMessageEventContent::text_plain("User is not in any rooms."));
 	}
 
-	rooms.sort_by_key(|r| r.1);
-	rooms.reverse();
-
 	let output = format!(
 		"Rooms {user_id} shares with us ({}):\n```\n{}\n```",
 		rooms.len(),
@@ -133,3 +145,111 @@ pub(super) async fn remote_user_in_rooms(
 
 	Ok(RoomMessageEventContent::text_markdown(output))
 }
+
+#[admin_command]
+pub(super) async fn ratelimits(
+	&self,
+	server_name: Option<Box<ServerName>>,
+) -> Result<RoomMessageEventContent> {
+	let origins = match server_name {
+		| Some(server_name) => {
+			let origin = self
+				.services
+				.ratelimit
+				.origin(&server_name)
+				.unwrap_or_default();
+
+			vec![(server_name.into(), origin)]
+		},
+		| None => self.services.ratelimit.origins(),
+	};
+
+	let now = Instant::now();
+	self.write_data(
+		&origins
+			.iter()
+			.map(|(server_name, origin)| {
+				json!({
+					"server_name": server_name,
+					"transactions": origin.transactions,
+					"limited": origin.limited,
+					"throttled_for_ms": origin
+						.throttled_until
+						.and_then(|until| until.checked_duration_since(now))
+						.map(|remaining| remaining.as_millis()),
+				})
+			})
+			.collect::<Vec<_>>(),
+	)
+	.await?;
+
+	if origins.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain(
+			"No servers sent us requests since the caches were last cleared.",
+		));
+	}
+
+	let mut msg = format!("Rate limiter state of {} servers:\n```\n", origins.len());
+	for (server_name, origin) in origins {
+		let throttled = origin
+			.throttled_until
+			.and_then(|until| until.checked_duration_since(now))
+			.map(|remaining| format!(" | Throttled for {}", time::pretty(remaining)))
+			.unwrap_or_default();
+
+		writeln!(
+			msg,
+			"{server_name} | Transactions in flight: {} | Limited requests: {}{throttled}",
+			origin.transactions, origin.limited,
+		)?;
+	}
+
+	msg.push_str("```\n");
+
+	let refused = self.services.ratelimit.refused();
+	write!(
+		msg,
+		"Refused since startup: {} concurrent transactions, {} over the PDU budget, {} \
+		 expensive requests",
+		refused.concurrent.load(Ordering::Relaxed),
+		refused.pdus.load(Ordering::Relaxed),
+		refused.expensive.load(Ordering::Relaxed),
+	)?;
+
+	Ok(RoomMessageEventContent::text_markdown(msg))
+}
+
+#[admin_command]
+pub(super) async fn throttle(
+	&self,
+	server_name: Box<ServerName>,
+	duration: String,
+) -> Result<RoomMessageEventContent> {
+	if server_name == self.services.server.name {
+		return Ok(RoomMessageEventContent::text_plain("Cannot throttle our own server."));
+	}
+
+	let duration = time::parse_duration(&duration)?;
+	self.services.ratelimit.throttle(&server_name, duration);
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Throttled {server_name} for {}.",
+		time::pretty(duration)
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unthrottle(
+	&self,
+	server_name: Box<ServerName>,
+) -> Result<RoomMessageEventContent> {
+	if !self.services.ratelimit.unthrottle(&server_name) {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"{server_name} is not throttled."
+		)));
+	}
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Lifted the throttle on {server_name}."
+	)))
+}
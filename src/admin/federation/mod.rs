@@ -39,4 +39,24 @@ pub(super) enum FederationCommand {
 	RemoteUserInRooms {
 		user_id: Box<UserId>,
 	},
+
+	/// - Shows the incoming federation rate limiter state of a server, or of
+	///   all servers which sent us requests recently
+	Ratelimits {
+		server_name: Option<Box<ServerName>>,
+	},
+
+	/// - Throttles incoming federation from a server for a while
+	///
+	/// The server may only have one transaction in flight and gets a tenth of
+	/// the usual budgets until the duration (e.g. "30m" or "2h") has passed.
+	Throttle {
+		server_name: Box<ServerName>,
+		duration: String,
+	},
+
+	/// - Lifts a throttle set with `throttle`
+	Unthrottle {
+		server_name: Box<ServerName>,
+	},
 }
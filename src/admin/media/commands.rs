@@ -8,6 +8,7 @@
 	EventId, Mxc, MxcUri, OwnedMxcUri, OwnedServerName, ServerName,
 	events::room::message::RoomMessageEventContent,
 };
+use serde_json::json;
 
 use crate::{admin_command, utils::parse_local_user_id};
 
@@ -227,6 +228,12 @@ pub(super) async fn delete_list(&self) -> Result<RoomMessageEventContent> {
 		}
 	}
 
+	self.write_data(&json!({
+		"deleted": mxc_deletion_count,
+		"failed_to_parse": failed_parsed_mxcs,
+	}))
+	.await?;
+
 	Ok(RoomMessageEventContent::text_plain(format!(
 		"Finished bulk MXC deletion, deleted {mxc_deletion_count} total MXCs from our database \
 		 and the filesystem. {failed_parsed_mxcs} MXCs failed to be parsed from the database.",
@@ -260,6 +267,9 @@ pub(super) async fn delete_past_remote_media(
 		)
 		.await?;
 
+	self.write_data(&json!({ "deleted": deleted_count }))
+		.await?;
+
 	Ok(RoomMessageEventContent::text_plain(format!(
 		"Deleted {deleted_count} total files.",
 	)))
@@ -274,6 +284,9 @@ pub(super) async fn delete_all_from_user(
 
 	let deleted_count = self.services.media.delete_from_user(&user_id).await?;
 
+	self.write_data(&json!({ "deleted": deleted_count }))
+		.await?;
+
 	Ok(RoomMessageEventContent::text_plain(format!(
 		"Deleted {deleted_count} total files.",
 	)))
@@ -336,11 +349,40 @@ pub(super) async fn delete_all_from_server(
 		}
 	}
 
+	self.write_data(&json!({ "deleted": deleted_count }))
+		.await?;
+
 	Ok(RoomMessageEventContent::text_plain(format!(
 		"Deleted {deleted_count} total files.",
 	)))
 }
 
+#[admin_command]
+pub(super) async fn quarantine(&self, mxc: OwnedMxcUri) -> Result<RoomMessageEventContent> {
+	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
+
+	let quarantined_by = self.sender_user().await?;
+
+	self.services.media.quarantine(&mxc, &quarantined_by);
+	self.write_data(&json!({ "mxc": mxc.to_string(), "quarantined_by": quarantined_by }))
+		.await?;
+
+	Ok(RoomMessageEventContent::text_plain(format!("Quarantined {mxc}.")))
+}
+
+#[admin_command]
+pub(super) async fn unquarantine(&self, mxc: OwnedMxcUri) -> Result<RoomMessageEventContent> {
+	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
+
+	if !self.services.media.is_quarantined(&mxc).await {
+		return Ok(RoomMessageEventContent::text_plain(format!("{mxc} is not quarantined.")));
+	}
+
+	self.services.media.unquarantine(&mxc);
+
+	Ok(RoomMessageEventContent::text_plain(format!("Lifted the quarantine of {mxc}.")))
+}
+
 #[admin_command]
 pub(super) async fn get_file_info(&self, mxc: OwnedMxcUri) -> Result<RoomMessageEventContent> {
 	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
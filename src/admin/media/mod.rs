@@ -64,6 +64,19 @@ pub(super) enum MediaCommand {
 		yes_i_want_to_delete_local_media: bool,
 	},
 
+	/// - Quarantines media so that it is no longer served to anyone, without
+	///   deleting it
+	Quarantine {
+		/// The MXC URL to quarantine
+		mxc: OwnedMxcUri,
+	},
+
+	/// - Lifts the quarantine of media
+	Unquarantine {
+		/// The MXC URL to unquarantine
+		mxc: OwnedMxcUri,
+	},
+
 	GetFileInfo {
 		/// The MXC URL to lookup info for.
 		mxc: OwnedMxcUri,
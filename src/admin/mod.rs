@@ -14,7 +14,9 @@
 pub(crate) mod debug;
 pub(crate) mod federation;
 pub(crate) mod media;
+pub(crate) mod policy;
 pub(crate) mod query;
+pub(crate) mod report;
 pub(crate) mod room;
 pub(crate) mod server;
 pub(crate) mod user;
@@ -28,7 +30,7 @@
 
 pub(crate) use crate::{
 	command::Command,
-	utils::{escape_html, get_room_info},
+	utils::{escape_html, get_room_info, rooms_json},
 };
 
 pub(crate) const PAGE_SIZE: usize = 100;
@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use conduwuit::{Err, Result};
+use futures::StreamExt;
+use ruma::{
+	OwnedRoomId, OwnedRoomOrAliasId, RoomId, ServerName, UserId,
+	events::room::message::RoomMessageEventContent,
+};
+use serde_json::json;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn add(&self, room: OwnedRoomOrAliasId) -> Result<RoomMessageEventContent> {
+	let room_id = self.services.rooms.alias.resolve(&room).await?;
+	self.services.policy.add_room(&room_id).await?;
+
+	let (users, rooms, servers) = self.services.policy.rule_counts(&room_id);
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Following policy room {room_id}: {users} user, {rooms} room and {servers} server ban \
+		 rules."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn remove(&self, room_id: OwnedRoomId) -> Result<RoomMessageEventContent> {
+	if !self.services.policy.is_policy_room(&room_id).await {
+		return Err!("{room_id} is not a policy room being followed.");
+	}
+
+	self.services.policy.remove_room(&room_id);
+	Ok(RoomMessageEventContent::text_plain("Policy room removed."))
+}
+
+#[admin_command]
+pub(super) async fn list(&self) -> Result<RoomMessageEventContent> {
+	let room_ids: Vec<OwnedRoomId> = self
+		.services
+		.policy
+		.rooms()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	self.write_data(
+		&room_ids
+			.iter()
+			.map(|room_id| {
+				let (users, rooms, servers) = self.services.policy.rule_counts(room_id);
+				json!({
+					"room_id": room_id,
+					"users": users,
+					"rooms": rooms,
+					"servers": servers,
+				})
+			})
+			.collect::<Vec<_>>(),
+	)
+	.await?;
+
+	if room_ids.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain("No policy rooms are being followed."));
+	}
+
+	let mut msg = format!("Following {} policy rooms:\n", room_ids.len());
+	for room_id in &room_ids {
+		let (users, rooms, servers) = self.services.policy.rule_counts(room_id);
+		writeln!(msg, "{room_id} | users: {users} | rooms: {rooms} | servers: {servers}")?;
+	}
+
+	Ok(RoomMessageEventContent::text_plain(msg))
+}
+
+#[admin_command]
+pub(super) async fn check(&self, entity: String) -> Result<RoomMessageEventContent> {
+	let policy = &self.services.policy;
+	let rule = if let Ok(user_id) = UserId::parse(&entity) {
+		policy.match_user(&user_id)
+	} else if let Ok(room_id) = RoomId::parse(&entity) {
+		policy.match_room(&room_id)
+	} else if let Ok(server_name) = ServerName::parse(&entity) {
+		policy.match_server(&server_name)
+	} else {
+		return Err!("{entity} is not a user ID, room ID or server name.");
+	};
+
+	self.write_data(&json!({
+		"entity": entity,
+		"rule": rule.as_ref().map(|rule| json!({
+			"entity": rule.entity,
+			"room_id": rule.room_id,
+			"reason": rule.reason,
+		})),
+	}))
+	.await?;
+
+	let msg = match rule {
+		| Some(rule) => format!(
+			"{entity} matches rule `{}` in {}: {}",
+			rule.entity, rule.room_id, rule.reason
+		),
+		| None => format!("{entity} does not match any policy rule."),
+	};
+
+	Ok(RoomMessageEventContent::text_plain(msg))
+}
@@ -0,0 +1,33 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+use ruma::{OwnedRoomId, OwnedRoomOrAliasId};
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum PolicyCommand {
+	/// - Subscribe to the ban rules of a policy room
+	///
+	/// The server has to be joined to the room. Matching invites, joins,
+	/// federation traffic, room directory entries and remote media are
+	/// refused.
+	Add {
+		room: OwnedRoomOrAliasId,
+	},
+
+	/// - Stop following a policy room
+	Remove {
+		room_id: OwnedRoomId,
+	},
+
+	/// - List the policy rooms being followed
+	List,
+
+	/// - Check whether a user ID, room ID or server name matches a ban rule
+	Check {
+		entity: String,
+	},
+}
@@ -26,6 +26,7 @@
 		room::message::{Relation::Reply, RoomMessageEventContent},
 	},
 };
+use serde_json::{Value, json};
 use service::{
 	Services,
 	admin::{CommandInput, CommandOutput, ProcessorFuture, ProcessorResult},
@@ -33,7 +34,11 @@
 use tracing::Level;
 use tracing_subscriber::{EnvFilter, filter::LevelFilter};
 
-use crate::{Command, admin, admin::AdminCommand};
+use crate::{
+	Command, admin,
+	admin::{AdminCommand, AdminSubcommand, OutputFormat},
+	escape_html,
+};
 
 #[must_use]
 pub(super) fn complete(line: &str) -> String { complete_command(AdminCommand::command(), line) }
@@ -63,10 +68,12 @@ async fn process_command(services: Arc<Services>, input: &CommandInput) -> Proce
 		body: &body,
 		timer: SystemTime::now(),
 		reply_id: input.reply_id.as_deref(),
+		format: command.format,
 		output: BufWriter::new(Vec::new()).into(),
+		data: None.into(),
 	};
 
-	let (result, mut logs) = process(&context, command, &args).await;
+	let (result, mut logs) = process(&context, command.command, &args).await;
 
 	let output = &mut context.output.lock().await;
 	output.flush().await.expect("final flush of output stream");
@@ -74,19 +81,37 @@ async fn process_command(services: Arc<Services>, input: &CommandInput) -> Proce
 	let output =
 		String::from_utf8(take(output.get_mut())).expect("invalid utf8 in command output stream");
 
+	if context.format == OutputFormat::Json {
+		let data = context.data.lock().await.take();
+		let (ok, content) = json_reply(&args, result.map(|()| (data, output)));
+		let output = reply(content, context.reply_id, context.format);
+		return if ok { Ok(Some(output)) } else { Err(output) };
+	}
+
 	match result {
-		| Ok(()) if logs.is_empty() =>
-			Ok(Some(reply(RoomMessageEventContent::notice_markdown(output), context.reply_id))),
+		| Ok(()) if logs.is_empty() => Ok(Some(reply(
+			RoomMessageEventContent::notice_markdown(output),
+			context.reply_id,
+			context.format,
+		))),
 
 		| Ok(()) => {
 			logs.write_str(output.as_str()).expect("output buffer");
-			Ok(Some(reply(RoomMessageEventContent::notice_markdown(logs), context.reply_id)))
+			Ok(Some(reply(
+				RoomMessageEventContent::notice_markdown(logs),
+				context.reply_id,
+				context.format,
+			)))
 		},
 		| Err(error) => {
 			write!(&mut logs, "Command failed with error:\n```\n{error:#?}\n```")
 				.expect("output buffer");
 
-			Err(reply(RoomMessageEventContent::notice_markdown(logs), context.reply_id))
+			Err(reply(
+				RoomMessageEventContent::notice_markdown(logs),
+				context.reply_id,
+				context.format,
+			))
 		},
 	}
 }
@@ -98,13 +123,42 @@ fn handle_panic(error: &Error, command: &CommandInput) -> ProcessorResult {
 	let msg = format!("Panic occurred while processing command:\n```\n{error:#?}\n```\n{link}");
 	let content = RoomMessageEventContent::notice_markdown(msg);
 	error!("Panic while processing command: {error:?}");
-	Err(reply(content, command.reply_id.as_deref()))
+	Err(reply(content, command.reply_id.as_deref(), OutputFormat::Markdown))
+}
+
+/// Renders the result of a command run with `--format json`, along with
+/// whether it succeeded. Successful commands reply with their structured
+/// `data`, or with their text `output` if they have none.
+fn json_reply(
+	args: &[String],
+	result: Result<(Option<Value>, String)>,
+) -> (bool, RoomMessageEventContent) {
+	let command = args.get(1..).unwrap_or_default();
+	let reply = match &result {
+		| Ok((data, output)) => json!({
+			"ok": true,
+			"command": command,
+			"data": data,
+			"output": data.is_none().then_some(output),
+		}),
+		| Err(error) => json!({
+			"ok": false,
+			"command": command,
+			"error": error.to_string(),
+		}),
+	};
+
+	let body = serde_json::to_string_pretty(&reply).expect("serialized JSON reply");
+	let html = format!("<pre><code class=\"language-json\">{}</code></pre>", escape_html(&body));
+	let content = RoomMessageEventContent::notice_html(body, html);
+
+	(result.is_ok(), content)
 }
 
 /// Parse and process a message from the admin room
 async fn process(
 	context: &Command<'_>,
-	command: AdminCommand,
+	command: AdminSubcommand,
 	args: &[String],
 ) -> (Result, String) {
 	let (capture, logs) = capture_create(context);
@@ -177,14 +231,33 @@ fn parse<'a>(
 	match parse_command(command_line) {
 		| Ok((command, args)) => Ok((command, args, body)),
 		| Err(error) => {
+			let argv = parse_line(command_line);
+			if requests_json(&argv) {
+				let (_, content) = json_reply(&argv, Err(error));
+				return Err(reply(content, input.reply_id.as_deref(), OutputFormat::Json));
+			}
+
 			let message = error
 				.to_string()
 				.replace("server.name", services.globals.server_name().as_str());
-			Err(reply(RoomMessageEventContent::notice_plain(message), input.reply_id.as_deref()))
+			Err(reply(
+				RoomMessageEventContent::notice_plain(message),
+				input.reply_id.as_deref(),
+				OutputFormat::Markdown,
+			))
 		},
 	}
 }
 
+/// Whether a command line which failed to parse asked for JSON output, so
+/// that scripts get a JSON error as well.
+fn requests_json(argv: &[String]) -> bool {
+	argv.iter().any(|arg| arg == "--format=json")
+		|| argv
+			.windows(2)
+			.any(|args| args[0] == "--format" && args[1] == "json")
+}
+
 fn parse_command(line: &str) -> Result<(AdminCommand, Vec<String>)> {
 	let argv = parse_line(line);
 	let command = AdminCommand::try_parse_from(&argv)?;
@@ -281,10 +354,11 @@ fn parse_line(command_line: &str) -> Vec<String> {
 fn reply(
 	mut content: RoomMessageEventContent,
 	reply_id: Option<&EventId>,
-) -> RoomMessageEventContent {
+	format: OutputFormat,
+) -> CommandOutput {
 	content.relates_to = reply_id.map(|event_id| Reply {
 		in_reply_to: InReplyTo { event_id: event_id.to_owned() },
 	});
 
-	content
+	CommandOutput { content, format: format.into() }
 }
@@ -0,0 +1,178 @@
+use std::{
+	fmt::Write,
+	time::{Duration, UNIX_EPOCH},
+};
+
+use conduwuit::{
+	Result,
+	matrix::pdu::PduEvent,
+	utils::{ReadyExt, stream::TryIgnore, time},
+};
+use conduwuit_macros::implement;
+use conduwuit_service::reports::{Report, ReportState};
+use futures::StreamExt;
+use ruma::events::room::message::RoomMessageEventContent;
+
+use crate::{Command, PAGE_SIZE, admin_command};
+
+/// Number of events before the reported event shown with it.
+const CONTEXT_EVENTS: usize = 5;
+
+/// Length at which the content of context events is cut off.
+const CONTEXT_CONTENT_LENGTH: usize = 200;
+
+#[admin_command]
+pub(super) async fn list(&self, all: bool) -> Result<RoomMessageEventContent> {
+	let reports: Vec<Report> = self
+		.services
+		.reports
+		.reports()
+		.ready_filter(|report| all || report.state == ReportState::Open)
+		.take(PAGE_SIZE)
+		.collect()
+		.await;
+
+	self.write_data(&reports).await?;
+
+	if reports.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain("No reports."));
+	}
+
+	let mut msg = format!("Reports ({}):\n```\n", reports.len());
+	for report in &reports {
+		let event_id = report
+			.event_id
+			.as_ref()
+			.map_or("(room)", |event_id| event_id.as_str());
+
+		writeln!(
+			msg,
+			"#{} | {} | {} | {} {} | {}",
+			report.id,
+			report.state.as_str(),
+			report.reporter,
+			report.room_id,
+			event_id,
+			report.reason.as_deref().unwrap_or_default(),
+		)?;
+	}
+	msg.push_str("```");
+
+	Ok(RoomMessageEventContent::notice_markdown(msg))
+}
+
+#[admin_command]
+pub(super) async fn view(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let Ok(report) = self.services.reports.get(id).await else {
+		return Ok(RoomMessageEventContent::text_plain(format!("Report #{id} does not exist.")));
+	};
+
+	self.write_data(&report).await?;
+
+	let received = UNIX_EPOCH.checked_add(Duration::from_millis(report.received_ts));
+	let mut msg = format!(
+		"Report #{id} ({})\n\nReporter: {}\nRoom ID: {}\nReceived: {}\nScore: {}\nReason: {}\n",
+		report.state.as_str(),
+		report.reporter,
+		report.room_id,
+		received
+			.map(|ts| time::format(ts, "%+"))
+			.unwrap_or_default(),
+		report
+			.score
+			.map(|score| score.to_string())
+			.unwrap_or_default(),
+		report.reason.as_deref().unwrap_or_default(),
+	);
+
+	if let Some(handled_by) = &report.handled_by {
+		writeln!(msg, "Handled by: {handled_by}")?;
+	}
+
+	let Some(event_id) = &report.event_id else {
+		return Ok(RoomMessageEventContent::notice_markdown(msg));
+	};
+
+	let Ok(pdu) = self.services.rooms.timeline.get_pdu(event_id).await else {
+		writeln!(msg, "\nThe reported event {event_id} is no longer known to us.")?;
+		return Ok(RoomMessageEventContent::notice_markdown(msg));
+	};
+
+	let context = self.event_context(&pdu).await?;
+	if !context.is_empty() {
+		writeln!(msg, "\nEvents before it:\n```")?;
+		for event in &context {
+			let content: String = event
+				.content
+				.get()
+				.chars()
+				.take(CONTEXT_CONTENT_LENGTH)
+				.collect();
+
+			writeln!(msg, "{} {} {}: {content}", event.event_id, event.sender, event.kind)?;
+		}
+		writeln!(msg, "```")?;
+	}
+
+	let event = serde_json::to_string_pretty(&pdu)?;
+	write!(
+		msg,
+		"\nReported event {event_id} sent by {}:\n```json\n{event}\n```",
+		pdu.sender
+	)?;
+
+	if pdu.is_redacted() {
+		match self.services.rooms.unredacted.get(event_id).await {
+			| Ok(unredacted) => {
+				let content: serde_json::Value = serde_json::from_str(unredacted.content.get())?;
+				let content = serde_json::to_string_pretty(&content)?;
+				write!(msg, "\n\nContent before it was redacted:\n```json\n{content}\n```")?;
+			},
+			| Err(_) => write!(msg, "\n\nThe content before it was redacted is no longer kept.")?,
+		}
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(msg))
+}
+
+#[admin_command]
+pub(super) async fn resolve(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let handled_by = self.sender_user().await?;
+	self.services
+		.reports
+		.set_state(id, ReportState::Resolved, &handled_by)
+		.await?;
+
+	Ok(RoomMessageEventContent::text_plain(format!("Report #{id} resolved.")))
+}
+
+#[admin_command]
+pub(super) async fn dismiss(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let handled_by = self.sender_user().await?;
+	self.services
+		.reports
+		.set_state(id, ReportState::Dismissed, &handled_by)
+		.await?;
+
+	Ok(RoomMessageEventContent::text_plain(format!("Report #{id} dismissed.")))
+}
+
+/// Returns the events before the reported one in its room, oldest first.
+#[implement(Command, params = "<'_>")]
+async fn event_context(&self, pdu: &PduEvent) -> Result<Vec<PduEvent>> {
+	let timeline = &self.services.rooms.timeline;
+	let count = timeline.get_pdu_count(&pdu.event_id).await?;
+
+	let mut events: Vec<PduEvent> = timeline
+		.pdus_rev(None, &pdu.room_id, Some(count))
+		.ignore_err()
+		.ready_filter(|(_, event)| event.event_id != pdu.event_id)
+		.map(|(_, event)| event)
+		.take(CONTEXT_EVENTS)
+		.collect()
+		.await;
+
+	events.reverse();
+
+	Ok(events)
+}
@@ -0,0 +1,32 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum ReportCommand {
+	/// - List the newest room and event reports made by local users
+	List {
+		/// Also list resolved and dismissed reports
+		#[arg(long)]
+		all: bool,
+	},
+
+	/// - Show a report, along with the reported event and the events before it
+	View {
+		id: u64,
+	},
+
+	/// - Mark a report as resolved, after action was taken on it
+	Resolve {
+		id: u64,
+	},
+
+	/// - Mark a report as dismissed, when no action is needed
+	Dismiss {
+		id: u64,
+	},
+}
@@ -6,6 +6,7 @@
 use ruma::{
 	OwnedRoomAliasId, OwnedRoomId, RoomId, events::room::message::RoomMessageEventContent,
 };
+use serde_json::json;
 
 use crate::{Command, escape_html};
 
@@ -129,7 +130,15 @@ pub(super) async fn reprocess(
 					}
 				},
 				| RoomAliasCommand::Which { .. } => {
-					match services.rooms.alias.resolve_local_alias(&room_alias).await {
+					let room_id = services.rooms.alias.resolve_local_alias(&room_alias).await;
+					context
+						.write_data(&json!({
+							"alias": room_alias,
+							"room_id": room_id.as_ref().ok(),
+						}))
+						.await?;
+
+					match room_id {
 						| Ok(id) => Ok(RoomMessageEventContent::text_plain(format!(
 							"Alias resolves to {id}"
 						))),
@@ -150,6 +159,8 @@ pub(super) async fn reprocess(
 					.collect()
 					.await;
 
+				context.write_data(&aliases).await?;
+
 				let plain_list = aliases.iter().fold(String::new(), |mut output, alias| {
 					writeln!(output, "- {alias}")
 						.expect("should be able to write to string buffer");
@@ -175,6 +186,19 @@ pub(super) async fn reprocess(
 					.await;
 
 				let server_name = services.globals.server_name();
+				context
+					.write_data(
+						&aliases
+							.iter()
+							.map(|(room_id, localpart)| {
+								json!({
+									"alias": format!("#{localpart}:{server_name}"),
+									"room_id": room_id,
+								})
+							})
+							.collect::<Vec<_>>(),
+					)
+					.await?;
 				let plain_list = aliases
 					.iter()
 					.fold(String::new(), |mut output, (alias, id)| {
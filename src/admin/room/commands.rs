@@ -1,8 +1,9 @@
 use conduwuit::Result;
 use futures::StreamExt;
 use ruma::{OwnedRoomId, events::room::message::RoomMessageEventContent};
+use serde_json::json;
 
-use crate::{PAGE_SIZE, admin_command, get_room_info};
+use crate::{PAGE_SIZE, admin_command, get_room_info, rooms_json};
 
 #[admin_command]
 pub(super) async fn list_rooms(
@@ -40,6 +41,8 @@ pub(super) async fn list_rooms(
 		.take(PAGE_SIZE)
 		.collect::<Vec<_>>();
 
+	self.write_data(&rooms_json(&rooms)).await?;
+
 	if rooms.is_empty() {
 		return Ok(RoomMessageEventContent::text_plain("No more rooms."));
 	}
@@ -65,5 +68,8 @@ pub(super) async fn list_rooms(
 pub(super) async fn exists(&self, room_id: OwnedRoomId) -> Result<RoomMessageEventContent> {
 	let result = self.services.rooms.metadata.exists(&room_id).await;
 
+	self.write_data(&json!({ "room_id": room_id, "exists": result }))
+		.await?;
+
 	Ok(RoomMessageEventContent::notice_markdown(format!("{result}")))
 }
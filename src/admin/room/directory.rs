@@ -3,7 +3,7 @@
 use futures::StreamExt;
 use ruma::{RoomId, events::room::message::RoomMessageEventContent};
 
-use crate::{Command, PAGE_SIZE, get_room_info};
+use crate::{Command, PAGE_SIZE, get_room_info, rooms_json};
 
 #[derive(Debug, Subcommand)]
 pub(crate) enum RoomDirectoryCommand {
@@ -38,7 +38,7 @@ pub(super) async fn reprocess(
 	let services = context.services;
 	match command {
 		| RoomDirectoryCommand::Publish { room_id } => {
-			services.rooms.directory.set_public(&room_id);
+			services.rooms.directory.set_public(&room_id).await;
 			Ok(RoomMessageEventContent::notice_plain("Room published"))
 		},
 		| RoomDirectoryCommand::Unpublish { room_id } => {
@@ -65,6 +65,8 @@ pub(super) async fn reprocess(
 				.take(PAGE_SIZE)
 				.collect();
 
+			context.write_data(&rooms_json(&rooms)).await?;
+
 			if rooms.is_empty() {
 				return Ok(RoomMessageEventContent::text_plain("No more rooms."));
 			}
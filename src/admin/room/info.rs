@@ -2,6 +2,7 @@
 use conduwuit::{Result, utils::ReadyExt};
 use futures::StreamExt;
 use ruma::{RoomId, events::room::message::RoomMessageEventContent};
+use serde_json::json;
 
 use crate::{admin_command, admin_command_dispatch};
 
@@ -64,6 +65,19 @@ async fn list_joined_members(
 		.collect()
 		.await;
 
+	self.write_data(&json!({
+		"room_id": room_id,
+		"name": room_name,
+		"members": member_info
+			.iter()
+			.map(|(displayname, user_id)| json!({
+				"user_id": user_id,
+				"displayname": displayname,
+			}))
+			.collect::<Vec<_>>(),
+	}))
+	.await?;
+
 	let output_plain = format!(
 		"{} Members in Room \"{}\":\n```\n{}\n```",
 		member_info.len(),
@@ -87,9 +101,15 @@ async fn view_room_topic(&self, room_id: Box<RoomId>) -> Result<RoomMessageEvent
 		.get_room_topic(&room_id)
 		.await
 	else {
+		self.write_data(&json!({ "room_id": room_id, "topic": null }))
+			.await?;
+
 		return Ok(RoomMessageEventContent::text_plain("Room does not have a room topic set."));
 	};
 
+	self.write_data(&json!({ "room_id": room_id, "topic": room_topic }))
+		.await?;
+
 	Ok(RoomMessageEventContent::notice_markdown(format!(
 		"Room topic:\n```\n{room_topic}\n```"
 	)))
@@ -1,17 +1,13 @@
-use api::client::leave_room;
+use api::client::evacuate_room;
 use clap::Subcommand;
-use conduwuit::{
-	Result, debug,
-	utils::{IterStream, ReadyExt},
-	warn,
-};
+use conduwuit::{Result, debug, utils::IterStream, warn};
 use futures::StreamExt;
 use ruma::{
 	OwnedRoomId, RoomAliasId, RoomId, RoomOrAliasId,
 	events::room::message::RoomMessageEventContent,
 };
 
-use crate::{admin_command, admin_command_dispatch, get_room_info};
+use crate::{admin_command, admin_command_dispatch, get_room_info, rooms_json};
 
 #[admin_command_dispatch]
 #[derive(Debug, Subcommand)]
@@ -73,8 +69,6 @@ async fn ban_room(&self, room: Box<RoomOrAliasId>) -> Result<RoomMessageEventCon
 		};
 
 		debug!("Room specified is a room ID, banning room ID");
-		self.services.rooms.metadata.ban_room(room_id, true);
-
 		room_id.to_owned()
 	} else if room.is_room_alias_id() {
 		let room_alias = match RoomAliasId::parse(&room) {
@@ -131,8 +125,6 @@ async fn ban_room(&self, room: Box<RoomOrAliasId>) -> Result<RoomMessageEventCon
 			},
 		};
 
-		self.services.rooms.metadata.ban_room(&room_id, true);
-
 		room_id
 	} else {
 		return Ok(RoomMessageEventContent::text_plain(
@@ -142,48 +134,7 @@ async fn ban_room(&self, room: Box<RoomOrAliasId>) -> Result<RoomMessageEventCon
 		));
 	};
 
-	debug!("Making all users leave the room {room_id} and forgetting it");
-	let mut users = self
-		.services
-		.rooms
-		.state_cache
-		.room_members(&room_id)
-		.map(ToOwned::to_owned)
-		.ready_filter(|user| self.services.globals.user_is_local(user))
-		.boxed();
-
-	while let Some(ref user_id) = users.next().await {
-		debug!(
-			"Attempting leave for user {user_id} in room {room_id} (ignoring all errors, \
-			 evicting admins too)",
-		);
-
-		if let Err(e) = leave_room(self.services, user_id, &room_id, None).await {
-			warn!("Failed to leave room: {e}");
-		}
-
-		self.services.rooms.state_cache.forget(&room_id, user_id);
-	}
-
-	self.services
-		.rooms
-		.alias
-		.local_aliases_for_room(&room_id)
-		.map(ToOwned::to_owned)
-		.for_each(|local_alias| async move {
-			self.services
-				.rooms
-				.alias
-				.remove_alias(&local_alias, &self.services.globals.server_user)
-				.await
-				.ok();
-		})
-		.await;
-
-	// unpublish from room directory
-	self.services.rooms.directory.set_not_public(&room_id);
-
-	self.services.rooms.metadata.disable_room(&room_id, true);
+	evacuate_room(self.services, &room_id, true).await;
 
 	Ok(RoomMessageEventContent::text_plain(
 		"Room banned, removed all our local users, and disabled incoming federation with room.",
@@ -306,54 +257,10 @@ async fn ban_list_of_rooms(&self) -> Result<RoomMessageEventContent> {
 	}
 
 	for room_id in room_ids {
-		self.services.rooms.metadata.ban_room(&room_id, true);
+		evacuate_room(self.services, &room_id, true).await;
 
 		debug!("Banned {room_id} successfully");
 		room_ban_count = room_ban_count.saturating_add(1);
-
-		debug!("Making all users leave the room {room_id} and forgetting it");
-		let mut users = self
-			.services
-			.rooms
-			.state_cache
-			.room_members(&room_id)
-			.map(ToOwned::to_owned)
-			.ready_filter(|user| self.services.globals.user_is_local(user))
-			.boxed();
-
-		while let Some(ref user_id) = users.next().await {
-			debug!(
-				"Attempting leave for user {user_id} in room {room_id} (ignoring all errors, \
-				 evicting admins too)",
-			);
-
-			if let Err(e) = leave_room(self.services, user_id, &room_id, None).await {
-				warn!("Failed to leave room: {e}");
-			}
-
-			self.services.rooms.state_cache.forget(&room_id, user_id);
-		}
-
-		// remove any local aliases, ignore errors
-		self.services
-			.rooms
-			.alias
-			.local_aliases_for_room(&room_id)
-			.map(ToOwned::to_owned)
-			.for_each(|local_alias| async move {
-				self.services
-					.rooms
-					.alias
-					.remove_alias(&local_alias, &self.services.globals.server_user)
-					.await
-					.ok();
-			})
-			.await;
-
-		// unpublish from room directory, ignore errors
-		self.services.rooms.directory.set_not_public(&room_id);
-
-		self.services.rooms.metadata.disable_room(&room_id, true);
 	}
 
 	Ok(RoomMessageEventContent::text_plain(format!(
@@ -462,6 +369,7 @@ async fn list_banned_rooms(&self, no_details: bool) -> Result<RoomMessageEventCo
 		.await;
 
 	if room_ids.is_empty() {
+		self.write_data(&room_ids).await?;
 		return Ok(RoomMessageEventContent::text_plain("No rooms are banned."));
 	}
 
@@ -475,6 +383,12 @@ async fn list_banned_rooms(&self, no_details: bool) -> Result<RoomMessageEventCo
 	rooms.sort_by_key(|r| r.1);
 	rooms.reverse();
 
+	if no_details {
+		self.write_data(&room_ids).await?;
+	} else {
+		self.write_data(&rooms_json(&rooms)).await?;
+	}
+
 	let output_plain = format!(
 		"Rooms Banned ({}):\n```\n{}\n```",
 		rooms.len(),
@@ -2,6 +2,7 @@
 
 use conduwuit::{Err, Result, info, utils::time, warn};
 use ruma::events::room::message::RoomMessageEventContent;
+use serde_json::json;
 
 use crate::admin_command;
 
@@ -14,6 +15,9 @@ pub(super) async fn uptime(&self) -> Result<RoomMessageEventContent> {
 		.elapsed()
 		.expect("standard duration");
 
+	self.write_data(&json!({ "uptime_secs": elapsed.as_secs() }))
+		.await?;
+
 	let result = time::pretty(elapsed);
 	Ok(RoomMessageEventContent::notice_plain(format!("{result}.")))
 }
@@ -47,12 +51,14 @@ pub(super) async fn list_features(
 ) -> Result<RoomMessageEventContent> {
 	let delim = if comma { "," } else { " " };
 	if enabled && !available {
+		self.write_data(&info::rustc::features()).await?;
 		let features = info::rustc::features().join(delim);
 		let out = format!("`\n{features}\n`");
 		return Ok(RoomMessageEventContent::text_markdown(out));
 	}
 
 	if available && !enabled {
+		self.write_data(&info::cargo::features()).await?;
 		let features = info::cargo::features().join(delim);
 		let out = format!("`\n{features}\n`");
 		return Ok(RoomMessageEventContent::text_markdown(out));
@@ -61,6 +67,19 @@ pub(super) async fn list_features(
 	let mut features = String::new();
 	let enabled = info::rustc::features();
 	let available = info::cargo::features();
+	self.write_data(
+		&available
+			.iter()
+			.map(|feature| {
+				json!({
+					"feature": feature,
+					"enabled": enabled.contains(&feature.as_str()),
+				})
+			})
+			.collect::<Vec<_>>(),
+	)
+	.await?;
+
 	for feature in available {
 		let active = enabled.contains(&feature.as_str());
 		let emoji = if active { "✅" } else { "❌" };
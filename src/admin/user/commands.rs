@@ -1,18 +1,18 @@
-use std::{collections::BTreeMap, fmt::Write as _};
+use std::{collections::BTreeMap, fmt::Write as _, sync::Arc, time::Duration};
 
-use api::client::{full_user_deactivate, join_room_by_id_helper, leave_room};
+use api::client::{admin_deactivate_user, join_room_by_id_helper, leave_room};
 use conduwuit::{
 	Result, debug, debug_warn, error, info, is_equal_to,
 	matrix::pdu::PduBuilder,
-	utils::{self, ReadyExt},
+	utils::{self, ReadyExt, stream::TryIgnore, time::parse_duration},
 	warn,
 };
-use conduwuit_api::client::{leave_all_rooms, update_avatar_url, update_displayname};
+use conduwuit_service::Services;
 use futures::StreamExt;
 use ruma::{
-	EventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId, RoomId, UserId,
+	EventId, OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId, RoomId, UserId,
 	events::{
-		RoomAccountDataEventType, StateEventType,
+		RoomAccountDataEventType, StateEventType, TimelineEventType,
 		room::{
 			message::RoomMessageEventContent,
 			power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
@@ -21,15 +21,23 @@
 		tag::{TagEvent, TagEventContent, TagInfo},
 	},
 };
+use serde_json::json;
+use tokio::time::sleep;
 
 use crate::{
-	admin_command, get_room_info,
+	admin_command, get_room_info, rooms_json,
 	utils::{parse_active_local_user_id, parse_local_user_id},
 };
 
 const AUTO_GEN_PASSWORD_LENGTH: usize = 25;
 const BULK_JOIN_REASON: &str = "Bulk force joining this room as initiated by the server admin.";
 
+/// pause between the redactions of `redact-all`, to not flood the rooms
+const REDACT_ALL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// users between the progress reports of `notice all`
+const NOTICE_PROGRESS_INTERVAL: usize = 500;
+
 #[admin_command]
 pub(super) async fn list_users(&self) -> Result<RoomMessageEventContent> {
 	let users: Vec<_> = self
@@ -40,6 +48,8 @@ pub(super) async fn list_users(&self) -> Result<RoomMessageEventContent> {
 		.collect()
 		.await;
 
+	self.write_data(&users).await?;
+
 	let mut plain_msg = format!("Found {} local user account(s):\n```\n", users.len());
 	plain_msg += users.join("\n").as_str();
 	plain_msg += "\n```";
@@ -70,6 +80,12 @@ pub(super) async fn create_user(
 		return Ok(RoomMessageEventContent::text_plain(format!("User {user_id} already exists")));
 	}
 
+	if self.services.server_notices.is_system_user(&user_id) {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} is reserved for server notices"
+		)));
+	}
+
 	let password = password.unwrap_or_else(|| utils::random_string(AUTO_GEN_PASSWORD_LENGTH));
 
 	// Create user
@@ -192,6 +208,9 @@ pub(super) async fn create_user(
 		debug!("create_user admin command called without an admin room being available");
 	}
 
+	self.write_data(&json!({ "user_id": user_id, "password": password }))
+		.await?;
+
 	Ok(RoomMessageEventContent::text_plain(format!(
 		"Created user with user_id: {user_id} and password: `{password}`"
 	)))
@@ -201,6 +220,7 @@ pub(super) async fn create_user(
 pub(super) async fn deactivate(
 	&self,
 	no_leave_rooms: bool,
+	erase: bool,
 	user_id: String,
 ) -> Result<RoomMessageEventContent> {
 	// Validate user id
@@ -213,7 +233,15 @@ pub(super) async fn deactivate(
 		));
 	}
 
-	self.services.users.deactivate_account(&user_id).await?;
+	if erase {
+		self.services
+			.admin
+			.send_message(RoomMessageEventContent::text_plain(format!(
+				"Erasing all events, media and account data of {user_id}..."
+			)))
+			.await
+			.ok();
+	}
 
 	if !no_leave_rooms {
 		self.services
@@ -223,24 +251,16 @@ pub(super) async fn deactivate(
 			)))
 			.await
 			.ok();
+	}
 
-		let all_joined_rooms: Vec<OwnedRoomId> = self
-			.services
-			.rooms
-			.state_cache
-			.rooms_joined(&user_id)
-			.map(Into::into)
-			.collect()
-			.await;
+	admin_deactivate_user(self.services, &user_id, erase, !no_leave_rooms).await?;
 
-		full_user_deactivate(self.services, &user_id, &all_joined_rooms).await?;
-		update_displayname(self.services, &user_id, None, &all_joined_rooms).await;
-		update_avatar_url(self.services, &user_id, None, None, &all_joined_rooms).await;
-		leave_all_rooms(self.services, &user_id).await;
-	}
+	self.write_data(&json!({ "user_id": user_id, "erased": erase }))
+		.await?;
 
+	let erased = if erase { " and erased" } else { "" };
 	Ok(RoomMessageEventContent::text_plain(format!(
-		"User {user_id} has been deactivated"
+		"User {user_id} has been deactivated{erased}"
 	)))
 }
 
@@ -266,9 +286,14 @@ pub(super) async fn reset_password(
 		.users
 		.set_password(&user_id, Some(new_password.as_str()))
 	{
-		| Ok(()) => Ok(RoomMessageEventContent::text_plain(format!(
-			"Successfully reset the password for user {user_id}: `{new_password}`"
-		))),
+		| Ok(()) => {
+			self.write_data(&json!({ "user_id": user_id, "password": new_password }))
+				.await?;
+
+			Ok(RoomMessageEventContent::text_plain(format!(
+				"Successfully reset the password for user {user_id}: `{new_password}`"
+			)))
+		},
 		| Err(e) => Ok(RoomMessageEventContent::text_plain(format!(
 			"Couldn't reset the password for user {user_id}: {e}"
 		))),
@@ -344,26 +369,13 @@ pub(super) async fn deactivate_all(
 	let mut deactivation_count: usize = 0;
 
 	for user_id in user_ids {
-		match self.services.users.deactivate_account(&user_id).await {
+		if !no_leave_rooms {
+			info!("Forcing user {user_id} to leave all rooms apart of deactivate-all");
+		}
+
+		match admin_deactivate_user(self.services, &user_id, false, !no_leave_rooms).await {
 			| Ok(()) => {
 				deactivation_count = deactivation_count.saturating_add(1);
-				if !no_leave_rooms {
-					info!("Forcing user {user_id} to leave all rooms apart of deactivate-all");
-					let all_joined_rooms: Vec<OwnedRoomId> = self
-						.services
-						.rooms
-						.state_cache
-						.rooms_joined(&user_id)
-						.map(Into::into)
-						.collect()
-						.await;
-
-					full_user_deactivate(self.services, &user_id, &all_joined_rooms).await?;
-					update_displayname(self.services, &user_id, None, &all_joined_rooms).await;
-					update_avatar_url(self.services, &user_id, None, None, &all_joined_rooms)
-						.await;
-					leave_all_rooms(self.services, &user_id).await;
-				}
 			},
 			| Err(e) => {
 				self.services
@@ -377,6 +389,12 @@ pub(super) async fn deactivate_all(
 		}
 	}
 
+	self.write_data(&json!({
+		"deactivated": deactivation_count,
+		"skipped_admins": admins,
+	}))
+	.await?;
+
 	if admins.is_empty() {
 		Ok(RoomMessageEventContent::text_plain(format!(
 			"Deactivated {deactivation_count} accounts."
@@ -411,6 +429,8 @@ pub(super) async fn list_joined_rooms(&self, user_id: String) -> Result<RoomMess
 	rooms.sort_by_key(|r| r.1);
 	rooms.reverse();
 
+	self.write_data(&rooms_json(&rooms)).await?;
+
 	let output_plain = format!(
 		"Rooms {user_id} Joined ({}):\n```\n{}\n```",
 		rooms.len(),
@@ -783,6 +803,190 @@ pub(super) async fn force_demote(
 	)))
 }
 
+#[admin_command]
+pub(super) async fn suspend(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if user_id == self.services.globals.server_user {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Not allowed to suspend the server service account.",
+		));
+	}
+
+	if !self.services.users.exists(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} does not exist."
+		)));
+	}
+
+	if self.services.users.is_suspended(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} is already suspended."
+		)));
+	}
+
+	self.services.users.suspend(&user_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"User {user_id} has been suspended."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unsuspend(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if !self.services.users.is_suspended(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} is not suspended."
+		)));
+	}
+
+	self.services.users.unsuspend(&user_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"User {user_id} is no longer suspended."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn shadow_ban(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if user_id == self.services.globals.server_user {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Not allowed to shadow-ban the server service account.",
+		));
+	}
+
+	if !self.services.users.exists(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} does not exist."
+		)));
+	}
+
+	if self.services.users.is_shadow_banned(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} is already shadow-banned."
+		)));
+	}
+
+	self.services.users.shadow_ban(&user_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"User {user_id} has been shadow-banned."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unshadow_ban(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if !self.services.users.is_shadow_banned(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} is not shadow-banned."
+		)));
+	}
+
+	self.services.users.unshadow_ban(&user_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"User {user_id} is no longer shadow-banned."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn issue_token(
+	&self,
+	user_id: String,
+	ttl: String,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	let ttl = parse_duration(&ttl)?;
+
+	if user_id == self.services.globals.server_user {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Not allowed to issue tokens for the server service account.",
+		));
+	}
+
+	let issued_by = self.sender_user().await?;
+
+	let (device_id, token) = self
+		.services
+		.users
+		.issue_admin_token(&user_id, &issued_by, ttl)
+		.await?;
+
+	warn!(%user_id, %device_id, %issued_by, ?ttl, "Issued admin access token");
+
+	self.write_data(&json!({
+		"user_id": user_id,
+		"device_id": device_id,
+		"access_token": token,
+		"expires_in_ms": ttl.as_millis(),
+	}))
+	.await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Issued an access token for {user_id} on device `{device_id}`, valid for \
+		 {ttl:?}:\n\n`{token}`\n\nRevoke it early with `!admin users revoke-tokens {user_id}`."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn revoke_tokens(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	let count = self.services.users.revoke_admin_tokens(&user_id).await;
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Revoked {count} admin-issued access tokens of {user_id}."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn lock(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if user_id == self.services.globals.server_user {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Not allowed to lock the server service account.",
+		));
+	}
+
+	if !self.services.users.exists(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} does not exist."
+		)));
+	}
+
+	if self.services.users.is_locked(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"User {user_id} is already locked."
+		)));
+	}
+
+	self.services.users.lock(&user_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!("User {user_id} has been locked.")))
+}
+
+#[admin_command]
+pub(super) async fn unlock(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if !self.services.users.is_locked(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!("User {user_id} is not locked.")));
+	}
+
+	self.services.users.unlock(&user_id);
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"User {user_id} has been unlocked."
+	)))
+}
+
 #[admin_command]
 pub(super) async fn make_user_admin(&self, user_id: String) -> Result<RoomMessageEventContent> {
 	let user_id = parse_local_user_id(self.services, &user_id)?;
@@ -889,6 +1093,8 @@ pub(super) async fn get_room_tags(
 			content: TagEventContent { tags: BTreeMap::new() },
 		});
 
+	self.write_data(&tags_event.content.tags).await?;
+
 	Ok(RoomMessageEventContent::notice_markdown(format!(
 		"```\n{:#?}\n```",
 		tags_event.content.tags
@@ -955,3 +1161,281 @@ pub(super) async fn redact_event(
 
 	Ok(RoomMessageEventContent::text_plain(""))
 }
+
+#[admin_command]
+pub(super) async fn redact_all(
+	&self,
+	user_id: String,
+	room: Option<OwnedRoomOrAliasId>,
+	since: Option<String>,
+	limit: Option<usize>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let since = since
+		.as_deref()
+		.map(parse_duration)
+		.transpose()?
+		.map(|since| {
+			let since = u64::try_from(since.as_millis()).unwrap_or(u64::MAX);
+			utils::time::now_millis().saturating_sub(since)
+		});
+
+	let rooms: Vec<OwnedRoomId> = match room {
+		| Some(room) => vec![self.services.rooms.alias.resolve(&room).await?],
+		| None =>
+			self.services
+				.rooms
+				.state_cache
+				.rooms_joined(&user_id)
+				.map(ToOwned::to_owned)
+				.collect()
+				.await,
+	};
+
+	let started = format!(
+		"Redacting the messages of {user_id} in {} rooms in the background. Progress is \
+		 reported in the admin room.",
+		rooms.len()
+	);
+
+	let services = Arc::clone(self.services);
+	self.services.server.runtime().spawn(async move {
+		let out = redact_all_events(&services, &user_id, &rooms, since, limit)
+			.await
+			.unwrap_or_else(|e| format!("Failed to redact the messages of {user_id}: {e}"));
+		services
+			.admin
+			.send_message(RoomMessageEventContent::notice_markdown(out))
+			.await
+			.ok();
+	});
+
+	Ok(RoomMessageEventContent::notice_plain(started))
+}
+
+/// Redacts the messages of `user_id` in `rooms`, pausing between redactions,
+/// and returns a summary. Progress is reported to the admin room after every
+/// room with redactions.
+async fn redact_all_events(
+	services: &Services,
+	user_id: &UserId,
+	rooms: &[OwnedRoomId],
+	since: Option<u64>,
+	limit: Option<usize>,
+) -> Result<String> {
+	let reason = format!(
+		"The administrator(s) of {} has redacted this user's messages.",
+		services.globals.server_name()
+	);
+
+	let mut remaining = limit.unwrap_or(usize::MAX);
+	let mut redacted: usize = 0;
+	let mut skipped: Vec<String> = Vec::new();
+	for (i, room_id) in rooms.iter().enumerate() {
+		if remaining == 0 || !services.server.running() {
+			break;
+		}
+
+		// Timestamps are not ordered in the timeline, so all of it is scanned
+		let event_ids: Vec<OwnedEventId> = services
+			.rooms
+			.timeline
+			.pdus_rev(None, room_id, None)
+			.ignore_err()
+			.ready_filter(|(_, pdu)| {
+				pdu.sender == user_id
+					&& pdu.state_key.is_none()
+					&& pdu.kind != TimelineEventType::RoomRedaction
+					&& !pdu.is_redacted()
+					&& since.is_none_or(|since| u64::from(pdu.origin_server_ts) >= since)
+			})
+			.map(|(_, pdu)| pdu.event_id)
+			.take(remaining)
+			.collect()
+			.await;
+
+		let mut room_redacted: usize = 0;
+		for event_id in &event_ids {
+			if !services.server.running() {
+				break;
+			}
+
+			let state_lock = services.rooms.state.mutex.lock(room_id).await;
+			let result = services
+				.rooms
+				.timeline
+				.build_and_append_pdu(
+					PduBuilder {
+						redacts: Some(event_id.clone()),
+						..PduBuilder::timeline(&RoomRedactionEventContent {
+							redacts: Some(event_id.clone()),
+							reason: Some(reason.clone()),
+						})
+					},
+					user_id,
+					room_id,
+					&state_lock,
+				)
+				.await;
+
+			drop(state_lock);
+			if let Err(e) = result {
+				// the user may not redact in this room; skip the rest of it
+				skipped.push(format!("{room_id}: {e}"));
+				break;
+			}
+
+			room_redacted = room_redacted.saturating_add(1);
+			remaining = remaining.saturating_sub(1);
+			sleep(REDACT_ALL_INTERVAL).await;
+		}
+
+		redacted = redacted.saturating_add(room_redacted);
+		if room_redacted > 0 {
+			services
+				.admin
+				.send_text(&format!(
+					"Redacted {room_redacted} messages of {user_id} in {room_id} ({}/{}).",
+					i.saturating_add(1),
+					rooms.len()
+				))
+				.await;
+		}
+	}
+
+	let mut out = format!("Redacted {redacted} messages of {user_id} in {} rooms.", rooms.len());
+	if remaining == 0 {
+		writeln!(out, "\nStopped at the limit; run the command again to continue.")?;
+	}
+
+	if !skipped.is_empty() {
+		writeln!(out, "\nSkipped rooms:\n```\n{}\n```", skipped.join("\n"))?;
+	}
+
+	Ok(out)
+}
+
+#[admin_command]
+pub(super) async fn notice(
+	&self,
+	target: String,
+	message: Vec<String>,
+) -> Result<RoomMessageEventContent> {
+	let message = message.join(" ");
+	if message.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain("Notice message is empty."));
+	}
+
+	let content = RoomMessageEventContent::text_markdown(&message);
+	if target != "all" {
+		let user_id = parse_active_local_user_id(self.services, &target).await?;
+		self.services.server_notices.send(&user_id, content).await?;
+
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"Sent server notice to {user_id}."
+		)));
+	}
+
+	let recipients: Vec<OwnedUserId> = self
+		.services
+		.server_notices
+		.recipients()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let started = format!(
+		"Sending the server notice to {} users in the background. Progress is reported in the \
+		 admin room.",
+		recipients.len()
+	);
+
+	let services = Arc::clone(self.services);
+	self.services.server.runtime().spawn(async move {
+		let out = send_notices(&services, &recipients, content).await;
+		services
+			.admin
+			.send_message(RoomMessageEventContent::notice_plain(out))
+			.await
+			.ok();
+	});
+
+	Ok(RoomMessageEventContent::notice_plain(started))
+}
+
+/// Sends a server notice to each of `recipients` and returns a summary.
+/// Progress is reported to the admin room every `NOTICE_PROGRESS_INTERVAL`
+/// users.
+async fn send_notices(
+	services: &Services,
+	recipients: &[OwnedUserId],
+	content: RoomMessageEventContent,
+) -> String {
+	let mut sent: usize = 0;
+	let mut failed: usize = 0;
+	for (i, user_id) in recipients.iter().enumerate() {
+		if !services.server.running() {
+			break;
+		}
+
+		match services.server_notices.send(user_id, content.clone()).await {
+			| Ok(_) => sent = sent.saturating_add(1),
+			| Err(e) => {
+				warn!("Failed to send server notice to {user_id}: {e}");
+				failed = failed.saturating_add(1);
+			},
+		}
+
+		let done = i.saturating_add(1);
+		if done % NOTICE_PROGRESS_INTERVAL == 0 && done < recipients.len() {
+			services
+				.admin
+				.send_text(&format!(
+					"Sent the server notice to {done}/{} users.",
+					recipients.len()
+				))
+				.await;
+		}
+	}
+
+	format!(
+		"Sent the server notice to {sent} of {} users; {failed} failed.",
+		recipients.len()
+	)
+}
+
+#[admin_command]
+pub(super) async fn export(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if self.services.server.config.user_export_path.is_none() {
+		return Ok(RoomMessageEventContent::text_plain(
+			"User exports are disabled. Set `user_export_path` to enable them.",
+		));
+	}
+
+	let services = Arc::clone(self.services);
+	let started = format!(
+		"Exporting the data of {user_id} in the background. The result is reported in the admin \
+		 room."
+	);
+
+	self.services.server.runtime().spawn(async move {
+		let out = match services.export.export_user(&user_id).await {
+			| Ok(export) => format!(
+				"Exported data of {user_id} to `{}`, including {} events and {} media files.",
+				export.path.display(),
+				export.events,
+				export.media,
+			),
+			| Err(e) => format!("Failed to export the data of {user_id}: {e}"),
+		};
+
+		services
+			.admin
+			.send_message(RoomMessageEventContent::notice_markdown(out))
+			.await
+			.ok();
+	});
+
+	Ok(RoomMessageEventContent::notice_plain(started))
+}
@@ -30,9 +30,15 @@ pub(super) enum UserCommand {
 	///
 	/// User will be removed from all rooms by default.
 	/// Use --no-leave-rooms to not leave all rooms by default.
+	///
+	/// Use --erase to also redact all of the user's messages and delete their
+	/// media and account data (GDPR erasure). The messages are redacted in the
+	/// background.
 	Deactivate {
 		#[arg(short, long)]
 		no_leave_rooms: bool,
+		#[arg(long)]
+		erase: bool,
 		user_id: String,
 	},
 
@@ -59,6 +65,67 @@ pub(super) enum UserCommand {
 		force: bool,
 	},
 
+	/// - Suspend a user
+	///
+	/// Suspended users can still read and leave rooms, but cannot send
+	/// events, join rooms or change their profile until unsuspended.
+	Suspend {
+		user_id: String,
+	},
+
+	/// - Lift the suspension of a user
+	Unsuspend {
+		user_id: String,
+	},
+
+	/// - Shadow-ban a user
+	///
+	/// Requests of shadow-banned users which would send events, change their
+	/// profile, invite, kick, ban or knock, upgrade rooms, publish rooms, add
+	/// aliases, type or send to-device messages to others appear to succeed,
+	/// but nothing is actually done. Rooms they create are created without
+	/// inviting anyone, an alias or a directory entry.
+	ShadowBan {
+		user_id: String,
+	},
+
+	/// - Lift the shadow-ban of a user
+	UnshadowBan {
+		user_id: String,
+	},
+
+	/// - Issue a time-limited access token to act as a local user
+	///
+	/// Creates a new session of the user for debugging their client issues.
+	/// Every request made with the token is logged. The session does not
+	/// show up as a new login to the user's other sessions, and cannot
+	/// publish device keys.
+	IssueToken {
+		user_id: String,
+
+		/// How long the token is valid for, e.g. "30m" or "2h"
+		#[arg(long, default_value("1h"))]
+		ttl: String,
+	},
+
+	/// - Revoke all access tokens issued to act as a local user
+	RevokeTokens {
+		user_id: String,
+	},
+
+	/// - Lock a user
+	///
+	/// Locked users are rejected on every request, and their clients are
+	/// asked to log out until the account is unlocked.
+	Lock {
+		user_id: String,
+	},
+
+	/// - Unlock a user
+	Unlock {
+		user_id: String,
+	},
+
 	/// - List local users in the database
 	#[clap(alias = "list")]
 	ListUsers,
@@ -127,6 +194,31 @@ pub(super) enum UserCommand {
 		event_id: Box<EventId>,
 	},
 
+	/// - Redacts the recent messages of a local user across rooms
+	///
+	/// Messages are redacted newest first in every room the user is joined
+	/// to, or only in `--room`. Use `--since` to only redact messages sent
+	/// within the given duration (e.g. "2h" or "3d"), and `--limit` to stop
+	/// after that many redactions.
+	///
+	/// Redactions are paced to avoid flooding rooms and federation, so they
+	/// run in the background and report their progress in the admin room.
+	/// Messages which are already redacted are skipped, so an interrupted run
+	/// resumes by running the command again. Rooms in which the user may not
+	/// redact are skipped and reported.
+	RedactAll {
+		user_id: String,
+
+		#[arg(long)]
+		room: Option<OwnedRoomOrAliasId>,
+
+		#[arg(long)]
+		since: Option<String>,
+
+		#[arg(long)]
+		limit: Option<usize>,
+	},
+
 	/// - Force joins a specified list of local users to join the specified
 	///   room.
 	///
@@ -153,4 +245,26 @@ pub(super) enum UserCommand {
 		#[arg(long)]
 		yes_i_want_to_do_this: bool,
 	},
+
+	/// - Send a server notice to a local user, or to every local user with
+	///   `all`
+	///
+	/// Notices are posted by the server notices user in a room of their own
+	/// for each user, which is created on demand.
+	Notice {
+		/// A local user, or `all`
+		target: String,
+
+		message: Vec<String>,
+	},
+
+	/// - Export all data of a local user to the configured `user_export_path`
+	///
+	/// The export contains the user's profile, account data, room
+	/// memberships, sent events, uploaded media, devices, pushers and room key
+	/// backup metadata, as JSON lines files along with the media files. The
+	/// export runs in the background.
+	Export {
+		user_id: String,
+	},
 }
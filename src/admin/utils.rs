@@ -1,5 +1,6 @@
 use conduwuit_core::{Err, Result, err};
 use ruma::{OwnedRoomId, OwnedUserId, RoomId, UserId};
+use serde_json::{Value, json};
 use service::Services;
 
 pub(crate) fn escape_html(s: &str) -> String {
@@ -29,6 +30,21 @@ pub(crate) async fn get_room_info(
 	)
 }
 
+/// Structured form of the room listings from [`get_room_info`], for
+/// `--format json`.
+pub(crate) fn rooms_json(rooms: &[(OwnedRoomId, u64, String)]) -> Vec<Value> {
+	rooms
+		.iter()
+		.map(|(room_id, joined_members, name)| {
+			json!({
+				"room_id": room_id,
+				"joined_members": joined_members,
+				"name": name,
+			})
+		})
+		.collect()
+}
+
 /// Parses user ID
 pub(crate) fn parse_user_id(services: &Services, user_id: &str) -> Result<OwnedUserId> {
 	UserId::parse_with_server_name(user_id.to_lowercase(), services.globals.server_name())
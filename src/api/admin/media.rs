@@ -0,0 +1,106 @@
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::IntoResponse,
+};
+use conduwuit::{Err, Result, warn};
+use ruma::{Mxc, ServerName};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{Admin, Query, paginate, users::parse_local_user_id};
+
+#[derive(Deserialize)]
+pub(crate) struct ListMediaQuery {
+	#[serde(default)]
+	from: usize,
+	limit: Option<usize>,
+}
+
+/// # `GET /_synapse/admin/v1/users/{userId}/media`
+///
+/// Lists the media uploaded by a local user.
+pub(crate) async fn list_user_media_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Path(user_id): Path<String>,
+	Query(query): Query<ListMediaQuery>,
+) -> Result<impl IntoResponse> {
+	let user_id = parse_local_user_id(&services, &user_id).await?;
+
+	let mxcs = services.media.get_all_user_mxcs(&user_id).await;
+	let total = mxcs.len();
+	let (mxcs, next_token) = paginate(mxcs, query.from, query.limit);
+
+	let mut media = Vec::with_capacity(mxcs.len());
+	for mxc in &mxcs {
+		let Ok(parsed) = Mxc::try_from(mxc.as_str()) else {
+			continue;
+		};
+
+		let meta = services.media.get_metadata(&parsed).await;
+		let upload_name = meta
+			.as_ref()
+			.and_then(|meta| meta.content_disposition.as_ref())
+			.and_then(|disposition| disposition.filename.clone());
+
+		media.push(json!({
+			"media_id": parsed.media_id,
+			"media_type": meta.and_then(|meta| meta.content_type),
+			"upload_name": upload_name,
+			"quarantined_by": services.media.quarantined_by(&parsed).await.ok(),
+		}));
+	}
+
+	Ok(Json(json!({
+		"media": media,
+		"next_token": next_token,
+		"total": total,
+	})))
+}
+
+/// # `POST /_synapse/admin/v1/media/quarantine/{serverName}/{mediaId}`
+///
+/// Quarantines media, so that it is no longer served to anyone.
+pub(crate) async fn quarantine_media_route(
+	State(services): State<crate::State>,
+	Admin(sender_user): Admin,
+	Path((server_name, media_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+	let Ok(server_name) = ServerName::parse(&server_name) else {
+		return Err!(Request(InvalidParam("Invalid server name.")));
+	};
+
+	let mxc = Mxc {
+		server_name: &server_name,
+		media_id: &media_id,
+	};
+
+	warn!(%sender_user, %mxc, "Quarantining media through the admin API");
+	services.media.quarantine(&mxc, &sender_user);
+
+	Ok(Json(json!({})))
+}
+
+/// # `POST /_synapse/admin/v1/media/unquarantine/{serverName}/{mediaId}`
+///
+/// Lifts the quarantine of media.
+pub(crate) async fn unquarantine_media_route(
+	State(services): State<crate::State>,
+	Admin(sender_user): Admin,
+	Path((server_name, media_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+	let Ok(server_name) = ServerName::parse(&server_name) else {
+		return Err!(Request(InvalidParam("Invalid server name.")));
+	};
+
+	let mxc = Mxc {
+		server_name: &server_name,
+		media_id: &media_id,
+	};
+
+	warn!(%sender_user, %mxc, "Lifting quarantine of media through the admin API");
+	services.media.unquarantine(&mxc);
+
+	Ok(Json(json!({})))
+}
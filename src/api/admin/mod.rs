@@ -0,0 +1,132 @@
+//! Subset of the Synapse admin API, for use with existing moderation tools and
+//! admin dashboards. Enabled with `allow_synapse_admin_api`.
+//!
+//! Every endpoint maps onto the same service calls as the equivalent admin
+//! room command.
+
+mod media;
+mod reports;
+mod rooms;
+mod server;
+mod users;
+
+use async_trait::async_trait;
+use axum::{RequestPartsExt, extract::FromRequestParts};
+use axum_extra::{
+	TypedHeader,
+	headers::{Authorization, authorization::Bearer},
+};
+use conduwuit::{Err, Error, Result, err, info};
+use http::{Method, request::Parts};
+use ruma::{OwnedUserId, api::client::error::ErrorKind};
+use serde::de::DeserializeOwned;
+
+pub(super) use self::{media::*, reports::*, rooms::*, server::*, users::*};
+use crate::State;
+
+/// Default number of entries returned by the list endpoints.
+const DEFAULT_LIMIT: usize = 100;
+
+/// A server admin authenticated with their access token.
+pub(crate) struct Admin(pub(crate) OwnedUserId);
+
+/// Query string of a request, parsed with `serde_html_form` so that errors
+/// are returned as Matrix errors.
+pub(crate) struct Query<T>(pub(crate) T);
+
+#[async_trait]
+impl FromRequestParts<State> for Admin {
+	type Rejection = Error;
+
+	async fn from_request_parts(parts: &mut Parts, services: &State) -> Result<Self> {
+		let bearer: Option<TypedHeader<Authorization<Bearer>>> = parts.extract().await?;
+		let Some(TypedHeader(Authorization(bearer))) = bearer else {
+			return Err(Error::BadRequest(ErrorKind::MissingToken, "Missing access token."));
+		};
+
+		let found = if services.oauth.is_delegated() {
+			services.oauth.find_from_token(bearer.token()).await?
+		} else {
+			services.users.find_from_token(bearer.token()).await.ok()
+		};
+
+		let Some((user_id, device_id)) = found else {
+			return Err(Error::BadRequest(
+				ErrorKind::UnknownToken { soft_logout: false },
+				"Unknown access token.",
+			));
+		};
+
+		if services
+			.users
+			.admin_issued_token(&user_id, &device_id)
+			.await
+			.is_ok()
+		{
+			return Err!(Request(Forbidden(
+				"Admin-issued access tokens cannot use the admin API."
+			)));
+		}
+
+		let restrictions = services.users.restrictions(&user_id).await;
+		if restrictions.locked {
+			return Err!(Request(UserLocked("This account has been locked.")));
+		}
+
+		// Suspended admins may still look, but not change anything.
+		if restrictions.suspended && parts.method != Method::GET {
+			return Err!(Request(UserSuspended("This account has been suspended.")));
+		}
+
+		if !services.users.is_admin(&user_id).await {
+			return Err!(Request(Forbidden("You are not a server admin.")));
+		}
+
+		info!(
+			%user_id,
+			method = %parts.method,
+			path = %parts.uri.path(),
+			"Admin API request"
+		);
+
+		Ok(Self(user_id))
+	}
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+	T: DeserializeOwned,
+	S: Send + Sync,
+{
+	type Rejection = Error;
+
+	async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self> {
+		let query = parts.uri.query().unwrap_or_default();
+
+		serde_html_form::from_str(query)
+			.map(Self)
+			.map_err(|e| err!(Request(InvalidParam("Invalid query string: {e}"))))
+	}
+}
+
+/// Parses a JSON request body. An empty body is accepted for endpoints whose
+/// parameters are all optional.
+fn json_body<T: DeserializeOwned + Default>(body: &[u8]) -> Result<T> {
+	if body.is_empty() {
+		return Ok(T::default());
+	}
+
+	serde_json::from_slice(body).map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))
+}
+
+/// Returns the requested page of `items`, along with the offset of the next
+/// page if there is one.
+fn paginate<T>(items: Vec<T>, from: usize, limit: Option<usize>) -> (Vec<T>, Option<usize>) {
+	let limit = limit.unwrap_or(DEFAULT_LIMIT);
+	let next = from.saturating_add(limit);
+	let next = (next < items.len()).then_some(next);
+	let page = items.into_iter().skip(from).take(limit).collect();
+
+	(page, next)
+}
@@ -0,0 +1,84 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use conduwuit::{Result, utils::ReadyExt};
+use futures::StreamExt;
+use ruma::{OwnedRoomId, OwnedUserId};
+use serde::Deserialize;
+use serde_json::json;
+use service::reports::Report;
+
+use super::{Admin, Query, paginate};
+
+#[derive(Deserialize)]
+pub(crate) struct ListEventReportsQuery {
+	#[serde(default)]
+	from: usize,
+	limit: Option<usize>,
+	room_id: Option<OwnedRoomId>,
+	user_id: Option<OwnedUserId>,
+}
+
+/// # `GET /_synapse/admin/v1/event_reports`
+///
+/// Lists event reports made by local users, newest first. `room_id` and
+/// `user_id` (the reporter) filter the reports.
+pub(crate) async fn list_event_reports_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Query(query): Query<ListEventReportsQuery>,
+) -> Result<impl IntoResponse> {
+	let reports: Vec<Report> = services
+		.reports
+		.reports()
+		.ready_filter(|report| {
+			report.event_id.is_some()
+				&& query
+					.room_id
+					.as_ref()
+					.is_none_or(|room_id| *room_id == report.room_id)
+				&& query
+					.user_id
+					.as_ref()
+					.is_none_or(|user_id| *user_id == report.reporter)
+		})
+		.collect()
+		.await;
+
+	let total = reports.len();
+	let (reports, next_token) = paginate(reports, query.from, query.limit);
+
+	let mut event_reports = Vec::with_capacity(reports.len());
+	for report in reports {
+		let Some(event_id) = report.event_id else {
+			continue;
+		};
+
+		let state_accessor = &services.rooms.state_accessor;
+		let sender = services
+			.rooms
+			.timeline
+			.get_pdu(&event_id)
+			.await
+			.map(|pdu| pdu.sender)
+			.ok();
+
+		event_reports.push(json!({
+			"id": report.id,
+			"received_ts": report.received_ts,
+			"room_id": report.room_id,
+			"name": state_accessor.get_name(&report.room_id).await.ok(),
+			"canonical_alias": state_accessor.get_canonical_alias(&report.room_id).await.ok(),
+			"event_id": event_id,
+			"user_id": report.reporter,
+			"sender": sender,
+			"reason": report.reason,
+			"score": report.score,
+			"state": report.state,
+		}));
+	}
+
+	Ok(Json(json!({
+		"event_reports": event_reports,
+		"next_token": next_token,
+		"total": total,
+	})))
+}
@@ -0,0 +1,163 @@
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::IntoResponse,
+};
+use bytes::Bytes;
+use conduwuit::{Err, Result, warn};
+use futures::StreamExt;
+use ruma::{OwnedRoomId, OwnedUserId, RoomId};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use service::Services;
+
+use super::{Admin, Query, json_body, paginate};
+use crate::client::{EvacuatedRoom, evacuate_room};
+
+#[derive(Deserialize)]
+pub(crate) struct ListRoomsQuery {
+	#[serde(default)]
+	from: usize,
+	limit: Option<usize>,
+}
+
+#[derive(Default, Deserialize)]
+pub(crate) struct DeleteRoomBody {
+	#[serde(default)]
+	block: bool,
+}
+
+/// # `GET /_synapse/admin/v1/rooms`
+///
+/// Lists the rooms known to the server.
+pub(crate) async fn list_rooms_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Query(query): Query<ListRoomsQuery>,
+) -> Result<impl IntoResponse> {
+	let rooms: Vec<OwnedRoomId> = services
+		.rooms
+		.metadata
+		.iter_ids()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let total = rooms.len();
+	let (rooms, next_batch) = paginate(rooms, query.from, query.limit);
+
+	let mut entries = Vec::with_capacity(rooms.len());
+	for room_id in &rooms {
+		entries.push(room_details(&services, room_id).await);
+	}
+
+	Ok(Json(json!({
+		"rooms": entries,
+		"offset": query.from,
+		"total_rooms": total,
+		"next_batch": next_batch,
+	})))
+}
+
+/// # `GET /_synapse/admin/v1/rooms/{roomId}`
+///
+/// Returns the details of a room.
+pub(crate) async fn get_room_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Path(room_id): Path<String>,
+) -> Result<impl IntoResponse> {
+	let room_id = parse_room_id(&services, &room_id).await?;
+
+	Ok(Json(room_details(&services, &room_id).await))
+}
+
+/// # `GET /_synapse/admin/v1/rooms/{roomId}/members`
+///
+/// Lists the joined members of a room.
+pub(crate) async fn get_room_members_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Path(room_id): Path<String>,
+) -> Result<impl IntoResponse> {
+	let room_id = parse_room_id(&services, &room_id).await?;
+
+	let members: Vec<OwnedUserId> = services
+		.rooms
+		.state_cache
+		.room_members(&room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	Ok(Json(json!({
+		"total": members.len(),
+		"members": members,
+	})))
+}
+
+/// # `DELETE /_synapse/admin/v1/rooms/{roomId}`
+///
+/// Makes all local users leave the room, removes its local aliases and
+/// directory listing, and disables federation with it. With `block`, the room
+/// is also banned so that local users cannot join it again.
+pub(crate) async fn delete_room_route(
+	State(services): State<crate::State>,
+	Admin(sender_user): Admin,
+	Path(room_id): Path<String>,
+	body: Bytes,
+) -> Result<impl IntoResponse> {
+	let room_id = parse_room_id(&services, &room_id).await?;
+	let body: DeleteRoomBody = json_body(&body)?;
+
+	if services
+		.admin
+		.get_admin_room()
+		.await
+		.is_ok_and(|admin_room_id| admin_room_id == room_id)
+	{
+		return Err!(Request(Forbidden("Not allowed to delete the admin room.")));
+	}
+
+	warn!(%sender_user, %room_id, block = body.block, "Deleting room through the admin API");
+
+	let EvacuatedRoom {
+		kicked_users,
+		failed_to_kick_users,
+		local_aliases,
+	} = evacuate_room(&services, &room_id, body.block).await;
+
+	Ok(Json(json!({
+		"kicked_users": kicked_users,
+		"failed_to_kick_users": failed_to_kick_users,
+		"local_aliases": local_aliases,
+		"new_room_id": null,
+	})))
+}
+
+async fn parse_room_id(services: &Services, room_id: &str) -> Result<OwnedRoomId> {
+	let Ok(room_id) = RoomId::parse(room_id) else {
+		return Err!(Request(InvalidParam("Invalid room ID.")));
+	};
+
+	if !services.rooms.metadata.exists(&room_id).await {
+		return Err!(Request(NotFound("Room not found.")));
+	}
+
+	Ok(room_id)
+}
+
+async fn room_details(services: &Services, room_id: &RoomId) -> Value {
+	let rooms = &services.rooms;
+
+	json!({
+		"room_id": room_id,
+		"name": rooms.state_accessor.get_name(room_id).await.ok(),
+		"canonical_alias": rooms.state_accessor.get_canonical_alias(room_id).await.ok(),
+		"joined_members": rooms.state_cache.room_joined_count(room_id).await.unwrap_or(0),
+		"version": rooms.state.get_room_version(room_id).await.ok(),
+		"public": rooms.directory.is_public_room(room_id).await,
+		"federatable": !rooms.metadata.is_disabled(room_id).await,
+		"blocked": rooms.metadata.is_banned(room_id).await,
+	})
+}
@@ -0,0 +1,45 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use conduwuit::Result;
+use serde_json::{Value, json};
+
+use super::Admin;
+
+/// # `GET /_synapse/admin/v1/server_version`
+///
+/// Returns the name and version of the server, for tools which check what
+/// they are talking to. Like on Synapse, this does not require authentication.
+pub(crate) async fn get_server_version_route() -> Result<impl IntoResponse> {
+	Ok(Json(json!({
+		"server_version": format!(
+			"{} {}",
+			conduwuit::version::name(),
+			conduwuit::version::version()
+		),
+	})))
+}
+
+/// # `GET /_synapse/admin/v1/registration_tokens`
+///
+/// Lists the registration token from the config. There is only ever one, and
+/// it has no usage limit or expiry.
+pub(crate) async fn list_registration_tokens_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+) -> Result<impl IntoResponse> {
+	let tokens: Vec<Value> = services
+		.globals
+		.registration_token
+		.iter()
+		.map(|token| {
+			json!({
+				"token": token,
+				"uses_allowed": null,
+				"pending": 0,
+				"completed": 0,
+				"expiry_time": null,
+			})
+		})
+		.collect();
+
+	Ok(Json(json!({ "registration_tokens": tokens })))
+}
@@ -0,0 +1,202 @@
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::IntoResponse,
+};
+use bytes::Bytes;
+use conduwuit::{Err, Result, utils::ReadyExt, warn};
+use futures::StreamExt;
+use ruma::{OwnedDeviceId, OwnedUserId, UserId};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use service::Services;
+
+use super::{Admin, Query, json_body, paginate};
+use crate::client::admin_deactivate_user;
+
+#[derive(Deserialize)]
+pub(crate) struct ListUsersQuery {
+	#[serde(default)]
+	from: usize,
+	limit: Option<usize>,
+	name: Option<String>,
+	#[serde(default)]
+	deactivated: bool,
+}
+
+#[derive(Default, Deserialize)]
+pub(crate) struct DeactivateBody {
+	#[serde(default)]
+	erase: bool,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ResetPasswordBody {
+	new_password: String,
+	logout_devices: Option<bool>,
+}
+
+/// # `GET /_synapse/admin/v2/users`
+///
+/// Lists the local users, excluding deactivated users unless `deactivated` is
+/// set. `name` filters on a part of the user ID.
+pub(crate) async fn list_users_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Query(query): Query<ListUsersQuery>,
+) -> Result<impl IntoResponse> {
+	let include_deactivated = query.deactivated;
+	let users: Vec<OwnedUserId> = services
+		.users
+		.stream()
+		.ready_filter(|user_id| services.globals.user_is_local(user_id))
+		.ready_filter(|user_id| {
+			query
+				.name
+				.as_deref()
+				.is_none_or(|name| user_id.as_str().contains(name))
+		})
+		.map(ToOwned::to_owned)
+		.filter_map(|user_id| async move {
+			let deactivated = services
+				.users
+				.is_deactivated(&user_id)
+				.await
+				.unwrap_or(false);
+
+			(include_deactivated || !deactivated).then_some(user_id)
+		})
+		.collect()
+		.await;
+
+	let total = users.len();
+	let (users, next_token) = paginate(users, query.from, query.limit);
+
+	let mut entries = Vec::with_capacity(users.len());
+	for user_id in &users {
+		entries.push(user_details(&services, user_id).await);
+	}
+
+	Ok(Json(json!({
+		"users": entries,
+		"next_token": next_token.map(|next| next.to_string()),
+		"total": total,
+	})))
+}
+
+/// # `GET /_synapse/admin/v2/users/{userId}`
+///
+/// Returns the details of a local user.
+pub(crate) async fn get_user_route(
+	State(services): State<crate::State>,
+	Admin(_): Admin,
+	Path(user_id): Path<String>,
+) -> Result<impl IntoResponse> {
+	let user_id = parse_local_user_id(&services, &user_id).await?;
+
+	Ok(Json(user_details(&services, &user_id).await))
+}
+
+/// # `POST /_synapse/admin/v1/deactivate/{userId}`
+///
+/// Deactivates a local user and makes them leave all rooms. With `erase`,
+/// their events, media and account data are erased first.
+pub(crate) async fn deactivate_user_route(
+	State(services): State<crate::State>,
+	Admin(sender_user): Admin,
+	Path(user_id): Path<String>,
+	body: Bytes,
+) -> Result<impl IntoResponse> {
+	let user_id = parse_local_user_id(&services, &user_id).await?;
+	let body: DeactivateBody = json_body(&body)?;
+
+	if user_id == services.globals.server_user {
+		return Err!(Request(Forbidden("Not allowed to deactivate the server service account.")));
+	}
+
+	warn!(%sender_user, %user_id, erase = body.erase, "Deactivating user through the admin API");
+
+	admin_deactivate_user(&services, &user_id, body.erase, true).await?;
+
+	Ok(Json(json!({ "id_server_unbind_result": "success" })))
+}
+
+/// # `POST /_synapse/admin/v1/reset_password/{userId}`
+///
+/// Sets a new password for a local user, and logs out all of their devices
+/// unless `logout_devices` is false.
+pub(crate) async fn reset_password_route(
+	State(services): State<crate::State>,
+	Admin(sender_user): Admin,
+	Path(user_id): Path<String>,
+	body: Bytes,
+) -> Result<impl IntoResponse> {
+	let user_id = parse_local_user_id(&services, &user_id).await?;
+	let body: ResetPasswordBody = json_body(&body)?;
+
+	if user_id == services.globals.server_user {
+		return Err!(Request(Forbidden(
+			"Not allowed to set the password for the server account. Please use the emergency \
+			 password config option."
+		)));
+	}
+
+	services
+		.users
+		.set_password(&user_id, Some(&body.new_password))?;
+
+	if body.logout_devices.unwrap_or(true) {
+		let devices: Vec<OwnedDeviceId> = services
+			.users
+			.all_device_ids(&user_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		for device_id in &devices {
+			services.users.remove_device(&user_id, device_id).await;
+		}
+	}
+
+	warn!(%sender_user, %user_id, "Reset password of user through the admin API");
+
+	Ok(Json(json!({})))
+}
+
+/// Parses a user ID from the request path, which must belong to an existing
+/// local user.
+pub(super) async fn parse_local_user_id(
+	services: &Services,
+	user_id: &str,
+) -> Result<OwnedUserId> {
+	let Ok(user_id) = UserId::parse(user_id) else {
+		return Err!(Request(InvalidParam("Invalid user ID.")));
+	};
+
+	if !services.globals.user_is_local(&user_id) {
+		return Err!(Request(InvalidParam("Can only look up local users.")));
+	}
+
+	if !services.users.exists(&user_id).await {
+		return Err!(Request(NotFound("User not found.")));
+	}
+
+	Ok(user_id)
+}
+
+async fn user_details(services: &Services, user_id: &UserId) -> Value {
+	let users = &services.users;
+
+	json!({
+		"name": user_id,
+		"displayname": users.displayname(user_id).await.ok(),
+		"avatar_url": users.avatar_url(user_id).await.ok(),
+		"admin": users.is_admin(user_id).await,
+		"deactivated": users.is_deactivated(user_id).await.unwrap_or(false),
+		"shadow_banned": users.is_shadow_banned(user_id).await,
+		"locked": users.is_locked(user_id).await,
+		"suspended": users.is_suspended(user_id).await,
+		"is_guest": false,
+		"user_type": null,
+	})
+}
@@ -3,13 +3,13 @@
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
 use conduwuit::{
-	Err, Error, Result, debug_info, err, error, info, is_equal_to,
+	Err, Error, Result, Server, debug_info, err, error, info, is_equal_to,
 	matrix::pdu::PduBuilder,
 	utils,
 	utils::{ReadyExt, stream::BroadbandExt},
 	warn,
 };
-use conduwuit_service::Services;
+use conduwuit_service::{Services, rooms::timeline};
 use futures::{FutureExt, StreamExt};
 use register::RegistrationKind;
 use ruma::{
@@ -56,6 +56,10 @@ pub(crate) async fn get_register_available_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_username_availability::v3::Request>,
 ) -> Result<get_username_availability::v3::Response> {
+	if body.appservice_info.is_none() {
+		services.oauth.check_local_auth()?;
+	}
+
 	// workaround for https://github.com/matrix-org/matrix-appservice-irc/issues/1780 due to inactivity of fixing the issue
 	let is_matrix_appservice_irc = body.appservice_info.as_ref().is_some_and(|appservice| {
 		appservice.registration.id == "irc"
@@ -104,7 +108,7 @@ pub(crate) async fn get_register_available_route(
 		};
 
 	// Check if username is creative enough
-	if services.users.exists(&user_id).await {
+	if services.users.exists(&user_id).await || services.server_notices.is_system_user(&user_id) {
 		return Err!(Request(UserInUse("User ID is not available.")));
 	}
 
@@ -145,6 +149,10 @@ pub(crate) async fn register_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<register::v3::Request>,
 ) -> Result<register::v3::Response> {
+	if body.appservice_info.is_none() {
+		services.oauth.check_local_auth()?;
+	}
+
 	let is_guest = body.kind == RegistrationKind::Guest;
 	let emergency_mode_enabled = services.config.emergency_password.is_some();
 
@@ -240,7 +248,9 @@ pub(crate) async fn register_route(
 				},
 			};
 
-			if services.users.exists(&proposed_user_id).await {
+			if services.users.exists(&proposed_user_id).await
+				|| services.server_notices.is_system_user(&proposed_user_id)
+			{
 				return Err!(Request(UserInUse("User ID is not available.")));
 			}
 
@@ -578,6 +588,8 @@ pub(crate) async fn change_password_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<change_password::v3::Request>,
 ) -> Result<change_password::v3::Response> {
+	services.oauth.check_local_auth()?;
+
 	// Authentication for this endpoint was made optional, but we need
 	// authentication currently
 	let sender_user = body
@@ -702,12 +714,16 @@ pub(crate) async fn whoami_route(
 /// - Forgets all to-device events
 /// - Triggers device list updates
 /// - Removes ability to log in again
+/// - If `erase` is set, deletes the user's media and account data and redacts
+///   all messages they sent in the background
 #[tracing::instrument(skip_all, fields(%client), name = "deactivate")]
 pub(crate) async fn deactivate_route(
 	State(services): State<crate::State>,
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<deactivate::v3::Request>,
 ) -> Result<deactivate::v3::Response> {
+	services.oauth.check_local_auth()?;
+
 	// Authentication for this endpoint was made optional, but we need
 	// authentication currently
 	let sender_user = body
@@ -751,6 +767,10 @@ pub(crate) async fn deactivate_route(
 		},
 	}
 
+	if body.erase {
+		full_user_erase(&services, sender_user).await?;
+	}
+
 	// Remove profile pictures and display name
 	let all_joined_rooms: Vec<OwnedRoomId> = services
 		.rooms
@@ -765,13 +785,14 @@ pub(crate) async fn deactivate_route(
 
 	full_user_deactivate(&services, sender_user, &all_joined_rooms).await?;
 
-	info!("User {sender_user} deactivated their account.");
+	let erased = if body.erase { " and erased their data" } else { "" };
+	info!("User {sender_user} deactivated their account{erased}.");
 
 	if services.server.config.admin_room_notices {
 		services
 			.admin
 			.send_message(RoomMessageEventContent::notice_plain(format!(
-				"User {sender_user} deactivated their account."
+				"User {sender_user} deactivated their account{erased}."
 			)))
 			.await
 			.ok();
@@ -824,13 +845,12 @@ pub(crate) async fn request_3pid_management_token_via_msisdn_route(
 /// # `GET /_matrix/client/v1/register/m.login.registration_token/validity`
 ///
 /// Checks if the provided registration token is valid at the time of checking
-///
-/// Currently does not have any ratelimiting, and this isn't very practical as
-/// there is only one registration token allowed.
 pub(crate) async fn check_registration_token_validity(
 	State(services): State<crate::State>,
 	body: Ruma<check_registration_token_validity::v1::Request>,
 ) -> Result<check_registration_token_validity::v1::Response> {
+	services.oauth.check_local_auth()?;
+
 	let Some(reg_token) = services.globals.registration_token.clone() else {
 		return Err!(Request(Forbidden("Server does not allow token registration")));
 	};
@@ -919,3 +939,144 @@ pub async fn full_user_deactivate(
 
 	Ok(())
 }
+
+/// Deactivates a local user on behalf of a server admin. The user is erased
+/// first if `erase` is set. Unless `leave_rooms` is false, their profile is
+/// removed and they leave all rooms as by [`full_user_deactivate`].
+pub async fn admin_deactivate_user(
+	services: &Services,
+	user_id: &UserId,
+	erase: bool,
+	leave_rooms: bool,
+) -> Result<()> {
+	if erase {
+		full_user_erase(services, user_id).await?;
+	}
+
+	services.users.deactivate_account(user_id).await?;
+
+	if leave_rooms {
+		let all_joined_rooms: Vec<OwnedRoomId> = services
+			.rooms
+			.state_cache
+			.rooms_joined(user_id)
+			.map(Into::into)
+			.collect()
+			.await;
+
+		full_user_deactivate(services, user_id, &all_joined_rooms).await?;
+	}
+
+	Ok(())
+}
+
+/// Erases a user's data as requested by `erase: true` on deactivation (GDPR
+/// erasure). This must run before [`full_user_deactivate`], which forgets the
+/// rooms the user was in.
+///
+/// - Marks the user as erased, hiding their events from anyone who was not
+///   joined to the room when they were sent
+/// - Redacts every message event the user sent in rooms they are or were joined
+///   to, in the background
+/// - Deletes all media uploaded by the user
+/// - Deletes all global and room account data
+/// - Removes the display name, avatar and all other profile data, which would
+///   otherwise survive a deactivation that does not leave rooms
+pub async fn full_user_erase(services: &Services, user_id: &UserId) -> Result<()> {
+	services.users.mark_erased(user_id);
+
+	let rooms_joined: Vec<OwnedRoomId> = services
+		.rooms
+		.state_cache
+		.rooms_joined(user_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	super::update_displayname(services, user_id, None, &rooms_joined).await;
+	super::update_avatar_url(services, user_id, None, None, &rooms_joined).await;
+
+	services
+		.users
+		.all_profile_keys(user_id)
+		.ready_for_each(|(profile_key, _)| {
+			services.users.set_profile_key(user_id, &profile_key, None);
+		})
+		.await;
+
+	let rooms_left: Vec<OwnedRoomId> = services
+		.rooms
+		.state_cache
+		.rooms_left(user_id)
+		.map(|(r, _)| r)
+		.collect()
+		.await;
+
+	let all_rooms: Vec<OwnedRoomId> = rooms_joined.into_iter().chain(rooms_left).collect();
+
+	for room_id in &all_rooms {
+		services
+			.account_data
+			.delete_all(Some(room_id), user_id)
+			.await;
+	}
+
+	services.account_data.delete_all(None, user_id).await;
+
+	let media_count = services
+		.media
+		.delete_from_user(user_id)
+		.await
+		.inspect_err(|e| warn!(%user_id, "Failed to delete user's media: {e}"))
+		.unwrap_or(0);
+
+	info!(
+		"Erased {user_id}: deleted {media_count} media files; redacting their events in {} \
+		 rooms in the background",
+		all_rooms.len()
+	);
+
+	// Scanning the timelines takes long in large rooms, so it is left to a task of
+	// its own rather than holding up the deactivation.
+	let server = services.server.clone();
+	let timeline = services.rooms.timeline.clone();
+	let user_id = user_id.to_owned();
+	services.server.runtime().spawn(async move {
+		redact_user_events(&server, &timeline, &user_id, &all_rooms).await;
+	});
+
+	Ok(())
+}
+
+/// Redacts the events the user sent in `rooms`, logging the progress after
+/// every room.
+async fn redact_user_events(
+	server: &Server,
+	timeline: &timeline::Service,
+	user_id: &UserId,
+	rooms: &[OwnedRoomId],
+) {
+	let mut redaction_count: usize = 0;
+	for (i, room_id) in rooms.iter().enumerate() {
+		if !server.running() {
+			warn!(%user_id, "Stopped erasing user's events at shutdown");
+			return;
+		}
+
+		let count = timeline
+			.redact_sender(user_id, room_id, "User data was erased upon account deactivation")
+			.await
+			.inspect_err(|e| warn!(%room_id, %user_id, "Failed to erase user's events: {e}"))
+			.unwrap_or(0);
+
+		redaction_count = redaction_count.saturating_add(count);
+		debug_info!(
+			%room_id,
+			"Redacted {count} events of {user_id} ({}/{})",
+			i.saturating_add(1),
+			rooms.len()
+		);
+	}
+
+	info!("Erased {user_id}: redacted {redaction_count} events in {} rooms", rooms.len());
+}
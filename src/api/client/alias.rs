@@ -45,6 +45,11 @@ pub(crate) async fn create_alias_route(
 		return Err!(Conflict("Alias already exists."));
 	}
 
+	// Shadow-banned users are told the alias was created
+	if services.users.is_shadow_banned(sender_user).await {
+		return Ok(create_alias::v3::Response::new());
+	}
+
 	services
 		.rooms
 		.alias
@@ -12,7 +12,7 @@
 use conduwuit_service::rooms::{lazy_loading, lazy_loading::Options, short::ShortStateKey};
 use futures::{
 	FutureExt, StreamExt, TryFutureExt, TryStreamExt,
-	future::{OptionFuture, join, join3, try_join3},
+	future::{OptionFuture, join, join3, try_join},
 };
 use ruma::{OwnedEventId, UserId, api::client::context::get_context, events::StateEventType};
 
@@ -63,18 +63,18 @@ pub(crate) async fn get_context_route(
 		.get_pdu(event_id)
 		.map_err(|_| err!(Request(NotFound("Base event not found."))));
 
-	let visible = services
-		.rooms
-		.state_accessor
-		.user_can_see_event(sender_user, room_id, event_id)
-		.map(Ok);
-
-	let (base_id, base_pdu, visible) = try_join3(base_id, base_pdu, visible).await?;
+	let (base_id, base_pdu) = try_join(base_id, base_pdu).await?;
 
 	if base_pdu.room_id != *room_id || base_pdu.event_id != *event_id {
 		return Err!(Request(NotFound("Base event not found.")));
 	}
 
+	let visible = services
+		.rooms
+		.state_accessor
+		.user_can_see_event(sender_user, &base_pdu)
+		.await;
+
 	if !visible {
 		debug_warn!(req_evt = ?event_id, ?base_id, ?room_id, "Event requested by {sender_user} but is not allowed to see it, returning 404");
 		return Err!(Request(NotFound("Event not found.")));
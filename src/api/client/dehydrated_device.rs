@@ -0,0 +1,128 @@
+use axum::extract::State;
+use conduwuit::{Err, Result, err};
+use futures::StreamExt;
+use ruma::api::client::dehydrated_device::{
+	delete_dehydrated_device::unstable as delete_dehydrated_device,
+	get_dehydrated_device::unstable as get_dehydrated_device, get_events::unstable as get_events,
+	put_dehydrated_device::unstable as put_dehydrated_device,
+};
+
+use crate::Ruma;
+
+/// Maximum number of to-device events returned per request to the dehydrated
+/// device events endpoint.
+const MAX_BATCH_EVENTS: usize = 100;
+
+/// # `PUT /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Creates or replaces the dehydrated device of the sender user.
+pub(crate) async fn put_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<put_dehydrated_device::Request>,
+) -> Result<put_dehydrated_device::Response> {
+	let sender_user = body
+		.sender_user
+		.as_deref()
+		.expect("user must be authenticated for this handler");
+
+	let device_id = body.body.device_id.clone();
+
+	services
+		.users
+		.set_dehydrated_device(sender_user, body.body)
+		.await?;
+
+	Ok(put_dehydrated_device::Response { device_id })
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Gets the dehydrated device of the sender user.
+pub(crate) async fn get_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_dehydrated_device::Request>,
+) -> Result<get_dehydrated_device::Response> {
+	let sender_user = body.sender_user();
+
+	let device = services
+		.users
+		.get_dehydrated_device(sender_user)
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device found."))))?;
+
+	Ok(get_dehydrated_device::Response {
+		device_id: device.device_id,
+		device_data: device.device_data,
+	})
+}
+
+/// # `DELETE /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Deletes the dehydrated device of the sender user.
+pub(crate) async fn delete_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_dehydrated_device::Request>,
+) -> Result<delete_dehydrated_device::Response> {
+	let sender_user = body.sender_user();
+
+	let device_id = services
+		.users
+		.remove_dehydrated_device(sender_user)
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device found."))))?;
+
+	Ok(delete_dehydrated_device::Response { device_id })
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device/{deviceId}/events`
+///
+/// Paginates the to-device events queued for the dehydrated device of the
+/// sender user. Events before `next_batch` are considered received and are
+/// removed from the queue.
+pub(crate) async fn get_dehydrated_events_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_events::Request>,
+) -> Result<get_events::Response> {
+	let sender_user = body.sender_user();
+
+	let device_id = services
+		.users
+		.get_dehydrated_device_id(sender_user)
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device found."))))?;
+
+	if device_id != body.device_id {
+		return Err!(Request(Forbidden("Not the dehydrated device of this user.")));
+	}
+
+	let since: Option<u64> = body
+		.next_batch
+		.as_deref()
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid next_batch token."))))?;
+
+	if let Some(since) = since {
+		services
+			.users
+			.remove_to_device_events(sender_user, &device_id, since)
+			.await;
+	}
+
+	let mut next_batch = since;
+	let events = services
+		.users
+		.get_to_device_events_with_count(sender_user, &device_id, since)
+		.take(MAX_BATCH_EVENTS)
+		.map(|(count, event)| {
+			next_batch = Some(count);
+			event
+		})
+		.collect()
+		.await;
+
+	Ok(get_events::Response {
+		events,
+		next_batch: next_batch.as_ref().map(ToString::to_string),
+	})
+}
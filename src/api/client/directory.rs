@@ -1,21 +1,9 @@
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
-use conduwuit::{
-	Err, Result, err, info,
-	utils::{
-		TryFutureExtExt,
-		math::Expected,
-		result::FlatOk,
-		stream::{ReadyExt, WidebandExt},
-	},
-};
+use conduwuit::{Err, Result, err, info};
 use conduwuit_service::Services;
-use futures::{
-	FutureExt, StreamExt, TryFutureExt,
-	future::{join, join4, join5},
-};
 use ruma::{
-	OwnedRoomId, RoomId, ServerName, UInt, UserId,
+	RoomId, ServerName, UInt, UserId,
 	api::{
 		client::{
 			directory::{
@@ -26,15 +14,11 @@
 		},
 		federation,
 	},
-	directory::{Filter, PublicRoomJoinRule, PublicRoomsChunk, RoomNetwork, RoomTypeFilter},
+	directory::{Filter, RoomNetwork},
 	events::{
 		StateEventType,
-		room::{
-			join_rules::{JoinRule, RoomJoinRulesEventContent},
-			power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
-		},
+		room::power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
 	},
-	uint,
 };
 
 use crate::Ruma;
@@ -44,6 +28,7 @@
 /// Lists the public rooms on this server.
 ///
 /// - Rooms are ordered by the number of joined members
+/// - Served from an index kept up to date as the rooms' state changes
 #[tracing::instrument(skip_all, fields(%client), name = "publicrooms")]
 pub(crate) async fn get_public_rooms_filtered_route(
 	State(services): State<crate::State>,
@@ -85,6 +70,7 @@ pub(crate) async fn get_public_rooms_filtered_route(
 /// Lists the public rooms on this server.
 ///
 /// - Rooms are ordered by the number of joined members
+/// - Served from an index kept up to date as the rooms' state changes
 #[tracing::instrument(skip_all, fields(%client), name = "publicrooms")]
 pub(crate) async fn get_public_rooms_route(
 	State(services): State<crate::State>,
@@ -152,10 +138,20 @@ pub(crate) async fn set_room_visibility_route(
 		return Err!(Request(Forbidden("Guests cannot publish to room directories")));
 	}
 
+	services
+		.policy
+		.check_room(&body.room_id, "publishing to the room directory")
+		.await?;
+
 	if !user_can_publish_room(&services, sender_user, &body.room_id).await? {
 		return Err!(Request(Forbidden("User is not allowed to publish this room")));
 	}
 
+	// Shadow-banned users are told the visibility changed
+	if services.users.is_shadow_banned(sender_user).await {
+		return Ok(set_room_visibility::v3::Response {});
+	}
+
 	match &body.visibility {
 		| room::Visibility::Public => {
 			if services.server.config.lockdown_public_room_directory
@@ -184,7 +180,7 @@ pub(crate) async fn set_room_visibility_route(
 				)));
 			}
 
-			services.rooms.directory.set_public(&body.room_id);
+			services.rooms.directory.set_public(&body.room_id).await;
 
 			if services.server.config.admin_room_notices {
 				services
@@ -255,7 +251,11 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 			.await?;
 
 		return Ok(get_public_rooms_filtered::v3::Response {
-			chunk: response.chunk,
+			chunk: response
+				.chunk
+				.into_iter()
+				.filter(|chunk| services.policy.match_room(&chunk.room_id).is_none())
+				.collect(),
 			prev_batch: response.prev_batch,
 			next_batch: response.next_batch,
 			total_room_count_estimate: response.total_room_count_estimate,
@@ -264,88 +264,19 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 
 	// Use limit or else 10, with maximum 100
 	let limit: usize = limit.map_or(10_u64, u64::from).try_into()?;
-	let mut num_since: usize = 0;
-
-	if let Some(s) = &since {
-		let mut characters = s.chars();
-		let backwards = match characters.next() {
-			| Some('n') => false,
-			| Some('p') => true,
-			| _ => {
-				return Err!(Request(InvalidParam("Invalid `since` token")));
-			},
-		};
-
-		num_since = characters
-			.collect::<String>()
-			.parse()
-			.map_err(|_| err!(Request(InvalidParam("Invalid `since` token."))))?;
-
-		if backwards {
-			num_since = num_since.saturating_sub(limit);
-		}
-	}
-
-	let mut all_rooms: Vec<PublicRoomsChunk> = services
+	let page = services
 		.rooms
 		.directory
-		.public_rooms()
-		.map(ToOwned::to_owned)
-		.wide_then(|room_id| public_rooms_chunk(services, room_id))
-		.ready_filter_map(|chunk| {
-			if !filter.room_types.is_empty() && !filter.room_types.contains(&RoomTypeFilter::from(chunk.room_type.clone())) {
-				return None;
-			}
-
-			if let Some(query) = filter.generic_search_term.as_ref().map(|q| q.to_lowercase()) {
-				if let Some(name) = &chunk.name {
-					if name.as_str().to_lowercase().contains(&query) {
-						return Some(chunk);
-					}
-				}
-
-				if let Some(topic) = &chunk.topic {
-					if topic.to_lowercase().contains(&query) {
-						return Some(chunk);
-					}
-				}
-
-				if let Some(canonical_alias) = &chunk.canonical_alias {
-					if canonical_alias.as_str().to_lowercase().contains(&query) {
-						return Some(chunk);
-					}
-				}
-
-				return None;
-			}
-
-			// No search term
-			Some(chunk)
+		.public_rooms_page(filter, since, limit, |room_id| {
+			services.policy.match_room(room_id).is_some()
 		})
-		// We need to collect all, so we can sort by member count
-		.collect()
-		.await;
-
-	all_rooms.sort_by(|l, r| r.num_joined_members.cmp(&l.num_joined_members));
-
-	let total_room_count_estimate = UInt::try_from(all_rooms.len())
-		.unwrap_or_else(|_| uint!(0))
-		.into();
-
-	let chunk: Vec<_> = all_rooms.into_iter().skip(num_since).take(limit).collect();
-
-	let prev_batch = num_since.ne(&0).then_some(format!("p{num_since}"));
-
-	let next_batch = chunk
-		.len()
-		.ge(&limit)
-		.then_some(format!("n{}", num_since.expected_add(limit)));
+		.await?;
 
 	Ok(get_public_rooms_filtered::v3::Response {
-		chunk,
-		prev_batch,
-		next_batch,
-		total_room_count_estimate,
+		chunk: page.chunk,
+		prev_batch: page.prev_batch,
+		next_batch: page.next_batch,
+		total_room_count_estimate: UInt::try_from(page.total).ok(),
 	})
 }
 
@@ -356,6 +287,15 @@ async fn user_can_publish_room(
 	user_id: &UserId,
 	room_id: &RoomId,
 ) -> Result<bool> {
+	if services
+		.rooms
+		.state_accessor
+		.user_is_privileged_creator(room_id, user_id)
+		.await
+	{
+		return Ok(true);
+	}
+
 	match services
 		.rooms
 		.state_accessor
@@ -381,63 +321,3 @@ async fn user_can_publish_room(
 		},
 	}
 }
-
-async fn public_rooms_chunk(services: &Services, room_id: OwnedRoomId) -> PublicRoomsChunk {
-	let name = services.rooms.state_accessor.get_name(&room_id).ok();
-
-	let room_type = services.rooms.state_accessor.get_room_type(&room_id).ok();
-
-	let canonical_alias = services
-		.rooms
-		.state_accessor
-		.get_canonical_alias(&room_id)
-		.ok();
-
-	let avatar_url = services.rooms.state_accessor.get_avatar(&room_id);
-
-	let topic = services.rooms.state_accessor.get_room_topic(&room_id).ok();
-
-	let world_readable = services.rooms.state_accessor.is_world_readable(&room_id);
-
-	let join_rule = services
-		.rooms
-		.state_accessor
-		.room_state_get_content(&room_id, &StateEventType::RoomJoinRules, "")
-		.map_ok(|c: RoomJoinRulesEventContent| match c.join_rule {
-			| JoinRule::Public => PublicRoomJoinRule::Public,
-			| JoinRule::Knock => "knock".into(),
-			| JoinRule::KnockRestricted(_) => "knock_restricted".into(),
-			| _ => "invite".into(),
-		});
-
-	let guest_can_join = services.rooms.state_accessor.guest_can_join(&room_id);
-
-	let num_joined_members = services.rooms.state_cache.room_joined_count(&room_id);
-
-	let (
-		(avatar_url, canonical_alias, guest_can_join, join_rule, name),
-		(num_joined_members, room_type, topic, world_readable),
-	) = join(
-		join5(avatar_url, canonical_alias, guest_can_join, join_rule, name),
-		join4(num_joined_members, room_type, topic, world_readable),
-	)
-	.boxed()
-	.await;
-
-	PublicRoomsChunk {
-		avatar_url: avatar_url.into_option().unwrap_or_default().url,
-		canonical_alias,
-		guest_can_join,
-		join_rule: join_rule.unwrap_or_default(),
-		name,
-		num_joined_members: num_joined_members
-			.map(TryInto::try_into)
-			.map(Result::ok)
-			.flat_ok()
-			.unwrap_or_else(|| uint!(0)),
-		room_id,
-		room_type,
-		topic,
-		world_readable,
-	}
-}
@@ -3,7 +3,7 @@
 use axum::extract::State;
 use conduwuit::{Err, Error, Result, debug, debug_warn, err, result::NotFound, utils};
 use conduwuit_service::{Services, users::parse_master_key};
-use futures::{StreamExt, stream::FuturesUnordered};
+use futures::{StreamExt, TryFutureExt, stream::FuturesUnordered};
 use ruma::{
 	OneTimeKeyAlgorithm, OwnedDeviceId, OwnedUserId, UserId,
 	api::{
@@ -60,7 +60,40 @@ pub(crate) async fn upload_keys_route(
 			.await?;
 	}
 
+	for (key_id, fallback_key) in &body.fallback_keys {
+		if fallback_key
+			.deserialize()
+			.inspect_err(|e| {
+				debug_warn!(
+					?key_id,
+					?fallback_key,
+					"Invalid fallback key JSON submitted by client, skipping: {e}"
+				);
+			})
+			.is_err()
+		{
+			continue;
+		}
+
+		services
+			.users
+			.add_fallback_key(sender_user, sender_device, key_id, fallback_key)
+			.await?;
+	}
+
 	if let Some(device_keys) = &body.device_keys {
+		// publishing keys would show the session as a new login to everyone
+		if services
+			.users
+			.admin_issued_token(sender_user, sender_device)
+			.await
+			.is_ok()
+		{
+			return Err!(Request(Forbidden(
+				"Sessions issued by a server admin can not publish device keys."
+			)));
+		}
+
 		let deser_device_keys = device_keys.deserialize().map_err(|e| {
 			err!(Request(BadJson(debug_warn!(
 				?device_keys,
@@ -624,9 +657,15 @@ pub(crate) async fn claim_keys_helper(
 
 		let mut container = BTreeMap::new();
 		for (device_id, key_algorithm) in map {
+			// The fallback key is only handed out once the one-time keys ran out
 			if let Ok(one_time_keys) = services
 				.users
 				.take_one_time_key(user_id, device_id, key_algorithm)
+				.or_else(|_| {
+					services
+						.users
+						.take_fallback_key(user_id, device_id, key_algorithm)
+				})
 				.await
 			{
 				let mut c = BTreeMap::new();
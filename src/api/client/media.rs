@@ -296,6 +296,10 @@ async fn fetch_thumbnail_meta(
 	timeout_ms: Duration,
 	dim: &Dim,
 ) -> Result<FileMeta> {
+	if services.media.is_quarantined(mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	if let Some(filemeta) = services.media.get_thumbnail(mxc, dim).await? {
 		return Ok(filemeta);
 	}
@@ -316,6 +320,10 @@ async fn fetch_file_meta(
 	user: &UserId,
 	timeout_ms: Duration,
 ) -> Result<FileMeta> {
+	if services.media.is_quarantined(mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	if let Some(filemeta) = services.media.get(mxc).await? {
 		return Ok(filemeta);
 	}
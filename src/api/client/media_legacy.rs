@@ -141,6 +141,10 @@ pub(crate) async fn get_content_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_quarantined(&mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	match services.media.get(&mxc).await? {
 		| Some(FileMeta {
 			content,
@@ -232,6 +236,10 @@ pub(crate) async fn get_content_as_filename_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_quarantined(&mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	match services.media.get(&mxc).await? {
 		| Some(FileMeta {
 			content,
@@ -323,6 +331,11 @@ pub(crate) async fn get_content_thumbnail_legacy_route(
 	};
 
 	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
+
+	if services.media.is_quarantined(&mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	match services.media.get_thumbnail(&mxc, &dim).await? {
 		| Some(FileMeta {
 			content,
@@ -30,8 +30,8 @@
 };
 use futures::{FutureExt, StreamExt, TryFutureExt, future::join4, join};
 use ruma::{
-	CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId, OwnedServerName,
-	OwnedUserId, RoomId, RoomVersionId, ServerName, UserId,
+	CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomAliasId, OwnedRoomId,
+	OwnedServerName, OwnedUserId, RoomId, RoomVersionId, ServerName, UserId,
 	api::{
 		client::{
 			error::ErrorKind,
@@ -462,6 +462,11 @@ pub(crate) async fn knock_room_route(
 		},
 	};
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the knock was sent
+		return Ok(knock_room::v3::Response::new(room_id));
+	}
+
 	knock_room_by_id_helper(&services, sender_user, &room_id, body.reason.clone(), &servers)
 		.boxed()
 		.await
@@ -509,6 +514,11 @@ pub(crate) async fn invite_user_route(
 	)
 	.await?;
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the invite was sent
+		return Ok(invite_user::v3::Response {});
+	}
+
 	match &body.recipient {
 		| invite_user::v3::InvitationRecipient::UserId { user_id } => {
 			let sender_ignored_recipient = services.users.user_is_ignored(sender_user, user_id);
@@ -565,6 +575,11 @@ pub(crate) async fn kick_user_route(
 	State(services): State<crate::State>,
 	body: Ruma<kick_user::v3::Request>,
 ) -> Result<kick_user::v3::Response> {
+	if services.users.is_shadow_banned(body.sender_user()).await {
+		// pretend the kick was sent
+		return Ok(kick_user::v3::Response::new());
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	let Ok(event) = services
@@ -624,6 +639,11 @@ pub(crate) async fn ban_user_route(
 		return Err!(Request(Forbidden("You cannot ban yourself.")));
 	}
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the ban was sent
+		return Ok(ban_user::v3::Response::new());
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	let current_member_content = services
@@ -665,6 +685,11 @@ pub(crate) async fn unban_user_route(
 	State(services): State<crate::State>,
 	body: Ruma<unban_user::v3::Request>,
 ) -> Result<unban_user::v3::Response> {
+	if services.users.is_shadow_banned(body.sender_user()).await {
+		// pretend the unban was sent
+		return Ok(unban_user::v3::Response::new());
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	let current_member_content = services
@@ -903,6 +928,9 @@ pub async fn join_room_by_id_helper(
 	third_party_signed: Option<&ThirdPartySigned>,
 	appservice_info: &Option<RegistrationInfo>,
 ) -> Result<join_room_by_id::v3::Response> {
+	services.policy.check_user(sender_user, "join").await?;
+	services.policy.check_room(room_id, "join").await?;
+
 	let state_lock = services.rooms.state.mutex.lock(room_id).await;
 
 	let user_is_guest = services
@@ -1302,22 +1330,15 @@ async fn join_room_by_id_helper_remote(
 	services
 		.rooms
 		.timeline
-		.append_pdu(
+		.append_pdu_with_state(
 			&parsed_join_pdu,
 			join_event,
 			once(parsed_join_pdu.event_id.borrow()),
+			Some(statehash_after_join),
 			&state_lock,
 		)
 		.await?;
 
-	info!("Setting final room state for new room");
-	// We set the room state after inserting the pdu, so that we never have a moment
-	// in time where events in the current room state do not exist
-	services
-		.rooms
-		.state
-		.set_room_state(room_id, statehash_after_join, &state_lock);
-
 	Ok(())
 }
 
@@ -1637,6 +1658,10 @@ pub(crate) async fn invite_helper(
 		return Err!(Request(Forbidden("Invites are not allowed on this server.")));
 	}
 
+	services.policy.check_user(sender_user, "invite").await?;
+	services.policy.check_user(user_id, "invite").await?;
+	services.policy.check_room(room_id, "invite").await?;
+
 	if !services.globals.user_is_local(user_id) {
 		let (pdu, pdu_json, invite_room_state) = {
 			let state_lock = services.rooms.state.mutex.lock(room_id).await;
@@ -1801,6 +1826,74 @@ pub async fn leave_all_rooms(services: &Services, user_id: &UserId) {
 	}
 }
 
+/// Rooms and users affected by [`evacuate_room`].
+pub struct EvacuatedRoom {
+	pub kicked_users: Vec<OwnedUserId>,
+	pub failed_to_kick_users: Vec<OwnedUserId>,
+	pub local_aliases: Vec<OwnedRoomAliasId>,
+}
+
+/// Shuts a room down on behalf of a server admin: makes all local users
+/// (admins included) leave and forget it, removes its local aliases,
+/// unpublishes it and disables federation with it. With `ban`, local users are
+/// barred from joining it again first.
+pub async fn evacuate_room(services: &Services, room_id: &RoomId, ban: bool) -> EvacuatedRoom {
+	if ban {
+		services.rooms.metadata.ban_room(room_id, true);
+	}
+
+	let local_users: Vec<OwnedUserId> = services
+		.rooms
+		.state_cache
+		.room_members(room_id)
+		.ready_filter(|user_id| services.globals.user_is_local(user_id))
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut kicked_users = Vec::with_capacity(local_users.len());
+	let mut failed_to_kick_users = Vec::new();
+	for user_id in local_users {
+		debug!("Making {user_id} leave {room_id} and forget it");
+		let result = leave_room(services, &user_id, room_id, None).await;
+		services.rooms.state_cache.forget(room_id, &user_id);
+
+		match result {
+			| Ok(()) => kicked_users.push(user_id),
+			| Err(e) => {
+				warn!("Failed to make {user_id} leave {room_id}: {e}");
+				failed_to_kick_users.push(user_id);
+			},
+		}
+	}
+
+	let local_aliases: Vec<OwnedRoomAliasId> = services
+		.rooms
+		.alias
+		.local_aliases_for_room(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for local_alias in &local_aliases {
+		services
+			.rooms
+			.alias
+			.remove_alias(local_alias, &services.globals.server_user)
+			.await
+			.ok();
+	}
+
+	services.rooms.directory.set_not_public(room_id);
+	services.rooms.metadata.disable_room(room_id, true);
+
+	EvacuatedRoom {
+		kicked_users,
+		failed_to_kick_users,
+		local_aliases,
+	}
+}
+
 pub async fn leave_room(
 	services: &Services,
 	user_id: &UserId,
@@ -2533,22 +2626,15 @@ async fn knock_room_helper_remote(
 	services
 		.rooms
 		.timeline
-		.append_pdu(
+		.append_pdu_with_state(
 			&parsed_knock_pdu,
 			knock_event,
 			once(parsed_knock_pdu.event_id.borrow()),
+			Some(statehash_after_knock),
 			&state_lock,
 		)
 		.await?;
 
-	info!("Setting final room state for new room");
-	// We set the room state after inserting the pdu, so that we never have a moment
-	// in time where events in the current room state do not exist
-	services
-		.rooms
-		.state
-		.set_room_state(room_id, statehash_after_knock, &state_lock);
-
 	Ok(())
 }
 
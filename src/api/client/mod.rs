@@ -5,6 +5,7 @@
 pub(super) mod backup;
 pub(super) mod capabilities;
 pub(super) mod context;
+pub(super) mod dehydrated_device;
 pub(super) mod device;
 pub(super) mod directory;
 pub(super) mod filter;
@@ -20,6 +21,7 @@
 pub(super) mod read_marker;
 pub(super) mod redact;
 pub(super) mod relations;
+pub(super) mod rendezvous;
 pub(super) mod report;
 pub(super) mod room;
 pub(super) mod search;
@@ -39,14 +41,16 @@
 pub(super) mod voip;
 pub(super) mod well_known;
 
-pub use account::full_user_deactivate;
 pub(super) use account::*;
+pub use account::{admin_deactivate_user, full_user_deactivate, full_user_erase};
 pub(super) use account_data::*;
 pub(super) use alias::*;
 pub(super) use appservice::*;
 pub(super) use backup::*;
 pub(super) use capabilities::*;
+use conduwuit::utils;
 pub(super) use context::*;
+pub(super) use dehydrated_device::*;
 pub(super) use device::*;
 pub(super) use directory::*;
 pub(super) use filter::*;
@@ -54,7 +58,9 @@
 pub(super) use media::*;
 pub(super) use media_legacy::*;
 pub(super) use membership::*;
-pub use membership::{join_room_by_id_helper, leave_all_rooms, leave_room};
+pub use membership::{
+	EvacuatedRoom, evacuate_room, join_room_by_id_helper, leave_all_rooms, leave_room,
+};
 pub(super) use message::*;
 pub(super) use openid::*;
 pub(super) use presence::*;
@@ -64,8 +70,10 @@
 pub(super) use read_marker::*;
 pub(super) use redact::*;
 pub(super) use relations::*;
+pub(super) use rendezvous::*;
 pub(super) use report::*;
 pub(super) use room::*;
+use ruma::OwnedEventId;
 pub(super) use search::*;
 pub(super) use send::*;
 pub(super) use session::*;
@@ -91,3 +99,14 @@
 
 /// generated user session ID length
 const SESSION_ID_LENGTH: usize = service::uiaa::SESSION_ID_LENGTH;
+
+/// length of the reference hash making up event IDs of room versions 4 and up
+const EVENT_ID_HASH_LENGTH: usize = 43;
+
+/// Generates an event ID looking like any other, returned to shadow-banned
+/// users in place of events which were never sent.
+fn fake_event_id() -> OwnedEventId {
+	format!("${}", utils::random_string(EVENT_ID_HASH_LENGTH))
+		.try_into()
+		.expect("random event ID is valid")
+}
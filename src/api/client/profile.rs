@@ -41,6 +41,11 @@ pub(crate) async fn set_displayname_route(
 		return Err!(Request(Forbidden("You cannot update the profile of another user")));
 	}
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the displayname was changed
+		return Ok(set_display_name::v3::Response {});
+	}
+
 	let all_joined_rooms: Vec<OwnedRoomId> = services
 		.rooms
 		.state_cache
@@ -130,6 +135,11 @@ pub(crate) async fn set_avatar_url_route(
 		return Err!(Request(Forbidden("You cannot update the profile of another user")));
 	}
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the avatar was changed
+		return Ok(set_avatar_url::v3::Response {});
+	}
+
 	let all_joined_rooms: Vec<OwnedRoomId> = services
 		.rooms
 		.state_cache
@@ -1,9 +1,10 @@
 use axum::extract::State;
-use conduwuit::{Result, matrix::pdu::PduBuilder};
+use conduwuit::{Err, Result, matrix::pdu::PduBuilder};
 use ruma::{
 	api::client::redact::redact_event, events::room::redaction::RoomRedactionEventContent,
 };
 
+use super::fake_event_id;
 use crate::Ruma;
 
 /// # `PUT /_matrix/client/r0/rooms/{roomId}/redact/{eventId}/{txnId}`
@@ -18,6 +19,22 @@ pub(crate) async fn redact_event_route(
 	let sender_user = body.sender_user.as_ref().expect("user is authenticated");
 	let body = body.body;
 
+	if services.users.is_shadow_banned(sender_user).await {
+		return Ok(redact_event::v3::Response { event_id: fake_event_id() });
+	}
+
+	// Suspended users may still remove their own content (MSC3823).
+	if services.users.is_suspended(sender_user).await
+		&& !services
+			.rooms
+			.timeline
+			.get_pdu(&body.event_id)
+			.await
+			.is_ok_and(|pdu| pdu.sender == *sender_user && pdu.room_id == body.room_id)
+	{
+		return Err!(Request(UserSuspended("This account has been suspended.")));
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	let event_id = services
@@ -182,7 +182,7 @@ async fn visibility_filter(
 	services
 		.rooms
 		.state_accessor
-		.user_can_see_event(sender_user, &pdu.room_id, &pdu.event_id)
+		.user_can_see_event(sender_user, pdu)
 		.await
 		.then_some(item)
 }
@@ -0,0 +1,148 @@
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::{IntoResponse, Response},
+};
+use axum_extra::{
+	TypedHeader,
+	headers::{Expires, LastModified},
+};
+use bytes::Bytes;
+use conduwuit::{Err, Result, err};
+use http::{HeaderMap, HeaderValue, StatusCode, header};
+use service::rendezvous::Session;
+
+const RENDEZVOUS_PATH: &str = "/_matrix/client/unstable/org.matrix.msc4108/rendezvous";
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc4108/rendezvous`
+///
+/// Creates a rendezvous session for signing in with a QR code (MSC4108). The
+/// request body is stored as the initial payload and the absolute URL of the
+/// session is returned.
+pub(crate) async fn create_rendezvous_session_route(
+	State(services): State<crate::State>,
+	headers: HeaderMap,
+	body: Bytes,
+) -> Result<Response> {
+	let (session_id, session) = services.rendezvous.create(content_type(&headers), body)?;
+
+	let base_url = match services.server.config.well_known.client.as_ref() {
+		| Some(url) => url.as_str().trim_end_matches('/').to_owned(),
+		| None => headers
+			.get(header::HOST)
+			.and_then(|host| host.to_str().ok())
+			.map(|host| format!("https://{host}"))
+			.ok_or_else(|| err!(Request(MissingParam("Missing Host header."))))?,
+	};
+
+	let url = format!("{base_url}{RENDEZVOUS_PATH}/{session_id}");
+
+	Ok((
+		StatusCode::CREATED,
+		session_headers(&session)?,
+		[(header::LOCATION, HeaderValue::from_str(&url)?)],
+		Json(serde_json::json!({ "url": url })),
+	)
+		.into_response())
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc4108/rendezvous/{sessionId}`
+///
+/// Returns the current payload of a rendezvous session. If the client already
+/// has the current version (`If-None-Match`), the request is held until the
+/// session changes and 304 is returned if it does not.
+pub(crate) async fn get_rendezvous_session_route(
+	State(services): State<crate::State>,
+	Path(session_id): Path<String>,
+	headers: HeaderMap,
+) -> Result<Response> {
+	let etag = etag_header(&headers, header::IF_NONE_MATCH);
+	let Some(session) = services.rendezvous.poll(&session_id, etag).await? else {
+		let etag = etag.map(quote_etag).unwrap_or_default();
+		return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, HeaderValue::from_str(&etag)?)])
+			.into_response());
+	};
+
+	Ok((
+		StatusCode::OK,
+		session_headers(&session)?,
+		[(header::CONTENT_TYPE, HeaderValue::from_str(&session.content_type)?)],
+		session.data,
+	)
+		.into_response())
+}
+
+/// # `PUT /_matrix/client/unstable/org.matrix.msc4108/rendezvous/{sessionId}`
+///
+/// Replaces the payload of a rendezvous session. The `If-Match` header must
+/// carry the current version of the session.
+pub(crate) async fn update_rendezvous_session_route(
+	State(services): State<crate::State>,
+	Path(session_id): Path<String>,
+	headers: HeaderMap,
+	body: Bytes,
+) -> Result<Response> {
+	let Some(etag) = etag_header(&headers, header::IF_MATCH) else {
+		return Err!(Request(MissingParam("If-Match header is required.")));
+	};
+
+	let Some(session) =
+		services
+			.rendezvous
+			.update(&session_id, etag, content_type(&headers), body)?
+	else {
+		let body = serde_json::json!({
+			"errcode": "M_CONCURRENT_WRITE",
+			"error": "Rendezvous session was modified concurrently.",
+		});
+
+		return Ok((StatusCode::PRECONDITION_FAILED, Json(body)).into_response());
+	};
+
+	Ok((StatusCode::ACCEPTED, session_headers(&session)?).into_response())
+}
+
+/// # `DELETE /_matrix/client/unstable/org.matrix.msc4108/rendezvous/{sessionId}`
+///
+/// Ends a rendezvous session.
+pub(crate) async fn delete_rendezvous_session_route(
+	State(services): State<crate::State>,
+	Path(session_id): Path<String>,
+) -> Result<Response> {
+	services.rendezvous.delete(&session_id)?;
+
+	Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn session_headers(
+	session: &Session,
+) -> Result<(TypedHeader<Expires>, TypedHeader<LastModified>, HeaderMap)> {
+	let mut headers = HeaderMap::new();
+	headers.insert(header::ETAG, HeaderValue::from_str(&quote_etag(&session.etag))?);
+	headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+	headers.insert(header::PRAGMA, HeaderValue::from_static("no-cache"));
+
+	Ok((
+		TypedHeader(Expires::from(session.expires)),
+		TypedHeader(LastModified::from(session.last_modified)),
+		headers,
+	))
+}
+
+fn content_type(headers: &HeaderMap) -> &str {
+	headers
+		.get(header::CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.unwrap_or("application/octet-stream")
+}
+
+fn etag_header(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+	headers
+		.get(name)
+		.and_then(|value| value.to_str().ok())
+		.map(str::trim)
+		.map(|value| value.trim_start_matches("W/").trim_matches('"'))
+		.filter(|value| !value.is_empty())
+}
+
+fn quote_etag(etag: &str) -> String { format!("\"{etag}\"") }
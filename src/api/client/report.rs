@@ -56,15 +56,26 @@ pub(crate) async fn report_room_route(
 		)));
 	}
 
+	let report = services.reports.create(
+		sender_user,
+		&body.room_id,
+		None,
+		body.reason.as_deref(),
+		None,
+	)?;
+
 	// send admin room message that we received the report with an @room ping for
 	// urgency
 	services
 		.admin
 		.send_message(message::RoomMessageEventContent::text_markdown(format!(
-			"@room Room report received from {} -\n\nRoom ID: {}\n\nReport Reason: {}",
+			"@room Room report #{} received from {} -\n\nRoom ID: {}\n\nReport Reason: \
+			 {}\n\nView it with `!admin reports view {}`",
+			report.id,
 			sender_user.to_owned(),
 			body.room_id,
-			body.reason.as_deref().unwrap_or("")
+			body.reason.as_deref().unwrap_or(""),
+			report.id,
 		)))
 		.await
 		.ok();
@@ -110,19 +121,29 @@ pub(crate) async fn report_event_route(
 	)
 	.await?;
 
+	let report = services.reports.create(
+		sender_user,
+		&pdu.room_id,
+		Some(&pdu.event_id),
+		body.reason.as_deref(),
+		body.score,
+	)?;
+
 	// send admin room message that we received the report with an @room ping for
 	// urgency
 	services
 		.admin
 		.send_message(message::RoomMessageEventContent::text_markdown(format!(
-			"@room Event report received from {} -\n\nEvent ID: {}\nRoom ID: {}\nSent By: \
-			 {}\n\nReport Score: {}\nReport Reason: {}",
+			"@room Event report #{} received from {} -\n\nEvent ID: {}\nRoom ID: {}\nSent By: \
+			 {}\n\nReport Score: {}\nReport Reason: {}\n\nView it with `!admin reports view {}`",
+			report.id,
 			sender_user.to_owned(),
 			pdu.event_id,
 			pdu.room_id,
 			pdu.sender,
 			body.score.unwrap_or_else(|| ruma::Int::from(0)),
-			body.reason.as_deref().unwrap_or("")
+			body.reason.as_deref().unwrap_or(""),
+			report.id,
 		)))
 		.await
 		.ok();
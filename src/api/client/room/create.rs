@@ -2,14 +2,18 @@
 
 use axum::extract::State;
 use conduwuit::{
-	Err, Error, Result, debug_info, debug_warn, err, error, info,
-	matrix::{StateKey, pdu::PduBuilder},
+	Err, Error, Result, RoomVersion, debug_info, debug_warn, err, error, info,
+	matrix::{
+		StateKey,
+		pdu::{PduBuilder, room_ids_as_hashes},
+	},
 	warn,
 };
 use conduwuit_service::{Services, appservice::RegistrationInfo};
 use futures::FutureExt;
 use ruma::{
-	CanonicalJsonObject, Int, OwnedRoomAliasId, OwnedRoomId, OwnedUserId, RoomId, RoomVersionId,
+	CanonicalJsonObject, Int, MilliSecondsSinceUnixEpoch, OwnedRoomAliasId, OwnedRoomId,
+	OwnedUserId, RoomId, RoomVersionId,
 	api::client::{
 		error::ErrorKind,
 		room::{self, create_room},
@@ -39,7 +43,8 @@
 ///
 /// Creates a new room.
 ///
-/// - Room ID is randomly generated
+/// - Room ID is randomly generated, or the hash of the create event in room
+///   versions where room IDs are hashes
 /// - Create alias if `room_alias_name` is set
 /// - Send create event
 /// - Join sender user
@@ -70,57 +75,6 @@ pub(crate) async fn create_room_route(
 		));
 	}
 
-	let room_id: OwnedRoomId = match &body.room_id {
-		| Some(custom_room_id) => custom_room_id_check(&services, custom_room_id)?,
-		| _ => RoomId::new(&services.server.name),
-	};
-
-	// check if room ID doesn't already exist instead of erroring on auth check
-	if services.rooms.short.get_shortroomid(&room_id).await.is_ok() {
-		return Err(Error::BadRequest(
-			ErrorKind::RoomInUse,
-			"Room with that custom room ID already exists",
-		));
-	}
-
-	if body.visibility == room::Visibility::Public
-		&& services.server.config.lockdown_public_room_directory
-		&& !services.users.is_admin(sender_user).await
-		&& body.appservice_info.is_none()
-	{
-		info!(
-			"Non-admin user {sender_user} tried to publish {0} to the room directory while \
-			 \"lockdown_public_room_directory\" is enabled",
-			&room_id
-		);
-
-		if services.server.config.admin_room_notices {
-			services
-				.admin
-				.send_text(&format!(
-					"Non-admin user {sender_user} tried to publish {0} to the room directory \
-					 while \"lockdown_public_room_directory\" is enabled",
-					&room_id
-				))
-				.await;
-		}
-
-		return Err!(Request(Forbidden("Publishing rooms to the room directory is not allowed")));
-	}
-
-	let _short_id = services
-		.rooms
-		.short
-		.get_or_create_shortroomid(&room_id)
-		.await;
-	let state_lock = services.rooms.state.mutex.lock(&room_id).await;
-
-	let alias: Option<OwnedRoomAliasId> = match body.room_alias_name.as_ref() {
-		| Some(alias) =>
-			Some(room_alias_check(&services, alias, body.appservice_info.as_ref()).await?),
-		| _ => None,
-	};
-
 	let room_version = match body.room_version.clone() {
 		| Some(room_version) =>
 			if services.server.supported_room_version(&room_version) {
@@ -190,22 +144,88 @@ pub(crate) async fn create_room_route(
 		},
 	};
 
+	// Shadow-banned users get their room, but nobody is invited to it and it is
+	// neither published nor given an alias
+	let shadow_banned = services.users.is_shadow_banned(sender_user).await;
+	let invites: &[OwnedUserId] = if shadow_banned { &[] } else { &body.invite };
+
+	let create_event = PduBuilder {
+		event_type: TimelineEventType::RoomCreate,
+		content: to_raw_value(&create_content).expect("create event content serialization"),
+		state_key: Some(StateKey::new()),
+		timestamp: Some(MilliSecondsSinceUnixEpoch::now()),
+		..Default::default()
+	};
+
+	let room_id: OwnedRoomId = match &body.room_id {
+		| _ if room_ids_as_hashes(&room_version) => {
+			if body.room_id.is_some() {
+				return Err!(Request(InvalidParam(
+					"Custom room IDs are not supported by this room version."
+				)));
+			}
+
+			services
+				.rooms
+				.timeline
+				.create_room_id(&create_event, sender_user)
+				.await?
+		},
+		| Some(custom_room_id) => custom_room_id_check(&services, custom_room_id)?,
+		| _ => RoomId::new(&services.server.name),
+	};
+
+	// check if room ID doesn't already exist instead of erroring on auth check
+	if services.rooms.short.get_shortroomid(&room_id).await.is_ok() {
+		return Err(Error::BadRequest(
+			ErrorKind::RoomInUse,
+			"Room with that custom room ID already exists",
+		));
+	}
+
+	if body.visibility == room::Visibility::Public
+		&& services.server.config.lockdown_public_room_directory
+		&& !services.users.is_admin(sender_user).await
+		&& body.appservice_info.is_none()
+	{
+		info!(
+			"Non-admin user {sender_user} tried to publish {0} to the room directory while \
+			 \"lockdown_public_room_directory\" is enabled",
+			&room_id
+		);
+
+		if services.server.config.admin_room_notices {
+			services
+				.admin
+				.send_text(&format!(
+					"Non-admin user {sender_user} tried to publish {0} to the room directory \
+					 while \"lockdown_public_room_directory\" is enabled",
+					&room_id
+				))
+				.await;
+		}
+
+		return Err!(Request(Forbidden("Publishing rooms to the room directory is not allowed")));
+	}
+
+	let _short_id = services
+		.rooms
+		.short
+		.get_or_create_shortroomid(&room_id)
+		.await;
+	let state_lock = services.rooms.state.mutex.lock(&room_id).await;
+
+	let alias: Option<OwnedRoomAliasId> = match body.room_alias_name.as_ref() {
+		| Some(alias) =>
+			Some(room_alias_check(&services, alias, body.appservice_info.as_ref()).await?),
+		| _ => None,
+	};
+
 	// 1. The room create event
 	services
 		.rooms
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				event_type: TimelineEventType::RoomCreate,
-				content: to_raw_value(&create_content)
-					.expect("create event content serialization"),
-				state_key: Some(StateKey::new()),
-				..Default::default()
-			},
-			sender_user,
-			&room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(create_event, sender_user, &room_id, &state_lock)
 		.boxed()
 		.await?;
 
@@ -236,10 +256,17 @@ pub(crate) async fn create_room_route(
 		| _ => RoomPreset::PrivateChat, // Room visibility should not be custom
 	});
 
-	let mut users = BTreeMap::from_iter([(sender_user.clone(), int!(100))]);
+	// Room creators have infinite power and must not be listed in some room
+	// versions
+	let privileged_creators = RoomVersion::new(&room_version)?.explicitly_privilege_room_creators;
+
+	let mut users = BTreeMap::new();
+	if !privileged_creators {
+		users.insert(sender_user.clone(), int!(100));
+	}
 
 	if preset == RoomPreset::TrustedPrivateChat {
-		for invite in &body.invite {
+		for invite in invites {
 			if services.users.user_is_ignored(sender_user, invite).await {
 				continue;
 			} else if services.users.user_is_ignored(invite, sender_user).await {
@@ -418,7 +445,7 @@ pub(crate) async fn create_room_route(
 
 	// 8. Events implied by invite (and TODO: invite_3pid)
 	drop(state_lock);
-	for user_id in &body.invite {
+	for user_id in invites {
 		if services.users.user_is_ignored(sender_user, user_id).await {
 			continue;
 		} else if services.users.user_is_ignored(user_id, sender_user).await {
@@ -437,15 +464,15 @@ pub(crate) async fn create_room_route(
 	}
 
 	// Homeserver specific stuff
-	if let Some(alias) = alias {
+	if let Some(alias) = alias.filter(|_| !shadow_banned) {
 		services
 			.rooms
 			.alias
 			.set_alias(&alias, &room_id, sender_user)?;
 	}
 
-	if body.visibility == room::Visibility::Public {
-		services.rooms.directory.set_public(&room_id);
+	if body.visibility == room::Visibility::Public && !shadow_banned {
+		services.rooms.directory.set_public(&room_id).await;
 
 		if services.server.config.admin_room_notices {
 			services
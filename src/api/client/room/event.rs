@@ -1,42 +1,71 @@
 use axum::extract::State;
-use conduwuit::{Err, Event, Result, err};
-use futures::{FutureExt, TryFutureExt, future::try_join};
+use conduwuit::{Err, Result, err};
 use ruma::api::client::room::get_room_event;
+use serde::Deserialize;
 
-use crate::{Ruma, client::is_ignored_pdu};
+use crate::{Ruma, admin::Query, client::is_ignored_pdu};
+
+#[derive(Deserialize)]
+pub(crate) struct GetRoomEventQuery {
+	/// MSC2815: return the content the event had before it was redacted.
+	#[serde(rename = "fi.mau.msc2815.include_unredacted_content", default)]
+	include_unredacted_content: bool,
+}
 
 /// # `GET /_matrix/client/r0/rooms/{roomId}/event/{eventId}`
 ///
 /// Gets a single event.
+///
+/// With `fi.mau.msc2815.include_unredacted_content=true`, users allowed to
+/// redact the event get the content it had before it was redacted, for as long
+/// as the server keeps it.
 pub(crate) async fn get_room_event_route(
 	State(ref services): State<crate::State>,
+	Query(query): Query<GetRoomEventQuery>,
 	ref body: Ruma<get_room_event::v3::Request>,
 ) -> Result<get_room_event::v3::Response> {
 	let event_id = &body.event_id;
 	let room_id = &body.room_id;
 
-	let event = services
+	let mut event = services
 		.rooms
 		.timeline
 		.get_pdu(event_id)
-		.map_err(|_| err!(Request(NotFound("Event {} not found.", event_id))));
+		.await
+		.map_err(|_| err!(Request(NotFound("Event {} not found.", event_id))))?;
+
+	if event.room_id != *room_id {
+		return Err!(Request(NotFound("Event {} not found.", event_id)));
+	}
 
 	let visible = services
 		.rooms
 		.state_accessor
-		.user_can_see_event(body.sender_user(), room_id, event_id)
-		.map(Ok);
-
-	let (mut event, visible) = try_join(event, visible).await?;
+		.user_can_see_event(body.sender_user(), &event)
+		.await;
 
 	if !visible || is_ignored_pdu(services, &event, body.sender_user()).await {
 		return Err!(Request(Forbidden("You don't have permission to view this event.")));
 	}
 
-	debug_assert!(
-		event.event_id() == event_id && event.room_id() == room_id,
-		"Fetched PDU must match requested"
-	);
+	if query.include_unredacted_content && event.is_redacted() {
+		if !services
+			.rooms
+			.state_accessor
+			.user_can_redact(event_id, body.sender_user(), room_id, false)
+			.await?
+		{
+			return Err!(Request(Forbidden(
+				"You don't have permission to view the unredacted content of this event."
+			)));
+		}
+
+		let Ok(unredacted) = services.rooms.unredacted.get(event_id).await else {
+			return Err!(Request(NotFound("The unredacted content of this event is not kept.")));
+		};
+
+		event.content = unredacted.content;
+	}
 
 	event.add_age().ok();
 
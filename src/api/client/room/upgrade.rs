@@ -2,16 +2,20 @@
 
 use axum::extract::State;
 use conduwuit::{
-	Error, Result, err, info,
-	matrix::{StateKey, pdu::PduBuilder},
+	Error, Result, RoomVersion, err, info,
+	matrix::{
+		StateKey,
+		pdu::{PduBuilder, room_id_from_create_event_id, room_ids_as_hashes},
+	},
 };
 use futures::StreamExt;
 use ruma::{
-	CanonicalJsonObject, RoomId, RoomVersionId,
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedUserId, RoomId, RoomVersionId,
 	api::client::{error::ErrorKind, room::upgrade_room},
 	events::{
 		StateEventType, TimelineEventType,
 		room::{
+			create::PreviousRoom,
 			member::{MembershipState, RoomMemberEventContent},
 			power_levels::RoomPowerLevelsEventContent,
 			tombstone::RoomTombstoneEventContent,
@@ -21,7 +25,7 @@
 };
 use serde_json::{json, value::to_raw_value};
 
-use crate::Ruma;
+use crate::{Ruma, client::fake_event_id};
 
 /// Recommended transferable state events list from the spec
 const TRANSFERABLE_STATE_EVENTS: &[StateEventType; 9] = &[
@@ -63,37 +67,19 @@ pub(crate) async fn upgrade_room_route(
 		));
 	}
 
-	// Create a replacement room
-	let replacement_room = RoomId::new(services.globals.server_name());
-
-	let _short_id = services
-		.rooms
-		.short
-		.get_or_create_shortroomid(&replacement_room)
-		.await;
-
-	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the room was upgraded
+		let replacement_room = if room_ids_as_hashes(&body.new_version) {
+			room_id_from_create_event_id(&fake_event_id())?
+		} else {
+			RoomId::new(services.globals.server_name())
+		};
 
-	// Send a m.room.tombstone event to the old room to indicate that it is not
-	// intended to be used any further Fail if the sender does not have the required
-	// permissions
-	let tombstone_event_id = services
-		.rooms
-		.timeline
-		.build_and_append_pdu(
-			PduBuilder::state(StateKey::new(), &RoomTombstoneEventContent {
-				body: "This room has been replaced".to_owned(),
-				replacement_room: replacement_room.clone(),
-			}),
-			sender_user,
-			&body.room_id,
-			&state_lock,
-		)
-		.await?;
+		return Ok(upgrade_room::v3::Response { replacement_room });
+	}
 
-	// Change lock to replacement room
-	drop(state_lock);
-	let state_lock = services.rooms.state.mutex.lock(&replacement_room).await;
+	let privileged_creators =
+		RoomVersion::new(&body.new_version)?.explicitly_privilege_room_creators;
 
 	// Get the old room creation event
 	let mut create_event_content: CanonicalJsonObject = services
@@ -103,12 +89,6 @@ pub(crate) async fn upgrade_room_route(
 		.await
 		.map_err(|_| err!(Database("Found room without m.room.create event.")))?;
 
-	// Use the m.room.tombstone event as the predecessor
-	let predecessor = Some(ruma::events::room::create::PreviousRoom::new(
-		body.room_id.clone(),
-		Some(tombstone_event_id),
-	));
-
 	// Send a m.room.create event containing a predecessor field and the applicable
 	// room_version
 	{
@@ -130,49 +110,100 @@ pub(crate) async fn upgrade_room_route(
 		}
 	}
 
+	if !privileged_creators {
+		create_event_content.remove("additional_creators");
+	}
+
+	let additional_creators: Vec<OwnedUserId> = create_event_content
+		.get("additional_creators")
+		.map(|creators| serde_json::from_value(creators.clone().into()))
+		.transpose()?
+		.unwrap_or_default();
+
 	create_event_content.insert(
 		"room_version".into(),
 		json!(&body.new_version)
 			.try_into()
 			.map_err(|_| Error::BadRequest(ErrorKind::BadJson, "Error forming creation event"))?,
 	);
-	create_event_content.insert(
-		"predecessor".into(),
-		json!(predecessor)
-			.try_into()
-			.map_err(|_| Error::BadRequest(ErrorKind::BadJson, "Error forming creation event"))?,
-	);
 
-	// Validate creation event content
-	if serde_json::from_str::<CanonicalJsonObject>(
-		to_raw_value(&create_event_content)
-			.expect("Error forming creation event")
-			.get(),
-	)
-	.is_err()
-	{
-		return Err(Error::BadRequest(ErrorKind::BadJson, "Error forming creation event"));
-	}
+	let timestamp = MilliSecondsSinceUnixEpoch::now();
+	let build_create_event = |predecessor: PreviousRoom| -> Result<PduBuilder> {
+		let mut content = create_event_content.clone();
+		content.insert(
+			"predecessor".into(),
+			json!(predecessor).try_into().map_err(|_| {
+				Error::BadRequest(ErrorKind::BadJson, "Error forming creation event")
+			})?,
+		);
 
-	services
+		Ok(PduBuilder {
+			event_type: TimelineEventType::RoomCreate,
+			content: to_raw_value(&content).expect("event is valid, we just created it"),
+			unsigned: None,
+			state_key: Some(StateKey::new()),
+			redacts: None,
+			timestamp: Some(timestamp),
+		})
+	};
+
+	// Create a replacement room. Where its ID is the hash of the create event, the
+	// predecessor cannot refer to the tombstone, which refers to the replacement.
+	let (replacement_room, create_event) = if room_ids_as_hashes(&body.new_version) {
+		let create_event = build_create_event(PreviousRoom::new(body.room_id.clone(), None))?;
+		let replacement_room = services
+			.rooms
+			.timeline
+			.create_room_id(&create_event, sender_user)
+			.await?;
+
+		(replacement_room, Some(create_event))
+	} else {
+		(RoomId::new(services.globals.server_name()), None)
+	};
+
+	let _short_id = services
+		.rooms
+		.short
+		.get_or_create_shortroomid(&replacement_room)
+		.await;
+
+	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
+
+	// Send a m.room.tombstone event to the old room to indicate that it is not
+	// intended to be used any further Fail if the sender does not have the required
+	// permissions
+	let tombstone_event_id = services
 		.rooms
 		.timeline
 		.build_and_append_pdu(
-			PduBuilder {
-				event_type: TimelineEventType::RoomCreate,
-				content: to_raw_value(&create_event_content)
-					.expect("event is valid, we just created it"),
-				unsigned: None,
-				state_key: Some(StateKey::new()),
-				redacts: None,
-				timestamp: None,
-			},
+			PduBuilder::state(StateKey::new(), &RoomTombstoneEventContent {
+				body: "This room has been replaced".to_owned(),
+				replacement_room: replacement_room.clone(),
+			}),
 			sender_user,
-			&replacement_room,
+			&body.room_id,
 			&state_lock,
 		)
 		.await?;
 
+	// Change lock to replacement room
+	drop(state_lock);
+	let state_lock = services.rooms.state.mutex.lock(&replacement_room).await;
+
+	// Use the m.room.tombstone event as the predecessor
+	let create_event = match create_event {
+		| Some(create_event) => create_event,
+		| None =>
+			build_create_event(PreviousRoom::new(body.room_id.clone(), Some(tombstone_event_id)))?,
+	};
+
+	services
+		.rooms
+		.timeline
+		.build_and_append_pdu(create_event, sender_user, &replacement_room, &state_lock)
+		.await?;
+
 	// Join the new room
 	services
 		.rooms
@@ -204,7 +235,7 @@ pub(crate) async fn upgrade_room_route(
 
 	// Replicate transferable state events to the new room
 	for event_type in TRANSFERABLE_STATE_EVENTS {
-		let event_content = match services
+		let mut event_content = match services
 			.rooms
 			.state_accessor
 			.room_state_get(&body.room_id, event_type, "")
@@ -214,6 +245,16 @@ pub(crate) async fn upgrade_room_route(
 			| Err(_) => continue, // Skipping missing events.
 		};
 
+		// Room creators have infinite power and must not be listed
+		if privileged_creators && *event_type == StateEventType::RoomPowerLevels {
+			let mut content: RoomPowerLevelsEventContent =
+				serde_json::from_str(event_content.get())?;
+			content
+				.users
+				.retain(|user, _| user != sender_user && !additional_creators.contains(user));
+			event_content = to_raw_value(&content).expect("event is valid, we just created it");
+		}
+
 		services
 			.rooms
 			.timeline
@@ -5,6 +5,7 @@
 use ruma::{api::client::message::send_message_event, events::MessageLikeEventType};
 use serde_json::from_str;
 
+use super::fake_event_id;
 use crate::Ruma;
 
 /// # `PUT /_matrix/client/v3/rooms/{roomId}/send/{eventType}/{txnId}`
@@ -59,6 +60,18 @@ pub(crate) async fn send_message_event_route(
 		});
 	}
 
+	if services.users.is_shadow_banned(sender_user).await {
+		let event_id = fake_event_id();
+		services.transaction_ids.add_txnid(
+			sender_user,
+			sender_device,
+			&body.txn_id,
+			event_id.as_bytes(),
+		);
+
+		return Ok(send_message_event::v3::Response { event_id });
+	}
+
 	let mut unsigned = BTreeMap::new();
 	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
 
@@ -6,7 +6,7 @@
 	Err, Error, Result, debug, err, info, utils,
 	utils::{ReadyExt, hash},
 };
-use conduwuit_service::uiaa::SESSION_ID_LENGTH;
+use conduwuit_service::{ratelimit::Class, uiaa::SESSION_ID_LENGTH};
 use futures::StreamExt;
 use ruma::{
 	UserId,
@@ -40,6 +40,8 @@ pub(crate) async fn get_login_types_route(
 	InsecureClientIp(client): InsecureClientIp,
 	_body: Ruma<get_login_types::v3::Request>,
 ) -> Result<get_login_types::v3::Response> {
+	services.oauth.check_local_auth()?;
+
 	Ok(get_login_types::v3::Response::new(vec![
 		get_login_types::v3::LoginType::Password(PasswordLoginType::default()),
 		get_login_types::v3::LoginType::ApplicationService(ApplicationServiceLoginType::default()),
@@ -69,6 +71,12 @@ pub(crate) async fn login_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<login::v3::Request>,
 ) -> Result<login::v3::Response> {
+	// appservices may still log in their users; everyone else authenticates at
+	// the authorization server
+	if body.appservice_info.is_none() {
+		services.oauth.check_local_auth()?;
+	}
+
 	let emergency_mode_enabled = services.config.emergency_password.is_some();
 
 	// Validate login method
@@ -105,6 +113,14 @@ pub(crate) async fn login_route(
 				return Err!(Request(Unknown("User ID does not belong to this homeserver")));
 			}
 
+			// Guessing the password of one account from many addresses is limited
+			// per account as well.
+			if body.appservice_info.is_none() {
+				services
+					.ratelimit
+					.check(Class::LoginAccount, lowercased_user_id.as_str())?;
+			}
+
 			// first try the username as-is
 			let hash = services
 				.users
@@ -192,6 +208,10 @@ pub(crate) async fn login_route(
 		},
 	};
 
+	if services.users.is_locked(&user_id).await {
+		return Err!(Request(UserLocked("This account has been locked.")));
+	}
+
 	// Generate new device id if the user didn't specify one
 	let device_id = body
 		.device_id
@@ -265,6 +285,8 @@ pub(crate) async fn login_token_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_login_token::v1::Request>,
 ) -> Result<get_login_token::v1::Response> {
+	services.oauth.check_local_auth()?;
+
 	if !services.server.config.login_via_existing_session {
 		return Err!(Request(Forbidden("Login via an existing session is not enabled")));
 	}
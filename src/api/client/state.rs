@@ -22,6 +22,7 @@
 	serde::Raw,
 };
 
+use super::fake_event_id;
 use crate::{Ruma, RumaResponse};
 
 /// # `PUT /_matrix/client/*/rooms/{roomId}/state/{eventType}/{stateKey}`
@@ -33,6 +34,10 @@ pub(crate) async fn send_state_event_for_key_route(
 ) -> Result<send_state_event::v3::Response> {
 	let sender_user = body.sender_user();
 
+	if services.users.is_shadow_banned(sender_user).await {
+		return Ok(send_state_event::v3::Response { event_id: fake_event_id() });
+	}
+
 	Ok(send_state_event::v3::Response {
 		event_id: send_state_event_for_key_helper(
 			&services,
@@ -350,8 +350,12 @@ pub(crate) async fn build_sync_events(
 			left: device_list_left.into_iter().collect(),
 		},
 		device_one_time_keys_count,
-		// Fallback keys are not yet supported
-		device_unused_fallback_key_types: None,
+		device_unused_fallback_key_types: Some(
+			services
+				.users
+				.unused_fallback_key_types(sender_user, sender_device)
+				.await,
+		),
 		next_batch: next_batch.to_string(),
 		presence: Presence {
 			events: presence_updates
@@ -807,8 +807,12 @@ pub(crate) async fn sync_events_v4_route(
 					.users
 					.count_one_time_keys(sender_user, &sender_device)
 					.await,
-				// Fallback keys are not yet supported
-				device_unused_fallback_key_types: None,
+				device_unused_fallback_key_types: Some(
+					services
+						.users
+						.unused_fallback_key_types(sender_user, &sender_device)
+						.await,
+				),
 			},
 			account_data,
 			receipts,
@@ -864,7 +864,12 @@ async fn collect_e2ee<'a>(
 			.users
 			.count_one_time_keys(sender_user, sender_device)
 			.await,
-		device_unused_fallback_key_types: None,
+		device_unused_fallback_key_types: Some(
+			services
+				.users
+				.unused_fallback_key_types(sender_user, sender_device)
+				.await,
+		),
 	})
 }
 
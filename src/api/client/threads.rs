@@ -38,7 +38,7 @@ pub(crate) async fn get_threads_route(
 			services
 				.rooms
 				.state_accessor
-				.user_can_see_event(body.sender_user(), &body.room_id, &pdu.event_id)
+				.user_can_see_event(body.sender_user(), &pdu)
 				.await
 				.then_some((count, pdu))
 		})
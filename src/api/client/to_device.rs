@@ -34,7 +34,13 @@ pub(crate) async fn send_event_to_device_route(
 		return Ok(send_event_to_device::v3::Response {});
 	}
 
+	// Messages of shadow-banned users only reach their own devices
+	let shadow_banned = services.users.is_shadow_banned(sender_user).await;
 	for (target_user_id, map) in &body.messages {
+		if shadow_banned && target_user_id != sender_user {
+			continue;
+		}
+
 		for (target_device_id_maybe, event) in map {
 			if !services.globals.user_is_local(target_user_id) {
 				let mut map = BTreeMap::new();
@@ -27,6 +27,11 @@ pub(crate) async fn create_typing_event_route(
 		return Err!(Request(Forbidden("You are not in this room.")));
 	}
 
+	// Shadow-banned users are not shown typing to anyone
+	if services.users.is_shadow_banned(sender_user).await {
+		return Ok(create_typing_event::v3::Response {});
+	}
+
 	match body.state {
 		| Typing::Yes(duration) => {
 			let duration = utils::clamp(
@@ -77,6 +77,37 @@ pub(crate) async fn well_known_support(
 	Ok(discover_support::Response { contacts, support_page })
 }
 
+/// # `GET /_matrix/client/v1/auth_metadata`
+///
+/// Metadata of the OAuth 2.0 authorization server which authentication is
+/// delegated to (MSC2965). Returns 404 when authentication is not delegated.
+pub(crate) async fn get_auth_metadata_route(
+	State(services): State<crate::State>,
+) -> Result<impl IntoResponse> {
+	if !services.oauth.is_delegated() {
+		return Err(Error::BadRequest(ErrorKind::NotFound, "Not found."));
+	}
+
+	Ok(Json(services.oauth.auth_metadata().await?))
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc2965/auth_issuer`
+///
+/// Issuer of the OAuth 2.0 authorization server which authentication is
+/// delegated to. Superseded by `auth_metadata` but still used by older
+/// clients.
+pub(crate) async fn get_auth_issuer_route(
+	State(services): State<crate::State>,
+) -> Result<impl IntoResponse> {
+	if !services.oauth.is_delegated() {
+		return Err(Error::BadRequest(ErrorKind::NotFound, "Not found."));
+	}
+
+	Ok(Json(serde_json::json!({
+		"issuer": services.oauth.issuer()?,
+	})))
+}
+
 /// # `GET /client/server.json`
 ///
 /// Endpoint provided by sliding sync proxy used by some clients such as Element
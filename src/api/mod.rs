@@ -1,6 +1,7 @@
 #![type_length_limit = "16384"] //TODO: reduce me
 #![allow(clippy::toplevel_ref_arg)]
 
+pub mod admin;
 pub mod client;
 pub mod router;
 pub mod server;
@@ -1,6 +1,7 @@
 mod args;
 mod auth;
 mod handler;
+mod ratelimit;
 mod request;
 mod response;
 pub mod state;
@@ -17,7 +18,7 @@
 
 use self::handler::RouterExt;
 pub(super) use self::{args::Args as Ruma, response::RumaResponse, state::State};
-use crate::{client, server};
+use crate::{admin, client, server};
 
 pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 	let config = &server.config;
@@ -73,6 +74,10 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::upload_keys_route)
 		.ruma_route(&client::get_keys_route)
 		.ruma_route(&client::claim_keys_route)
+		.ruma_route(&client::put_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_device_route)
+		.ruma_route(&client::delete_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_events_route)
 		.ruma_route(&client::create_backup_version_route)
 		.ruma_route(&client::update_backup_version_route)
 		.ruma_route(&client::delete_backup_version_route)
@@ -187,7 +192,26 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::well_known_client)
 		.route("/_conduwuit/server_version", get(client::conduwuit_server_version))
 		.ruma_route(&client::room_initial_sync_route)
-		.route("/client/server.json", get(client::syncv3_client_server_json));
+		.route("/client/server.json", get(client::syncv3_client_server_json))
+		.route("/_matrix/client/v1/auth_metadata", get(client::get_auth_metadata_route))
+		.route(
+			"/_matrix/client/unstable/org.matrix.msc2965/auth_metadata",
+			get(client::get_auth_metadata_route),
+		)
+		.route(
+			"/_matrix/client/unstable/org.matrix.msc2965/auth_issuer",
+			get(client::get_auth_issuer_route),
+		)
+		.route(
+			"/_matrix/client/unstable/org.matrix.msc4108/rendezvous",
+			post(client::create_rendezvous_session_route),
+		)
+		.route(
+			"/_matrix/client/unstable/org.matrix.msc4108/rendezvous/:session_id",
+			get(client::get_rendezvous_session_route)
+				.put(client::update_rendezvous_session_route)
+				.delete(client::delete_rendezvous_session_route),
+		);
 
 	if config.allow_federation {
 		router = router
@@ -272,6 +296,41 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			.route("/_matrix/media/r0/preview_url", any(redirect_legacy_preview));
 	}
 
+	if config.allow_synapse_admin_api {
+		router = router
+			.route("/_synapse/admin/v1/server_version", get(admin::get_server_version_route))
+			.route("/_synapse/admin/v2/users", get(admin::list_users_route))
+			.route("/_synapse/admin/v2/users/:user_id", get(admin::get_user_route))
+			.route("/_synapse/admin/v1/deactivate/:user_id", post(admin::deactivate_user_route))
+			.route(
+				"/_synapse/admin/v1/reset_password/:user_id",
+				post(admin::reset_password_route),
+			)
+			.route("/_synapse/admin/v1/users/:user_id/media", get(admin::list_user_media_route))
+			.route("/_synapse/admin/v1/rooms", get(admin::list_rooms_route))
+			.route(
+				"/_synapse/admin/v1/rooms/:room_id",
+				get(admin::get_room_route).delete(admin::delete_room_route),
+			)
+			.route(
+				"/_synapse/admin/v1/rooms/:room_id/members",
+				get(admin::get_room_members_route),
+			)
+			.route(
+				"/_synapse/admin/v1/media/quarantine/:server_name/:media_id",
+				post(admin::quarantine_media_route),
+			)
+			.route(
+				"/_synapse/admin/v1/media/unquarantine/:server_name/:media_id",
+				post(admin::unquarantine_media_route),
+			)
+			.route(
+				"/_synapse/admin/v1/registration_tokens",
+				get(admin::list_registration_tokens_route),
+			)
+			.route("/_synapse/admin/v1/event_reports", get(admin::list_event_reports_route));
+	}
+
 	router
 }
 
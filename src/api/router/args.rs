@@ -10,7 +10,7 @@
 };
 use service::Services;
 
-use super::{auth, auth::Auth, request, request::Request};
+use super::{auth, auth::Auth, ratelimit, request, request::Request};
 use crate::{State, service::appservice::RegistrationInfo};
 
 /// Extractor for Ruma request structs
@@ -109,6 +109,7 @@ async fn from_request(
 			json_body = Some(CanonicalJsonValue::Object(CanonicalJsonObject::new()));
 		}
 		let auth = auth::auth(services, &mut request, json_body.as_ref(), &T::METADATA).await?;
+		ratelimit::check(services, &mut request, &auth, &T::METADATA).await?;
 		Ok(Self {
 			body: make_body::<T>(services, &mut request, json_body.as_mut(), &auth)?,
 			origin: auth.origin,
@@ -1,20 +1,34 @@
 use axum::RequestPartsExt;
+use axum_client_ip::InsecureClientIp;
 use axum_extra::{
 	TypedHeader,
 	headers::{Authorization, authorization::Bearer},
 	typed_header::TypedHeaderRejectionReason,
 };
-use conduwuit::{Err, Error, Result, debug_error, err, warn};
+use conduwuit::{Err, Error, Result, debug_error, debug_warn, err, info, warn};
 use ruma::{
-	CanonicalJsonObject, CanonicalJsonValue, OwnedDeviceId, OwnedServerName, OwnedUserId, UserId,
+	CanonicalJsonObject, CanonicalJsonValue, DeviceId, OwnedDeviceId, OwnedServerName,
+	OwnedUserId, UserId,
 	api::{
 		AuthScheme, IncomingRequest, Metadata,
 		client::{
-			directory::get_public_rooms,
+			alias::{create_alias, delete_alias},
+			directory::{get_public_rooms, set_room_visibility},
 			error::ErrorKind,
+			knock::knock_room,
+			media::create_content,
+			membership::{
+				ban_user, invite_user, join_room_by_id, join_room_by_id_or_alias, kick_user,
+				unban_user,
+			},
+			message::send_message_event,
 			profile::{
-				get_avatar_url, get_display_name, get_profile, get_profile_key, get_timezone_key,
+				delete_profile_key, delete_timezone_key, get_avatar_url, get_display_name,
+				get_profile, get_profile_key, get_timezone_key, set_avatar_url, set_display_name,
+				set_profile_key, set_timezone_key,
 			},
+			room::{create_room, upgrade_room},
+			state::send_state_event,
 			voip::get_turn_server_info,
 		},
 		federation::{authentication::XMatrix, openid::get_openid_userinfo},
@@ -23,6 +37,7 @@
 use service::{
 	Services,
 	server_keys::{PubKeyMap, PubKeys},
+	users::AdminIssuedToken,
 };
 
 use super::request::Request;
@@ -57,10 +72,7 @@ pub(super) async fn auth(
 	let token = if let Some(token) = token {
 		match services.appservice.find_from_token(token).await {
 			| Some(reg_info) => Token::Appservice(Box::new(reg_info)),
-			| _ => match services.users.find_from_token(token).await {
-				| Ok((user_id, device_id)) => Token::User((user_id, device_id)),
-				| _ => Token::Invalid,
-			},
+			| _ => find_user_from_token(services, token, metadata).await?,
 		}
 	} else {
 		Token::None
@@ -114,7 +126,7 @@ pub(super) async fn auth(
 
 	match (metadata.authentication, token) {
 		| (AuthScheme::AccessToken, Token::Appservice(info)) =>
-			Ok(auth_appservice(services, request, info).await?),
+			Ok(auth_appservice(services, request, info, metadata).await?),
 		| (
 			AuthScheme::None | AuthScheme::AccessTokenOptional | AuthScheme::AppserviceToken,
 			Token::Appservice(info),
@@ -142,12 +154,36 @@ pub(super) async fn auth(
 		| (
 			AuthScheme::AccessToken | AuthScheme::AccessTokenOptional | AuthScheme::None,
 			Token::User((user_id, device_id)),
-		) => Ok(Auth {
-			origin: None,
-			sender_user: Some(user_id),
-			sender_device: Some(device_id),
-			appservice_info: None,
-		}),
+		) => {
+			let client_ip = request
+				.parts
+				.extract::<InsecureClientIp>()
+				.await
+				.ok()
+				.map(|InsecureClientIp(ip)| ip);
+
+			check_user_restrictions(services, &user_id, metadata).await?;
+
+			if let Ok(token) = services
+				.users
+				.admin_issued_token(&user_id, &device_id)
+				.await
+			{
+				check_admin_issued_token(services, request, &user_id, &device_id, &token).await?;
+			}
+
+			services
+				.users
+				.update_device_last_seen(&user_id, &device_id, client_ip)
+				.await;
+
+			Ok(Auth {
+				origin: None,
+				sender_user: Some(user_id),
+				sender_device: Some(device_id),
+				appservice_info: None,
+			})
+		},
 		| (AuthScheme::ServerSignatures, Token::None) =>
 			Ok(auth_server(services, request, json_body).await?),
 		| (
@@ -195,10 +231,117 @@ pub(super) async fn auth(
 	}
 }
 
+/// Rejects every request of locked users (MSC3939), and requests of
+/// suspended users (MSC3823) to endpoints which would change state.
+async fn check_user_restrictions(
+	services: &Services,
+	user_id: &UserId,
+	metadata: &Metadata,
+) -> Result {
+	let restrictions = services.users.restrictions(user_id).await;
+	if restrictions.locked {
+		return Err!(Request(UserLocked("This account has been locked.")));
+	}
+
+	if restrictions.suspended && is_restricted_when_suspended(metadata) {
+		return Err!(Request(UserSuspended("This account has been suspended.")));
+	}
+
+	Ok(())
+}
+
+/// Rejects expired access tokens which were issued by a server admin, and
+/// logs every request made with them.
+async fn check_admin_issued_token(
+	services: &Services,
+	request: &Request,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	token: &AdminIssuedToken,
+) -> Result {
+	if token.is_expired() {
+		services.users.remove_device(user_id, device_id).await;
+		return Err(Error::BadRequest(
+			ErrorKind::UnknownToken { soft_logout: false },
+			"Access token has expired.",
+		));
+	}
+
+	info!(
+		%user_id,
+		%device_id,
+		issued_by = %token.issued_by,
+		method = %request.parts.method,
+		path = %request.parts.uri.path(),
+		"Request made with admin-issued access token"
+	);
+
+	Ok(())
+}
+
+fn is_restricted_when_suspended(metadata: &Metadata) -> bool {
+	matches!(
+		metadata,
+		&ban_user::v3::Request::METADATA
+			| &create_alias::v3::Request::METADATA
+			| &create_content::v3::Request::METADATA
+			| &create_room::v3::Request::METADATA
+			| &delete_alias::v3::Request::METADATA
+			| &delete_profile_key::unstable::Request::METADATA
+			| &delete_timezone_key::unstable::Request::METADATA
+			| &invite_user::v3::Request::METADATA
+			| &join_room_by_id::v3::Request::METADATA
+			| &join_room_by_id_or_alias::v3::Request::METADATA
+			| &kick_user::v3::Request::METADATA
+			| &knock_room::v3::Request::METADATA
+			| &send_message_event::v3::Request::METADATA
+			| &send_state_event::v3::Request::METADATA
+			| &set_avatar_url::v3::Request::METADATA
+			| &set_display_name::v3::Request::METADATA
+			| &set_profile_key::unstable::Request::METADATA
+			| &set_room_visibility::v3::Request::METADATA
+			| &set_timezone_key::unstable::Request::METADATA
+			| &unban_user::v3::Request::METADATA
+			| &upgrade_room::v3::Request::METADATA
+	)
+}
+
+async fn find_user_from_token(
+	services: &Services,
+	token: &str,
+	metadata: &Metadata,
+) -> Result<Token> {
+	if services.oauth.is_delegated() {
+		return match services.oauth.find_from_token(token).await {
+			| Ok(found) => Ok(found.map_or(Token::Invalid, Token::User)),
+			// Routes which don't require authentication are still served while the
+			// authorization server can't be reached.
+			| Err(e)
+				if !matches!(e, Error::Request(..))
+					&& matches!(
+						metadata.authentication,
+						AuthScheme::None | AuthScheme::AccessTokenOptional
+					) =>
+			{
+				debug_warn!("Serving request unauthenticated, token introspection failed: {e}");
+				Ok(Token::None)
+			},
+			| Err(e) => Err(e),
+		};
+	}
+
+	Ok(services
+		.users
+		.find_from_token(token)
+		.await
+		.map_or(Token::Invalid, Token::User))
+}
+
 async fn auth_appservice(
 	services: &Services,
 	request: &Request,
 	info: Box<RegistrationInfo>,
+	metadata: &Metadata,
 ) -> Result<Auth> {
 	let user_id_default = || {
 		UserId::parse_with_server_name(
@@ -220,6 +363,9 @@ async fn auth_appservice(
 		return Err!(Request(Exclusive("User is not in namespace.")));
 	}
 
+	// Masquerading must not get around the suspension or lock of a user.
+	check_user_restrictions(services, &user_id, metadata).await?;
+
 	Ok(Auth {
 		origin: None,
 		sender_user: Some(user_id),
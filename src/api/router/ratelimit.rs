@@ -0,0 +1,73 @@
+use axum::RequestPartsExt;
+use axum_client_ip::SecureClientIp;
+use conduwuit::Result;
+use ruma::api::{
+	Metadata,
+	client::{
+		account::{check_registration_token_validity, register},
+		knock::knock_room,
+		media::create_content,
+		membership::{join_room_by_id, join_room_by_id_or_alias},
+		message::send_message_event,
+		session::login,
+	},
+	federation::{
+		backfill::get_backfill,
+		event::{get_missing_events, get_room_state, get_room_state_ids},
+	},
+};
+use service::{Services, ratelimit::Class};
+
+use super::{auth::Auth, request::Request};
+
+/// Rate limits the request if its endpoint has a budget. Requests of local
+/// users are limited per user, requests of remote servers per origin and
+/// other requests per client IP address. Appservices are exempt.
+pub(super) async fn check(
+	services: &Services,
+	request: &mut Request,
+	auth: &Auth,
+	metadata: &Metadata,
+) -> Result {
+	let Some(class) = class(metadata) else {
+		return Ok(());
+	};
+
+	if auth.appservice_info.is_some() {
+		return Ok(());
+	}
+
+	if let Some(origin) = &auth.origin {
+		return services.ratelimit.check_expensive(origin);
+	}
+
+	if let Some(sender_user) = &auth.sender_user {
+		return services.ratelimit.check_user(class, sender_user).await;
+	}
+
+	// The address of the connection; forwarding headers are set by the client and
+	// would let it pick a fresh bucket for every request.
+	let Ok(SecureClientIp(client_ip)) = request.parts.extract::<SecureClientIp>().await else {
+		return Ok(());
+	};
+
+	services.ratelimit.check(class, &client_ip.to_string())
+}
+
+fn class(metadata: &Metadata) -> Option<Class> {
+	match metadata {
+		| &login::v3::Request::METADATA => Some(Class::Login),
+		| &register::v3::Request::METADATA
+		| &check_registration_token_validity::v1::Request::METADATA => Some(Class::Register),
+		| &send_message_event::v3::Request::METADATA => Some(Class::Message),
+		| &join_room_by_id::v3::Request::METADATA
+		| &join_room_by_id_or_alias::v3::Request::METADATA
+		| &knock_room::v3::Request::METADATA => Some(Class::Join),
+		| &create_content::v3::Request::METADATA => Some(Class::MediaUpload),
+		| &get_room_state::v1::Request::METADATA
+		| &get_room_state_ids::v1::Request::METADATA
+		| &get_backfill::v1::Request::METADATA
+		| &get_missing_events::v1::Request::METADATA => Some(Class::FederationExpensive),
+		| _ => None,
+	}
+}
@@ -96,6 +96,9 @@ pub(crate) async fn create_invite_route(
 		.try_into()
 		.map_err(|e| err!(Request(InvalidParam("Invalid sender property: {e}"))))?;
 
+	services.policy.check_user(sender, "invite").await?;
+	services.policy.check_room(&body.room_id, "invite").await?;
+
 	if services.rooms.metadata.is_banned(&body.room_id).await
 		&& !services.users.is_admin(&invited_user).await
 	{
@@ -68,6 +68,8 @@ pub(crate) async fn create_join_event_template_route(
 		}
 	}
 
+	services.policy.check_user(&body.user_id, "join").await?;
+
 	let room_version_id = services.rooms.state.get_room_version(&body.room_id).await?;
 	if !body.ver.contains(&room_version_id) {
 		return Err(Error::BadRequest(
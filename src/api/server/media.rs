@@ -30,6 +30,10 @@ pub(crate) async fn get_content_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_quarantined(&mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	let Some(FileMeta {
 		content,
 		content_type,
@@ -73,6 +77,10 @@ pub(crate) async fn get_content_thumbnail_route(
 		media_id: &body.media_id,
 	};
 
+	if services.media.is_quarantined(&mxc).await {
+		return Err!(Request(NotFound("Media is quarantined.")));
+	}
+
 	let Some(FileMeta {
 		content,
 		content_type,
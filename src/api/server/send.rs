@@ -78,6 +78,15 @@ pub(crate) async fn send_transaction_message_route(
 		)));
 	}
 
+	services
+		.policy
+		.check_server(body.origin(), "federation transaction")
+		.await?;
+
+	let _transaction = services
+		.ratelimit
+		.check_transaction(body.origin(), body.pdus.len())?;
+
 	let txn_start_time = Instant::now();
 	trace!(
 		pdus = body.pdus.len(),
@@ -3,7 +3,7 @@
 use either::Either;
 use figment::Figment;
 
-use super::DEPRECATED_KEYS;
+use super::{DEPRECATED_KEYS, DatabaseBackend};
 use crate::{Config, Err, Result, Server, debug, debug_info, debug_warn, error, warn};
 
 /// Performs check() with additional checks specific to reloading old config
@@ -106,6 +106,13 @@ pub fn check(config: &Config) -> Result {
 		});
 	}
 
+	if config.database_backend == DatabaseBackend::Memory {
+		warn!(
+			"Using the in-memory database backend. All data will be lost when conduwuit shuts \
+			 down. This is only intended for tests and ephemeral servers."
+		);
+	}
+
 	// rocksdb does not allow max_log_files to be 0
 	if config.rocksdb_max_log_files == 0 {
 		return Err!(Config(
@@ -261,6 +268,52 @@ pub fn check(config: &Config) -> Result {
 		}
 	}
 
+	if config.oauth.enable && config.oauth.issuer.is_none() {
+		return Err!(Config(
+			"oauth.issuer",
+			"Delegating authentication to an OAuth 2.0 server requires its issuer URL to be set."
+		));
+	}
+
+	if config.oauth.enable && config.allow_registration {
+		warn!(
+			"`allow_registration` has no effect while authentication is delegated to an OAuth \
+			 2.0 server; accounts are provisioned by the authorization server instead."
+		);
+	}
+
+	for (key, per_second) in [
+		("client_ratelimit_login_per_second", config.client_ratelimit_login_per_second),
+		(
+			"client_ratelimit_register_per_second",
+			config.client_ratelimit_register_per_second,
+		),
+		(
+			"client_ratelimit_message_per_second",
+			config.client_ratelimit_message_per_second,
+		),
+		("client_ratelimit_join_per_second", config.client_ratelimit_join_per_second),
+		(
+			"client_ratelimit_media_upload_per_second",
+			config.client_ratelimit_media_upload_per_second,
+		),
+		(
+			"federation_ratelimit_pdus_per_second",
+			config.federation_ratelimit_pdus_per_second,
+		),
+		(
+			"federation_ratelimit_expensive_per_second",
+			config.federation_ratelimit_expensive_per_second,
+		),
+	] {
+		if !per_second.is_finite() || per_second <= 0.0 {
+			return Err!(Config(
+				"ratelimit",
+				"{key} must be a finite number greater than zero, got {per_second}."
+			));
+		}
+	}
+
 	if !Server::available_room_versions()
 		.any(|(version, _)| version == config.default_room_version)
 	{
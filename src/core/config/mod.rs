@@ -52,7 +52,7 @@
 ### For more information, see:
 ### https://conduwuit.puppyirl.gay/configuration.html
 "#,
-	ignore = "catchall well_known tls blurhashing allow_invalid_tls_certificates_yes_i_know_what_the_fuck_i_am_doing_with_this_and_i_know_this_is_insecure"
+	ignore = "catchall well_known tls blurhashing oauth allow_invalid_tls_certificates_yes_i_know_what_the_fuck_i_am_doing_with_this_and_i_know_this_is_insecure"
 )]
 pub struct Config {
 	/// The server_name is the pretty name of this server. It is used as a
@@ -129,6 +129,15 @@ pub struct Config {
 	/// example: "/var/lib/conduwuit"
 	pub database_path: PathBuf,
 
+	/// Storage engine for the database. "rocksdb" stores the database in
+	/// `database_path`. "memory" keeps the database in memory only and loses
+	/// everything on shutdown; it is intended for tests and ephemeral servers.
+	/// Media is still stored under `database_path` with either engine.
+	///
+	/// default: "rocksdb"
+	#[serde(default)]
+	pub database_backend: DatabaseBackend,
+
 	/// conduwuit supports online database backups using RocksDB's Backup engine
 	/// API. To use this, set a database backup path that conduwuit can write
 	/// to.
@@ -146,6 +155,15 @@ pub struct Config {
 	#[serde(default = "default_database_backups_to_keep")]
 	pub database_backups_to_keep: i16,
 
+	/// Directory to write per-user data exports to, created with the
+	/// `!admin user export` command. Each export is a directory of JSON lines
+	/// files along with the user's uploaded media.
+	///
+	/// User exports are disabled if this is not set.
+	///
+	/// example: "/opt/conduwuit-user-exports"
+	pub user_export_path: Option<PathBuf>,
+
 	/// Text which will be added to the end of the user's displayname upon
 	/// registration with a space before the text. In Conduit, this was the
 	/// lightning bolt emoji.
@@ -550,6 +568,16 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub allow_federation: bool,
 
+	/// Enables a subset of the Synapse admin API under `/_synapse/admin`, for
+	/// use with existing moderation tools and admin dashboards. Requests must
+	/// be made with the access token of a server admin.
+	///
+	/// This consists of listing, querying, deactivating and resetting the
+	/// password of users; listing, inspecting and deleting rooms; listing and
+	/// quarantining media; and listing registration tokens and event reports.
+	#[serde(default)]
+	pub allow_synapse_admin_api: bool,
+
 	/// Allows federation requests to be made to itself
 	///
 	/// This isn't intended and is very likely a bug if federation requests are
@@ -603,6 +631,14 @@ pub struct Config {
 	#[serde(default)]
 	pub lockdown_public_room_directory: bool,
 
+	/// Set this to true to let the user directory search return every local
+	/// user. By default only users sharing a room with the searching user or
+	/// joined to a public room are returned. Remote users are never returned
+	/// unless they share a room with the searching user or are in a public
+	/// room.
+	#[serde(default)]
+	pub user_directory_search_all_users: bool,
+
 	/// Set this to true to allow federating device display names / allow
 	/// external users to see your device display name. If federation is
 	/// disabled entirely (`allow_federation`), this is inherently false. For
@@ -631,7 +667,7 @@ pub struct Config {
 	/// Set to false to disable users from joining or creating room versions
 	/// that aren't officially supported by conduwuit.
 	///
-	/// conduwuit officially supports room versions 6 - 11.
+	/// conduwuit officially supports room versions 6 - 12.
 	///
 	/// conduwuit has slightly experimental (though works fine in practice)
 	/// support for versions 3 - 5.
@@ -823,6 +859,73 @@ pub struct Config {
 	#[serde(default = "default_login_token_ttl")]
 	pub login_token_ttl: u64,
 
+	/// Enables the rendezvous session API which allows signing in a new device
+	/// by scanning a QR code from an existing one (MSC4108). Sessions are only
+	/// kept in memory.
+	///
+	/// default: true
+	#[serde(default = "true_fn")]
+	pub allow_rendezvous: bool,
+
+	/// Lifetime of a rendezvous session in seconds. A session expires after
+	/// this time regardless of activity.
+	///
+	/// default: 300
+	#[serde(default = "default_rendezvous_session_ttl")]
+	pub rendezvous_session_ttl: u64,
+
+	/// Maximum number of concurrent rendezvous sessions. New sessions are
+	/// rejected while this many are active.
+	///
+	/// default: 1024
+	#[serde(default = "default_rendezvous_max_sessions")]
+	pub rendezvous_max_sessions: usize,
+
+	/// Maximum size in bytes of the payload stored in a rendezvous session.
+	///
+	/// default: 4096
+	#[serde(default = "default_rendezvous_max_content_length")]
+	pub rendezvous_max_content_length: usize,
+
+	/// Minimum time in seconds between updates of a device's last-seen
+	/// timestamp and IP address. Authenticated requests refresh these values
+	/// at most this often, unless the IP address changed.
+	///
+	/// default: 300
+	#[serde(default = "default_device_last_seen_update_interval")]
+	pub device_last_seen_update_interval: u64,
+
+	/// Automatically delete devices which have not been seen for this many
+	/// seconds, along with their keys and queued to-device messages. Devices
+	/// of appservice users and devices without an access token (such as
+	/// dehydrated devices) are never pruned.
+	///
+	/// For example, 7776000 prunes devices unused for 90 days. Set to 0 to
+	/// disable.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub prune_stale_devices_after: u64,
+
+	/// Interval in seconds at which stale devices are searched for and pruned,
+	/// if `prune_stale_devices_after` is non-zero. Cached device bookkeeping is
+	/// cleaned up and devices of expired admin-issued tokens are removed at the
+	/// same interval.
+	///
+	/// default: 3600
+	#[serde(default = "default_prune_stale_devices_interval")]
+	pub prune_stale_devices_interval: u64,
+
+	/// Number of seconds the original content of redacted events is kept for,
+	/// so that room moderators can still review it through MSC2815 and server
+	/// admins through `!admin debug get-unredacted-content`. Expired content is
+	/// purged hourly. Set to 0 to discard content immediately on redaction.
+	/// The content of events sent by erased users is never kept.
+	///
+	/// default: 604800
+	#[serde(default = "default_redacted_content_retention")]
+	pub redacted_content_retention: u64,
+
 	/// Static TURN username to provide the client if not using a shared secret
 	/// ("turn_secret"), It is recommended to use a shared secret over static
 	/// credentials.
@@ -1396,6 +1499,163 @@ pub struct Config {
 	#[serde(default, with = "serde_regex")]
 	pub forbidden_remote_room_directory_server_names: RegexSet,
 
+	/// Set this to true to have events checked by the policy server of rooms
+	/// which opted in to one (MSC4284). A room opts in with an
+	/// `org.matrix.msc4284.policy` state event naming the server in `via`.
+	/// Events the policy server considers spam are soft-failed when received
+	/// over federation and refused when sent by our users.
+	#[serde(default)]
+	pub spam_check_policy_servers: bool,
+
+	/// URL of a local webhook which vets events instead of the rooms' policy
+	/// servers. It is sent `{"event_id": ..., "pdu": ...}` for events in rooms
+	/// which opted in to a policy server, and answers with `{"action":
+	/// "allow"}`, `"soft_fail"` or `"reject"`.
+	///
+	/// Setting this enables spam checking regardless of
+	/// `spam_check_policy_servers`.
+	///
+	/// example: "http://127.0.0.1:8009/check"
+	pub spam_check_webhook: Option<Url>,
+
+	/// Maximum time to wait for a spam check (seconds).
+	///
+	/// default: 5
+	#[serde(default = "default_spam_check_timeout")]
+	pub spam_check_timeout: u64,
+
+	/// Whether events are allowed when the spam checker fails or times out.
+	/// If false, such events are soft-failed.
+	#[serde(default = "true_fn")]
+	pub spam_check_fail_open: bool,
+
+	/// Set this to false to disable rate limiting of the client API. Requests
+	/// exceeding their budget are answered with `M_LIMIT_EXCEEDED` and a
+	/// `retry_after_ms` hint. Appservices and server admins are never limited.
+	#[serde(default = "true_fn")]
+	pub client_ratelimit: bool,
+
+	/// Login attempts allowed per second from one IP address.
+	///
+	/// default: 0.17
+	#[serde(default = "default_client_ratelimit_login_per_second")]
+	pub client_ratelimit_login_per_second: f64,
+
+	/// Login attempts allowed in a burst before
+	/// `client_ratelimit_login_per_second` applies.
+	///
+	/// default: 3
+	#[serde(default = "default_client_ratelimit_login_burst")]
+	pub client_ratelimit_login_burst: u32,
+
+	/// Password login attempts allowed per second for one account, from any
+	/// IP address.
+	///
+	/// default: 0.17
+	#[serde(default = "default_client_ratelimit_login_account_per_second")]
+	pub client_ratelimit_login_account_per_second: f64,
+
+	/// Password login attempts allowed for one account in a burst before
+	/// `client_ratelimit_login_account_per_second` applies.
+	///
+	/// default: 3
+	#[serde(default = "default_client_ratelimit_login_account_burst")]
+	pub client_ratelimit_login_account_burst: u32,
+
+	/// Registrations allowed per second from one IP address.
+	///
+	/// default: 0.17
+	#[serde(default = "default_client_ratelimit_register_per_second")]
+	pub client_ratelimit_register_per_second: f64,
+
+	/// Registrations allowed in a burst before
+	/// `client_ratelimit_register_per_second` applies. Every step of the
+	/// interactive registration flow counts.
+	///
+	/// default: 6
+	#[serde(default = "default_client_ratelimit_register_burst")]
+	pub client_ratelimit_register_burst: u32,
+
+	/// Messages a user may send per second.
+	///
+	/// default: 0.2
+	#[serde(default = "default_client_ratelimit_message_per_second")]
+	pub client_ratelimit_message_per_second: f64,
+
+	/// Messages a user may send in a burst before
+	/// `client_ratelimit_message_per_second` applies.
+	///
+	/// default: 10
+	#[serde(default = "default_client_ratelimit_message_burst")]
+	pub client_ratelimit_message_burst: u32,
+
+	/// Rooms a user may join or knock on per second.
+	///
+	/// default: 0.1
+	#[serde(default = "default_client_ratelimit_join_per_second")]
+	pub client_ratelimit_join_per_second: f64,
+
+	/// Rooms a user may join or knock on in a burst before
+	/// `client_ratelimit_join_per_second` applies.
+	///
+	/// default: 10
+	#[serde(default = "default_client_ratelimit_join_burst")]
+	pub client_ratelimit_join_burst: u32,
+
+	/// Media uploads a user may make per second.
+	///
+	/// default: 0.2
+	#[serde(default = "default_client_ratelimit_media_upload_per_second")]
+	pub client_ratelimit_media_upload_per_second: f64,
+
+	/// Media uploads a user may make in a burst before
+	/// `client_ratelimit_media_upload_per_second` applies.
+	///
+	/// default: 10
+	#[serde(default = "default_client_ratelimit_media_upload_burst")]
+	pub client_ratelimit_media_upload_burst: u32,
+
+	/// Set this to false to disable rate limiting of incoming federation.
+	/// Remote servers exceeding their budget are answered with
+	/// `M_LIMIT_EXCEEDED`. Admins can further throttle a misbehaving server
+	/// with `!admin federation throttle`.
+	#[serde(default = "true_fn")]
+	pub federation_ratelimit: bool,
+
+	/// Transactions of one remote server processed at the same time. Set to 0
+	/// for no limit.
+	///
+	/// default: 3
+	#[serde(default = "default_federation_ratelimit_concurrent_transactions")]
+	pub federation_ratelimit_concurrent_transactions: usize,
+
+	/// PDUs a remote server may send us per second.
+	///
+	/// default: 100
+	#[serde(default = "default_federation_ratelimit_pdus_per_second")]
+	pub federation_ratelimit_pdus_per_second: f64,
+
+	/// PDUs a remote server may send us in a burst before
+	/// `federation_ratelimit_pdus_per_second` applies.
+	///
+	/// default: 1000
+	#[serde(default = "default_federation_ratelimit_pdus_burst")]
+	pub federation_ratelimit_pdus_burst: u32,
+
+	/// Requests to expensive endpoints (`/state`, `/state_ids`, `/backfill`
+	/// and `/get_missing_events`) a remote server may make per second.
+	///
+	/// default: 2
+	#[serde(default = "default_federation_ratelimit_expensive_per_second")]
+	pub federation_ratelimit_expensive_per_second: f64,
+
+	/// Requests to expensive endpoints a remote server may make in a burst
+	/// before `federation_ratelimit_expensive_per_second` applies.
+	///
+	/// default: 50
+	#[serde(default = "default_federation_ratelimit_expensive_burst")]
+	pub federation_ratelimit_expensive_burst: u32,
+
 	/// Vector list of IPv4 and IPv6 CIDR ranges / subnets *in quotes* that you
 	/// do not want conduwuit to send outbound requests to. Defaults to
 	/// RFC1918, unroutable, loopback, multicast, and testnet addresses for
@@ -1695,6 +1955,28 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub admin_room_notices: bool,
 
+	/// Localpart of the system user which sends server notices. Each user
+	/// receiving a notice is invited to a room of their own with this user,
+	/// tagged `m.server_notice` so clients can show it as system alerts. The
+	/// name cannot be registered; if an account of this name exists already,
+	/// notices are refused instead of being sent from it.
+	///
+	/// default: "notices"
+	#[serde(default = "default_server_notices_localpart")]
+	pub server_notices_localpart: String,
+
+	/// Display name of the server notices system user.
+	///
+	/// default: "Server Notices"
+	#[serde(default = "default_server_notices_displayname")]
+	pub server_notices_displayname: String,
+
+	/// Name of the rooms server notices are sent in.
+	///
+	/// default: "Server Notices"
+	#[serde(default = "default_server_notices_room_name")]
+	pub server_notices_room_name: String,
+
 	/// Enable database pool affinity support. On supporting systems, block
 	/// device queue topologies are detected and the request pool is optimized
 	/// for the hardware; db_pool_workers is determined automatically.
@@ -1816,6 +2098,11 @@ pub struct Config {
 	// external structure; separate section
 	#[serde(default)]
 	pub blurhashing: BlurhashConfig,
+
+	// external structure; separate section
+	#[serde(default)]
+	pub oauth: OAuthConfig,
+
 	#[serde(flatten)]
 	#[allow(clippy::zero_sized_map_values)]
 	// this is a catchall, the map shouldn't be zero at runtime
@@ -1891,6 +2178,83 @@ pub struct BlurhashConfig {
 	pub blurhash_max_raw_size: u64,
 }
 
+#[derive(Clone, Debug, Deserialize, Default)]
+#[allow(rustdoc::broken_intra_doc_links, rustdoc::bare_urls)]
+#[config_example_generator(filename = "conduwuit-example.toml", section = "global.oauth")]
+pub struct OAuthConfig {
+	/// Delegates authentication to an external OAuth 2.0 / OpenID Connect
+	/// authorization server (MSC3861). Access tokens are introspected
+	/// against the server (RFC 7662) instead of being looked up locally.
+	///
+	/// When enabled, the local login, registration, password change and
+	/// account deactivation endpoints are disabled; users manage their
+	/// account at the authorization server instead.
+	#[serde(default)]
+	pub enable: bool,
+
+	/// The issuer URL of the authorization server. The server metadata is
+	/// discovered from `{issuer}/.well-known/openid-configuration` and
+	/// advertised to clients through `/auth_metadata`.
+	///
+	/// example: "https://auth.example.com/"
+	pub issuer: Option<Url>,
+
+	/// Overrides the token introspection endpoint of the authorization
+	/// server. If unset, the endpoint is taken from the discovered server
+	/// metadata.
+	///
+	/// example: "https://auth.example.com/oauth2/introspect"
+	pub introspection_endpoint: Option<Url>,
+
+	/// Client ID this homeserver uses to authenticate to the introspection
+	/// endpoint.
+	pub client_id: Option<String>,
+
+	/// Client secret this homeserver uses to authenticate to the
+	/// introspection endpoint (client_secret_basic).
+	///
+	/// display: sensitive
+	pub client_secret: Option<String>,
+
+	/// URL where users can manage their account at the authorization server.
+	/// This is advertised to clients in the server metadata.
+	///
+	/// example: "https://auth.example.com/account/"
+	pub account_management_url: Option<Url>,
+
+	/// Number of seconds an introspection result is cached for. Tokens which
+	/// expire sooner are cached only until their expiry. Set to 0 to
+	/// introspect every request.
+	///
+	/// default: 60
+	#[serde(default = "default_oauth_introspection_cache_ttl")]
+	pub introspection_cache_ttl: u64,
+
+	/// Maximum number of introspected tokens to keep cached.
+	///
+	/// default: 10000
+	#[serde(default = "default_oauth_introspection_cache_capacity")]
+	pub introspection_cache_capacity: u32,
+
+	/// Timeout in seconds for requests made to the authorization server.
+	///
+	/// default: 10
+	#[serde(default = "default_oauth_request_timeout")]
+	pub request_timeout: u64,
+}
+
+/// Storage engine for the database.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseBackend {
+	/// Stores the database in `database_path`.
+	#[default]
+	RocksDb,
+
+	/// Keeps the database in memory only.
+	Memory,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(transparent)]
 struct ListeningPort {
@@ -2124,6 +2488,18 @@ fn default_openid_token_ttl() -> u64 { 60 * 60 }
 
 fn default_login_token_ttl() -> u64 { 2 * 60 * 1000 }
 
+fn default_rendezvous_session_ttl() -> u64 { 5 * 60 }
+
+fn default_rendezvous_max_sessions() -> usize { 1024 }
+
+fn default_rendezvous_max_content_length() -> usize { 4096 }
+
+fn default_device_last_seen_update_interval() -> u64 { 5 * 60 }
+
+fn default_prune_stale_devices_interval() -> u64 { 60 * 60 }
+
+fn default_redacted_content_retention() -> u64 { 60 * 60 * 24 * 7 }
+
 fn default_turn_ttl() -> u64 { 60 * 60 * 24 }
 
 fn default_presence_idle_timeout_s() -> u64 { 5 * 60 }
@@ -2207,6 +2583,48 @@ fn default_url_preview_max_spider_size() -> usize {
 
 fn default_new_user_displayname_suffix() -> String { "🏳️‍⚧️".to_owned() }
 
+fn default_spam_check_timeout() -> u64 { 5 }
+
+fn default_server_notices_localpart() -> String { "notices".to_owned() }
+
+fn default_server_notices_displayname() -> String { "Server Notices".to_owned() }
+
+fn default_server_notices_room_name() -> String { "Server Notices".to_owned() }
+
+fn default_client_ratelimit_login_per_second() -> f64 { 0.17 }
+
+fn default_client_ratelimit_login_burst() -> u32 { 3 }
+
+fn default_client_ratelimit_login_account_per_second() -> f64 { 0.17 }
+
+fn default_client_ratelimit_login_account_burst() -> u32 { 3 }
+
+fn default_client_ratelimit_register_per_second() -> f64 { 0.17 }
+
+fn default_client_ratelimit_register_burst() -> u32 { 6 }
+
+fn default_client_ratelimit_message_per_second() -> f64 { 0.2 }
+
+fn default_client_ratelimit_message_burst() -> u32 { 10 }
+
+fn default_client_ratelimit_join_per_second() -> f64 { 0.1 }
+
+fn default_client_ratelimit_join_burst() -> u32 { 10 }
+
+fn default_client_ratelimit_media_upload_per_second() -> f64 { 0.2 }
+
+fn default_client_ratelimit_media_upload_burst() -> u32 { 10 }
+
+fn default_federation_ratelimit_concurrent_transactions() -> usize { 3 }
+
+fn default_federation_ratelimit_pdus_per_second() -> f64 { 100.0 }
+
+fn default_federation_ratelimit_pdus_burst() -> u32 { 1000 }
+
+fn default_federation_ratelimit_expensive_per_second() -> f64 { 2.0 }
+
+fn default_federation_ratelimit_expensive_burst() -> u32 { 50 }
+
 fn default_sentry_endpoint() -> Option<Url> {
 	Url::parse("https://fe2eb4536aa04949e28eff3128d64757@o4506996327251968.ingest.us.sentry.io/4506996334657536").ok()
 }
@@ -2273,3 +2691,9 @@ pub(super) fn default_blurhash_x_component() -> u32 { 4 }
 pub(super) fn default_blurhash_y_component() -> u32 { 3 }
 
 // end recommended & blurhashing defaults
+
+fn default_oauth_introspection_cache_ttl() -> u64 { 60 }
+
+fn default_oauth_introspection_cache_capacity() -> u32 { 10_000 }
+
+fn default_oauth_request_timeout() -> u64 { 10 }
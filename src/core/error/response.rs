@@ -8,6 +8,7 @@
 		uiaa::UiaaResponse,
 	},
 };
+use serde_json::json;
 
 use super::Error;
 use crate::error;
@@ -32,9 +33,15 @@ fn from(error: Error) -> Self {
 			return Self::AuthResponse(uiaainfo);
 		}
 
-		let body = ErrorBody::Standard {
-			kind: error.kind(),
-			message: error.message(),
+		let body = match error.kind() {
+			// MSC3939: locked out clients keep their session, so they can resume once
+			// the account is unlocked.
+			| ErrorKind::UserLocked => ErrorBody::Json(json!({
+				"errcode": "M_USER_LOCKED",
+				"error": error.message(),
+				"soft_logout": true,
+			})),
+			| kind => ErrorBody::Standard { kind, message: error.message() },
 		};
 
 		Self::MatrixError(ruma::api::client::error::Error {
@@ -72,12 +79,14 @@ pub(super) fn bad_request_code(kind: &ErrorKind) -> StatusCode {
 		| GuestAccessForbidden
 		| ThreepidAuthFailed
 		| UserDeactivated
+		| UserSuspended
 		| ThreepidDenied
 		| WrongRoomKeysVersion { .. }
 		| Forbidden { .. } => StatusCode::FORBIDDEN,
 
 		// 401
-		| UnknownToken { .. } | MissingToken | Unauthorized => StatusCode::UNAUTHORIZED,
+		| UnknownToken { .. } | MissingToken | Unauthorized | UserLocked =>
+			StatusCode::UNAUTHORIZED,
 
 		// 400
 		| _ => StatusCode::BAD_REQUEST,
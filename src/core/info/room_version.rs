@@ -22,6 +22,24 @@
 
 type RoomVersion = (RoomVersionId, RoomVersionStability);
 
+/// Room version 12 is not known to ruma yet and is represented by a custom
+/// version identifier.
+#[must_use]
+pub fn room_version_12() -> RoomVersionId {
+	RoomVersionId::try_from("12").expect("valid room version identifier")
+}
+
+/// The room version whose event format, hashing, signing and redaction rules
+/// ruma should apply for `version`. Room version 12 did not change them from
+/// room version 11.
+#[must_use]
+pub fn event_rules_version(version: &RoomVersionId) -> &RoomVersionId {
+	match version.as_str() {
+		| "12" => &RoomVersionId::V11,
+		| _ => version,
+	}
+}
+
 impl crate::Server {
 	#[inline]
 	pub fn supported_room_version(&self, version: &RoomVersionId) -> bool {
@@ -56,5 +74,6 @@ pub fn available_room_versions() -> impl Iterator<Item = RoomVersion> {
 		.iter()
 		.cloned()
 		.zip(once(RoomVersionStability::Stable).cycle())
+		.chain(once((room_version_12(), RoomVersionStability::Stable)))
 		.chain(unstable_room_versions)
 }
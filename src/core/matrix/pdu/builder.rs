@@ -10,7 +10,7 @@
 use super::StateKey;
 
 /// Build the start of a PDU in order to add it to the Database.
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Builder {
 	#[serde(rename = "type")]
 	pub event_type: TimelineEventType,
@@ -1,7 +1,12 @@
-use ruma::{CanonicalJsonObject, OwnedEventId, RoomVersionId};
+use ruma::{
+	CanonicalJsonObject, CanonicalJsonValue, EventId, OwnedEventId, OwnedRoomId, RoomId,
+	RoomVersionId,
+};
 use serde_json::value::RawValue as RawJsonValue;
 
-use crate::{Result, err};
+use crate::{
+	Result, err, info::room_version::event_rules_version, matrix::state_res::RoomVersion,
+};
 
 /// Generates a correct eventId for the incoming pdu.
 ///
@@ -19,13 +24,61 @@ pub fn gen_event_id_canonical_json(
 	Ok((event_id, value))
 }
 
+/// Adds the `room_id` derived from the event ID to an `m.room.create` event of
+/// a room version where it is omitted from the event. The room ID is not part
+/// of what was hashed and signed, so this must only be called once the event
+/// was verified as received; it is stripped again before the event is sent.
+pub fn add_create_room_id(
+	value: &mut CanonicalJsonObject,
+	event_id: &EventId,
+	room_version_id: &RoomVersionId,
+) -> Result {
+	let is_create = value
+		.get("type")
+		.and_then(CanonicalJsonValue::as_str)
+		.is_some_and(|kind| kind == "m.room.create");
+
+	if is_create && room_ids_as_hashes(room_version_id) && !value.contains_key("room_id") {
+		let room_id = room_id_from_create_event_id(event_id)?;
+		value.insert("room_id".into(), CanonicalJsonValue::String(room_id.into()));
+	}
+
+	Ok(())
+}
+
 /// Generates a correct eventId for the incoming pdu.
 pub fn gen_event_id(
 	value: &CanonicalJsonObject,
 	room_version_id: &RoomVersionId,
 ) -> Result<OwnedEventId> {
+	let room_version_id = event_rules_version(room_version_id);
 	let reference_hash = ruma::signatures::reference_hash(value, room_version_id)?;
 	let event_id: OwnedEventId = format!("${reference_hash}").try_into()?;
 
 	Ok(event_id)
 }
+
+/// The ID of a room whose `m.room.create` event has `event_id`, in room
+/// versions where room IDs are the reference hash of the create event.
+pub fn room_id_from_create_event_id(event_id: &EventId) -> Result<OwnedRoomId> {
+	let hash = event_id.as_str().trim_start_matches('$');
+	let room_id: OwnedRoomId = format!("!{hash}").try_into()?;
+
+	Ok(room_id)
+}
+
+/// Whether the `m.room.create` event of rooms of this version carries no
+/// `room_id`, which is derived from its event ID instead.
+#[must_use]
+pub fn room_ids_as_hashes(room_version_id: &RoomVersionId) -> bool {
+	RoomVersion::new(room_version_id).is_ok_and(|version| version.room_ids_as_hashes)
+}
+
+/// The ID of the `m.room.create` event of a room whose ID is the reference hash
+/// of that event.
+pub fn create_event_id_from_room_id(room_id: &RoomId) -> Result<OwnedEventId> {
+	let hash = room_id.as_str().trim_start_matches('!');
+	let event_id: OwnedEventId = format!("${hash}").try_into()?;
+
+	Ok(event_id)
+}
@@ -9,7 +9,7 @@
 	value::{RawValue as RawJsonValue, to_raw_value},
 };
 
-use crate::{Error, Result, implement};
+use crate::{Error, Result, implement, info::room_version::event_rules_version};
 
 #[derive(Deserialize)]
 struct ExtractRedactedBecause {
@@ -23,6 +23,7 @@ pub fn redact(&mut self, room_version_id: &RoomVersionId, reason: &Self) -> Resu
 	let mut content = serde_json::from_str(self.content.get())
 		.map_err(|_| Error::bad_database("PDU in db has invalid content."))?;
 
+	let room_version_id = event_rules_version(room_version_id);
 	redact_content_in_place(&mut content, room_version_id, self.kind.to_string())
 		.map_err(|e| Error::Redaction(self.sender.server_name().to_owned(), e))?;
 
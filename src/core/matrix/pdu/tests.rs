@@ -1,4 +1,12 @@
-use super::Count;
+use ruma::{
+	CanonicalJsonObject, RoomVersionId,
+	serde::Base64,
+	signatures::{Ed25519KeyPair, PublicKeyMap, Verified, hash_and_sign_event, verify_event},
+};
+use serde_json::{json, value::to_raw_value};
+
+use super::{Count, Pdu, add_create_room_id, gen_event_id_canonical_json};
+use crate::info::room_version::event_rules_version;
 
 #[test]
 fn backfilled_parse() {
@@ -15,3 +23,46 @@ fn normal_parse() {
 
 	assert!(!backfilled, "backfilled variant");
 }
+
+#[test]
+fn hashed_create_room_id() {
+	let room_version = RoomVersionId::try_from("12").expect("valid room version");
+	let rules = event_rules_version(&room_version);
+
+	let der = Ed25519KeyPair::generate().expect("generated keypair");
+	let keypair = Ed25519KeyPair::from_der(&der, "1".into()).expect("valid keypair");
+	let keys: PublicKeyMap = [(
+		"example.com".to_owned(),
+		[("ed25519:1".to_owned(), Base64::new(keypair.public_key().to_vec()))].into(),
+	)]
+	.into();
+
+	let mut create: CanonicalJsonObject = serde_json::from_value(json!({
+		"type": "m.room.create",
+		"state_key": "",
+		"sender": "@alice:example.com",
+		"content": { "room_version": "12" },
+		"origin_server_ts": 1,
+		"depth": 1,
+		"prev_events": [],
+		"auth_events": [],
+	}))
+	.unwrap();
+	hash_and_sign_event("example.com", &keypair, &mut create, rules).unwrap();
+
+	// The event is verified as it was received, without a room_id
+	let pdu = to_raw_value(&create).unwrap();
+	let (event_id, mut value) = gen_event_id_canonical_json(&pdu, &room_version).unwrap();
+	assert!(!value.contains_key("room_id"));
+	assert!(matches!(verify_event(&keys, &value, rules), Ok(Verified::All)));
+
+	add_create_room_id(&mut value, &event_id, &room_version).unwrap();
+	let pdu = Pdu::from_id_val(&event_id, value.clone()).unwrap();
+	assert_eq!(
+		pdu.room_id.as_str().trim_start_matches('!'),
+		event_id.as_str().trim_start_matches('$')
+	);
+
+	// The derived room_id was never signed
+	assert!(!matches!(verify_event(&keys, &value, rules), Ok(Verified::All)));
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, collections::BTreeSet};
+use std::{borrow::Borrow, collections::BTreeSet, iter::once};
 
 use futures::{
 	Future,
@@ -173,6 +173,7 @@ pub async fn auth_check<F, Fut, Fetched, Incoming>(
 		struct RoomCreateContentFields {
 			room_version: Option<Raw<RoomVersionId>>,
 			creator: Option<Raw<IgnoredAny>>,
+			additional_creators: Option<Vec<Raw<IgnoredAny>>>,
 		}
 
 		debug!("start m.room.create check");
@@ -183,15 +184,28 @@ struct RoomCreateContentFields {
 			return Ok(false);
 		}
 
-		// If the domain of the room_id does not match the domain of the sender, reject
-		let Some(room_id_server_name) = incoming_event.room_id().server_name() else {
-			warn!("room ID has no servername");
-			return Ok(false);
-		};
+		if room_version.room_ids_as_hashes {
+			// The room ID is the reference hash of the create event
+			let hash = incoming_event
+				.event_id()
+				.borrow()
+				.as_str()
+				.trim_start_matches('$');
+			if incoming_event.room_id().as_str().strip_prefix('!') != Some(hash) {
+				warn!("room ID is not the reference hash of the create event");
+				return Ok(false);
+			}
+		} else {
+			// If the domain of the room_id does not match the domain of the sender, reject
+			let Some(room_id_server_name) = incoming_event.room_id().server_name() else {
+				warn!("room ID has no servername");
+				return Ok(false);
+			};
 
-		if room_id_server_name != sender.server_name() {
-			warn!("servername of room ID does not match servername of sender");
-			return Ok(false);
+			if room_id_server_name != sender.server_name() {
+				warn!("servername of room ID does not match servername of sender");
+				return Ok(false);
+			}
 		}
 
 		// If content.room_version is present and is not a recognized version, reject
@@ -212,6 +226,17 @@ struct RoomCreateContentFields {
 			}
 		}
 
+		// If additional_creators is present and not an array of user IDs, reject
+		if room_version.explicitly_privilege_room_creators
+			&& content.additional_creators.is_some_and(|creators| {
+				creators
+					.iter()
+					.any(|creator| creator.deserialize_as::<OwnedUserId>().is_err())
+			}) {
+			warn!("invalid user ID in additional_creators of m.room.create content");
+			return Ok(false);
+		}
+
 		debug!("m.room.create event was allowed");
 		return Ok(true);
 	}
@@ -256,15 +281,34 @@ struct RoomCreateContentFields {
 		| Some(e) => e,
 	};
 
-	// 3. If event does not have m.room.create in auth_events reject
-	if !incoming_event
+	let create_in_auth_events = incoming_event
 		.auth_events()
-		.any(|id| id.borrow() == room_create_event.event_id().borrow())
-	{
+		.any(|id| id.borrow() == room_create_event.event_id().borrow());
+
+	if room_version.room_ids_as_hashes {
+		// The create event is implied by the room ID and must not be listed
+		if create_in_auth_events {
+			warn!("m.room.create event in auth events");
+			return Ok(false);
+		}
+
+		let hash = room_create_event
+			.event_id()
+			.borrow()
+			.as_str()
+			.trim_start_matches('$');
+		if incoming_event.room_id().as_str().strip_prefix('!') != Some(hash) {
+			warn!("room ID does not match the m.room.create event");
+			return Ok(false);
+		}
+	} else if !create_in_auth_events {
+		// 3. If event does not have m.room.create in auth_events reject
 		warn!("no m.room.create event in auth events");
 		return Ok(false);
 	}
 
+	let creators = room_creators(room_version, &room_create_event)?;
+
 	// If the create event content has the field m.federate set to false and the
 	// sender domain of the event does not match the sender domain of the create
 	// event, reject.
@@ -363,6 +407,7 @@ struct RoomCreateContentFederate {
 			user_for_join_auth.as_deref(),
 			&user_for_join_auth_membership,
 			&room_create_event,
+			&creators,
 		)? {
 			return Ok(false);
 		}
@@ -395,6 +440,7 @@ struct RoomCreateContentFederate {
 
 	// If type is m.room.third_party_invite
 	let sender_power_level = match &power_levels_event {
+		| _ if creators.contains(sender) => Int::MAX,
 		| Some(pl) => {
 			let content =
 				deserialize_power_levels_content_fields(pl.content().get(), room_version)?;
@@ -456,6 +502,7 @@ struct RoomCreateContentFederate {
 			incoming_event,
 			power_levels_event.as_ref(),
 			sender_power_level,
+			&creators,
 		) {
 			| Some(required_pwr_lvl) =>
 				if !required_pwr_lvl {
@@ -522,6 +569,7 @@ fn valid_membership_change(
 	user_for_join_auth: Option<&UserId>,
 	user_for_join_auth_membership: &MembershipState,
 	create_room: &impl Event,
+	creators: &BTreeSet<OwnedUserId>,
 ) -> Result<bool> {
 	#[derive(Deserialize)]
 	struct GetThirdPartyInvite {
@@ -549,14 +597,19 @@ struct GetThirdPartyInvite {
 		| None => RoomPowerLevelsEventContent::default(),
 	};
 
-	let sender_power = power_levels
-		.users
-		.get(sender)
+	let sender_power = creators
+		.contains(sender)
+		.then_some(&Int::MAX)
+		.or_else(|| power_levels.users.get(sender))
 		.or_else(|| sender_is_joined.then_some(&power_levels.users_default));
 
-	let target_power = power_levels.users.get(target_user).or_else(|| {
-		(target_membership == MembershipState::Join).then_some(&power_levels.users_default)
-	});
+	let target_power = creators
+		.contains(target_user)
+		.then_some(&Int::MAX)
+		.or_else(|| power_levels.users.get(target_user))
+		.or_else(|| {
+			(target_membership == MembershipState::Join).then_some(&power_levels.users_default)
+		});
 
 	let mut join_rules = JoinRule::Invite;
 	if let Some(jr) = &join_rules_event {
@@ -578,6 +631,7 @@ struct GetThirdPartyInvite {
 			let content =
 				deserialize_power_levels_content_fields(pl.content().get(), room_version)?;
 			let user_pl = match content.get_user_power(user_for_join_auth) {
+				| _ if creators.contains(user_for_join_auth) => Int::MAX,
 				| Some(level) => *level,
 				| _ => content.users_default,
 			};
@@ -810,6 +864,32 @@ struct GetThirdPartyInvite {
 	})
 }
 
+/// The users with infinite power in the room: the sender of the
+/// `m.room.create` event and its `additional_creators`. Empty in room versions
+/// which do not explicitly privilege room creators.
+pub fn room_creators(
+	room_version: &RoomVersion,
+	room_create_event: &impl Event,
+) -> Result<BTreeSet<OwnedUserId>> {
+	#[derive(Deserialize)]
+	struct RoomCreateContentCreators {
+		#[serde(default)]
+		additional_creators: Vec<OwnedUserId>,
+	}
+
+	if !room_version.explicitly_privilege_room_creators {
+		return Ok(BTreeSet::new());
+	}
+
+	let content: RoomCreateContentCreators = from_json_str(room_create_event.content().get())?;
+
+	Ok(content
+		.additional_creators
+		.into_iter()
+		.chain(once(room_create_event.sender().to_owned()))
+		.collect())
+}
+
 /// Is the user allowed to send a specific event based on the rooms power
 /// levels.
 ///
@@ -844,6 +924,7 @@ fn check_power_levels(
 	power_event: impl Event,
 	previous_power_event: Option<impl Event>,
 	user_level: Int,
+	creators: &BTreeSet<OwnedUserId>,
 ) -> Option<bool> {
 	match power_event.state_key() {
 		| Some("") => {},
@@ -870,6 +951,17 @@ fn check_power_levels(
 	// and integers here
 	debug!("validation of power event finished");
 
+	// If users contains a room creator, reject
+	if room_version.explicitly_privilege_room_creators
+		&& user_content
+			.users
+			.keys()
+			.any(|user| creators.contains(user))
+	{
+		warn!("m.room.power_levels cannot list room creators");
+		return Some(false);
+	}
+
 	#[allow(clippy::manual_let_else)]
 	let current_state = match previous_power_event {
 		| Some(current_state) => current_state,
@@ -1112,25 +1204,28 @@ fn verify_third_party_invite(
 
 #[cfg(test)]
 mod tests {
-	use std::sync::Arc;
-
-	use ruma::events::{
-		StateEventType, TimelineEventType,
-		room::{
-			join_rules::{
-				AllowRule, JoinRule, Restricted, RoomJoinRulesEventContent, RoomMembership,
+	use std::{collections::BTreeSet, sync::Arc};
+
+	use ruma::{
+		events::{
+			StateEventType, TimelineEventType,
+			room::{
+				join_rules::{
+					AllowRule, JoinRule, Restricted, RoomJoinRulesEventContent, RoomMembership,
+				},
+				member::{MembershipState, RoomMemberEventContent},
 			},
-			member::{MembershipState, RoomMemberEventContent},
 		},
+		int,
 	};
-	use serde_json::value::to_raw_value as to_raw_json_value;
+	use serde_json::{json, value::to_raw_value as to_raw_json_value};
 
 	use crate::state_res::{
 		Event, EventTypeExt, RoomVersion, StateMap,
-		event_auth::valid_membership_change,
+		event_auth::{check_power_levels, valid_membership_change},
 		test_utils::{
-			INITIAL_EVENTS, INITIAL_EVENTS_CREATE_ROOM, PduEvent, alice, charlie, ella, event_id,
-			member_content_ban, member_content_join, room_id, to_pdu_event,
+			INITIAL_EVENTS, INITIAL_EVENTS_CREATE_ROOM, PduEvent, alice, bob, charlie, ella,
+			event_id, member_content_ban, member_content_join, room_id, to_pdu_event,
 		},
 	};
 
@@ -1174,6 +1269,7 @@ fn test_ban_pass() {
 				None,
 				&MembershipState::Leave,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
@@ -1219,6 +1315,7 @@ fn test_join_non_creator() {
 				None,
 				&MembershipState::Leave,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
@@ -1264,6 +1361,7 @@ fn test_join_creator() {
 				None,
 				&MembershipState::Leave,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
@@ -1309,6 +1407,7 @@ fn test_ban_fail() {
 				None,
 				&MembershipState::Leave,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
@@ -1371,6 +1470,7 @@ fn test_restricted_join_rule() {
 				Some(alice()),
 				&MembershipState::Join,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
@@ -1389,6 +1489,7 @@ fn test_restricted_join_rule() {
 				Some(ella()),
 				&MembershipState::Leave,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
@@ -1443,8 +1544,61 @@ fn test_knock() {
 				None,
 				&MembershipState::Leave,
 				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				&BTreeSet::new(),
 			)
 			.unwrap()
 		);
 	}
+
+	#[test]
+	fn power_levels_listing_creator() {
+		let creators = BTreeSet::from([alice().to_owned()]);
+		let power_levels = |users| {
+			to_pdu_event(
+				"PL",
+				alice(),
+				TimelineEventType::RoomPowerLevels,
+				Some(""),
+				to_raw_json_value(&json!({ "users": users })).unwrap(),
+				&["MA"],
+				&["MA"],
+			)
+		};
+
+		let listing_creator = power_levels(json!({ alice(): 100, bob(): 50 }));
+		assert_eq!(
+			check_power_levels(
+				&RoomVersion::V12,
+				&*listing_creator,
+				None::<&PduEvent>,
+				int!(100),
+				&creators,
+			),
+			Some(false)
+		);
+
+		// Creators are only implicit in room versions which privilege them
+		assert_eq!(
+			check_power_levels(
+				&RoomVersion::V11,
+				&*listing_creator,
+				None::<&PduEvent>,
+				int!(100),
+				&creators,
+			),
+			Some(true)
+		);
+
+		let without_creator = power_levels(json!({ bob(): 50 }));
+		assert_eq!(
+			check_power_levels(
+				&RoomVersion::V12,
+				&*without_creator,
+				None::<&PduEvent>,
+				int!(100),
+				&creators,
+			),
+			Some(true)
+		);
+	}
 }
@@ -19,9 +19,12 @@
 	hash::{BuildHasher, Hash},
 };
 
-use futures::{Future, FutureExt, StreamExt, TryFutureExt, TryStreamExt, future, stream};
+use futures::{
+	Future, FutureExt, StreamExt, TryFutureExt, TryStreamExt, future, future::OptionFuture,
+	stream,
+};
 use ruma::{
-	EventId, Int, MilliSecondsSinceUnixEpoch, RoomVersionId,
+	EventId, Int, MilliSecondsSinceUnixEpoch, OwnedEventId, RoomVersionId,
 	events::{
 		StateEventType, TimelineEventType,
 		room::member::{MembershipState, RoomMemberEventContent},
@@ -34,11 +37,14 @@
 use self::power_levels::PowerLevelsContentFields;
 pub use self::{
 	event_auth::{auth_check, auth_types_for_event},
-	room_version::RoomVersion,
+	room_version::{RoomVersion, StateResolutionVersion},
 };
 use crate::{
 	debug,
-	matrix::{event::Event, pdu::StateKey},
+	matrix::{
+		event::Event,
+		pdu::{StateKey, create_event_id_from_room_id},
+	},
 	trace, warn,
 };
 
@@ -93,11 +99,13 @@ pub async fn resolve<'a, E, Sets, SetIter, Hasher, Fetch, FetchFut, Exists, Exis
 	SetIter: Iterator<Item = &'a StateMap<E::Id>> + Clone + Send,
 	Hasher: BuildHasher + Send + Sync,
 	E: Event + Clone + Send + Sync,
-	E::Id: Borrow<EventId> + Send + Sync,
+	E::Id: Borrow<EventId> + From<OwnedEventId> + Send + Sync,
 	for<'b> &'b E: Send,
 {
 	debug!("State resolution starting");
 
+	let room_version = RoomVersion::new(room_version)?;
+
 	// Split non-conflicting and conflicting state
 	let (clean, conflicting) = separate(state_sets.into_iter());
 
@@ -112,8 +120,20 @@ pub async fn resolve<'a, E, Sets, SetIter, Hasher, Fetch, FetchFut, Exists, Exis
 	debug!(count = conflicting.len(), "conflicting events");
 	trace!(map = ?conflicting, "conflicting events");
 
-	let auth_chain_diff =
-		get_auth_chain_diff(auth_chain_sets).chain(conflicting.into_values().flatten());
+	let conflicting: Vec<_> = conflicting.into_values().flatten().collect();
+
+	// State resolution v2.1 also considers the events between conflicted events
+	let conflicted_subgraph = match room_version.state_res {
+		| StateResolutionVersion::V2_1 =>
+			conflicted_state_subgraph(&conflicting, &event_fetch, parallel_fetches).await,
+		| _ => HashSet::new(),
+	};
+
+	debug!(count = conflicted_subgraph.len(), "conflicted state subgraph");
+
+	let auth_chain_diff = get_auth_chain_diff(auth_chain_sets)
+		.chain(conflicting)
+		.chain(conflicted_subgraph);
 
 	// `all_conflicted` contains unique items
 	// synapse says `full_set = {eid for eid in full_conflicted_set if eid in
@@ -146,6 +166,7 @@ pub async fn resolve<'a, E, Sets, SetIter, Hasher, Fetch, FetchFut, Exists, Exis
 	// Sort the control events based on power_level/clock/event_id and
 	// outgoing/incoming edges
 	let sorted_control_levels = reverse_topological_power_sort(
+		&room_version,
 		control_events,
 		&all_conflicted,
 		&event_fetch,
@@ -156,12 +177,17 @@ pub async fn resolve<'a, E, Sets, SetIter, Hasher, Fetch, FetchFut, Exists, Exis
 	debug!(count = sorted_control_levels.len(), "power events");
 	trace!(list = ?sorted_control_levels, "sorted power events");
 
-	let room_version = RoomVersion::new(room_version)?;
-	// Sequentially auth check each control event.
+	// Sequentially auth check each control event. State resolution v2.1 starts
+	// from empty state rather than the unconflicted state.
+	let initial_state = match room_version.state_res {
+		| StateResolutionVersion::V2_1 => StateMap::new(),
+		| _ => clean.clone(),
+	};
+
 	let resolved_control = iterative_auth_check(
 		&room_version,
 		sorted_control_levels.iter(),
-		clean.clone(),
+		initial_state,
 		&event_fetch,
 		parallel_fetches,
 	)
@@ -281,6 +307,69 @@ fn get_auth_chain_diff<Id, Hasher>(
 		.filter_map(move |(id, count)| (count < num_sets).then_some(id))
 }
 
+/// Returns the events which are reachable from one conflicted event and from
+/// which another conflicted event is reachable, following `auth_events`.
+async fn conflicted_state_subgraph<E, F, Fut>(
+	conflicted: &[E::Id],
+	fetch_event: &F,
+	parallel_fetches: usize,
+) -> HashSet<E::Id>
+where
+	F: Fn(E::Id) -> Fut + Sync,
+	Fut: Future<Output = Option<E>> + Send,
+	E: Event + Send + Sync,
+	E::Id: Borrow<EventId> + Clone + Send + Sync,
+{
+	// The auth events of everything reachable from the conflicted events, fetched
+	// one generation of auth events at a time
+	let mut graph: HashMap<E::Id, Vec<E::Id>> = HashMap::new();
+	let mut todo: HashSet<E::Id> = conflicted.iter().cloned().collect();
+	while !todo.is_empty() {
+		let fetched: Vec<(E::Id, Vec<E::Id>)> = stream::iter(todo.drain())
+			.map(|event_id| {
+				fetch_event(event_id.clone()).map(move |event| {
+					let auth_events = event
+						.map(|event| event.auth_events().cloned().collect())
+						.unwrap_or_default();
+
+					(event_id, auth_events)
+				})
+			})
+			.buffer_unordered(parallel_fetches)
+			.collect()
+			.boxed()
+			.await;
+
+		graph.extend(fetched.iter().cloned());
+		todo.extend(
+			fetched
+				.into_iter()
+				.flat_map(|(_, auth_events)| auth_events)
+				.filter(|auth_event| !graph.contains_key(auth_event.borrow())),
+		);
+	}
+
+	let mut referenced_by: HashMap<&E::Id, Vec<&E::Id>> = HashMap::new();
+	for (event_id, auth_events) in &graph {
+		for auth_event in auth_events {
+			referenced_by.entry(auth_event).or_default().push(event_id);
+		}
+	}
+
+	// Walking back from the conflicted events yields those leading to them
+	let mut subgraph: HashSet<E::Id> = HashSet::new();
+	let mut todo: Vec<&E::Id> = conflicted.iter().collect();
+	while let Some(event_id) = todo.pop() {
+		for &referrer in referenced_by.get(event_id).into_iter().flatten() {
+			if subgraph.insert(referrer.clone()) {
+				todo.push(referrer);
+			}
+		}
+	}
+
+	subgraph
+}
+
 /// Events are sorted from "earliest" to "latest".
 ///
 /// They are compared using the negative power level (reverse topological
@@ -291,6 +380,7 @@ fn get_auth_chain_diff<Id, Hasher>(
 /// earlier (further back in time) origin server timestamp.
 #[tracing::instrument(level = "debug", skip_all)]
 async fn reverse_topological_power_sort<E, F, Fut>(
+	room_version: &RoomVersion,
 	events_to_sort: Vec<E::Id>,
 	auth_diff: &HashSet<E::Id>,
 	fetch_event: &F,
@@ -300,7 +390,7 @@ async fn reverse_topological_power_sort<E, F, Fut>(
 	F: Fn(E::Id) -> Fut + Sync,
 	Fut: Future<Output = Option<E>> + Send,
 	E: Event + Send + Sync,
-	E::Id: Borrow<EventId> + Send + Sync,
+	E::Id: Borrow<EventId> + From<OwnedEventId> + Send + Sync,
 {
 	debug!("reverse topological sort of power events");
 
@@ -312,8 +402,13 @@ async fn reverse_topological_power_sort<E, F, Fut>(
 	// This is used in the `key_fn` passed to the lexico_topo_sort fn
 	let event_to_pl = stream::iter(graph.keys())
 		.map(|event_id| {
-			get_power_level_for_sender(event_id.clone(), fetch_event, parallel_fetches)
-				.map(move |res| res.map(|pl| (event_id, pl)))
+			get_power_level_for_sender(
+				room_version,
+				event_id.clone(),
+				fetch_event,
+				parallel_fetches,
+			)
+			.map(move |res| res.map(|pl| (event_id, pl)))
 		})
 		.buffer_unordered(parallel_fetches)
 		.try_fold(HashMap::new(), |mut event_to_pl, (event_id, pl)| {
@@ -473,6 +568,7 @@ fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other))
 /// the eventId at the eventId's generation (we walk backwards to `EventId`s
 /// most recent previous power level event).
 async fn get_power_level_for_sender<E, F, Fut>(
+	room_version: &RoomVersion,
 	event_id: E::Id,
 	fetch_event: &F,
 	parallel_fetches: usize,
@@ -481,12 +577,23 @@ async fn get_power_level_for_sender<E, F, Fut>(
 	F: Fn(E::Id) -> Fut + Sync,
 	Fut: Future<Output = Option<E>> + Send,
 	E: Event + Send,
-	E::Id: Borrow<EventId> + Send,
+	E::Id: Borrow<EventId> + From<OwnedEventId> + Send,
 {
 	debug!("fetch event ({event_id}) senders power level");
 
 	let event = fetch_event(event_id.clone()).await;
 
+	// Room creators have infinite power where they are explicitly privileged
+	if let Some(event) = &event {
+		if let Some(create) = fetch_room_create_event(room_version, event, fetch_event).await {
+			if event_auth::room_creators(room_version, &create)
+				.is_ok_and(|creators| creators.contains(event.sender()))
+			{
+				return Ok(Int::MAX);
+			}
+		}
+	}
+
 	let auth_events = event.as_ref().map(Event::auth_events).into_iter().flatten();
 
 	let pl = stream::iter(auth_events)
@@ -538,6 +645,7 @@ async fn iterative_auth_check<'a, E, F, Fut, I>(
 	E::Id: Borrow<EventId> + Clone + Eq + Ord + Send + Sync + 'a,
 	I: Iterator<Item = &'a E::Id> + Debug + Send + 'a,
 	E: Event + Clone + Send + Sync,
+	E::Id: From<OwnedEventId>,
 {
 	debug!("starting iterative auth check");
 	trace!(
@@ -571,6 +679,13 @@ async fn iterative_auth_check<'a, E, F, Fut, I>(
 		.boxed()
 		.await;
 
+	let room_create_event: OptionFuture<_> = events_to_check
+		.first()
+		.map(|event| fetch_room_create_event(room_version, event, fetch_event))
+		.into();
+
+	let room_create_event = room_create_event.await.flatten();
+
 	let auth_events = &auth_events;
 	let mut resolved_state = unconflicted_state;
 	for event in &events_to_check {
@@ -603,6 +718,11 @@ async fn iterative_auth_check<'a, E, F, Fut, I>(
 			}
 		}
 
+		// The create event is implied by the room ID where it is not listed
+		if let Some(create) = &room_create_event {
+			auth_state.insert((StateEventType::RoomCreate, StateKey::new()), create.clone());
+		}
+
 		stream::iter(
 			auth_types
 				.iter()
@@ -795,6 +915,28 @@ async fn add_event_and_auth_chain_to_graph<E, F, Fut>(
 	}
 }
 
+/// Fetches the `m.room.create` event of the room of `event` in room versions
+/// where the room ID is its reference hash, as it is not among the
+/// `auth_events` there.
+async fn fetch_room_create_event<E, F, Fut>(
+	room_version: &RoomVersion,
+	event: &E,
+	fetch_event: &F,
+) -> Option<E>
+where
+	F: Fn(E::Id) -> Fut + Sync,
+	Fut: Future<Output = Option<E>> + Send,
+	E: Event + Send,
+	E::Id: From<OwnedEventId>,
+{
+	if !room_version.room_ids_as_hashes {
+		return None;
+	}
+
+	let event_id = create_event_id_from_room_id(event.room_id()).ok()?;
+	fetch_event(event_id.into()).await
+}
+
 async fn is_power_event_id<E, F, Fut>(event_id: &E::Id, fetch: &F) -> bool
 where
 	F: Fn(E::Id) -> Fut + Sync,
@@ -908,10 +1050,15 @@ async fn test_event_sort() {
 			.collect::<Vec<_>>();
 
 		let fetcher = |id| ready(events.get(&id).cloned());
-		let sorted_power_events =
-			super::reverse_topological_power_sort(power_events, &auth_chain, &fetcher, 1)
-				.await
-				.unwrap();
+		let sorted_power_events = super::reverse_topological_power_sort(
+			&RoomVersion::V6,
+			power_events,
+			&auth_chain,
+			&fetcher,
+			1,
+		)
+		.await
+		.unwrap();
 
 		let resolved_power = super::iterative_auth_check(
 			&RoomVersion::V6,
@@ -1652,4 +1799,186 @@ fn separate_mixed() {
 			StateEventType::RoomMember => "@c:hs1" => vec![2],
 		],);
 	}
+
+	#[tokio::test]
+	async fn conflicted_state_subgraph() {
+		use futures::future::ready;
+
+		let events: HashMap<OwnedEventId, Arc<PduEvent>> = [
+			to_pdu_event::<&str>(
+				"CREATE",
+				alice(),
+				TimelineEventType::RoomCreate,
+				Some(""),
+				to_raw_json_value(&json!({ "creator": alice() })).unwrap(),
+				&[],
+				&[],
+			),
+			to_pdu_event(
+				"A",
+				alice(),
+				TimelineEventType::RoomMember,
+				Some(alice().as_str()),
+				member_content_join(),
+				&["CREATE"],
+				&["CREATE"],
+			),
+			to_pdu_event(
+				"B",
+				alice(),
+				TimelineEventType::RoomPowerLevels,
+				Some(""),
+				to_raw_json_value(&json!({ "users": { alice(): 100 } })).unwrap(),
+				&["A"],
+				&["A"],
+			),
+			to_pdu_event(
+				"C",
+				alice(),
+				TimelineEventType::RoomJoinRules,
+				Some(""),
+				to_raw_json_value(&RoomJoinRulesEventContent::new(JoinRule::Public)).unwrap(),
+				&["B"],
+				&["B"],
+			),
+			// Reached from neither conflicted event
+			to_pdu_event(
+				"X",
+				alice(),
+				TimelineEventType::RoomTopic,
+				Some(""),
+				to_raw_json_value(&json!({ "topic": "x" })).unwrap(),
+				&["A"],
+				&["A"],
+			),
+		]
+		.into_iter()
+		.map(|ev| (ev.event_id.clone(), ev))
+		.collect();
+
+		let fetcher = |id| ready(events.get(&id).cloned());
+		let subgraph =
+			super::conflicted_state_subgraph(&[event_id("C"), event_id("A")], &fetcher, 2).await;
+
+		// B leads from C to A; the create event below A and X beside it do not
+		assert_eq!(subgraph, hashset![event_id("B"), event_id("C")]);
+	}
+
+	#[tokio::test]
+	async fn resolve_v2_1_from_empty_state() {
+		use futures::future::ready;
+
+		let _ = tracing::subscriber::set_default(
+			tracing_subscriber::fmt().with_test_writer().finish(),
+		);
+
+		// The room ID is the hash of the create event in room version 12
+		let create = to_init_pdu_event(
+			"test",
+			alice(),
+			TimelineEventType::RoomCreate,
+			Some(""),
+			to_raw_json_value(&json!({ "room_version": "12" })).unwrap(),
+		);
+		let events = [
+			create.clone(),
+			to_pdu_event::<&str>(
+				"MA",
+				alice(),
+				TimelineEventType::RoomMember,
+				Some(alice().as_str()),
+				member_content_join(),
+				&[],
+				&[],
+			),
+			to_pdu_event(
+				"PL",
+				alice(),
+				TimelineEventType::RoomPowerLevels,
+				Some(""),
+				to_raw_json_value(&json!({ "users": { bob(): 50 } })).unwrap(),
+				&["MA"],
+				&["MA"],
+			),
+			to_pdu_event(
+				"MB",
+				bob(),
+				TimelineEventType::RoomMember,
+				Some(bob().as_str()),
+				member_content_join(),
+				&["PL"],
+				&["PL"],
+			),
+			to_pdu_event(
+				"TA",
+				alice(),
+				TimelineEventType::RoomTopic,
+				Some(""),
+				to_raw_json_value(&json!({ "topic": "alice" })).unwrap(),
+				&["PL", "MA"],
+				&["MB"],
+			),
+			to_pdu_event(
+				"TB",
+				bob(),
+				TimelineEventType::RoomTopic,
+				Some(""),
+				to_raw_json_value(&json!({ "topic": "bob" })).unwrap(),
+				&["PL", "MB"],
+				&["MB"],
+			),
+			to_pdu_event(
+				"BAN",
+				alice(),
+				TimelineEventType::RoomMember,
+				Some(bob().as_str()),
+				member_content_ban(),
+				&["PL", "MA", "MB"],
+				&["TA", "TB"],
+			),
+		];
+
+		let state_set = |topic: &str| -> StateMap<OwnedEventId> {
+			[
+				(StateEventType::RoomCreate, "", create.event_id.clone()),
+				(StateEventType::RoomMember, alice().as_str(), event_id("MA")),
+				(StateEventType::RoomPowerLevels, "", event_id("PL")),
+				(StateEventType::RoomMember, bob().as_str(), event_id("BAN")),
+				(StateEventType::RoomTopic, "", event_id(topic)),
+			]
+			.into_iter()
+			.map(|(ty, key, id)| (ty.with_state_key(key), id))
+			.collect()
+		};
+
+		let events: HashMap<_, _> = events
+			.into_iter()
+			.map(|ev| (ev.event_id.clone(), ev))
+			.collect();
+
+		let fetcher = |id| ready(events.get(&id).cloned());
+		let exists = |id| ready(events.contains_key(&id));
+		let auth_chain_sets: Vec<HashSet<OwnedEventId>> = vec![HashSet::new(), HashSet::new()];
+		let resolved = super::resolve(
+			&RoomVersionId::try_from("12").unwrap(),
+			[state_set("TA"), state_set("TB")].iter(),
+			&auth_chain_sets,
+			&fetcher,
+			&exists,
+			1,
+		)
+		.await
+		.unwrap();
+
+		// Bob's later topic is checked against the state its auth events imply, not
+		// against the unconflicted ban which would reject it under v2
+		assert_eq!(
+			resolved.get(&StateEventType::RoomTopic.with_state_key("")),
+			Some(&event_id("TB"))
+		);
+		assert_eq!(
+			resolved.get(&StateEventType::RoomMember.with_state_key(bob().as_str())),
+			Some(&event_id("BAN"))
+		);
+	}
 }
@@ -29,6 +29,12 @@ pub enum StateResolutionVersion {
 	V1,
 	/// State resolution for room at version 2 or later.
 	V2,
+	/// State resolution v2.1 for rooms at version 12 or later: the first
+	/// iterative auth check starts from empty state and walks the conflicted
+	/// state subgraph.
+	///
+	/// See: [MSC4297](https://github.com/matrix-org/matrix-spec-proposals/pull/4297) for more information.
+	V2_1,
 }
 
 #[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
@@ -80,6 +86,16 @@ pub struct RoomVersion {
 	///
 	/// See: [MSC2175](https://github.com/matrix-org/matrix-spec-proposals/pull/2175) for more information.
 	pub use_room_create_sender: bool,
+	/// Room IDs are the reference hash of the `m.room.create` event, which
+	/// is consequently not listed in the `auth_events` of other events.
+	///
+	/// See: [MSC4291](https://github.com/matrix-org/matrix-spec-proposals/pull/4291) for more information.
+	pub room_ids_as_hashes: bool,
+	/// The sender and `additional_creators` of the `m.room.create` event have
+	/// infinite power and cannot be listed in `m.room.power_levels`.
+	///
+	/// See: [MSC4289](https://github.com/matrix-org/matrix-spec-proposals/pull/4289) for more information.
+	pub explicitly_privilege_room_creators: bool,
 }
 
 impl RoomVersion {
@@ -97,6 +113,8 @@ impl RoomVersion {
 		knock_restricted_join_rule: false,
 		integer_power_levels: false,
 		use_room_create_sender: false,
+		room_ids_as_hashes: false,
+		explicitly_privilege_room_creators: false,
 	};
 	pub const V10: Self = Self {
 		knock_restricted_join_rule: true,
@@ -107,6 +125,12 @@ impl RoomVersion {
 		use_room_create_sender: true,
 		..Self::V10
 	};
+	pub const V12: Self = Self {
+		state_res: StateResolutionVersion::V2_1,
+		room_ids_as_hashes: true,
+		explicitly_privilege_room_creators: true,
+		..Self::V11
+	};
 	pub const V2: Self = Self {
 		state_res: StateResolutionVersion::V2,
 		..Self::V1
@@ -144,6 +168,7 @@ pub fn new(version: &RoomVersionId) -> Result<Self> {
 			| RoomVersionId::V9 => Self::V9,
 			| RoomVersionId::V10 => Self::V10,
 			| RoomVersionId::V11 => Self::V11,
+			| ver if ver.as_str() == "12" => Self::V12,
 			| ver => return Err(Error::Unsupported(format!("found version `{ver}`"))),
 		})
 	}
@@ -103,6 +103,46 @@ pub fn common_prefix<'a>(choice: &'a [&str]) -> &'a str {
 	})
 }
 
+/// Matches a glob pattern against a string, where `*` matches any number of
+/// characters and `?` matches exactly one.
+/// ```
+/// use conduwuit_core::utils::string::glob_matches;
+/// assert!(glob_matches("*.example.com", "evil.example.com"));
+/// assert!(!glob_matches("@spam?:example.com", "@spam:example.com"));
+/// ```
+#[must_use]
+pub fn glob_matches(pattern: &str, input: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let input: Vec<char> = input.chars().collect();
+
+	// Position of the last `*` in the pattern and of the input it was tried at;
+	// on a mismatch the star is made to consume one more character.
+	let mut star: Option<(usize, usize)> = None;
+	let (mut p, mut i) = (0_usize, 0_usize);
+	while i < input.len() {
+		match pattern.get(p) {
+			| Some('*') => {
+				star = Some((p, i));
+				p = p.saturating_add(1);
+			},
+			| Some(&c) if c == '?' || c == input[i] => {
+				p = p.saturating_add(1);
+				i = i.saturating_add(1);
+			},
+			| _ => match star {
+				| Some((star_p, star_i)) => {
+					star = Some((star_p, star_i.saturating_add(1)));
+					p = star_p.saturating_add(1);
+					i = star_i.saturating_add(1);
+				},
+				| None => return false,
+			},
+		}
+	}
+
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
 /// Parses the bytes into a string.
 pub fn string_from_bytes(bytes: &[u8]) -> Result<String> {
 	let str: &str = str_from_bytes(bytes)?;
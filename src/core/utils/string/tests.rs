@@ -68,3 +68,21 @@ fn between_infallible() {
 	assert_eq!("\"foo".between_infallible(("\"", "\"")), "\"foo");
 	assert_eq!("foo".between_infallible(("\"", "\"")), "foo");
 }
+
+#[test]
+fn glob_matches_literal() {
+	assert!(super::glob_matches("@alice:example.com", "@alice:example.com"));
+	assert!(!super::glob_matches("@alice:example.com", "@alice:example.org"));
+}
+
+#[test]
+fn glob_matches_wildcards() {
+	assert!(super::glob_matches("*", ""));
+	assert!(super::glob_matches("*.example.com", "evil.example.com"));
+	assert!(!super::glob_matches("*.example.com", "example.com"));
+	assert!(super::glob_matches("@*:example.com", "@spam:example.com"));
+	assert!(super::glob_matches("@spam?:*", "@spam1:example.com"));
+	assert!(!super::glob_matches("@spam?:*", "@spam:example.com"));
+	assert!(super::glob_matches("a*b*c", "axxbyyc"));
+	assert!(!super::glob_matches("a*b*c", "axxbyy"));
+}
@@ -0,0 +1,194 @@
+//! Stage writes to several maps and commit them atomically.
+//!
+//! Writes through `Map` are applied one column at a time; a crash between
+//! related writes to different maps can leave them inconsistent. A `Batch`
+//! collects puts and deletes for any maps of the same `Database` and applies
+//! them in a single atomic write. Dropping a batch without committing it
+//! discards the staged writes.
+
+use std::{collections::BTreeMap, convert::AsRef, fmt::Debug, sync::Arc};
+
+use conduwuit::{Err, Result};
+use rocksdb::WriteBatchWithTransaction;
+use serde::Serialize;
+
+use crate::{
+	Database, Engine, Map,
+	engine::Backend,
+	keyval::{KeyBuf, ValBuf},
+	map::{Column, write_options_default},
+	memory, ser,
+	util::or_else,
+};
+
+#[must_use = "a batch does nothing unless committed"]
+pub struct Batch<'a> {
+	db: &'a Arc<Engine>,
+	batch: WriteBatchWithTransaction<false>,
+	memory: memory::WriteBatch,
+	wake: Vec<(&'a Map, Vec<u8>)>,
+	staged: BTreeMap<(&'a str, Vec<u8>), Option<Vec<u8>>>,
+}
+
+impl Database {
+	/// Starts a batch of writes across any maps of this database.
+	#[inline]
+	pub fn batch(&self) -> Batch<'_> { Batch::new(&self.db) }
+}
+
+impl<'a> Batch<'a> {
+	fn new(db: &'a Arc<Engine>) -> Self {
+		Self {
+			db,
+			batch: WriteBatchWithTransaction::default(),
+			memory: memory::WriteBatch::default(),
+			wake: Vec::new(),
+			staged: BTreeMap::new(),
+		}
+	}
+
+	/// Stage an insert
+	///
+	/// - Key is serialized
+	/// - Val is serialized
+	#[inline]
+	pub fn put<K, V>(&mut self, map: &'a Map, key: K, val: V)
+	where
+		K: Serialize + Debug,
+		V: Serialize,
+	{
+		let mut val_buf = ValBuf::new();
+		let val = ser::serialize(&mut val_buf, val).expect("failed to serialize batch val");
+		self.put_raw(map, key, val);
+	}
+
+	/// Stage an insert
+	///
+	/// - Key is serialized
+	/// - Val is raw
+	#[inline]
+	pub fn put_raw<K, V>(&mut self, map: &'a Map, key: K, val: V)
+	where
+		K: Serialize + Debug,
+		V: AsRef<[u8]>,
+	{
+		let mut key_buf = KeyBuf::new();
+		let key = ser::serialize(&mut key_buf, key).expect("failed to serialize batch key");
+		self.insert(map, key, val);
+	}
+
+	/// Stage an insert
+	///
+	/// - Key is raw
+	/// - Val is serialized
+	#[inline]
+	pub fn raw_put<K, V>(&mut self, map: &'a Map, key: K, val: V)
+	where
+		K: AsRef<[u8]>,
+		V: Serialize,
+	{
+		let mut val_buf = ValBuf::new();
+		let val = ser::serialize(&mut val_buf, val).expect("failed to serialize batch val");
+		self.insert(map, &key, val);
+	}
+
+	/// Stage an insert
+	///
+	/// - Key is raw
+	/// - Val is raw
+	#[tracing::instrument(skip_all, fields(%map), level = "trace")]
+	pub fn insert<K, V>(&mut self, map: &'a Map, key: &K, val: V)
+	where
+		K: AsRef<[u8]> + ?Sized,
+		V: AsRef<[u8]>,
+	{
+		debug_assert!(Arc::ptr_eq(map.db(), self.db), "map from another database");
+
+		match map.column() {
+			| Column::Memory(column) => self.memory.put(column, key.as_ref(), val.as_ref()),
+			| Column::RocksDb(cf, _) => self.batch.put_cf(&**cf, key, val),
+		}
+
+		self.wake.push((map, key.as_ref().to_vec()));
+		self.staged
+			.insert((map.name(), key.as_ref().to_vec()), Some(val.as_ref().to_vec()));
+	}
+
+	/// Stage a delete
+	///
+	/// - Key is serialized
+	#[inline]
+	pub fn del<K>(&mut self, map: &'a Map, key: K)
+	where
+		K: Serialize + Debug,
+	{
+		let mut key_buf = KeyBuf::new();
+		let key = ser::serialize(&mut key_buf, key).expect("failed to serialize batch key");
+		self.remove(map, key);
+	}
+
+	/// Stage a delete
+	///
+	/// - Key is raw
+	#[tracing::instrument(skip_all, fields(%map), level = "trace")]
+	pub fn remove<K>(&mut self, map: &'a Map, key: &K)
+	where
+		K: AsRef<[u8]> + ?Sized,
+	{
+		debug_assert!(Arc::ptr_eq(map.db(), self.db), "map from another database");
+
+		match map.column() {
+			| Column::Memory(column) => self.memory.delete(column, key.as_ref()),
+			| Column::RocksDb(cf, _) => self.batch.delete_cf(&**cf, key),
+		}
+
+		self.staged
+			.insert((map.name(), key.as_ref().to_vec()), None);
+	}
+
+	/// Fetch a value as it will be once this batch is committed: the last
+	/// write staged for the key, otherwise the value in the database.
+	///
+	/// - Key is raw
+	pub fn get<K>(&self, map: &'a Map, key: &K) -> Result<Vec<u8>>
+	where
+		K: AsRef<[u8]> + ?Sized,
+	{
+		match self.staged.get(&(map.name(), key.as_ref().to_vec())) {
+			| Some(Some(val)) => Ok(val.clone()),
+			| Some(None) => Err!(Request(NotFound("Not found in database"))),
+			| None => map.get_blocking(key).map(|handle| handle.to_vec()),
+		}
+	}
+
+	/// Number of staged writes.
+	#[inline]
+	#[must_use]
+	pub fn len(&self) -> usize { self.batch.len().saturating_add(self.memory.len()) }
+
+	#[inline]
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+	/// Applies all staged writes atomically.
+	#[tracing::instrument(skip_all, fields(len = self.len()), level = "trace")]
+	pub fn commit(self) {
+		match &self.db.backend {
+			| Backend::Memory(memory) => memory.write(self.memory),
+			| Backend::RocksDb(db) => {
+				let write_options = write_options_default(self.db);
+				db.write_opt(self.batch, &write_options)
+					.or_else(or_else)
+					.expect("database batch write error");
+			},
+		}
+
+		if !self.db.corked() {
+			self.db.flush().expect("database flush error");
+		}
+
+		for (map, key) in &self.wake {
+			map.wake(key);
+		}
+	}
+}
@@ -9,28 +9,23 @@
 mod open;
 mod repair;
 
-use std::{
-	ffi::CStr,
-	sync::{
-		Arc,
-		atomic::{AtomicU32, Ordering},
-	},
+use std::sync::{
+	Arc,
+	atomic::{AtomicU32, Ordering},
 };
 
-use conduwuit::{Err, Result, debug, info, warn};
-use rocksdb::{
-	AsColumnFamilyRef, BoundColumnFamily, DBCommon, DBWithThreadMode, MultiThreaded,
-	WaitForCompactOptions,
-};
+use conduwuit::{Result, debug, info, warn};
+use rocksdb::{DBCommon, DBWithThreadMode, MultiThreaded, WaitForCompactOptions};
 
 use crate::{
 	Context,
+	memory::Memory,
 	pool::Pool,
 	util::{map_err, result},
 };
 
 pub struct Engine {
-	pub(crate) db: Db,
+	pub(crate) backend: Backend,
 	pub(crate) pool: Arc<Pool>,
 	pub(crate) ctx: Arc<Context>,
 	pub(super) read_only: bool,
@@ -39,6 +34,12 @@ pub struct Engine {
 	corks: AtomicU32,
 }
 
+/// Storage engine underneath all maps of the database.
+pub(crate) enum Backend {
+	RocksDb(Arc<Db>),
+	Memory(Arc<Memory>),
+}
+
 pub(crate) type Db = DBWithThreadMode<MultiThreaded>;
 
 impl Engine {
@@ -50,12 +51,16 @@ impl Engine {
 		),
 	)]
 	pub fn wait_compactions_blocking(&self) -> Result {
+		let Backend::RocksDb(db) = &self.backend else {
+			return Ok(());
+		};
+
 		let mut opts = WaitForCompactOptions::default();
 		opts.set_abort_on_pause(true);
 		opts.set_flush(false);
 		opts.set_timeout(0);
 
-		self.db.wait_for_compact(&opts).map_err(map_err)
+		db.wait_for_compact(&opts).map_err(map_err)
 	}
 
 	#[tracing::instrument(
@@ -66,8 +71,12 @@ pub fn wait_compactions_blocking(&self) -> Result {
 		),
 	)]
 	pub fn sort(&self) -> Result {
+		let Backend::RocksDb(db) = &self.backend else {
+			return Ok(());
+		};
+
 		let flushoptions = rocksdb::FlushOptions::default();
-		result(DBCommon::flush_opt(&self.db, &flushoptions))
+		result(DBCommon::flush_opt(db, &flushoptions))
 	}
 
 	#[tracing::instrument(
@@ -77,13 +86,28 @@ pub fn sort(&self) -> Result {
 			sequence = ?self.current_sequence(),
 		),
 	)]
-	pub fn update(&self) -> Result { self.db.try_catch_up_with_primary().map_err(map_err) }
+	pub fn update(&self) -> Result {
+		match &self.backend {
+			| Backend::RocksDb(db) => db.try_catch_up_with_primary().map_err(map_err),
+			| Backend::Memory(_) => Ok(()),
+		}
+	}
 
 	#[tracing::instrument(level = "info", skip_all)]
-	pub fn sync(&self) -> Result { result(DBCommon::flush_wal(&self.db, true)) }
+	pub fn sync(&self) -> Result {
+		match &self.backend {
+			| Backend::RocksDb(db) => result(DBCommon::flush_wal(db, true)),
+			| Backend::Memory(_) => Ok(()),
+		}
+	}
 
 	#[tracing::instrument(level = "debug", skip_all)]
-	pub fn flush(&self) -> Result { result(DBCommon::flush_wal(&self.db, false)) }
+	pub fn flush(&self) -> Result {
+		match &self.backend {
+			| Backend::RocksDb(db) => result(DBCommon::flush_wal(db, false)),
+			| Backend::Memory(_) => Ok(()),
+		}
+	}
 
 	#[inline]
 	pub(crate) fn cork(&self) { self.corks.fetch_add(1, Ordering::Relaxed); }
@@ -94,35 +118,14 @@ pub fn flush(&self) -> Result { result(DBCommon::flush_wal(&self.db, false)) }
 	#[inline]
 	pub fn corked(&self) -> bool { self.corks.load(Ordering::Relaxed) > 0 }
 
-	/// Query for database property by null-terminated name which is expected to
-	/// have a result with an integer representation. This is intended for
-	/// low-overhead programmatic use.
-	pub(crate) fn property_integer(
-		&self,
-		cf: &impl AsColumnFamilyRef,
-		name: &CStr,
-	) -> Result<u64> {
-		result(self.db.property_int_value_cf(cf, name))
-			.and_then(|val| val.map_or_else(|| Err!("Property {name:?} not found."), Ok))
-	}
-
-	/// Query for database property by name receiving the result in a string.
-	pub(crate) fn property(&self, cf: &impl AsColumnFamilyRef, name: &str) -> Result<String> {
-		result(self.db.property_value_cf(cf, name))
-			.and_then(|val| val.map_or_else(|| Err!("Property {name:?} not found."), Ok))
-	}
-
-	pub(crate) fn cf(&self, name: &str) -> Arc<BoundColumnFamily<'_>> {
-		self.db
-			.cf_handle(name)
-			.expect("column must be described prior to database open")
-	}
-
 	#[inline]
 	#[must_use]
 	#[tracing::instrument(name = "sequence", level = "debug", skip_all, fields(sequence))]
 	pub fn current_sequence(&self) -> u64 {
-		let sequence = self.db.latest_sequence_number();
+		let sequence = match &self.backend {
+			| Backend::RocksDb(db) => db.latest_sequence_number(),
+			| Backend::Memory(memory) => memory.current_sequence(),
+		};
 
 		#[cfg(debug_assertions)]
 		tracing::Span::current().record("sequence", sequence);
@@ -137,6 +140,10 @@ pub fn is_read_only(&self) -> bool { self.secondary || self.read_only }
 	#[inline]
 	#[must_use]
 	pub fn is_secondary(&self) -> bool { self.secondary }
+
+	#[inline]
+	#[must_use]
+	pub fn is_memory(&self) -> bool { matches!(self.backend, Backend::Memory(_)) }
 }
 
 impl Drop for Engine {
@@ -144,8 +151,10 @@ impl Drop for Engine {
 	fn drop(&mut self) {
 		const BLOCKING: bool = true;
 
-		debug!("Waiting for background tasks to finish...");
-		self.db.cancel_all_background_work(BLOCKING);
+		if let Backend::RocksDb(db) = &self.backend {
+			debug!("Waiting for background tasks to finish...");
+			db.cancel_all_background_work(BLOCKING);
+		}
 
 		info!(
 			sequence = %self.current_sequence(),
@@ -1,9 +1,9 @@
 use std::fmt::Write;
 
-use conduwuit::{Result, error, implement, info, utils::time::rfc2822_from_seconds, warn};
+use conduwuit::{Err, Result, error, implement, info, utils::time::rfc2822_from_seconds, warn};
 use rocksdb::backup::{BackupEngine, BackupEngineOptions};
 
-use super::Engine;
+use super::{Backend, Engine};
 use crate::{or_else, util::map_err};
 
 #[implement(Engine)]
@@ -16,14 +16,16 @@ pub fn backup(&self) -> Result {
 		return Ok(());
 	}
 
+	let Backend::RocksDb(db) = &self.backend else {
+		return Err!(Database("Backups are not supported by the in-memory database backend."));
+	};
+
 	let options =
 		BackupEngineOptions::new(path.expect("valid database backup path")).map_err(map_err)?;
 	let mut engine = BackupEngine::open(&options, &*self.ctx.env.lock()?).map_err(map_err)?;
 	if config.database_backups_to_keep > 0 {
 		let flush = !self.is_read_only();
-		engine
-			.create_new_backup_flush(&self.db, flush)
-			.map_err(map_err)?;
+		engine.create_new_backup_flush(db, flush).map_err(map_err)?;
 
 		let engine_info = engine.get_backup_info();
 		let info = &engine_info.last().expect("backup engine info is not empty");
@@ -1,15 +1,15 @@
 use conduwuit::{Result, implement};
 use rocksdb::LiveFile as SstFile;
 
-use super::Engine;
+use super::{Backend, Engine};
 use crate::util::map_err;
 
 #[implement(Engine)]
 pub fn file_list(&self) -> impl Iterator<Item = Result<SstFile>> + Send + use<> {
-	self.db
-		.live_files()
-		.map_err(map_err)
-		.into_iter()
-		.flat_map(Vec::into_iter)
-		.map(Ok)
+	let files = match &self.backend {
+		| Backend::RocksDb(db) => db.live_files().map_err(map_err),
+		| Backend::Memory(_) => Ok(Vec::new()),
+	};
+
+	files.into_iter().flat_map(Vec::into_iter).map(Ok)
 }
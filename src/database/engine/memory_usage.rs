@@ -3,15 +3,23 @@
 use conduwuit::{Result, implement};
 use rocksdb::perf::get_memory_usage_stats;
 
-use super::Engine;
+use super::{Backend, Db, Engine};
 use crate::or_else;
 
 #[implement(Engine)]
 pub fn memory_usage(&self) -> Result<String> {
 	let mut res = String::new();
-	let stats = get_memory_usage_stats(Some(&[&self.db]), Some(&[&*self.ctx.row_cache.lock()?]))
-		.or_else(or_else)?;
 	let mibs = |input| f64::from(u32::try_from(input / 1024).unwrap_or(0)) / 1024.0;
+	let db: &Db = match &self.backend {
+		| Backend::RocksDb(db) => db,
+		| Backend::Memory(memory) => {
+			writeln!(res, "In-memory database: {:.2} MiB", mibs(u64::try_from(memory.usage())?))?;
+			return Ok(res);
+		},
+	};
+
+	let stats = get_memory_usage_stats(Some(&[db]), Some(&[&*self.ctx.row_cache.lock()?]))
+		.or_else(or_else)?;
 	writeln!(
 		res,
 		"Memory buffers: {:.2} MiB\nPending write: {:.2} MiB\nTable readers: {:.2} MiB\nRow \
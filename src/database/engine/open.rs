@@ -4,17 +4,17 @@
 	sync::{Arc, atomic::AtomicU32},
 };
 
-use conduwuit::{Result, debug, implement, info, warn};
+use conduwuit::{Result, config::DatabaseBackend, debug, implement, info, warn};
 use rocksdb::{ColumnFamilyDescriptor, Options};
 
 use super::{
-	Db, Engine,
+	Backend, Db, Engine,
 	cf_opts::cf_options,
 	db_opts::db_options,
 	descriptor::{self, Descriptor},
 	repair::repair,
 };
-use crate::{Context, or_else};
+use crate::{Context, memory::Memory, or_else};
 
 #[implement(Engine)]
 #[tracing::instrument(skip_all)]
@@ -22,6 +22,9 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 	let server = &ctx.server;
 	let config = &server.config;
 	let path = &config.database_path;
+	if config.database_backend == DatabaseBackend::Memory {
+		return Ok(Self::open_memory(&ctx, desc));
+	}
 
 	let db_opts = db_options(
 		config,
@@ -56,7 +59,7 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 	);
 
 	Ok(Arc::new(Self {
-		db,
+		backend: Backend::RocksDb(Arc::new(db)),
 		pool: ctx.pool.clone(),
 		ctx: ctx.clone(),
 		read_only: config.rocksdb_read_only,
@@ -66,6 +69,22 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 	}))
 }
 
+#[implement(Engine)]
+#[tracing::instrument(name = "memory", skip_all)]
+fn open_memory(ctx: &Arc<Context>, desc: &[Descriptor]) -> Arc<Self> {
+	info!(columns = desc.len(), "Opened in-memory database. Nothing will be persisted.");
+
+	Arc::new(Self {
+		backend: Backend::Memory(Memory::new(desc)),
+		pool: ctx.pool.clone(),
+		ctx: ctx.clone(),
+		read_only: false,
+		secondary: false,
+		checksums: false,
+		corks: AtomicU32::new(0),
+	})
+}
+
 #[implement(Engine)]
 #[tracing::instrument(name = "configure", skip_all)]
 fn configure_cfds(
@@ -7,11 +7,20 @@
 use crate::{Deserialized, Slice, keyval::deserialize_val};
 
 pub struct Handle<'a> {
-	val: DBPinnableSlice<'a>,
+	val: Inner<'a>,
+}
+
+enum Inner<'a> {
+	Pinned(DBPinnableSlice<'a>),
+	Owned(Vec<u8>),
 }
 
 impl<'a> From<DBPinnableSlice<'a>> for Handle<'a> {
-	fn from(val: DBPinnableSlice<'a>) -> Self { Self { val } }
+	fn from(val: DBPinnableSlice<'a>) -> Self { Self { val: Inner::Pinned(val) } }
+}
+
+impl From<Vec<u8>> for Handle<'_> {
+	fn from(val: Vec<u8>) -> Self { Self { val: Inner::Owned(val) } }
 }
 
 impl Debug for Handle<'_> {
@@ -72,10 +81,15 @@ impl Deref for Handle<'_> {
 	type Target = Slice;
 
 	#[inline]
-	fn deref(&self) -> &Self::Target { &self.val }
+	fn deref(&self) -> &Self::Target {
+		match &self.val {
+			| Inner::Pinned(val) => val,
+			| Inner::Owned(val) => val,
+		}
+	}
 }
 
 impl AsRef<Slice> for Handle<'_> {
 	#[inline]
-	fn as_ref(&self) -> &Slice { &self.val }
+	fn as_ref(&self) -> &Slice { self }
 }
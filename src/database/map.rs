@@ -33,20 +33,20 @@
 	sync::Arc,
 };
 
-use conduwuit::Result;
-use rocksdb::{AsColumnFamilyRef, ColumnFamily, ReadOptions, WriteOptions};
+use conduwuit::{Err, Result};
+use rocksdb::{ColumnFamily, ReadOptions, WriteOptions};
 
 pub(crate) use self::options::{
 	cache_iter_options_default, cache_read_options_default, iter_options_default,
 	read_options_default, write_options_default,
 };
 pub use self::{get_batch::Get, qry_batch::Qry};
-use crate::{Engine, watchers::Watchers};
+use crate::{Engine, engine::Db, memory, util::result, watchers::Watchers};
 
 pub struct Map {
 	name: &'static str,
 	watchers: Watchers,
-	cf: Arc<ColumnFamily>,
+	column: Column,
 	db: Arc<Engine>,
 	read_options: ReadOptions,
 	cache_read_options: ReadOptions,
@@ -58,7 +58,7 @@ pub(crate) fn open(db: &Arc<Engine>, name: &'static str) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			name,
 			watchers: Watchers::default(),
-			cf: open::open(db, name),
+			column: open::open(db, name),
 			db: db.clone(),
 			read_options: read_options_default(db),
 			cache_read_options: cache_read_options_default(db),
@@ -79,11 +79,21 @@ pub fn watch_prefix<'a, K>(
 
 	#[inline]
 	pub fn property_integer(&self, name: &CStr) -> Result<u64> {
-		self.db.property_integer(&self.cf(), name)
+		match &self.column {
+			| Column::RocksDb(cf, db) => result(db.property_int_value_cf(&**cf, name))
+				.and_then(|val| val.map_or_else(|| Err!("Property {name:?} not found."), Ok)),
+			| Column::Memory(_) => Err!("Property {name:?} not found."),
+		}
 	}
 
 	#[inline]
-	pub fn property(&self, name: &str) -> Result<String> { self.db.property(&self.cf(), name) }
+	pub fn property(&self, name: &str) -> Result<String> {
+		match &self.column {
+			| Column::RocksDb(cf, db) => result(db.property_value_cf(&**cf, name))
+				.and_then(|val| val.map_or_else(|| Err!("Property {name:?} not found."), Ok)),
+			| Column::Memory(_) => Err!("Property {name:?} not found."),
+		}
+	}
 
 	#[inline]
 	pub fn name(&self) -> &str { self.name }
@@ -92,7 +102,17 @@ pub fn name(&self) -> &str { self.name }
 	pub(crate) fn db(&self) -> &Arc<Engine> { &self.db }
 
 	#[inline]
-	pub(crate) fn cf(&self) -> impl AsColumnFamilyRef + '_ { &*self.cf }
+	pub(crate) fn column(&self) -> &Column { &self.column }
+
+	#[inline]
+	pub(crate) fn wake(&self, key: &[u8]) { self.watchers.wake(key); }
+}
+
+/// Storage of a map in the database's backend. A RocksDB column carries the
+/// instance it belongs to, so it is only reachable through a RocksDB backend.
+pub(crate) enum Column {
+	RocksDb(Arc<ColumnFamily>, Arc<Db>),
+	Memory(memory::Column),
 }
 
 impl Debug for Map {
@@ -1,6 +1,7 @@
 use conduwuit::{Err, Result, implement};
 use rocksdb::{BottommostLevelCompaction, CompactOptions};
 
+use super::Column;
 use crate::keyval::KeyBuf;
 
 #[derive(Clone, Debug, Default)]
@@ -31,6 +32,10 @@ pub struct Options {
 	fields(%self),
 )]
 pub fn compact_blocking(&self, opts: Options) -> Result {
+	let Column::RocksDb(cf, db) = self.column() else {
+		return Ok(());
+	};
+
 	let mut co = CompactOptions::default();
 	co.set_exclusive_manual_compaction(opts.exclusive);
 	co.set_bottommost_level_compaction(match opts.exhaustive {
@@ -54,9 +59,7 @@ pub fn compact_blocking(&self, opts: Options) -> Result {
 		| (Some(_), Some(_)) => return Err!("compacting between specific levels not supported"),
 	}
 
-	self.db
-		.db
-		.compact_range_cf_opt(&self.cf(), opts.range.0, opts.range.1, &co);
+	db.compact_range_cf_opt(&**cf, opts.range.0, opts.range.1, &co);
 
 	Ok(())
 }
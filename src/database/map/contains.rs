@@ -9,6 +9,7 @@
 use futures::FutureExt;
 use serde::Serialize;
 
+use super::Column;
 use crate::{keyval::KeyBuf, ser};
 
 /// Returns true if the map contains the key.
@@ -97,7 +98,9 @@ pub(crate) fn maybe_exists<K>(&self, key: &K) -> bool
 where
 	K: AsRef<[u8]> + ?Sized,
 {
-	self.db
-		.db
-		.key_may_exist_cf_opt(&self.cf(), key, &self.cache_read_options)
+	match self.column() {
+		| Column::Memory(column) => column.contains(key.as_ref()),
+		| Column::RocksDb(cf, db) =>
+			db.key_may_exist_cf_opt(&**cf, key, &self.cache_read_options),
+	}
 }
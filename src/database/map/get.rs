@@ -2,9 +2,10 @@
 
 use conduwuit::{Err, Result, err, implement, utils::result::MapExpect};
 use futures::{Future, FutureExt, TryFutureExt, future::ready};
-use rocksdb::{DBPinnableSlice, ReadOptions};
+use rocksdb::ReadOptions;
 use tokio::task;
 
+use super::Column;
 use crate::{
 	Handle,
 	util::{is_incomplete, map_err, or_else},
@@ -73,33 +74,37 @@ fn get_blocking_opts<K>(
 	&self,
 	key: &K,
 	read_options: &ReadOptions,
-) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>
+) -> Result<Option<Handle<'_>>, rocksdb::Error>
 where
 	K: AsRef<[u8]> + ?Sized,
 {
-	self.db.db.get_pinned_cf_opt(&self.cf(), key, read_options)
+	match self.column() {
+		| Column::Memory(column) => Ok(column.get(key.as_ref()).map(Handle::from)),
+		| Column::RocksDb(cf, db) => db
+			.get_pinned_cf_opt(&**cf, key, read_options)
+			.map(|val| val.map(Handle::from)),
+	}
 }
 
 #[inline]
 pub(super) fn handle_from(
-	result: Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>,
+	result: Result<Option<Handle<'_>>, rocksdb::Error>,
 ) -> Result<Handle<'_>> {
 	result
 		.map_err(map_err)?
-		.map(Handle::from)
 		.ok_or(err!(Request(NotFound("Not found in database"))))
 }
 
 #[inline]
 pub(super) fn cached_handle_from(
-	result: Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>,
+	result: Result<Option<Handle<'_>>, rocksdb::Error>,
 ) -> Result<Option<Handle<'_>>> {
 	match result {
 		// cache hit; not found
 		| Ok(None) => Err!(Request(NotFound("Not found in database"))),
 
 		// cache hit; value found
-		| Ok(Some(result)) => Ok(Some(Handle::from(result))),
+		| Ok(Some(result)) => Ok(Some(result)),
 
 		// cache miss; unknown
 		| Err(error) if is_incomplete(&error) => Ok(None),
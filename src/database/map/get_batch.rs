@@ -7,10 +7,14 @@
 		stream::{WidebandExt, automatic_amplification, automatic_width},
 	},
 };
+use either::Either;
 use futures::{Stream, StreamExt, TryStreamExt};
-use rocksdb::{DBPinnableSlice, ReadOptions};
+use rocksdb::ReadOptions;
 
-use super::get::{cached_handle_from, handle_from};
+use super::{
+	Column,
+	get::{cached_handle_from, handle_from},
+};
 use crate::Handle;
 
 pub trait Get<'a, K, S>
@@ -91,7 +95,7 @@ fn get_batch_blocking_opts<'a, I, K>(
 	&self,
 	keys: I,
 	read_options: &ReadOptions,
-) -> impl Iterator<Item = Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>> + Send + use<'_, I, K>
+) -> impl Iterator<Item = Result<Option<Handle<'_>>, rocksdb::Error>> + Send + use<'_, I, K>
 where
 	I: Iterator<Item = &'a K> + ExactSizeIterator + Send,
 	K: AsRef<[u8]> + Send + ?Sized + Sync + 'a,
@@ -100,8 +104,15 @@ fn get_batch_blocking_opts<'a, I, K>(
 	// comparator**.
 	const SORTED: bool = false;
 
-	self.db
-		.db
-		.batched_multi_get_cf_opt(&self.cf(), keys, SORTED, read_options)
-		.into_iter()
+	match self.column() {
+		| Column::Memory(column) =>
+			Either::Left(keys.map(move |key| {
+				Ok::<_, rocksdb::Error>(column.get(key.as_ref()).map(Handle::from))
+			})),
+		| Column::RocksDb(cf, db) => Either::Right(
+			db.batched_multi_get_cf_opt(&**cf, keys, SORTED, read_options)
+				.into_iter()
+				.map(|result| result.map(|val| val.map(Handle::from))),
+		),
+	}
 }
@@ -9,9 +9,10 @@
 use rocksdb::WriteBatchWithTransaction;
 use serde::Serialize;
 
+use super::Column;
 use crate::{
 	keyval::{KeyBuf, ValBuf},
-	ser,
+	memory, ser,
 	util::or_else,
 };
 
@@ -197,12 +198,15 @@ pub fn insert<K, V>(&self, key: &K, val: V)
 	K: AsRef<[u8]> + ?Sized,
 	V: AsRef<[u8]>,
 {
-	let write_options = &self.write_options;
-	self.db
-		.db
-		.put_cf_opt(&self.cf(), key, val, write_options)
-		.or_else(or_else)
-		.expect("database insert error");
+	match self.column() {
+		| Column::Memory(column) => column.put(key.as_ref(), val.as_ref()),
+		| Column::RocksDb(cf, db) => {
+			let write_options = &self.write_options;
+			db.put_cf_opt(&**cf, key, val, write_options)
+				.or_else(or_else)
+				.expect("database insert error");
+		},
+	}
 
 	if !self.db.corked() {
 		self.db.flush().expect("database flush error");
@@ -219,18 +223,28 @@ pub fn insert_batch<'a, I, K, V>(&'a self, iter: I)
 	K: AsRef<[u8]> + Sized + Debug + 'a,
 	V: AsRef<[u8]> + Sized + 'a,
 {
-	let mut batch = WriteBatchWithTransaction::<false>::default();
-	for (key, val) in iter {
-		batch.put_cf(&self.cf(), key.as_ref(), val.as_ref());
+	match self.column() {
+		| Column::Memory(column) => {
+			let mut batch = memory::WriteBatch::default();
+			for (key, val) in iter {
+				batch.put(column, key.as_ref(), val.as_ref());
+			}
+
+			column.write(batch);
+		},
+		| Column::RocksDb(cf, db) => {
+			let mut batch = WriteBatchWithTransaction::<false>::default();
+			for (key, val) in iter {
+				batch.put_cf(&**cf, key.as_ref(), val.as_ref());
+			}
+
+			let write_options = &self.write_options;
+			db.write_opt(batch, write_options)
+				.or_else(or_else)
+				.expect("database insert batch error");
+		},
 	}
 
-	let write_options = &self.write_options;
-	self.db
-		.db
-		.write_opt(batch, write_options)
-		.or_else(or_else)
-		.expect("database insert batch error");
-
 	if !self.db.corked() {
 		self.db.flush().expect("database flush error");
 	}
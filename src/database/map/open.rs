@@ -2,10 +2,23 @@
 
 use rocksdb::ColumnFamily;
 
-use crate::Engine;
+use super::Column;
+use crate::{
+	Engine,
+	engine::{Backend, Db},
+};
 
-pub(super) fn open(db: &Arc<Engine>, name: &str) -> Arc<ColumnFamily> {
-	let bounded_arc = db.cf(name);
+pub(super) fn open(db: &Arc<Engine>, name: &str) -> Column {
+	match &db.backend {
+		| Backend::RocksDb(db) => Column::RocksDb(open_cf(db, name), db.clone()),
+		| Backend::Memory(memory) => Column::Memory(memory.column(name)),
+	}
+}
+
+fn open_cf(db: &Db, name: &str) -> Arc<ColumnFamily> {
+	let bounded_arc = db
+		.cf_handle(name)
+		.expect("column must be described prior to database open");
 	let bounded_ptr = Arc::into_raw(bounded_arc);
 	let cf_ptr = bounded_ptr.cast::<ColumnFamily>();
 
@@ -29,6 +42,7 @@ pub(super) fn open(db: &Arc<Engine>, name: &str) -> Arc<ColumnFamily> {
 	// same inner data, the same Drop behavior, Deref, etc. We're just losing the
 	// lifetime parameter. We should not hold this handle, even in its Arc, after
 	// closing the database (dropping `Engine`). Since `Arc<Engine>` is a sibling
-	// member along with this handle in `Map`, that is prevented.
+	// member along with this handle in `Map`, and the column holds its own
+	// reference to the instance, that is prevented.
 	unsafe { Arc::from_raw(cf_ptr) }
 }
@@ -3,6 +3,7 @@
 use conduwuit::{arrayvec::ArrayVec, implement};
 use serde::Serialize;
 
+use super::Column;
 use crate::{keyval::KeyBuf, ser, util::or_else};
 
 #[implement(super::Map)]
@@ -42,12 +43,15 @@ pub fn remove<K>(&self, key: &K)
 where
 	K: AsRef<[u8]> + ?Sized + Debug,
 {
-	let write_options = &self.write_options;
-	self.db
-		.db
-		.delete_cf_opt(&self.cf(), key, write_options)
-		.or_else(or_else)
-		.expect("database remove error");
+	match self.column() {
+		| Column::Memory(column) => column.delete(key.as_ref()),
+		| Column::RocksDb(cf, db) => {
+			let write_options = &self.write_options;
+			db.delete_cf_opt(&**cf, key, write_options)
+				.or_else(or_else)
+				.expect("database remove error");
+		},
+	}
 
 	if !self.db.corked() {
 		self.db.flush().expect("database flush error");
@@ -80,6 +80,14 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		index_size: 512,
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "eventid_unredacted",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "fallbackkeyid_fallbackkey",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "global",
 		..descriptor::RANDOM_SMALL
@@ -104,6 +112,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "mediaid_file",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "mediaid_quarantined",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "mediaid_user",
 		..descriptor::RANDOM_SMALL
@@ -121,6 +133,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		index_size: 512,
 		..descriptor::SEQUENTIAL
 	},
+	Descriptor {
+		name: "policyroomids",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "publicroomids",
 		..descriptor::RANDOM_SMALL
@@ -141,6 +157,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "referencedevents",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "reportid_report",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_invitedcount",
 		..descriptor::RANDOM_SMALL
@@ -326,10 +346,18 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		block_size: 512,
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "tokenuserid_directory",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "url_previews",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userdeviceid_adminissued",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userdeviceid_metadata",
 		..descriptor::RANDOM_SMALL
@@ -358,18 +386,38 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_blurhash",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_dehydrateddevice",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_devicelistversion",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_directory",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_directorypublic",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_displayname",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_erased",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_lastonetimekeyupdate",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_locked",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_masterkeyid",
 		..descriptor::RANDOM_SMALL
@@ -386,6 +434,18 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_selfsigningkeyid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_servernoticeroomid",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_shadowbanned",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_suspended",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_usersigningkeyid",
 		..descriptor::RANDOM_SMALL
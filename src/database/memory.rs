@@ -0,0 +1,211 @@
+//! In-memory storage engine.
+//!
+//! Every column is an ordered map of raw keys to raw values. All columns sit
+//! behind a single lock so writes spanning several columns apply atomically.
+//! Nothing is persisted; this engine exists for tests and ephemeral servers.
+
+use std::{
+	collections::BTreeMap,
+	ops::Bound::{self, Excluded, Included, Unbounded},
+	sync::{
+		Arc, RwLock,
+		atomic::{AtomicU64, Ordering},
+	},
+};
+
+use crate::engine::descriptor::Descriptor;
+
+pub(crate) struct Memory {
+	names: BTreeMap<&'static str, usize>,
+	trees: RwLock<Vec<Tree>>,
+	sequence: AtomicU64,
+}
+
+/// Handle to one column of the in-memory engine.
+#[derive(Clone)]
+pub(crate) struct Column {
+	memory: Arc<Memory>,
+	id: usize,
+}
+
+/// Staged writes for `Memory::write`.
+#[derive(Default)]
+pub(crate) struct WriteBatch {
+	ops: Vec<(usize, Vec<u8>, Option<Vec<u8>>)>,
+}
+
+/// Iterator over a column with the semantics of a RocksDB raw iterator. The
+/// current entry is copied out so the lock is only held while moving.
+pub(crate) struct Cursor {
+	column: Column,
+	item: Option<Entry>,
+}
+
+type Tree = BTreeMap<Vec<u8>, Vec<u8>>;
+type Entry = (Vec<u8>, Vec<u8>);
+type Range<'a> = (Bound<&'a [u8]>, Bound<&'a [u8]>);
+
+impl Memory {
+	pub(crate) fn new(desc: &[Descriptor]) -> Arc<Self> {
+		let names: BTreeMap<_, _> = desc
+			.iter()
+			.enumerate()
+			.map(|(id, desc)| (desc.name, id))
+			.collect();
+
+		Arc::new(Self {
+			trees: RwLock::new(vec![Tree::new(); names.len()]),
+			names,
+			sequence: AtomicU64::new(0),
+		})
+	}
+
+	pub(crate) fn column(self: &Arc<Self>, name: &str) -> Column {
+		let id = *self
+			.names
+			.get(name)
+			.expect("column must be described prior to database open");
+
+		Column { memory: self.clone(), id }
+	}
+
+	/// Applies all writes in the batch under one lock.
+	pub(crate) fn write(&self, batch: WriteBatch) {
+		let mut trees = self.trees.write().expect("locked for writing");
+		for (id, key, val) in batch.ops {
+			match val {
+				| Some(val) => trees[id].insert(key, val),
+				| None => trees[id].remove(&key),
+			};
+		}
+
+		self.sequence.fetch_add(1, Ordering::Relaxed);
+	}
+
+	#[inline]
+	pub(crate) fn current_sequence(&self) -> u64 { self.sequence.load(Ordering::Relaxed) }
+
+	/// Bytes held by keys and values of all columns.
+	pub(crate) fn usage(&self) -> usize {
+		self.trees
+			.read()
+			.expect("locked for reading")
+			.iter()
+			.flat_map(Tree::iter)
+			.map(|(key, val)| key.len().saturating_add(val.len()))
+			.fold(0_usize, usize::saturating_add)
+	}
+}
+
+impl Column {
+	pub(crate) fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.read(|tree| tree.get(key).cloned())
+	}
+
+	pub(crate) fn contains(&self, key: &[u8]) -> bool { self.read(|tree| tree.contains_key(key)) }
+
+	pub(crate) fn put(&self, key: &[u8], val: &[u8]) {
+		let mut batch = WriteBatch::default();
+		batch.put(self, key, val);
+		self.memory.write(batch);
+	}
+
+	pub(crate) fn delete(&self, key: &[u8]) {
+		let mut batch = WriteBatch::default();
+		batch.delete(self, key);
+		self.memory.write(batch);
+	}
+
+	#[inline]
+	pub(crate) fn write(&self, batch: WriteBatch) { self.memory.write(batch); }
+
+	#[inline]
+	pub(crate) fn cursor(&self) -> Cursor { Cursor { column: self.clone(), item: None } }
+
+	fn read<T, F>(&self, f: F) -> T
+	where
+		F: FnOnce(&Tree) -> T,
+	{
+		let trees = self.memory.trees.read().expect("locked for reading");
+		f(&trees[self.id])
+	}
+}
+
+impl WriteBatch {
+	#[inline]
+	pub(crate) fn put(&mut self, column: &Column, key: &[u8], val: &[u8]) {
+		self.ops.push((column.id, key.to_vec(), Some(val.to_vec())));
+	}
+
+	#[inline]
+	pub(crate) fn delete(&mut self, column: &Column, key: &[u8]) {
+		self.ops.push((column.id, key.to_vec(), None));
+	}
+
+	#[inline]
+	pub(crate) fn len(&self) -> usize { self.ops.len() }
+}
+
+impl Cursor {
+	/// Positions at the first entry at or after `key`.
+	pub(crate) fn seek(&mut self, key: &[u8]) {
+		self.item = self.first((Included(key), Unbounded));
+	}
+
+	/// Positions at the last entry at or before `key`.
+	pub(crate) fn seek_for_prev(&mut self, key: &[u8]) {
+		self.item = self.last((Unbounded, Included(key)));
+	}
+
+	pub(crate) fn seek_to_first(&mut self) { self.item = self.first((Unbounded, Unbounded)); }
+
+	pub(crate) fn seek_to_last(&mut self) { self.item = self.last((Unbounded, Unbounded)); }
+
+	pub(crate) fn next(&mut self) {
+		if let Some((key, _)) = self.item.take() {
+			self.item = self.first((Excluded(key.as_slice()), Unbounded));
+		}
+	}
+
+	pub(crate) fn prev(&mut self) {
+		if let Some((key, _)) = self.item.take() {
+			self.item = self.last((Unbounded, Excluded(key.as_slice())));
+		}
+	}
+
+	#[inline]
+	pub(crate) fn valid(&self) -> bool { self.item.is_some() }
+
+	#[inline]
+	pub(crate) fn key(&self) -> Option<&[u8]> {
+		self.item.as_ref().map(|(key, _)| key.as_slice())
+	}
+
+	#[inline]
+	pub(crate) fn value(&self) -> Option<&[u8]> {
+		self.item.as_ref().map(|(_, val)| val.as_slice())
+	}
+
+	#[inline]
+	pub(crate) fn item(&self) -> Option<(&[u8], &[u8])> {
+		self.item
+			.as_ref()
+			.map(|(key, val)| (key.as_slice(), val.as_slice()))
+	}
+
+	fn first(&self, range: Range<'_>) -> Option<Entry> {
+		self.column.read(|tree| {
+			tree.range::<[u8], _>(range)
+				.next()
+				.map(|(key, val)| (key.clone(), val.clone()))
+		})
+	}
+
+	fn last(&self, range: Range<'_>) -> Option<Entry> {
+		self.column.read(|tree| {
+			tree.range::<[u8], _>(range)
+				.next_back()
+				.map(|(key, val)| (key.clone(), val.clone()))
+		})
+	}
+}
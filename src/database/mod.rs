@@ -7,6 +7,7 @@
 conduwuit::mod_dtor! {}
 conduwuit::rustc_flags_capture! {}
 
+mod batch;
 #[cfg(test)]
 mod benches;
 mod cork;
@@ -17,6 +18,7 @@
 pub mod keyval;
 mod map;
 pub mod maps;
+mod memory;
 mod pool;
 mod ser;
 mod stream;
@@ -30,6 +32,7 @@
 use conduwuit::{Result, Server, err};
 
 pub use self::{
+	batch::Batch,
 	de::{Ignore, IgnoreAll},
 	deserialized::Deserialized,
 	handle::Handle,
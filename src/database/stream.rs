@@ -13,6 +13,8 @@
 	Map, Slice,
 	engine::Db,
 	keyval::{Key, KeyVal, Val},
+	map::Column,
+	memory,
 	util::{is_incomplete, map_err},
 };
 
@@ -43,17 +45,23 @@ fn seek_and_get(&mut self) -> Option<Result<T>> {
 	}
 }
 
-type Inner<'a> = DBRawIteratorWithThreadMode<'a, Db>;
+/// Cursor of the map's backend.
+enum Inner<'a> {
+	RocksDb(DBRawIteratorWithThreadMode<'a, Db>),
+	Memory(memory::Cursor),
+}
+
 type From<'a> = Option<Key<'a>>;
 
 impl<'a> State<'a> {
 	#[inline]
 	pub(super) fn new(map: &'a Arc<Map>, opts: ReadOptions) -> Self {
-		Self {
-			inner: map.db().db.raw_iterator_cf_opt(&map.cf(), opts),
-			init: true,
-			seek: false,
-		}
+		let inner = match map.column() {
+			| Column::Memory(column) => Inner::Memory(column.cursor()),
+			| Column::RocksDb(cf, db) => Inner::RocksDb(db.raw_iterator_cf_opt(&**cf, opts)),
+		};
+
+		Self { inner, init: true, seek: false }
 	}
 
 	#[inline]
@@ -122,12 +130,102 @@ fn _fetch_val(&self) -> Option<Val<'_>> { self.inner.value() }
 	fn fetch(&self) -> Option<KeyVal<'_>> { self.inner.item() }
 
 	#[inline]
-	pub(super) fn status(&self) -> Option<rocksdb::Error> { self.inner.status().err() }
+	pub(super) fn status(&self) -> Option<rocksdb::Error> { self.inner.status() }
 
 	#[inline]
 	pub(super) fn valid(&self) -> bool { self.inner.valid() }
 }
 
+impl Inner<'_> {
+	#[inline]
+	fn seek(&mut self, key: &[u8]) {
+		match self {
+			| Self::RocksDb(inner) => inner.seek(key),
+			| Self::Memory(inner) => inner.seek(key),
+		}
+	}
+
+	#[inline]
+	fn seek_for_prev(&mut self, key: &[u8]) {
+		match self {
+			| Self::RocksDb(inner) => inner.seek_for_prev(key),
+			| Self::Memory(inner) => inner.seek_for_prev(key),
+		}
+	}
+
+	#[inline]
+	fn seek_to_first(&mut self) {
+		match self {
+			| Self::RocksDb(inner) => inner.seek_to_first(),
+			| Self::Memory(inner) => inner.seek_to_first(),
+		}
+	}
+
+	#[inline]
+	fn seek_to_last(&mut self) {
+		match self {
+			| Self::RocksDb(inner) => inner.seek_to_last(),
+			| Self::Memory(inner) => inner.seek_to_last(),
+		}
+	}
+
+	#[inline]
+	fn next(&mut self) {
+		match self {
+			| Self::RocksDb(inner) => inner.next(),
+			| Self::Memory(inner) => inner.next(),
+		}
+	}
+
+	#[inline]
+	fn prev(&mut self) {
+		match self {
+			| Self::RocksDb(inner) => inner.prev(),
+			| Self::Memory(inner) => inner.prev(),
+		}
+	}
+
+	#[inline]
+	fn key(&self) -> Option<Key<'_>> {
+		match self {
+			| Self::RocksDb(inner) => inner.key(),
+			| Self::Memory(inner) => inner.key(),
+		}
+	}
+
+	#[inline]
+	fn value(&self) -> Option<Val<'_>> {
+		match self {
+			| Self::RocksDb(inner) => inner.value(),
+			| Self::Memory(inner) => inner.value(),
+		}
+	}
+
+	#[inline]
+	fn item(&self) -> Option<KeyVal<'_>> {
+		match self {
+			| Self::RocksDb(inner) => inner.item(),
+			| Self::Memory(inner) => inner.item(),
+		}
+	}
+
+	#[inline]
+	fn status(&self) -> Option<rocksdb::Error> {
+		match self {
+			| Self::RocksDb(inner) => inner.status().err(),
+			| Self::Memory(_) => None,
+		}
+	}
+
+	#[inline]
+	fn valid(&self) -> bool {
+		match self {
+			| Self::RocksDb(inner) => inner.valid(),
+			| Self::Memory(inner) => inner.valid(),
+		}
+	}
+}
+
 fn keyval_longevity<'a, 'b: 'a>(item: KeyVal<'a>) -> KeyVal<'b> {
 	(slice_longevity::<'a, 'b>(item.0), slice_longevity::<'a, 'b>(item.1))
 }
@@ -9,7 +9,10 @@
 use serde::Serialize;
 
 use crate::{
-	Ignore, Interfix, de, ser,
+	Ignore, Interfix, de,
+	engine::descriptor::{self, Descriptor},
+	memory::{Memory, WriteBatch},
+	ser,
 	ser::{Json, serialize_to_vec},
 };
 
@@ -547,3 +550,76 @@ fn serde_tuple_option_none_none_none() {
 	assert_eq!(None, cc.0);
 	assert_eq!(bb, cc);
 }
+
+fn memory_column() -> crate::memory::Column {
+	let desc = Descriptor { name: "test", ..descriptor::RANDOM };
+	let memory = Memory::new(&[desc]);
+	let column = memory.column("test");
+	for key in [b"a", b"c", b"e"] {
+		column.put(key, key);
+	}
+
+	column
+}
+
+#[test]
+fn memory_get_put_delete() {
+	let column = memory_column();
+	assert_eq!(column.get(b"c"), Some(b"c".to_vec()));
+	assert!(!column.contains(b"b"));
+
+	column.delete(b"c");
+	assert_eq!(column.get(b"c"), None);
+}
+
+#[test]
+fn memory_cursor_fwd() {
+	let column = memory_column();
+	let mut cursor = column.cursor();
+
+	cursor.seek(b"b");
+	assert_eq!(cursor.key(), Some(&b"c"[..]));
+
+	cursor.next();
+	assert_eq!(cursor.key(), Some(&b"e"[..]));
+
+	cursor.next();
+	assert!(!cursor.valid());
+
+	cursor.seek_to_first();
+	assert_eq!(cursor.item(), Some((&b"a"[..], &b"a"[..])));
+}
+
+#[test]
+fn memory_cursor_rev() {
+	let column = memory_column();
+	let mut cursor = column.cursor();
+
+	cursor.seek_for_prev(b"d");
+	assert_eq!(cursor.key(), Some(&b"c"[..]));
+
+	cursor.prev();
+	assert_eq!(cursor.key(), Some(&b"a"[..]));
+
+	cursor.prev();
+	assert!(!cursor.valid());
+
+	cursor.seek_to_last();
+	assert_eq!(cursor.key(), Some(&b"e"[..]));
+
+	cursor.seek_for_prev(b"0");
+	assert!(!cursor.valid());
+}
+
+#[test]
+fn memory_write_batch() {
+	let column = memory_column();
+	let mut batch = WriteBatch::default();
+	batch.put(&column, b"b", b"b");
+	batch.delete(&column, b"a");
+	assert_eq!(column.get(b"b"), None);
+
+	column.write(batch);
+	assert_eq!(column.get(b"a"), None);
+	assert_eq!(column.get(b"b"), Some(b"b".to_vec()));
+}
@@ -4,7 +4,9 @@
 	Err, Result, err, implement,
 	utils::{ReadyExt, result::LogErr, stream::TryIgnore},
 };
-use database::{Deserialized, Handle, Ignore, Json, Map};
+use database::{
+	Batch, Database, Deserialized, Handle, Ignore, Interfix, Json, Map, serialize_key,
+};
 use futures::{Stream, StreamExt, TryFutureExt};
 use ruma::{
 	RoomId, UserId,
@@ -24,6 +26,7 @@ pub struct Service {
 }
 
 struct Data {
+	db: Arc<Database>,
 	roomuserdataid_accountdata: Arc<Map>,
 	roomusertype_roomuserdataid: Arc<Map>,
 }
@@ -39,6 +42,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				globals: args.depend::<globals::Service>("globals"),
 			},
 			db: Data {
+				db: args.db.clone(),
 				roomuserdataid_accountdata: args.db["roomuserdataid_accountdata"].clone(),
 				roomusertype_roomuserdataid: args.db["roomusertype_roomuserdataid"].clone(),
 			},
@@ -58,6 +62,25 @@ pub async fn update(
 	user_id: &UserId,
 	event_type: RoomAccountDataEventType,
 	data: &serde_json::Value,
+) -> Result<()> {
+	let mut batch = self.db.db.batch();
+	self.stage_update(&mut batch, room_id, user_id, event_type, data)?;
+	batch.commit();
+
+	Ok(())
+}
+
+/// Stages `update` into a batch, so it is committed atomically with the
+/// other writes of the batch.
+#[allow(clippy::needless_pass_by_value)]
+#[implement(Service)]
+pub fn stage_update<'a>(
+	&'a self,
+	batch: &mut Batch<'a>,
+	room_id: Option<&RoomId>,
+	user_id: &UserId,
+	event_type: RoomAccountDataEventType,
+	data: &serde_json::Value,
 ) -> Result<()> {
 	if data.get("type").is_none() || data.get("content").is_none() {
 		return Err!(Request(InvalidParam("Account data doesn't have all required fields.")));
@@ -65,22 +88,41 @@ pub async fn update(
 
 	let count = self.services.globals.next_count().unwrap();
 	let roomuserdataid = (room_id, user_id, count, &event_type);
-	self.db
-		.roomuserdataid_accountdata
-		.put(roomuserdataid, Json(data));
+	batch.put(&self.db.roomuserdataid_accountdata, roomuserdataid, Json(data));
 
 	let key = (room_id, user_id, &event_type);
-	let prev = self.db.roomusertype_roomuserdataid.qry(&key).await;
-	self.db.roomusertype_roomuserdataid.put(key, roomuserdataid);
+	let key = serialize_key(key)?;
+	let prev = batch.get(&self.db.roomusertype_roomuserdataid, &key);
+	batch.raw_put(&self.db.roomusertype_roomuserdataid, &key, roomuserdataid);
 
 	// Remove old entry
 	if let Ok(prev) = prev {
-		self.db.roomuserdataid_accountdata.remove(&prev);
+		batch.remove(&self.db.roomuserdataid_accountdata, &prev);
 	}
 
 	Ok(())
 }
 
+/// Deletes all of the user's global account data, or all of their account
+/// data for a room.
+#[implement(Service)]
+pub async fn delete_all(&self, room_id: Option<&RoomId>, user_id: &UserId) {
+	let prefix = (room_id, user_id, Interfix);
+	self.db
+		.roomuserdataid_accountdata
+		.keys_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| self.db.roomuserdataid_accountdata.remove(key))
+		.await;
+
+	self.db
+		.roomusertype_roomuserdataid
+		.keys_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| self.db.roomusertype_roomuserdataid.remove(key))
+		.await;
+}
+
 /// Searches the room account data for a specific kind.
 #[implement(Service)]
 pub async fn get_global<T>(&self, user_id: &UserId, kind: GlobalAccountDataEventType) -> Result<T>
@@ -7,12 +7,14 @@
 
 use conduwuit::{Server, debug, defer, error, log, log::is_systemd_mode};
 use futures::future::{AbortHandle, Abortable};
-use ruma::events::room::message::RoomMessageEventContent;
 use rustyline_async::{Readline, ReadlineError, ReadlineEvent};
 use termimad::MadSkin;
 use tokio::task::JoinHandle;
 
-use crate::{Dep, admin};
+use crate::{
+	Dep, admin,
+	admin::{CommandOutput, OutputFormat},
+};
 
 pub struct Console {
 	server: Arc<Server>,
@@ -168,19 +170,27 @@ async fn handle(self: Arc<Self>, line: String) {
 
 	async fn process(self: Arc<Self>, line: String) {
 		match self.admin.command_in_place(line, None).await {
-			| Ok(Some(ref content)) => self.output(content),
-			| Err(ref content) => self.output_err(content),
+			| Ok(Some(ref output)) => self.output(output),
+			| Err(ref output) => self.output_err(output),
 			| _ => unreachable!(),
 		}
 	}
 
-	fn output_err(self: Arc<Self>, output_content: &RoomMessageEventContent) {
-		let output = configure_output_err(self.output.clone());
-		output.print_text(output_content.body());
+	fn output_err(self: Arc<Self>, output: &CommandOutput) {
+		let body = output.content.body();
+		match output.format {
+			| OutputFormat::Json => eprintln!("{body}"),
+			| OutputFormat::Markdown =>
+				configure_output_err(self.output.clone()).print_text(body),
+		}
 	}
 
-	fn output(self: Arc<Self>, output_content: &RoomMessageEventContent) {
-		self.output.print_text(output_content.body());
+	fn output(self: Arc<Self>, output: &CommandOutput) {
+		let body = output.content.body();
+		match output.format {
+			| OutputFormat::Json => println!("{body}"),
+			| OutputFormat::Markdown => self.output.print_text(body),
+		}
 	}
 
 	fn set_history(&self, readline: &mut Readline) {
@@ -209,15 +219,22 @@ fn tab_complete(&self, line: &str) -> String {
 	}
 }
 
-/// Standalone/static markdown printer for errors.
-pub fn print_err(markdown: &str) {
-	let output = configure_output_err(MadSkin::default_dark());
-	output.print_text(markdown);
+/// Standalone/static printer for errors.
+pub fn print_err(output: &CommandOutput) {
+	let body = output.content.body();
+	match output.format {
+		| OutputFormat::Json => eprintln!("{body}"),
+		| OutputFormat::Markdown =>
+			configure_output_err(MadSkin::default_dark()).print_text(body),
+	}
 }
-/// Standalone/static markdown printer.
-pub fn print(markdown: &str) {
-	let output = configure_output(MadSkin::default_dark());
-	output.print_text(markdown);
+/// Standalone/static printer.
+pub fn print(output: &CommandOutput) {
+	let body = output.content.body();
+	match output.format {
+		| OutputFormat::Json => println!("{body}"),
+		| OutputFormat::Markdown => configure_output(MadSkin::default_dark()).print_text(body),
+	}
 }
 
 fn configure_output_err(mut output: MadSkin) -> MadSkin {
@@ -1,8 +1,11 @@
 use std::collections::BTreeMap;
 
-use conduwuit::{Result, pdu::PduBuilder};
+use conduwuit::{
+	Result, RoomVersion,
+	pdu::{PduBuilder, room_ids_as_hashes},
+};
 use ruma::{
-	RoomId, RoomVersionId,
+	MilliSecondsSinceUnixEpoch, RoomId, RoomVersionId,
 	events::room::{
 		canonical_alias::RoomCanonicalAliasEventContent,
 		create::RoomCreateEventContent,
@@ -24,17 +27,8 @@
 /// Users in this room are considered admins by conduwuit, and the room can be
 /// used to issue admin commands by talking to the server user inside it.
 pub async fn create_admin_room(services: &Services) -> Result {
-	let room_id = RoomId::new(services.globals.server_name());
 	let room_version = &services.config.default_room_version;
 
-	let _short_id = services
-		.rooms
-		.short
-		.get_or_create_shortroomid(&room_id)
-		.await;
-
-	let state_lock = services.rooms.state.mutex.lock(&room_id).await;
-
 	// Create a user for the server
 	let server_user = services.globals.server_user.as_ref();
 	services.users.create(server_user, None)?;
@@ -48,21 +42,39 @@ pub async fn create_admin_room(services: &Services) -> Result {
 		}
 	};
 
+	let create_event = PduBuilder {
+		timestamp: Some(MilliSecondsSinceUnixEpoch::now()),
+		..PduBuilder::state(String::new(), &RoomCreateEventContent {
+			federate: true,
+			predecessor: None,
+			room_version: room_version.clone(),
+			..create_content
+		})
+	};
+
+	let room_id = if room_ids_as_hashes(room_version) {
+		services
+			.rooms
+			.timeline
+			.create_room_id(&create_event, server_user)
+			.await?
+	} else {
+		RoomId::new(services.globals.server_name())
+	};
+
+	let _short_id = services
+		.rooms
+		.short
+		.get_or_create_shortroomid(&room_id)
+		.await;
+
+	let state_lock = services.rooms.state.mutex.lock(&room_id).await;
+
 	// 1. The room create event
 	services
 		.rooms
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder::state(String::new(), &RoomCreateEventContent {
-				federate: true,
-				predecessor: None,
-				room_version: room_version.clone(),
-				..create_content
-			}),
-			server_user,
-			&room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(create_event, server_user, &room_id, &state_lock)
 		.await?;
 
 	// 2. Make server user/bot join
@@ -80,8 +92,11 @@ pub async fn create_admin_room(services: &Services) -> Result {
 		)
 		.await?;
 
-	// 3. Power levels
-	let users = BTreeMap::from_iter([(server_user.into(), 69420.into())]);
+	// 3. Power levels. The creator is privileged already in some room versions.
+	let mut users = BTreeMap::new();
+	if !RoomVersion::new(room_version)?.explicitly_privilege_room_creators {
+		users.insert(server_user.into(), 69420.into());
+	}
 
 	services
 		.rooms
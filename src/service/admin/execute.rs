@@ -1,7 +1,8 @@
 use conduwuit::{Err, Result, debug, debug_info, error, implement, info};
-use ruma::events::room::message::RoomMessageEventContent;
 use tokio::time::{Duration, sleep};
 
+use crate::admin::CommandOutput;
+
 pub(super) const SIGNAL: &str = "SIGUSR2";
 
 /// Possibly spawn the terminal console at startup if configured.
@@ -100,28 +101,28 @@ async fn execute_command(&self, i: usize, command: String) -> Result {
 
 #[cfg(feature = "console")]
 #[implement(super::Service)]
-fn execute_command_output(i: usize, content: &RoomMessageEventContent) -> Result {
+fn execute_command_output(i: usize, output: &CommandOutput) -> Result {
 	debug_info!("Execute command #{i} completed:");
-	super::console::print(content.body());
+	super::console::print(output);
 	Ok(())
 }
 
 #[cfg(feature = "console")]
 #[implement(super::Service)]
-fn execute_command_error(i: usize, content: &RoomMessageEventContent) -> Result {
-	super::console::print_err(content.body());
+fn execute_command_error(i: usize, output: &CommandOutput) -> Result {
+	super::console::print_err(output);
 	Err!(debug_error!("Execute command #{i} failed."))
 }
 
 #[cfg(not(feature = "console"))]
 #[implement(super::Service)]
-fn execute_command_output(i: usize, content: &RoomMessageEventContent) -> Result {
-	info!("Execute command #{i} completed:\n{:#}", content.body());
+fn execute_command_output(i: usize, output: &CommandOutput) -> Result {
+	info!("Execute command #{i} completed:\n{:#}", output.content.body());
 	Ok(())
 }
 
 #[cfg(not(feature = "console"))]
 #[implement(super::Service)]
-fn execute_command_error(i: usize, content: &RoomMessageEventContent) -> Result {
-	Err!(error!("Execute command #{i} failed:\n{:#}", content.body()))
+fn execute_command_error(i: usize, output: &CommandOutput) -> Result {
+	Err!(error!("Execute command #{i} failed:\n{:#}", output.content.body()))
 }
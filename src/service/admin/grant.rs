@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
 
-use conduwuit::{Err, Result, debug_info, debug_warn, error, implement, matrix::pdu::PduBuilder};
+use conduwuit::{
+	Err, Result, RoomVersion, debug_info, debug_warn, error, implement, matrix::pdu::PduBuilder,
+};
 use ruma::{
 	RoomId, UserId,
 	events::{
@@ -101,9 +103,15 @@ pub async fn make_user_admin(&self, user_id: &UserId) -> Result {
 		.await
 		.unwrap_or_default();
 
-	room_power_levels
-		.users
-		.insert(server_user.into(), 69420.into());
+	// The server user created the room, which makes it privileged already in
+	// some room versions
+	let room_version = self.services.state.get_room_version(&room_id).await?;
+	if !RoomVersion::new(&room_version)?.explicitly_privilege_room_creators {
+		room_power_levels
+			.users
+			.insert(server_user.into(), 69420.into());
+	}
+
 	room_power_levels.users.insert(user_id.into(), 100.into());
 
 	self.services
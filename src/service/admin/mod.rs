@@ -69,8 +69,24 @@ pub struct CommandInput {
 /// dropped to produce no response.
 pub type ProcessorResult = Result<Option<CommandOutput>, CommandOutput>;
 
-/// Alias for the output structure.
-pub type CommandOutput = RoomMessageEventContent;
+/// Output of a command: the reply message and the format of its body.
+#[derive(Debug)]
+pub struct CommandOutput {
+	pub content: RoomMessageEventContent,
+	pub format: OutputFormat,
+}
+
+/// Format of the body of a command's output, which decides how the console
+/// prints it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OutputFormat {
+	/// Markdown, rendered for the terminal
+	#[default]
+	Markdown,
+
+	/// JSON, printed verbatim so that it can be parsed by scripts
+	Json,
+}
 
 /// Maximum number of commands which can be queued for dispatch.
 const COMMAND_QUEUE_LIMIT: usize = 512;
@@ -199,7 +215,7 @@ async fn handle_command(&self, command: CommandInput) {
 		match self.process_command(command).await {
 			| Ok(None) => debug!("Command successful with no response"),
 			| Ok(Some(output)) | Err(output) => self
-				.handle_response(output)
+				.handle_response(output.content)
 				.await
 				.unwrap_or_else(default_log),
 		}
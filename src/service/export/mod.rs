@@ -0,0 +1,360 @@
+//! Per-user data exports, for account portability and data access requests.
+//!
+//! Every export is a new directory below `user_export_path`, named after the
+//! user and the time of the export. It holds one JSON lines file for each
+//! kind of data, and the user's uploaded media in `media/`.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use conduwuit::{
+	Err, Result, Server, debug_warn, implement, info,
+	matrix::pdu::PduEvent,
+	utils::{self, ReadyExt, stream::TryIgnore},
+};
+use futures::StreamExt;
+use ruma::{Mxc, OwnedRoomId, UserId, events::AnyRawAccountDataEvent};
+use serde::Serialize;
+use serde_json::json;
+use tokio::{
+	fs,
+	io::{AsyncWriteExt, BufWriter},
+};
+
+use crate::{Dep, account_data, key_backups, media, pusher, rooms, users};
+
+pub struct Service {
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	account_data: Dep<account_data::Service>,
+	key_backups: Dep<key_backups::Service>,
+	media: Dep<media::Service>,
+	pusher: Dep<pusher::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+	users: Dep<users::Service>,
+}
+
+/// Summary of a finished export.
+pub struct Export {
+	/// Directory the export was written to.
+	pub path: PathBuf,
+
+	/// Number of events sent by the user which were exported.
+	pub events: usize,
+
+	/// Number of media files uploaded by the user which were exported.
+	pub media: usize,
+}
+
+/// Writes one JSON value per line to a file of the export.
+struct JsonLines(BufWriter<fs::File>);
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				server: args.server.clone(),
+				account_data: args.depend::<account_data::Service>("account_data"),
+				key_backups: args.depend::<key_backups::Service>("key_backups"),
+				media: args.depend::<media::Service>("media"),
+				pusher: args.depend::<pusher::Service>("pusher"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+				users: args.depend::<users::Service>("users"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Exports all data of a local user into a new directory below the
+/// configured `user_export_path`.
+#[implement(Service)]
+pub async fn export_user(&self, user_id: &UserId) -> Result<Export> {
+	let Some(export_path) = &self.services.server.config.user_export_path else {
+		return Err!(Config("user_export_path", "User exports are disabled."));
+	};
+
+	let dir =
+		export_path.join(format!("{}-{}", user_id.localpart(), utils::millis_since_unix_epoch()));
+
+	fs::create_dir_all(dir.join("media")).await?;
+
+	let rooms_joined = self
+		.services
+		.state_cache
+		.rooms_joined(user_id)
+		.map(ToOwned::to_owned);
+
+	let rooms_left = self
+		.services
+		.state_cache
+		.rooms_left(user_id)
+		.map(|(r, _)| r);
+
+	let rooms: Vec<OwnedRoomId> = rooms_joined.chain(rooms_left).collect().await;
+
+	self.export_profile(user_id, &dir).await?;
+	self.export_account_data(user_id, &rooms, &dir).await?;
+	self.export_rooms(user_id, &dir).await?;
+	let events = self.export_events(user_id, &rooms, &dir).await?;
+	let media = self.export_media(user_id, &dir).await?;
+	self.export_devices(user_id, &dir).await?;
+	self.export_key_backups(user_id, &dir).await?;
+
+	info!("Exported data of {user_id} to {}", dir.display());
+
+	Ok(Export { path: dir, events, media })
+}
+
+#[implement(Service)]
+async fn export_profile(&self, user_id: &UserId, dir: &Path) -> Result {
+	let users = &self.services.users;
+	let mut out = JsonLines::create(&dir.join("profile.jsonl")).await?;
+
+	if let Ok(displayname) = users.displayname(user_id).await {
+		out.write(&json!({"key": "displayname", "value": displayname}))
+			.await?;
+	}
+
+	if let Ok(avatar_url) = users.avatar_url(user_id).await {
+		out.write(&json!({"key": "avatar_url", "value": avatar_url}))
+			.await?;
+	}
+
+	if let Ok(blurhash) = users.blurhash(user_id).await {
+		out.write(&json!({"key": "blurhash", "value": blurhash}))
+			.await?;
+	}
+
+	let profile_keys: Vec<_> = users.all_profile_keys(user_id).collect().await;
+	for (key, value) in profile_keys {
+		out.write(&json!({"key": key, "value": value})).await?;
+	}
+
+	out.finish().await
+}
+
+#[implement(Service)]
+async fn export_account_data(
+	&self,
+	user_id: &UserId,
+	rooms: &[OwnedRoomId],
+	dir: &Path,
+) -> Result {
+	let mut out = JsonLines::create(&dir.join("account_data.jsonl")).await?;
+
+	let room_ids = rooms.iter().map(|room_id| Some(&**room_id));
+	for room_id in std::iter::once(None).chain(room_ids) {
+		let events: Vec<_> = self
+			.services
+			.account_data
+			.changes_since(room_id, user_id, 0, None)
+			.collect()
+			.await;
+
+		for event in events {
+			let event = match event {
+				| AnyRawAccountDataEvent::Global(event) => event.json(),
+				| AnyRawAccountDataEvent::Room(event) => event.json(),
+			};
+
+			out.write(&json!({"room_id": room_id, "event": event}))
+				.await?;
+		}
+	}
+
+	out.finish().await
+}
+
+#[implement(Service)]
+async fn export_rooms(&self, user_id: &UserId, dir: &Path) -> Result {
+	let state_cache = &self.services.state_cache;
+	let mut out = JsonLines::create(&dir.join("rooms.jsonl")).await?;
+
+	let joined = state_cache
+		.rooms_joined(user_id)
+		.map(|room_id| (room_id.to_owned(), "join"));
+
+	let invited = state_cache
+		.rooms_invited(user_id)
+		.map(|(room_id, _)| (room_id, "invite"));
+
+	let knocked = state_cache
+		.rooms_knocked(user_id)
+		.map(|(room_id, _)| (room_id, "knock"));
+
+	let left = state_cache
+		.rooms_left(user_id)
+		.map(|(room_id, _)| (room_id, "leave"));
+
+	let memberships: Vec<_> = joined
+		.chain(invited)
+		.chain(knocked)
+		.chain(left)
+		.collect()
+		.await;
+
+	for (room_id, membership) in memberships {
+		out.write(&json!({"room_id": room_id, "membership": membership}))
+			.await?;
+	}
+
+	out.finish().await
+}
+
+/// Exports the events sent by the user, returning their number.
+#[implement(Service)]
+async fn export_events(
+	&self,
+	user_id: &UserId,
+	rooms: &[OwnedRoomId],
+	dir: &Path,
+) -> Result<usize> {
+	let mut out = JsonLines::create(&dir.join("events.jsonl")).await?;
+
+	let mut count: usize = 0;
+	for room_id in rooms {
+		let events: Vec<PduEvent> = self
+			.services
+			.timeline
+			.pdus(None, room_id, None)
+			.ignore_err()
+			.ready_filter(|(_, pdu)| pdu.sender == user_id)
+			.map(|(_, pdu)| pdu)
+			.collect()
+			.await;
+
+		for event in &events {
+			out.write(event).await?;
+		}
+
+		count = count.saturating_add(events.len());
+	}
+
+	out.finish().await?;
+
+	Ok(count)
+}
+
+/// Exports the media uploaded by the user, returning the number of files.
+#[implement(Service)]
+async fn export_media(&self, user_id: &UserId, dir: &Path) -> Result<usize> {
+	let mut out = JsonLines::create(&dir.join("media.jsonl")).await?;
+
+	let mut count: usize = 0;
+	for mxc in self.services.media.get_all_user_mxcs(user_id).await {
+		let Ok(parsed) = Mxc::try_from(mxc.as_str()) else {
+			debug_warn!(?mxc, "Skipping invalid MXC URI of {user_id}");
+			continue;
+		};
+
+		let meta = match self.services.media.get(&parsed).await {
+			| Ok(Some(meta)) => meta,
+			| Ok(None) => {
+				debug_warn!(?mxc, "Skipping media of {user_id} missing from the database");
+				continue;
+			},
+			| Err(e) => {
+				debug_warn!(?mxc, "Skipping media of {user_id}: {e}");
+				continue;
+			},
+		};
+
+		let Some(content) = meta.content else {
+			debug_warn!(?mxc, "Skipping media of {user_id} missing from the media directory");
+			continue;
+		};
+
+		let content_type = meta.content_type;
+		let file = format!("media/{}", parsed.media_id);
+		fs::write(dir.join(&file), content).await?;
+
+		out.write(&json!({"mxc": mxc, "content_type": content_type, "file": file}))
+			.await?;
+
+		count = count.saturating_add(1);
+	}
+
+	out.finish().await?;
+
+	Ok(count)
+}
+
+#[implement(Service)]
+async fn export_devices(&self, user_id: &UserId, dir: &Path) -> Result {
+	let mut out = JsonLines::create(&dir.join("devices.jsonl")).await?;
+	let devices: Vec<_> = self
+		.services
+		.users
+		.all_devices_metadata(user_id)
+		.collect()
+		.await;
+
+	for device in &devices {
+		out.write(device).await?;
+	}
+
+	out.finish().await?;
+
+	let mut out = JsonLines::create(&dir.join("pushers.jsonl")).await?;
+	for pusher in self.services.pusher.get_pushers(user_id).await {
+		out.write(&pusher).await?;
+	}
+
+	out.finish().await
+}
+
+/// Exports the metadata of the user's room key backups. The backed up keys
+/// are encrypted client side, so they are of no use without the client.
+#[implement(Service)]
+async fn export_key_backups(&self, user_id: &UserId, dir: &Path) -> Result {
+	let key_backups = &self.services.key_backups;
+	let mut out = JsonLines::create(&dir.join("key_backups.jsonl")).await?;
+
+	let backups: Vec<_> = key_backups.get_all_backups(user_id).collect().await;
+	for (version, algorithm) in backups {
+		let count = key_backups.count_keys(user_id, &version).await;
+		let etag = key_backups.get_etag(user_id, &version).await;
+
+		out.write(&json!({
+			"version": version,
+			"algorithm": algorithm,
+			"count": count,
+			"etag": etag,
+		}))
+		.await?;
+	}
+
+	out.finish().await
+}
+
+impl JsonLines {
+	async fn create(path: &Path) -> Result<Self> {
+		Ok(Self(BufWriter::new(fs::File::create(path).await?)))
+	}
+
+	async fn write<T: Serialize + ?Sized>(&mut self, value: &T) -> Result {
+		let mut line = serde_json::to_vec(value)?;
+		line.push(b'\n');
+		self.0.write_all(&line).await?;
+
+		Ok(())
+	}
+
+	async fn finish(mut self) -> Result {
+		self.0.flush().await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,81 @@
+use std::{env, fs, path::Path};
+
+use conduwuit::{config::Figment, utils};
+use ruma::{
+	EventId,
+	events::{RoomAccountDataEventType, room::message::RoomMessageEventContent},
+	user_id,
+};
+use serde_json::{Value, json};
+
+use crate::tests::services_with;
+
+fn read_lines(dir: &Path, file: &str) -> Vec<Value> {
+	fs::read_to_string(dir.join(file))
+		.unwrap()
+		.lines()
+		.map(|line| serde_json::from_str(line).unwrap())
+		.collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn export_round_trip() {
+	let export_path = env::temp_dir()
+		.join(format!("conduwuit-export-tests-{}", utils::millis_since_unix_epoch()));
+
+	let services = services_with(Figment::new().merge(("user_export_path", &export_path))).await;
+
+	let alice = user_id!("@alice:example.com");
+	services.users.create(alice, Some("hunter2")).unwrap();
+	services
+		.users
+		.set_displayname(alice, Some("Alice".to_owned()));
+
+	let setting = json!({"type": "org.example.setting", "content": {"enabled": true}});
+	services
+		.account_data
+		.update(None, alice, "org.example.setting".into(), &setting)
+		.await
+		.unwrap();
+
+	let content = RoomMessageEventContent::text_plain("hello");
+	services.server_notices.send(alice, content).await.unwrap();
+
+	let room_id = services.server_notices.room_id(alice).await.unwrap();
+
+	let export = services.export.export_user(alice).await.unwrap();
+	assert!(export.path.starts_with(&export_path));
+	assert_eq!(export.events, 0);
+	assert_eq!(export.media, 0);
+
+	let profile = read_lines(&export.path, "profile.jsonl");
+	assert!(profile.contains(&json!({"key": "displayname", "value": "Alice"})));
+
+	let rooms = read_lines(&export.path, "rooms.jsonl");
+	assert_eq!(rooms, [json!({"room_id": room_id, "membership": "invite"})]);
+
+	let account_data = read_lines(&export.path, "account_data.jsonl");
+	assert!(account_data.contains(&json!({"room_id": null, "event": setting})));
+	assert!(
+		account_data
+			.iter()
+			.any(|line| line["room_id"] == room_id.as_str()
+				&& line["event"]["type"] == RoomAccountDataEventType::Tag.to_string())
+	);
+
+	// The events of the notices room were all sent by the system user; each
+	// exported line is the stored event.
+	let system_user = services.server_notices.system_user();
+	let export = services.export.export_user(system_user).await.unwrap();
+	let events = read_lines(&export.path, "events.jsonl");
+	assert_eq!(events.len(), export.events);
+	assert!(!events.is_empty());
+
+	for event in events {
+		let event_id = EventId::parse(event["event_id"].as_str().unwrap()).unwrap();
+		let pdu = services.rooms.timeline.get_pdu(&event_id).await.unwrap();
+		assert_eq!(event, serde_json::to_value(&pdu).unwrap());
+	}
+
+	fs::remove_dir_all(&export_path).unwrap();
+}
@@ -5,7 +5,7 @@
 	utils::stream::{ReadyExt, TryIgnore},
 };
 use database::{Deserialized, Ignore, Interfix, Json, Map};
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use ruma::{
 	OwnedRoomId, RoomId, UserId,
 	api::client::backup::{BackupAlgorithm, KeyBackupData, RoomKeyBackup},
@@ -137,6 +137,22 @@ pub async fn get_latest_backup(
 		.ok_or_else(|| err!(Request(NotFound("No backup found"))))
 }
 
+/// Returns every backup version of the user along with its algorithm.
+#[implement(Service)]
+pub fn get_all_backups<'a>(
+	&'a self,
+	user_id: &'a UserId,
+) -> impl Stream<Item = (String, Raw<BackupAlgorithm>)> + Send + 'a {
+	type KeyVal<'a> = ((Ignore, &'a str), Raw<BackupAlgorithm>);
+
+	let prefix = (user_id, Interfix);
+	self.db
+		.backupid_algorithm
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.map(|((_, version), algorithm): KeyVal<'_>| (version.to_owned(), algorithm))
+}
+
 #[implement(Service)]
 pub async fn get_backup(&self, user_id: &UserId, version: &str) -> Result<Raw<BackupAlgorithm>> {
 	let key = (user_id, version);
@@ -4,14 +4,15 @@
 	Err, Result, debug, debug_info, err,
 	utils::{ReadyExt, str_from_bytes, stream::TryIgnore, string_from_bytes},
 };
-use database::{Database, Interfix, Map};
+use database::{Database, Deserialized, Interfix, Map};
 use futures::StreamExt;
-use ruma::{Mxc, OwnedMxcUri, UserId, http_headers::ContentDisposition};
+use ruma::{Mxc, OwnedMxcUri, OwnedUserId, UserId, http_headers::ContentDisposition};
 
 use super::{preview::UrlPreviewData, thumbnail::Dim};
 
 pub(crate) struct Data {
 	mediaid_file: Arc<Map>,
+	mediaid_quarantined: Arc<Map>,
 	mediaid_user: Arc<Map>,
 	url_previews: Arc<Map>,
 }
@@ -27,6 +28,7 @@ impl Data {
 	pub(super) fn new(db: &Arc<Database>) -> Self {
 		Self {
 			mediaid_file: db["mediaid_file"].clone(),
+			mediaid_quarantined: db["mediaid_quarantined"].clone(),
 			mediaid_user: db["mediaid_user"].clone(),
 			url_previews: db["url_previews"].clone(),
 		}
@@ -141,6 +143,29 @@ pub(super) async fn search_file_metadata(
 		Ok(Metadata { content_disposition, content_type, key })
 	}
 
+	pub(super) fn quarantine(&self, mxc: &Mxc<'_>, quarantined_by: &UserId) {
+		self.mediaid_quarantined
+			.raw_put(mxc.to_string(), quarantined_by);
+	}
+
+	pub(super) fn unquarantine(&self, mxc: &Mxc<'_>) {
+		self.mediaid_quarantined.remove(&mxc.to_string());
+	}
+
+	pub(super) async fn is_quarantined(&self, mxc: &Mxc<'_>) -> bool {
+		self.mediaid_quarantined
+			.exists(&mxc.to_string())
+			.await
+			.is_ok()
+	}
+
+	pub(super) async fn quarantined_by(&self, mxc: &Mxc<'_>) -> Result<OwnedUserId> {
+		self.mediaid_quarantined
+			.get(&mxc.to_string())
+			.await
+			.deserialized()
+	}
+
 	/// Gets all the MXCs associated with a user
 	pub(super) async fn get_all_user_mxcs(&self, user_id: &UserId) -> Vec<OwnedMxcUri> {
 		self.mediaid_user
@@ -14,7 +14,7 @@
 	utils::{self, MutexMap},
 	warn,
 };
-use ruma::{Mxc, OwnedMxcUri, UserId, http_headers::ContentDisposition};
+use ruma::{Mxc, OwnedMxcUri, OwnedUserId, UserId, http_headers::ContentDisposition};
 use tokio::{
 	fs,
 	io::{AsyncReadExt, AsyncWriteExt, BufReader},
@@ -22,7 +22,7 @@
 
 use self::data::{Data, Metadata};
 pub use self::thumbnail::Dim;
-use crate::{Dep, client, globals, sending};
+use crate::{Dep, client, globals, policy, sending};
 
 #[derive(Debug)]
 pub struct FileMeta {
@@ -41,6 +41,7 @@ struct Services {
 	server: Arc<Server>,
 	client: Dep<client::Service>,
 	globals: Dep<globals::Service>,
+	policy: Dep<policy::Service>,
 	sending: Dep<sending::Service>,
 }
 
@@ -63,6 +64,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				server: args.server.clone(),
 				client: args.depend::<client::Service>("client"),
 				globals: args.depend::<globals::Service>("globals"),
+				policy: args.depend::<policy::Service>("policy"),
 				sending: args.depend::<sending::Service>("sending"),
 			},
 		}))
@@ -129,6 +131,12 @@ pub async fn delete(&self, mxc: &Mxc<'_>) -> Result<()> {
 		}
 	}
 
+	/// Returns the MXC URIs of all media uploaded by the specified user
+	#[inline]
+	pub async fn get_all_user_mxcs(&self, user: &UserId) -> Vec<OwnedMxcUri> {
+		self.db.get_all_user_mxcs(user).await
+	}
+
 	/// Deletes all media by the specified user
 	///
 	/// currently, this is only practical for local users
@@ -157,6 +165,28 @@ pub async fn delete_from_user(&self, user: &UserId) -> Result<usize> {
 		Ok(deletion_count)
 	}
 
+	/// Quarantines media, so that it is no longer served to anyone. Remote
+	/// media which is quarantined is not fetched again either.
+	pub fn quarantine(&self, mxc: &Mxc<'_>, quarantined_by: &UserId) {
+		self.db.quarantine(mxc, quarantined_by);
+	}
+
+	/// Lifts the quarantine of media.
+	#[inline]
+	pub fn unquarantine(&self, mxc: &Mxc<'_>) { self.db.unquarantine(mxc); }
+
+	/// Whether the media was quarantined by an admin.
+	#[inline]
+	pub async fn is_quarantined(&self, mxc: &Mxc<'_>) -> bool {
+		self.db.is_quarantined(mxc).await
+	}
+
+	/// Returns the admin who quarantined the media.
+	#[inline]
+	pub async fn quarantined_by(&self, mxc: &Mxc<'_>) -> Result<OwnedUserId> {
+		self.db.quarantined_by(mxc).await
+	}
+
 	/// Downloads a file.
 	pub async fn get(&self, mxc: &Mxc<'_>) -> Result<Option<FileMeta>> {
 		match self.db.search_file_metadata(mxc, &Dim::default()).await {
@@ -29,7 +29,7 @@ pub async fn fetch_remote_thumbnail(
 	timeout_ms: Duration,
 	dim: &Dim,
 ) -> Result<FileMeta> {
-	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_authorized(mxc).await?;
 
 	let result = self
 		.fetch_thumbnail_authenticated(mxc, user, server, timeout_ms, dim)
@@ -52,7 +52,7 @@ pub async fn fetch_remote_content(
 	server: Option<&ServerName>,
 	timeout_ms: Duration,
 ) -> Result<FileMeta> {
-	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_authorized(mxc).await?;
 
 	let result = self
 		.fetch_content_authenticated(mxc, user, server, timeout_ms)
@@ -355,7 +355,7 @@ pub async fn fetch_remote_thumbnail_legacy(
 	};
 
 	self.check_legacy_freeze()?;
-	self.check_fetch_authorized(&mxc)?;
+	self.check_fetch_authorized(&mxc).await?;
 	let reponse = self
 		.services
 		.sending
@@ -388,7 +388,7 @@ pub async fn fetch_remote_content_legacy(
 	timeout_ms: Duration,
 ) -> Result<media::get_content::v3::Response, Error> {
 	self.check_legacy_freeze()?;
-	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_authorized(mxc).await?;
 	let response = self
 		.services
 		.sending
@@ -420,7 +420,7 @@ pub async fn fetch_remote_content_legacy(
 }
 
 #[implement(super::Service)]
-fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result<()> {
+async fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result<()> {
 	if self
 		.services
 		.server
@@ -440,6 +440,16 @@ fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result<()> {
 		return Err!(Request(NotFound("Media not found.")));
 	}
 
+	if self
+		.services
+		.policy
+		.check_server(mxc.server_name, "media fetch")
+		.await
+		.is_err()
+	{
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
 	Ok(())
 }
 
@@ -62,6 +62,7 @@ async fn fresh(services: &Services) -> Result<()> {
 	db["global"].insert(b"retroactively_fix_bad_data_from_roomuserid_joined", []);
 	db["global"].insert(b"fix_referencedevents_missing_sep", []);
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
+	db["global"].insert(b"feat_user_directory", []);
 
 	// Create the admin room and server user on first run
 	crate::admin::create_admin_room(services).boxed().await?;
@@ -133,6 +134,16 @@ async fn migrate(services: &Services) -> Result<()> {
 		fix_readreceiptid_readreceipt_duplicates(services).await?;
 	}
 
+	if db["global"]
+		.get(b"feat_user_directory")
+		.await
+		.is_not_found()
+	{
+		let count = services.user_directory.rebuild().await;
+		db["global"].insert(b"feat_user_directory", []);
+		info!("Migration: Indexed {count} users in the user directory");
+	}
+
 	if services.globals.db.database_version().await < 17 {
 		services.globals.db.bump_database_version(17);
 		info!("Migration: Bumped database version to 17");
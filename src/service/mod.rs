@@ -5,6 +5,8 @@
 mod migrations;
 mod service;
 pub mod services;
+#[cfg(test)]
+mod tests;
 
 pub mod account_data;
 pub mod admin;
@@ -12,20 +14,28 @@
 pub mod client;
 pub mod config;
 pub mod emergency;
+pub mod export;
 pub mod federation;
 pub mod globals;
 pub mod key_backups;
 pub mod media;
+pub mod oauth;
+pub mod policy;
 pub mod presence;
 pub mod pusher;
+pub mod ratelimit;
+pub mod rendezvous;
+pub mod reports;
 pub mod resolver;
 pub mod rooms;
 pub mod sending;
 pub mod server_keys;
+pub mod server_notices;
 pub mod sync;
 pub mod transaction_ids;
 pub mod uiaa;
 pub mod updates;
+pub mod user_directory;
 pub mod users;
 
 extern crate conduwuit_core as conduwuit;
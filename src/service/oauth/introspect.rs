@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use conduwuit::{Err, Result, err};
+use ruma::OwnedDeviceId;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use url::Url;
+
+/// Token introspection response (RFC 7662 section 2.2). Only the members
+/// needed to authenticate a request are kept.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Introspection {
+	pub active: bool,
+
+	#[serde(default)]
+	pub scope: Option<String>,
+
+	#[serde(default)]
+	pub username: Option<String>,
+
+	#[serde(default)]
+	pub sub: Option<String>,
+
+	/// Expiry of the token in seconds since the unix epoch.
+	#[serde(default)]
+	pub exp: Option<u64>,
+}
+
+/// Matrix-specific scopes granted to a token (MSC2967).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Scope {
+	/// Full access to the client-server API.
+	pub api: bool,
+
+	/// The device the token was issued for.
+	pub device_id: Option<OwnedDeviceId>,
+}
+
+const API_SCOPES: &[&str] =
+	&["urn:matrix:org.matrix.msc2967.client:api:*", "urn:matrix:client:api:*"];
+
+const DEVICE_SCOPES: &[&str] =
+	&["urn:matrix:org.matrix.msc2967.client:device:", "urn:matrix:client:device:"];
+
+impl Scope {
+	/// Parses a space-delimited scope string.
+	#[must_use]
+	pub fn parse(scope: &str) -> Self {
+		scope
+			.split_ascii_whitespace()
+			.fold(Self::default(), |mut parsed, token| {
+				if API_SCOPES.contains(&token) {
+					parsed.api = true;
+				}
+
+				if let Some(device_id) = DEVICE_SCOPES
+					.iter()
+					.find_map(|prefix| token.strip_prefix(prefix))
+					.filter(|device_id| !device_id.is_empty())
+				{
+					parsed.device_id = Some(device_id.into());
+				}
+
+				parsed
+			})
+	}
+}
+
+/// Performs the introspection request against the authorization server. The
+/// homeserver authenticates itself with client_secret_basic when credentials
+/// are configured.
+pub(super) async fn request(
+	client: &reqwest::Client,
+	endpoint: &Url,
+	client_id: Option<&str>,
+	client_secret: Option<&str>,
+	token: &str,
+	timeout: Duration,
+) -> Result<Introspection> {
+	let mut request = client
+		.post(endpoint.clone())
+		.timeout(timeout)
+		.header(http::header::ACCEPT, "application/json")
+		.form(&[("token", token), ("token_type_hint", "access_token")]);
+
+	if let Some(client_id) = client_id {
+		request = request.basic_auth(client_id, client_secret);
+	}
+
+	let response = request.send().await?;
+	let status = response.status();
+	if !status.is_success() {
+		return Err!(BadServerResponse("Introspection endpoint responded with {status}"));
+	}
+
+	let body = response.bytes().await?;
+	serde_json::from_slice(&body)
+		.map_err(|e| err!(BadServerResponse("Invalid introspection response: {e}")))
+}
+
+/// Fetches the OpenID Connect discovery document of an issuer.
+pub(super) async fn discover(
+	client: &reqwest::Client,
+	issuer: &Url,
+	timeout: Duration,
+) -> Result<JsonValue> {
+	let url =
+		format!("{}/.well-known/openid-configuration", issuer.as_str().trim_end_matches('/'));
+	let response = client
+		.get(url)
+		.timeout(timeout)
+		.header(http::header::ACCEPT, "application/json")
+		.send()
+		.await?;
+
+	let status = response.status();
+	if !status.is_success() {
+		return Err!(BadServerResponse("Authorization server discovery responded with {status}"));
+	}
+
+	let body = response.bytes().await?;
+	let metadata: JsonValue = serde_json::from_slice(&body)
+		.map_err(|e| err!(BadServerResponse("Invalid authorization server metadata: {e}")))?;
+
+	if !metadata.is_object() {
+		return Err!(BadServerResponse("Authorization server metadata is not an object"));
+	}
+
+	Ok(metadata)
+}
@@ -0,0 +1,378 @@
+mod introspect;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	fmt::Write,
+	sync::{Arc, Mutex, RwLock},
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, Error, Result, Server, debug, debug_warn, err, implement,
+	utils::{self, math::usize_from_u64_truncated},
+};
+use http::StatusCode;
+use lru_cache::LruCache;
+use ruma::{OwnedDeviceId, OwnedUserId, UserId, api::client::error::ErrorKind};
+use serde_json::Value as JsonValue;
+use url::Url;
+
+pub use self::introspect::{Introspection, Scope};
+use crate::{Dep, client, globals, users};
+
+pub struct Service {
+	sessions: Mutex<SessionCache>,
+	metadata: RwLock<Option<Arc<JsonValue>>>,
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	client: Dep<client::Service>,
+	globals: Dep<globals::Service>,
+	users: Dep<users::Service>,
+}
+
+/// An introspected access token which is still considered valid.
+#[derive(Clone, Debug)]
+struct Session {
+	user_id: OwnedUserId,
+	device_id: OwnedDeviceId,
+	expires: Instant,
+}
+
+type SessionCache = LruCache<String, Session>;
+
+/// Length of the local access token assigned to devices provisioned on behalf
+/// of the authorization server. It is never handed out.
+const PLACEHOLDER_TOKEN_LENGTH: usize = 32;
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config.oauth;
+		let capacity = usize_from_u64_truncated(config.introspection_cache_capacity.into());
+
+		Ok(Arc::new(Self {
+			sessions: LruCache::new(capacity).into(),
+			metadata: RwLock::new(None),
+			services: Services {
+				server: args.server.clone(),
+				client: args.depend::<client::Service>("client"),
+				globals: args.depend::<globals::Service>("globals"),
+				users: args.depend::<users::Service>("users"),
+			},
+		}))
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let sessions = self.sessions.lock().expect("locked").len();
+		writeln!(out, "oauth_sessions: {sessions}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.sessions.lock().expect("locked").clear();
+		self.metadata.write().expect("locked for writing").take();
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Whether authentication is delegated to an external OAuth 2.0 server.
+#[implement(Service)]
+#[inline]
+#[must_use]
+pub fn is_delegated(&self) -> bool { self.services.server.config.oauth.enable }
+
+/// Returns an error when local authentication endpoints are called while
+/// authentication is delegated to an OAuth 2.0 server.
+#[implement(Service)]
+pub fn check_local_auth(&self) -> Result {
+	if self.is_delegated() {
+		return Err(Error::Request(
+			ErrorKind::Unrecognized,
+			"Authentication is delegated to an OAuth 2.0 server.".into(),
+			StatusCode::NOT_FOUND,
+		));
+	}
+
+	Ok(())
+}
+
+/// Find out which user and device an access token belongs to by introspecting
+/// it at the authorization server. Returns None for inactive or unusable
+/// tokens; errors are reserved for failures to reach the server.
+#[implement(Service)]
+pub async fn find_from_token(&self, token: &str) -> Result<Option<(OwnedUserId, OwnedDeviceId)>> {
+	if let Some(session) = self.cached_session(token) {
+		return Ok(Some((session.user_id, session.device_id)));
+	}
+
+	let introspection = self.introspect(token).await?;
+	let Some((user_id, device_id)) = self.validate(&introspection)? else {
+		return Ok(None);
+	};
+
+	if !self.services.users.exists(&user_id).await {
+		self.provision_user(&user_id).await?;
+	} else if self.services.users.is_deactivated(&user_id).await? {
+		return Err!(Request(UserDeactivated("The user has been deactivated")));
+	}
+
+	if self
+		.services
+		.users
+		.get_device_metadata(&user_id, &device_id)
+		.await
+		.is_err()
+	{
+		let token = utils::random_string(PLACEHOLDER_TOKEN_LENGTH);
+		self.services
+			.users
+			.create_device(&user_id, &device_id, &token, None, None)
+			.await?;
+	}
+
+	self.cache_session(token, &user_id, &device_id, introspection.exp);
+
+	Ok(Some((user_id, device_id)))
+}
+
+/// Maps an introspection response onto a local user and device.
+#[implement(Service)]
+fn validate(
+	&self,
+	introspection: &Introspection,
+) -> Result<Option<(OwnedUserId, OwnedDeviceId)>> {
+	if !introspection.active {
+		return Ok(None);
+	}
+
+	if introspection
+		.exp
+		.is_some_and(|exp| exp.saturating_mul(1000) <= utils::millis_since_unix_epoch())
+	{
+		return Ok(None);
+	}
+
+	let scope = Scope::parse(introspection.scope.as_deref().unwrap_or_default());
+	if !scope.api {
+		debug_warn!("Introspected token is missing the client API scope");
+		return Ok(None);
+	}
+
+	let Some(device_id) = scope.device_id else {
+		debug_warn!("Introspected token is missing a device scope");
+		return Ok(None);
+	};
+
+	let Some(username) = introspection.username.as_deref() else {
+		debug_warn!("Introspection response did not include a username");
+		return Ok(None);
+	};
+
+	// The localpart is used as is; rewriting it could map the token onto a
+	// different user.
+	let server_name = self.services.globals.server_name();
+	let user_id = match UserId::parse_with_server_name(username, server_name) {
+		| Ok(user_id) if user_id.validate_strict().is_ok() => user_id,
+		| _ => {
+			debug_warn!(?username, "Introspected username is not a valid localpart");
+			return Ok(None);
+		},
+	};
+
+	Ok(Some((user_id, device_id)))
+}
+
+#[implement(Service)]
+fn cached_session(&self, token: &str) -> Option<Session> {
+	let mut sessions = self.sessions.lock().expect("locked");
+	let session = sessions.get_mut(token)?.clone();
+	if session.expires <= Instant::now() {
+		sessions.remove(token);
+		return None;
+	}
+
+	Some(session)
+}
+
+#[implement(Service)]
+fn cache_session(
+	&self,
+	token: &str,
+	user_id: &UserId,
+	device_id: &OwnedDeviceId,
+	exp: Option<u64>,
+) {
+	let ttl = Duration::from_secs(self.services.server.config.oauth.introspection_cache_ttl);
+	let ttl = exp
+		.map(|exp| exp.saturating_mul(1000))
+		.map(|exp| exp.saturating_sub(utils::millis_since_unix_epoch()))
+		.map(Duration::from_millis)
+		.map_or(ttl, |remaining| remaining.min(ttl));
+
+	if ttl.is_zero() {
+		return;
+	}
+
+	let session = Session {
+		user_id: user_id.to_owned(),
+		device_id: device_id.clone(),
+		expires: Instant::now() + ttl,
+	};
+
+	self.sessions
+		.lock()
+		.expect("locked")
+		.insert(token.to_owned(), session);
+}
+
+/// Forgets the cached introspections of a user's tokens, so that the next
+/// request with any of them is introspected again.
+#[implement(Service)]
+pub fn invalidate_sessions(&self, user_id: &UserId) {
+	let mut sessions = self.sessions.lock().expect("locked");
+	let tokens: Vec<String> = sessions
+		.iter()
+		.filter(|(_, session)| session.user_id == user_id)
+		.map(|(token, _)| token.clone())
+		.collect();
+
+	for token in &tokens {
+		sessions.remove(token);
+	}
+}
+
+/// Creates a local account for a user the authorization server knows about
+/// but which has not used this homeserver before.
+#[implement(Service)]
+async fn provision_user(&self, user_id: &UserId) -> Result {
+	if !self.services.globals.user_is_local(user_id) {
+		return Err!(Request(Forbidden("User does not belong to this homeserver")));
+	}
+
+	debug!(%user_id, "Provisioning user authenticated by the OAuth 2.0 server");
+
+	// The password is never revealed; local password login is disabled while
+	// authentication is delegated.
+	let password = utils::random_string(PLACEHOLDER_TOKEN_LENGTH);
+	self.services.users.create(user_id, Some(&password))?;
+	self.services
+		.users
+		.set_displayname(user_id, Some(user_id.localpart().to_owned()));
+
+	Ok(())
+}
+
+/// Introspects a token at the authorization server (RFC 7662).
+#[implement(Service)]
+async fn introspect(&self, token: &str) -> Result<Introspection> {
+	let config = &self.services.server.config.oauth;
+	let endpoint = self.introspection_endpoint().await?;
+
+	introspect::request(
+		&self.services.client.default,
+		&endpoint,
+		config.client_id.as_deref(),
+		config.client_secret.as_deref(),
+		token,
+		Duration::from_secs(config.request_timeout),
+	)
+	.await
+	.map_err(|e| {
+		Error::Request(
+			ErrorKind::Unknown,
+			format!("Failed to introspect access token: {e}").into(),
+			StatusCode::SERVICE_UNAVAILABLE,
+		)
+	})
+}
+
+#[implement(Service)]
+async fn introspection_endpoint(&self) -> Result<Url> {
+	if let Some(endpoint) = self
+		.services
+		.server
+		.config
+		.oauth
+		.introspection_endpoint
+		.clone()
+	{
+		return Ok(endpoint);
+	}
+
+	self.server_metadata()
+		.await?
+		.get("introspection_endpoint")
+		.and_then(JsonValue::as_str)
+		.ok_or_else(|| {
+			err!(Config("oauth.introspection_endpoint", "Not found in server metadata."))
+		})
+		.and_then(|endpoint| {
+			Url::parse(endpoint)
+				.map_err(|e| err!(BadServerResponse("Invalid introspection endpoint: {e}")))
+		})
+}
+
+/// Authorization server metadata advertised to clients (MSC2965). The
+/// account management URL is included when configured (MSC4191).
+#[implement(Service)]
+pub async fn auth_metadata(&self) -> Result<JsonValue> {
+	let mut metadata = self.server_metadata().await?.as_ref().clone();
+	if let (Some(url), Some(object)) = (
+		self.services
+			.server
+			.config
+			.oauth
+			.account_management_url
+			.as_ref(),
+		metadata.as_object_mut(),
+	) {
+		object.insert("account_management_uri".into(), url.as_str().into());
+	}
+
+	Ok(metadata)
+}
+
+/// Issuer of the authorization server.
+#[implement(Service)]
+pub fn issuer(&self) -> Result<&Url> {
+	self.services
+		.server
+		.config
+		.oauth
+		.issuer
+		.as_ref()
+		.ok_or_else(|| err!(Request(NotFound("Authentication is not delegated."))))
+}
+
+/// Discovers the authorization server metadata from its issuer. The result is
+/// kept until the cache is cleared.
+#[implement(Service)]
+async fn server_metadata(&self) -> Result<Arc<JsonValue>> {
+	if let Some(metadata) = self.metadata.read().expect("locked for reading").clone() {
+		return Ok(metadata);
+	}
+
+	let config = &self.services.server.config.oauth;
+	let issuer = self.issuer()?;
+	let metadata = introspect::discover(
+		&self.services.client.default,
+		issuer,
+		Duration::from_secs(config.request_timeout),
+	)
+	.await
+	.map(Arc::new)?;
+
+	self.metadata
+		.write()
+		.expect("locked for writing")
+		.replace(metadata.clone());
+
+	Ok(metadata)
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use conduwuit::config::Figment;
+use ruma::{DeviceId, device_id, user_id};
+use serde_json::json;
+
+use super::introspect::{self, Introspection, Scope};
+use crate::tests::{mock_server, services, services_with};
+
+const ALICE_SCOPE: &str = "urn:matrix:client:api:* urn:matrix:client:device:ALICEDEV";
+
+const ALICE_ACTIVE: &str = r#"{"active":true,"username":"alice","exp":4102444800,"scope":"urn:matrix:client:api:* urn:matrix:client:device:ALICEDEV"}"#;
+
+#[test]
+fn scope_parses_stable_and_unstable() {
+	let scope = Scope::parse(
+		"openid urn:matrix:org.matrix.msc2967.client:api:* \
+		 urn:matrix:org.matrix.msc2967.client:device:ABCDEFGHIJ",
+	);
+	assert!(scope.api);
+	assert_eq!(scope.device_id.as_deref().map(DeviceId::as_str), Some("ABCDEFGHIJ"));
+
+	let scope = Scope::parse("urn:matrix:client:api:* urn:matrix:client:device:KLMNOPQRST");
+	assert!(scope.api);
+	assert_eq!(scope.device_id.as_deref().map(DeviceId::as_str), Some("KLMNOPQRST"));
+}
+
+#[test]
+fn scope_without_api_or_device() {
+	let scope = Scope::parse("openid urn:matrix:client:device:");
+	assert_eq!(scope, Scope::default());
+}
+
+#[tokio::test]
+async fn introspect_active_token() {
+	let (url, server) = mock_server(
+		"/oauth2/introspect",
+		"200 OK",
+		r#"{"active":true,"username":"alice","sub":"01J","exp":4102444800,"scope":"urn:matrix:client:api:* urn:matrix:client:device:ALICEDEV"}"#,
+	)
+	.await;
+
+	let client = reqwest::Client::new();
+	let introspection = introspect::request(
+		&client,
+		&url,
+		Some("homeserver"),
+		Some("secret"),
+		"mct_token",
+		Duration::from_secs(5),
+	)
+	.await
+	.unwrap();
+
+	assert!(introspection.active);
+	assert_eq!(introspection.username.as_deref(), Some("alice"));
+	assert_eq!(introspection.exp, Some(4_102_444_800));
+
+	let scope = Scope::parse(introspection.scope.as_deref().unwrap());
+	assert_eq!(scope.device_id.as_deref().map(DeviceId::as_str), Some("ALICEDEV"));
+
+	let request = server.await.unwrap();
+	assert!(request.starts_with("POST /oauth2/introspect"));
+	assert!(request.to_lowercase().contains("authorization: basic"));
+	assert!(request.contains("token=mct_token"));
+}
+
+#[tokio::test]
+async fn introspect_inactive_token() {
+	let (url, _server) = mock_server("/oauth2/introspect", "200 OK", r#"{"active":false}"#).await;
+
+	let client = reqwest::Client::new();
+	let introspection =
+		introspect::request(&client, &url, None, None, "revoked", Duration::from_secs(5))
+			.await
+			.unwrap();
+
+	assert!(!introspection.active);
+	assert!(introspection.username.is_none());
+}
+
+#[tokio::test]
+async fn introspect_server_error() {
+	let (url, _server) = mock_server("/oauth2/introspect", "503 Service Unavailable", "{}").await;
+
+	let client = reqwest::Client::new();
+	let result =
+		introspect::request(&client, &url, None, None, "token", Duration::from_secs(5)).await;
+
+	assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validate_introspection() {
+	let services = services().await;
+	let oauth = &services.oauth;
+	let valid = Introspection {
+		active: true,
+		username: Some("alice".into()),
+		scope: Some(ALICE_SCOPE.into()),
+		..Introspection::default()
+	};
+
+	let (user_id, device_id) = oauth.validate(&valid).unwrap().unwrap();
+	assert_eq!(user_id, user_id!("@alice:example.com"));
+	assert_eq!(device_id, device_id!("ALICEDEV"));
+
+	let inactive = Introspection { active: false, ..valid.clone() };
+	assert!(oauth.validate(&inactive).unwrap().is_none());
+
+	let expired = Introspection { exp: Some(1), ..valid.clone() };
+	assert!(oauth.validate(&expired).unwrap().is_none());
+
+	let no_api = Introspection {
+		scope: Some("urn:matrix:client:device:ALICEDEV".into()),
+		..valid.clone()
+	};
+	assert!(oauth.validate(&no_api).unwrap().is_none());
+
+	let no_device = Introspection {
+		scope: Some("urn:matrix:client:api:*".into()),
+		..valid.clone()
+	};
+	assert!(oauth.validate(&no_device).unwrap().is_none());
+
+	let uppercase = Introspection {
+		username: Some("Alice".into()),
+		..valid.clone()
+	};
+	assert!(oauth.validate(&uppercase).unwrap().is_none());
+
+	let invalid = Introspection {
+		username: Some("al ice".into()),
+		..valid.clone()
+	};
+	assert!(oauth.validate(&invalid).unwrap().is_none());
+
+	let no_username = Introspection { username: None, ..valid };
+	assert!(oauth.validate(&no_username).unwrap().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn find_from_token_cached() {
+	// No introspection endpoint is configured, so anything not served from the
+	// cache fails.
+	let services = services().await;
+	let oauth = &services.oauth;
+	let alice = user_id!("@alice:example.com");
+	let device_id = device_id!("ALICEDEV").to_owned();
+
+	oauth.cache_session("cached", alice, &device_id, None);
+	let found = oauth.find_from_token("cached").await.unwrap();
+	assert_eq!(found, Some((alice.to_owned(), device_id.clone())));
+
+	oauth.cache_session("expired", alice, &device_id, Some(1));
+	assert!(oauth.cached_session("expired").is_none());
+	assert!(oauth.find_from_token("expired").await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn find_from_token_provisions_and_deactivation_invalidates() {
+	let (url, server) = mock_server("/oauth2/introspect", "200 OK", ALICE_ACTIVE).await;
+	let services = services_with(
+		Figment::new()
+			.merge(("oauth", json!({ "enable": true, "introspection_endpoint": url.as_str() }))),
+	)
+	.await;
+
+	let oauth = &services.oauth;
+	let alice = user_id!("@alice:example.com");
+	let device_id = device_id!("ALICEDEV");
+
+	let found = oauth.find_from_token("mct_token").await.unwrap();
+	assert_eq!(found, Some((alice.to_owned(), device_id.to_owned())));
+	server.await.unwrap();
+
+	assert!(services.users.exists(alice).await);
+	assert!(
+		services
+			.users
+			.get_device_metadata(alice, device_id)
+			.await
+			.is_ok()
+	);
+	assert_eq!(services.users.displayname(alice).await.unwrap(), "alice");
+
+	// The mock server only answers once, so this is served from the cache.
+	let found = oauth.find_from_token("mct_token").await.unwrap();
+	assert_eq!(found, Some((alice.to_owned(), device_id.to_owned())));
+
+	services.users.deactivate_account(alice).await.unwrap();
+	assert!(oauth.cached_session("mct_token").is_none());
+	assert!(oauth.find_from_token("mct_token").await.is_err());
+}
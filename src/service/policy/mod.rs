@@ -0,0 +1,398 @@
+//! Moderation policy lists (`m.policy.rule.*`).
+//!
+//! Admins register policy rooms the server is joined to. The ban rules of
+//! those rooms are cached and kept current as rule events arrive; invites,
+//! joins, federation traffic, room directory entries and media from matching
+//! entities are refused.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	fmt::Write,
+	sync::{Arc, Mutex, RwLock},
+};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, PduEvent, Result, Server, implement, info,
+	utils::{ReadyExt, stream::TryIgnore, string::glob_matches},
+};
+use database::Map;
+use futures::{Stream, StreamExt};
+use lru_cache::LruCache;
+use ruma::{
+	OwnedRoomId, RoomId, ServerName, UserId,
+	events::{TimelineEventType, policy::rule::PolicyRuleEventContent},
+};
+
+use crate::{Dep, admin, globals, rooms};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+	rules: RwLock<BTreeMap<OwnedRoomId, Rules>>,
+	reported: Mutex<LruCache<String, ()>>,
+}
+
+struct Data {
+	policyroomids: Arc<Map>,
+}
+
+struct Services {
+	server: Arc<Server>,
+	admin: Dep<admin::Service>,
+	globals: Dep<globals::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+}
+
+/// Ban rules of one policy room.
+#[derive(Default)]
+struct Rules {
+	users: RuleSet,
+	rooms: RuleSet,
+	servers: RuleSet,
+}
+
+/// Rules of one type, by the state key of their event. Rules without
+/// wildcards are looked up directly; only the glob rules are matched one by
+/// one.
+#[derive(Default)]
+struct RuleSet {
+	rules: HashMap<String, Rule>,
+	exact: HashMap<String, BTreeSet<String>>,
+	globs: BTreeSet<String>,
+}
+
+/// A ban rule; `entity` is a glob matched against user IDs, room IDs or
+/// server names depending on the rule type.
+#[derive(Clone, Debug)]
+pub struct Rule {
+	pub entity: String,
+	pub reason: String,
+	pub room_id: OwnedRoomId,
+}
+
+/// Recommendations treated as a ban. The second is still sent by older
+/// moderation bots.
+const BAN_RECOMMENDATIONS: &[&str] = &["m.ban", "org.matrix.mjolnir.ban"];
+
+/// Number of refusals remembered so each is reported to the admin room once.
+const REPORTED_MAX: usize = 4096;
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				policyroomids: args.db["policyroomids"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				admin: args.depend::<admin::Service>("admin"),
+				globals: args.depend::<globals::Service>("globals"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+			},
+			rules: RwLock::default(),
+			reported: LruCache::new(REPORTED_MAX).into(),
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result<()> {
+		let room_ids: Vec<OwnedRoomId> = self.rooms().map(ToOwned::to_owned).collect().await;
+		for room_id in &room_ids {
+			self.load_room(room_id).await;
+		}
+
+		Ok(())
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let rules = self
+			.rules
+			.read()
+			.expect("locked for reading")
+			.values()
+			.map(|rules| {
+				rules
+					.users
+					.len()
+					.saturating_add(rules.rooms.len())
+					.saturating_add(rules.servers.len())
+			})
+			.sum::<usize>();
+
+		writeln!(out, "policy_rules: {rules}")?;
+
+		let reported = self.reported.lock().expect("locked").len();
+		writeln!(out, "policy_reported: {reported}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) { self.reported.lock().expect("locked").clear(); }
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Subscribes to the rules of a policy room. The server has to be joined to the
+/// room already.
+#[implement(Service)]
+pub async fn add_room(&self, room_id: &RoomId) -> Result {
+	if !self
+		.services
+		.state_cache
+		.server_in_room(self.services.globals.server_name(), room_id)
+		.await
+	{
+		return Err!(
+			"This server is not joined to {room_id}; join it with the server user first."
+		);
+	}
+
+	self.db.policyroomids.insert(room_id, []);
+	self.load_room(room_id).await;
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub fn remove_room(&self, room_id: &RoomId) {
+	self.db.policyroomids.remove(room_id);
+	self.rules
+		.write()
+		.expect("locked for writing")
+		.remove(room_id);
+}
+
+#[implement(Service)]
+pub fn rooms(&self) -> impl Stream<Item = &RoomId> + Send + '_ {
+	self.db.policyroomids.keys().ignore_err()
+}
+
+#[implement(Service)]
+pub async fn is_policy_room(&self, room_id: &RoomId) -> bool {
+	self.db.policyroomids.exists(room_id).await.is_ok()
+}
+
+/// Number of user, room and server rules cached for a policy room.
+#[implement(Service)]
+pub fn rule_counts(&self, room_id: &RoomId) -> (usize, usize, usize) {
+	self.rules
+		.read()
+		.expect("locked for reading")
+		.get(room_id)
+		.map_or((0, 0, 0), |rules| (rules.users.len(), rules.rooms.len(), rules.servers.len()))
+}
+
+/// Applies a rule event appended to a policy room. Events of rooms which are
+/// not registered as policy rooms are ignored.
+#[implement(Service)]
+pub async fn update_rule(&self, pdu: &PduEvent) {
+	if !self.is_policy_room(&pdu.room_id).await {
+		return;
+	}
+
+	let applied = self
+		.rules
+		.write()
+		.expect("locked for writing")
+		.get_mut(&pdu.room_id)
+		.map(|rules| rules.apply(pdu))
+		.is_some();
+
+	// The rules of the room were not loaded yet.
+	if !applied {
+		self.load_room(&pdu.room_id).await;
+	}
+
+	self.reported.lock().expect("locked").clear();
+}
+
+#[implement(Service)]
+async fn load_room(&self, room_id: &RoomId) {
+	let mut rules = Rules::default();
+	self.services
+		.state_accessor
+		.room_state_full_pdus(room_id)
+		.ignore_err()
+		.ready_for_each(|pdu| rules.apply(&pdu))
+		.await;
+
+	info!(
+		users = rules.users.len(),
+		rooms = rules.rooms.len(),
+		servers = rules.servers.len(),
+		"Loaded policy rules from {room_id}"
+	);
+
+	self.rules
+		.write()
+		.expect("locked for writing")
+		.insert(room_id.to_owned(), rules);
+
+	self.reported.lock().expect("locked").clear();
+}
+
+/// Finds a rule banning the user, or the user's server.
+#[implement(Service)]
+pub fn match_user(&self, user_id: &UserId) -> Option<Rule> {
+	self.find(|rules| &rules.users, user_id.as_str())
+		.or_else(|| self.match_server(user_id.server_name()))
+}
+
+#[implement(Service)]
+pub fn match_room(&self, room_id: &RoomId) -> Option<Rule> {
+	self.find(|rules| &rules.rooms, room_id.as_str())
+}
+
+#[implement(Service)]
+pub fn match_server(&self, server_name: &ServerName) -> Option<Rule> {
+	self.find(|rules| &rules.servers, server_name.as_str())
+		.or_else(|| self.find(|rules| &rules.servers, server_name.host()))
+}
+
+/// Refuses `action` if the user or their server is banned by a policy list.
+#[implement(Service)]
+pub async fn check_user(&self, user_id: &UserId, action: &str) -> Result {
+	match self.match_user(user_id) {
+		| Some(rule) => self.refuse(user_id.as_str(), action, &rule).await,
+		| None => Ok(()),
+	}
+}
+
+/// Refuses `action` if the room is banned by a policy list.
+#[implement(Service)]
+pub async fn check_room(&self, room_id: &RoomId, action: &str) -> Result {
+	match self.match_room(room_id) {
+		| Some(rule) => self.refuse(room_id.as_str(), action, &rule).await,
+		| None => Ok(()),
+	}
+}
+
+/// Refuses `action` if the server is banned by a policy list.
+#[implement(Service)]
+pub async fn check_server(&self, server_name: &ServerName, action: &str) -> Result {
+	match self.match_server(server_name) {
+		| Some(rule) => self.refuse(server_name.as_str(), action, &rule).await,
+		| None => Ok(()),
+	}
+}
+
+#[implement(Service)]
+fn find<F>(&self, set: F, subject: &str) -> Option<Rule>
+where
+	F: Fn(&Rules) -> &RuleSet,
+{
+	self.rules
+		.read()
+		.expect("locked for reading")
+		.values()
+		.find_map(|rules| set(rules).find(subject))
+		.cloned()
+}
+
+/// Reports the match to the admin room, once per subject and action until the
+/// rules change, and returns the error for the refused request.
+#[implement(Service)]
+async fn refuse(&self, subject: &str, action: &str, rule: &Rule) -> Result {
+	let first = self
+		.reported
+		.lock()
+		.expect("locked")
+		.insert(format!("{action} {subject}"), ())
+		.is_none();
+
+	if first && self.services.server.config.admin_room_notices {
+		self.services
+			.admin
+			.send_text(&format!(
+				"Refused {action} for {subject}: matches policy rule `{}` in {} ({})",
+				rule.entity, rule.room_id, rule.reason
+			))
+			.await;
+	}
+
+	Err!(Request(Forbidden("{subject} is banned by a policy list on this server.")))
+}
+
+impl Rules {
+	/// Adds, replaces or removes the rule of a rule event.
+	fn apply(&mut self, pdu: &PduEvent) {
+		let set = match pdu.kind {
+			| TimelineEventType::PolicyRuleUser => &mut self.users,
+			| TimelineEventType::PolicyRuleRoom => &mut self.rooms,
+			| TimelineEventType::PolicyRuleServer => &mut self.servers,
+			| _ => return,
+		};
+
+		let Some(state_key) = pdu.state_key.as_deref() else {
+			return;
+		};
+
+		// Rules are removed by replacing their content with an empty object,
+		// which fails to deserialize here.
+		match pdu.get_content::<PolicyRuleEventContent>() {
+			| Ok(content) if BAN_RECOMMENDATIONS.contains(&content.recommendation.as_str()) => {
+				set.insert(state_key, Rule {
+					entity: content.entity,
+					reason: content.reason,
+					room_id: pdu.room_id.clone(),
+				});
+			},
+			| _ => set.remove(state_key),
+		}
+	}
+}
+
+impl RuleSet {
+	fn insert(&mut self, state_key: &str, rule: Rule) {
+		self.remove(state_key);
+		if rule.entity.contains(['*', '?']) {
+			self.globs.insert(state_key.to_owned());
+		} else {
+			self.exact
+				.entry(rule.entity.clone())
+				.or_default()
+				.insert(state_key.to_owned());
+		}
+
+		self.rules.insert(state_key.to_owned(), rule);
+	}
+
+	fn remove(&mut self, state_key: &str) {
+		let Some(rule) = self.rules.remove(state_key) else {
+			return;
+		};
+
+		if self.globs.remove(state_key) {
+			return;
+		}
+
+		if let Some(state_keys) = self.exact.get_mut(&rule.entity) {
+			state_keys.remove(state_key);
+			if state_keys.is_empty() {
+				self.exact.remove(&rule.entity);
+			}
+		}
+	}
+
+	fn find(&self, subject: &str) -> Option<&Rule> {
+		self.exact
+			.get(subject)
+			.and_then(BTreeSet::first)
+			.and_then(|state_key| self.rules.get(state_key))
+			.or_else(|| {
+				self.globs
+					.iter()
+					.filter_map(|state_key| self.rules.get(state_key))
+					.find(|rule| glob_matches(&rule.entity, subject))
+			})
+	}
+
+	fn len(&self) -> usize { self.rules.len() }
+}
@@ -0,0 +1,139 @@
+use conduwuit::{
+	matrix::pdu::{EventHash, PduEvent},
+	utils,
+};
+use ruma::{
+	OwnedEventId, RoomId, events::TimelineEventType, owned_room_id, owned_user_id, room_id,
+	server_name, uint, user_id,
+};
+use serde_json::{Value, json, value::to_raw_value};
+
+use super::Rules;
+use crate::tests::services;
+
+fn rule(kind: TimelineEventType, state_key: &str, content: Value) -> PduEvent {
+	PduEvent {
+		event_id: OwnedEventId::try_from(format!("${state_key}:example.com")).unwrap(),
+		room_id: owned_room_id!("!policy:example.com"),
+		sender: owned_user_id!("@moderator:example.com"),
+		origin: None,
+		origin_server_ts: utils::millis_since_unix_epoch().try_into().unwrap(),
+		kind,
+		content: to_raw_value(&content).unwrap(),
+		state_key: Some(state_key.into()),
+		prev_events: vec![],
+		depth: uint!(0),
+		auth_events: vec![],
+		redacts: None,
+		unsigned: None,
+		hashes: EventHash { sha256: String::new() },
+		signatures: None,
+	}
+}
+
+fn ban(entity: &str) -> Value {
+	json!({ "entity": entity, "recommendation": "m.ban", "reason": "spam" })
+}
+
+#[test]
+fn rules_are_applied_by_state_key() {
+	let mut rules = Rules::default();
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "a", ban("@spam:example.org")));
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "b", ban("@*:spam.example")));
+	rules.apply(&rule(TimelineEventType::PolicyRuleServer, "c", ban("evil.example")));
+	assert_eq!(rules.users.len(), 2);
+	assert_eq!(rules.servers.len(), 1);
+
+	let found = rules.users.find("@spam:example.org").unwrap();
+	assert_eq!(found.entity, "@spam:example.org");
+	assert_eq!(found.reason, "spam");
+	assert!(rules.users.find("@ham:example.org").is_none());
+
+	// Replacing a rule drops the entity it banned before.
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "a", ban("@other:example.org")));
+	assert!(rules.users.find("@spam:example.org").is_none());
+	assert!(rules.users.find("@other:example.org").is_some());
+	assert_eq!(rules.users.len(), 2);
+
+	// Rules are removed by emptying their content; other recommendations are
+	// not bans.
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "a", json!({})));
+	let mut mute = ban("@muted:example.org");
+	mute["recommendation"] = "org.example.mute".into();
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "d", mute));
+	assert!(rules.users.find("@other:example.org").is_none());
+	assert!(rules.users.find("@muted:example.org").is_none());
+	assert_eq!(rules.users.len(), 1);
+
+	// Events without a state key or of other types are ignored.
+	let mut message = rule(TimelineEventType::RoomMessage, "e", ban("@spam:example.org"));
+	rules.apply(&message);
+	message.kind = TimelineEventType::PolicyRuleUser;
+	message.state_key = None;
+	rules.apply(&message);
+	assert_eq!(rules.users.len(), 1);
+}
+
+#[test]
+fn globs_match_entities() {
+	let mut rules = Rules::default();
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "a", ban("@*:spam.example")));
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "b", ban("@bot?:example.org")));
+	rules.apply(&rule(TimelineEventType::PolicyRuleServer, "c", ban("*.evil.example")));
+
+	assert!(rules.users.find("@anyone:spam.example").is_some());
+	assert!(rules.users.find("@anyone:notspam.example").is_none());
+	assert!(rules.users.find("@bot1:example.org").is_some());
+	assert!(rules.users.find("@bot12:example.org").is_none());
+	assert!(rules.servers.find("a.evil.example").is_some());
+	assert!(rules.servers.find("evil.example").is_none());
+
+	// A glob rule which is removed no longer matches.
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "a", json!({})));
+	assert!(rules.users.find("@anyone:spam.example").is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn users_match_by_user_or_server() {
+	let services = services().await;
+	let policy = &services.policy;
+	let room_id: &RoomId = room_id!("!policy:example.com");
+
+	let mut rules = Rules::default();
+	rules.apply(&rule(TimelineEventType::PolicyRuleUser, "a", ban("@spam:example.org")));
+	rules.apply(&rule(TimelineEventType::PolicyRuleServer, "b", ban("*.evil.example")));
+	rules.apply(&rule(TimelineEventType::PolicyRuleRoom, "c", ban("!bad:example.org")));
+	policy
+		.rules
+		.write()
+		.expect("locked for writing")
+		.insert(room_id.to_owned(), rules);
+
+	assert!(policy.match_user(user_id!("@spam:example.org")).is_some());
+	assert!(
+		policy
+			.match_user(user_id!("@alice:a.evil.example"))
+			.is_some()
+	);
+	assert!(policy.match_user(user_id!("@alice:example.org")).is_none());
+	assert!(
+		policy
+			.match_server(server_name!("b.evil.example:8448"))
+			.is_some()
+	);
+	assert!(policy.match_room(room_id!("!bad:example.org")).is_some());
+	assert_eq!(policy.rule_counts(room_id), (1, 1, 1));
+
+	assert!(
+		policy
+			.check_user(user_id!("@spam:example.org"), "join")
+			.await
+			.is_err()
+	);
+	assert!(
+		policy
+			.check_user(user_id!("@alice:example.org"), "join")
+			.await
+			.is_ok()
+	);
+}
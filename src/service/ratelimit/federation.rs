@@ -0,0 +1,213 @@
+use std::{
+	sync::atomic::{AtomicU64, Ordering},
+	time::{Duration, Instant},
+};
+
+use conduwuit::{Error, Result, debug_warn, implement};
+use http::StatusCode;
+use ruma::{OwnedServerName, ServerName, api::client::error::ErrorKind};
+
+use super::{Class, Service};
+
+/// Budgets of throttled origins are divided by this.
+const THROTTLE_FACTOR: f64 = 10.0;
+
+/// Origins are pruned once there are this many of them.
+const ORIGINS_PRUNE_LEN: usize = 4096;
+
+/// Limiter state and counters of a remote server.
+#[derive(Clone, Debug, Default)]
+pub struct Origin {
+	/// Transactions being processed.
+	pub transactions: usize,
+
+	/// Requests refused for exceeding a limit.
+	pub limited: u64,
+
+	/// Set by an admin to restrict the origin further for a while.
+	pub throttled_until: Option<Instant>,
+}
+
+/// Requests of all origins refused since startup. Unlike the per-origin
+/// counters these are not reset by clearing the caches.
+#[derive(Debug, Default)]
+pub struct Refused {
+	/// Transactions refused for too many in flight.
+	pub concurrent: AtomicU64,
+
+	/// Transactions refused for exceeding the PDU budget.
+	pub pdus: AtomicU64,
+
+	/// Requests to expensive endpoints refused for exceeding their budget.
+	pub expensive: AtomicU64,
+}
+
+/// Held while a transaction of an origin is processed.
+pub struct Transaction<'a> {
+	service: &'a Service,
+	origin: OwnedServerName,
+}
+
+/// Admits a transaction of `pdus` PDUs from `origin` if the origin has neither
+/// too many transactions in flight nor exceeded its PDU budget. The returned
+/// guard has to be held while the transaction is processed.
+#[implement(Service)]
+pub fn check_transaction(&self, origin: &ServerName, pdus: usize) -> Result<Transaction<'_>> {
+	let config = &self.services.server.config;
+	let throttled = self.is_throttled(origin);
+	let limit = if throttled {
+		1
+	} else {
+		config.federation_ratelimit_concurrent_transactions
+	};
+
+	{
+		let mut origins = self.origins.lock().expect("locked");
+		if origins.len() >= ORIGINS_PRUNE_LEN {
+			origins.retain(|_, origin| origin.is_active());
+		}
+
+		let entry = origins.entry(origin.to_owned()).or_default();
+		if config.federation_ratelimit && limit > 0 && entry.transactions >= limit {
+			entry.limited = entry.limited.saturating_add(1);
+			self.refused.concurrent.fetch_add(1, Ordering::Relaxed);
+			debug_warn!(
+				transactions = entry.transactions,
+				"Refusing transaction from {origin}: too many in flight"
+			);
+
+			return Err(Error::Request(
+				ErrorKind::LimitExceeded { retry_after: None },
+				"Too many concurrent transactions.".into(),
+				StatusCode::TOO_MANY_REQUESTS,
+			));
+		}
+
+		entry.transactions = entry.transactions.saturating_add(1);
+	}
+
+	// The guard undoes the increment above if the PDU budget is exceeded.
+	let transaction = Transaction { service: self, origin: origin.to_owned() };
+	self.check_origin(Class::FederationPdu, origin, pdus)?;
+
+	Ok(transaction)
+}
+
+/// Takes a token from the budget of `origin` for expensive endpoints.
+#[implement(Service)]
+pub fn check_expensive(&self, origin: &ServerName) -> Result {
+	self.check_origin(Class::FederationExpensive, origin, 1)
+}
+
+#[implement(Service)]
+fn check_origin(&self, class: Class, origin: &ServerName, cost: usize) -> Result {
+	let scale = if self.is_throttled(origin) {
+		THROTTLE_FACTOR.recip()
+	} else {
+		1.0
+	};
+
+	self.take(class, origin.as_str(), cost, scale)
+		.inspect_err(|_| {
+			debug_warn!(?class, "Rate limiting {origin}");
+			let refused = match class {
+				| Class::FederationExpensive => &self.refused.expensive,
+				| _ => &self.refused.pdus,
+			};
+
+			refused.fetch_add(1, Ordering::Relaxed);
+			let mut origins = self.origins.lock().expect("locked");
+			let entry = origins.entry(origin.to_owned()).or_default();
+			entry.limited = entry.limited.saturating_add(1);
+		})
+}
+
+/// Restricts an origin to one transaction at a time and a tenth of its
+/// budgets until `duration` has passed.
+#[implement(Service)]
+pub fn throttle(&self, origin: &ServerName, duration: Duration) {
+	self.origins
+		.lock()
+		.expect("locked")
+		.entry(origin.to_owned())
+		.or_default()
+		.throttled_until = Instant::now().checked_add(duration);
+}
+
+/// Lifts an admin-imposed throttle. Returns false if the origin was not
+/// throttled.
+#[implement(Service)]
+pub fn unthrottle(&self, origin: &ServerName) -> bool {
+	self.origins
+		.lock()
+		.expect("locked")
+		.get_mut(origin)
+		.and_then(|origin| origin.throttled_until.take())
+		.is_some_and(|until| until > Instant::now())
+}
+
+#[implement(Service)]
+pub fn is_throttled(&self, origin: &ServerName) -> bool {
+	self.origins
+		.lock()
+		.expect("locked")
+		.get(origin)
+		.and_then(|origin| origin.throttled_until)
+		.is_some_and(|until| until > Instant::now())
+}
+
+/// Limiter state of the origins which sent requests since the last cache
+/// clear, or were throttled.
+#[implement(Service)]
+pub fn origins(&self) -> Vec<(OwnedServerName, Origin)> {
+	let mut origins: Vec<_> = self
+		.origins
+		.lock()
+		.expect("locked")
+		.iter()
+		.map(|(name, origin)| (name.clone(), origin.clone()))
+		.collect();
+
+	origins.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+	origins
+}
+
+/// Requests of all origins refused since startup.
+#[implement(Service)]
+#[inline]
+pub fn refused(&self) -> &Refused { &self.refused }
+
+#[implement(Service)]
+pub fn origin(&self, origin: &ServerName) -> Option<Origin> {
+	self.origins.lock().expect("locked").get(origin).cloned()
+}
+
+impl Origin {
+	pub(super) fn is_active(&self) -> bool {
+		self.transactions > 0
+			|| self
+				.throttled_until
+				.is_some_and(|until| until > Instant::now())
+	}
+}
+
+impl Refused {
+	pub(super) fn write(&self, out: &mut dyn std::fmt::Write) -> std::fmt::Result {
+		let concurrent = self.concurrent.load(Ordering::Relaxed);
+		let pdus = self.pdus.load(Ordering::Relaxed);
+		let expensive = self.expensive.load(Ordering::Relaxed);
+
+		writeln!(out, "ratelimit_refused_concurrent: {concurrent}")?;
+		writeln!(out, "ratelimit_refused_pdus: {pdus}")?;
+		writeln!(out, "ratelimit_refused_expensive: {expensive}")
+	}
+}
+
+impl Drop for Transaction<'_> {
+	fn drop(&mut self) {
+		let mut origins = self.service.origins.lock().expect("locked");
+		if let Some(origin) = origins.get_mut(&self.origin) {
+			origin.transactions = origin.transactions.saturating_sub(1);
+		}
+	}
+}
@@ -0,0 +1,259 @@
+//! Token bucket rate limiting of client and federation requests.
+
+mod federation;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	fmt::Write,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use conduwuit::{Error, Result, Server, debug, debug_warn, implement};
+use http::StatusCode;
+use ruma::{
+	OwnedServerName, UserId,
+	api::client::error::{ErrorKind, RetryAfter},
+};
+use tokio::{
+	sync::Notify,
+	time::{MissedTickBehavior, interval},
+};
+
+pub use self::federation::{Origin, Refused, Transaction};
+use crate::{Dep, users};
+
+pub struct Service {
+	services: Services,
+	buckets: Mutex<HashMap<(Class, String), Bucket>>,
+	origins: Mutex<HashMap<OwnedServerName, Origin>>,
+	refused: Refused,
+	interrupt: Notify,
+}
+
+struct Services {
+	server: Arc<Server>,
+	users: Dep<users::Service>,
+}
+
+/// Classes of requests with their own budget.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Class {
+	Login,
+	LoginAccount,
+	Register,
+	Message,
+	Join,
+	MediaUpload,
+	FederationPdu,
+	FederationExpensive,
+}
+
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+
+	/// Scale of the budget at the last request, so pruning refills the bucket
+	/// with the budget it was taken from.
+	scale: f64,
+}
+
+/// Interval at which full buckets are dropped; a full bucket behaves the same
+/// as a missing one.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				server: args.server.clone(),
+				users: args.depend::<users::Service>("users"),
+			},
+			buckets: Mutex::default(),
+			origins: Mutex::default(),
+			refused: Refused::default(),
+			interrupt: Notify::new(),
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let mut i = interval(PRUNE_INTERVAL);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		loop {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			let pruned = self.prune();
+			if pruned > 0 {
+				debug!("Pruned {pruned} full rate limit buckets");
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let buckets = self.buckets.lock().expect("locked").len();
+		let origins = self.origins.lock().expect("locked").len();
+
+		writeln!(out, "ratelimit_buckets: {buckets}")?;
+		writeln!(out, "ratelimit_origins: {origins}")?;
+		self.refused.write(out)?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.buckets.lock().expect("locked").clear();
+		self.origins
+			.lock()
+			.expect("locked")
+			.retain(|_, origin| origin.is_active());
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Takes a token from the bucket of a local user. Server admins are exempt.
+#[implement(Service)]
+pub async fn check_user(&self, class: Class, user_id: &UserId) -> Result {
+	let Err(e) = self.check(class, user_id.as_str()) else {
+		return Ok(());
+	};
+
+	// Only looked up once the budget is exceeded, as this is comparatively costly.
+	if self.services.users.is_admin(user_id).await {
+		return Ok(());
+	}
+
+	debug_warn!(?class, "Rate limiting {user_id}");
+	Err(e)
+}
+
+/// Takes a token from the bucket of `key`, which identifies the client (e.g.
+/// its IP address).
+#[implement(Service)]
+#[inline]
+pub fn check(&self, class: Class, key: &str) -> Result { self.take(class, key, 1, 1.0) }
+
+/// Takes `cost` tokens from the bucket of `key`; the budget of the class is
+/// multiplied by `scale`.
+#[implement(Service)]
+fn take(&self, class: Class, key: &str, cost: usize, scale: f64) -> Result {
+	let (per_second, burst) = self.scaled_budget(class, scale);
+	if per_second <= 0.0 {
+		return Ok(());
+	}
+
+	let now = Instant::now();
+	let mut buckets = self.buckets.lock().expect("locked");
+	let bucket = buckets
+		.entry((class, key.to_owned()))
+		.or_insert_with(|| Bucket { tokens: burst, updated: now, scale });
+
+	bucket.scale = scale;
+
+	// A request costing more than the whole burst is let through on a full bucket.
+	let cost = f64::from(u32::try_from(cost).unwrap_or(u32::MAX)).min(burst);
+	if bucket.refill(now, per_second, burst) >= cost {
+		bucket.tokens -= cost;
+		return Ok(());
+	}
+
+	let retry_after =
+		Duration::try_from_secs_f64((cost - bucket.tokens) / per_second).unwrap_or(Duration::MAX);
+	Err(Error::Request(
+		ErrorKind::LimitExceeded {
+			retry_after: Some(RetryAfter::Delay(retry_after)),
+		},
+		"Too many requests.".into(),
+		StatusCode::TOO_MANY_REQUESTS,
+	))
+}
+
+/// Drops the buckets which have refilled completely, returning their number.
+#[implement(Service)]
+fn prune(&self) -> usize {
+	let now = Instant::now();
+	let mut buckets = self.buckets.lock().expect("locked");
+	let len = buckets.len();
+	buckets.retain(|&(class, _), bucket| {
+		let (per_second, burst) = self.scaled_budget(class, bucket.scale);
+		per_second > 0.0 && bucket.refill(now, per_second, burst) < burst
+	});
+
+	len.saturating_sub(buckets.len())
+}
+
+/// Rate per second and burst size of a class. A rate of zero means requests
+/// of the class are not limited.
+#[implement(Service)]
+fn budget(&self, class: Class) -> (f64, f64) {
+	let config = &self.services.server.config;
+	let (per_second, burst) = match class {
+		| Class::Login =>
+			(config.client_ratelimit_login_per_second, config.client_ratelimit_login_burst),
+		| Class::LoginAccount => (
+			config.client_ratelimit_login_account_per_second,
+			config.client_ratelimit_login_account_burst,
+		),
+		| Class::Register => (
+			config.client_ratelimit_register_per_second,
+			config.client_ratelimit_register_burst,
+		),
+		| Class::Message => (
+			config.client_ratelimit_message_per_second,
+			config.client_ratelimit_message_burst,
+		),
+		| Class::Join =>
+			(config.client_ratelimit_join_per_second, config.client_ratelimit_join_burst),
+		| Class::MediaUpload => (
+			config.client_ratelimit_media_upload_per_second,
+			config.client_ratelimit_media_upload_burst,
+		),
+		| Class::FederationPdu => (
+			config.federation_ratelimit_pdus_per_second,
+			config.federation_ratelimit_pdus_burst,
+		),
+		| Class::FederationExpensive => (
+			config.federation_ratelimit_expensive_per_second,
+			config.federation_ratelimit_expensive_burst,
+		),
+	};
+
+	let enabled = match class {
+		| Class::FederationPdu | Class::FederationExpensive => config.federation_ratelimit,
+		| _ => config.client_ratelimit,
+	};
+
+	if !enabled {
+		return (0.0, 0.0);
+	}
+
+	(per_second, f64::from(burst.max(1)))
+}
+
+#[implement(Service)]
+fn scaled_budget(&self, class: Class, scale: f64) -> (f64, f64) {
+	let (per_second, burst) = self.budget(class);
+
+	(per_second * scale, (burst * scale).max(1.0))
+}
+
+impl Bucket {
+	/// Adds the tokens accrued since the last update and returns the count.
+	fn refill(&mut self, now: Instant, per_second: f64, burst: f64) -> f64 {
+		let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+		self.tokens = elapsed.mul_add(per_second, self.tokens).min(burst);
+		self.updated = now;
+		self.tokens
+	}
+}
@@ -0,0 +1,202 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use conduwuit::{Error, config::Figment};
+use ruma::{
+	api::client::error::{ErrorKind, RetryAfter},
+	server_name,
+};
+
+use super::Class;
+use crate::{Service as _, tests::services_with};
+
+fn config() -> Figment {
+	Figment::new()
+		.merge(("client_ratelimit_login_per_second", 0.5))
+		.merge(("client_ratelimit_login_burst", 2))
+}
+
+/// Moves the last update of a bucket back in time.
+fn backdate(services: &crate::Services, class: Class, key: &str, by: Duration) {
+	let mut buckets = services.ratelimit.buckets.lock().expect("locked");
+	let bucket = buckets.get_mut(&(class, key.to_owned())).unwrap();
+	bucket.updated = bucket.updated.checked_sub(by).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn take_until_exhausted() {
+	let services = services_with(config()).await;
+	let ratelimit = &services.ratelimit;
+
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_ok());
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_ok());
+
+	let Err(Error::Request(ErrorKind::LimitExceeded { retry_after }, ..)) =
+		ratelimit.check(Class::Login, "192.0.2.1")
+	else {
+		panic!("expected the bucket to be exhausted");
+	};
+
+	let Some(RetryAfter::Delay(delay)) = retry_after else {
+		panic!("expected a retry delay");
+	};
+	assert!(delay > Duration::ZERO && delay <= Duration::from_secs(2));
+
+	// Other keys and classes have buckets of their own.
+	assert!(ratelimit.check(Class::Login, "192.0.2.2").is_ok());
+	assert!(ratelimit.check(Class::LoginAccount, "192.0.2.1").is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refill_over_time() {
+	let services = services_with(config()).await;
+	let ratelimit = &services.ratelimit;
+
+	for _ in 0..2 {
+		ratelimit.check(Class::Login, "192.0.2.1").unwrap();
+	}
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_err());
+
+	// Two seconds refill one token at half a token per second.
+	backdate(&services, Class::Login, "192.0.2.1", Duration::from_secs(2));
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_ok());
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_err());
+
+	// Refilling stops at the burst.
+	backdate(&services, Class::Login, "192.0.2.1", Duration::from_secs(3600));
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_ok());
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_ok());
+	assert!(ratelimit.check(Class::Login, "192.0.2.1").is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prune_full_buckets() {
+	let services = services_with(config()).await;
+	let ratelimit = &services.ratelimit;
+
+	ratelimit.check(Class::Login, "192.0.2.1").unwrap();
+	ratelimit.check(Class::Login, "192.0.2.2").unwrap();
+	assert_eq!(ratelimit.prune(), 0);
+
+	backdate(&services, Class::Login, "192.0.2.1", Duration::from_secs(2));
+	assert_eq!(ratelimit.prune(), 1);
+
+	let buckets = ratelimit.buckets.lock().expect("locked");
+	assert!(!buckets.contains_key(&(Class::Login, "192.0.2.1".to_owned())));
+	assert!(buckets.contains_key(&(Class::Login, "192.0.2.2".to_owned())));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn disabled_is_unlimited() {
+	let services = services_with(config().merge(("client_ratelimit", false))).await;
+	for _ in 0..10 {
+		assert!(services.ratelimit.check(Class::Login, "192.0.2.1").is_ok());
+	}
+
+	assert!(
+		services
+			.ratelimit
+			.buckets
+			.lock()
+			.expect("locked")
+			.is_empty()
+	);
+}
+
+fn federation_config() -> Figment {
+	Figment::new()
+		.merge(("federation_ratelimit_concurrent_transactions", 2))
+		.merge(("federation_ratelimit_pdus_per_second", 1.0))
+		.merge(("federation_ratelimit_pdus_burst", 100))
+		.merge(("federation_ratelimit_expensive_per_second", 1.0))
+		.merge(("federation_ratelimit_expensive_burst", 10))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_transactions_per_origin() {
+	let services = services_with(federation_config()).await;
+	let ratelimit = &services.ratelimit;
+	let origin = server_name!("a.example.org");
+
+	let first = ratelimit.check_transaction(origin, 1).unwrap();
+	let _second = ratelimit.check_transaction(origin, 1).unwrap();
+	assert!(ratelimit.check_transaction(origin, 1).is_err());
+	assert_eq!(ratelimit.origin(origin).unwrap().transactions, 2);
+
+	// Other origins are unaffected.
+	assert!(
+		ratelimit
+			.check_transaction(server_name!("b.example.org"), 1)
+			.is_ok()
+	);
+
+	drop(first);
+	assert!(ratelimit.check_transaction(origin, 1).is_ok());
+	assert_eq!(ratelimit.origin(origin).unwrap().limited, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pdu_budget_per_origin() {
+	let services = services_with(federation_config()).await;
+	let ratelimit = &services.ratelimit;
+	let origin = server_name!("a.example.org");
+
+	ratelimit.check_transaction(origin, 60).unwrap();
+	assert!(ratelimit.check_transaction(origin, 60).is_err());
+
+	// A refused transaction does not stay in flight.
+	assert_eq!(ratelimit.origin(origin).unwrap().transactions, 0);
+	assert!(ratelimit.check_transaction(origin, 40).is_ok());
+	assert!(
+		ratelimit
+			.check_transaction(server_name!("b.example.org"), 100)
+			.is_ok()
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn throttled_origin() {
+	let services = services_with(federation_config()).await;
+	let ratelimit = &services.ratelimit;
+	let origin = server_name!("a.example.org");
+
+	ratelimit.throttle(origin, Duration::from_secs(60));
+	assert!(ratelimit.is_throttled(origin));
+
+	// One transaction at a time and a tenth of the budgets.
+	let transaction = ratelimit.check_transaction(origin, 1).unwrap();
+	assert!(ratelimit.check_transaction(origin, 1).is_err());
+	drop(transaction);
+
+	assert!(ratelimit.check_expensive(origin).is_ok());
+	assert!(ratelimit.check_expensive(origin).is_err());
+
+	assert!(ratelimit.unthrottle(origin));
+	assert!(!ratelimit.unthrottle(origin));
+	assert!(ratelimit.check_transaction(origin, 1).is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refused_survive_clear_cache() {
+	let services = services_with(federation_config()).await;
+	let ratelimit = &services.ratelimit;
+	let origin = server_name!("a.example.org");
+
+	for _ in 0..10 {
+		ratelimit.check_expensive(origin).unwrap();
+	}
+	assert!(ratelimit.check_expensive(origin).is_err());
+	assert!(ratelimit.check_transaction(origin, 200).is_ok());
+	assert!(ratelimit.check_transaction(origin, 1).is_err());
+
+	ratelimit.clear_cache().await;
+	assert!(ratelimit.origin(origin).is_none());
+
+	let refused = ratelimit.refused();
+	assert_eq!(refused.expensive.load(Ordering::Relaxed), 1);
+	assert_eq!(refused.pdus.load(Ordering::Relaxed), 1);
+	assert_eq!(refused.concurrent.load(Ordering::Relaxed), 0);
+
+	let mut out = String::new();
+	ratelimit.memory_usage(&mut out).await.unwrap();
+	assert!(out.contains("ratelimit_refused_expensive: 1"));
+}
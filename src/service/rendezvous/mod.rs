@@ -0,0 +1,266 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	fmt::Write,
+	sync::{Arc, RwLock},
+	time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use conduwuit::{Err, Error, Result, Server, debug, err, implement, utils};
+use http::StatusCode;
+use ruma::api::client::error::ErrorKind;
+use tokio::{
+	sync::{Notify, watch},
+	time::{MissedTickBehavior, interval, timeout},
+};
+
+pub struct Service {
+	sessions: RwLock<Sessions>,
+	interrupt: Notify,
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+}
+
+/// A rendezvous session (MSC4108). The payload is opaque to the server and
+/// replaced wholesale by each write.
+#[derive(Clone, Debug)]
+pub struct Session {
+	pub content_type: String,
+	pub data: Bytes,
+	pub etag: String,
+	pub expires: SystemTime,
+	pub last_modified: SystemTime,
+}
+
+struct Entry {
+	session: Session,
+	changed: watch::Sender<String>,
+}
+
+type Sessions = HashMap<String, Entry>;
+
+const SESSION_ID_LENGTH: usize = 32;
+const ETAG_LENGTH: usize = 16;
+
+/// Maximum time a GET waits for the session to change when the client already
+/// has the current version.
+const POLL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval at which expired sessions are removed.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			sessions: RwLock::new(Sessions::new()),
+			interrupt: Notify::new(),
+			services: Services { server: args.server.clone() },
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "rendezvous", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if !self.services.server.config.allow_rendezvous {
+			return Ok(());
+		}
+
+		let mut i = interval(PRUNE_INTERVAL);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		loop {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			self.prune();
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let (count, bytes) = {
+			let sessions = self.sessions.read().expect("locked for reading");
+			let bytes = sessions
+				.values()
+				.map(|entry| entry.session.data.len())
+				.fold(0_usize, usize::saturating_add);
+
+			(sessions.len(), bytes)
+		};
+
+		writeln!(out, "rendezvous_sessions: {count} ({})", utils::bytes::pretty(bytes))?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) { self.prune(); }
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Creates a new session holding the initial payload. Returns the session ID.
+#[implement(Service)]
+pub fn create(&self, content_type: &str, data: Bytes) -> Result<(String, Session)> {
+	self.check_enabled()?;
+	self.check_content_length(&data)?;
+
+	let config = &self.services.server.config;
+	let now = SystemTime::now();
+	let session = Session {
+		content_type: content_type.to_owned(),
+		data,
+		etag: utils::random_string(ETAG_LENGTH),
+		expires: now + Duration::from_secs(config.rendezvous_session_ttl),
+		last_modified: now,
+	};
+
+	let mut sessions = self.sessions.write().expect("locked for writing");
+	if sessions.len() >= config.rendezvous_max_sessions {
+		return Err(Error::Request(
+			ErrorKind::LimitExceeded { retry_after: None },
+			"Too many rendezvous sessions are in progress.".into(),
+			StatusCode::TOO_MANY_REQUESTS,
+		));
+	}
+
+	let session_id = utils::random_string(SESSION_ID_LENGTH);
+	let (changed, _) = watch::channel(session.etag.clone());
+	sessions.insert(session_id.clone(), Entry { session: session.clone(), changed });
+
+	debug!(?session_id, "Created rendezvous session");
+	Ok((session_id, session))
+}
+
+/// Returns the session. When `etag` matches the current version, waits for
+/// the session to change first and returns None if it did not.
+#[implement(Service)]
+pub async fn poll(&self, session_id: &str, etag: Option<&str>) -> Result<Option<Session>> {
+	self.check_enabled()?;
+
+	let (session, mut changed) = {
+		let sessions = self.sessions.read().expect("locked for reading");
+		let entry = sessions
+			.get(session_id)
+			.filter(|entry| !is_expired(&entry.session))
+			.ok_or_else(not_found)?;
+
+		(entry.session.clone(), entry.changed.subscribe())
+	};
+
+	if etag.is_none_or(|etag| etag != session.etag) {
+		return Ok(Some(session));
+	}
+
+	let wait = POLL_TIMEOUT.min(
+		session
+			.expires
+			.duration_since(SystemTime::now())
+			.unwrap_or_default(),
+	);
+
+	if timeout(wait, changed.changed()).await.is_err() {
+		return Ok(None);
+	}
+
+	let sessions = self.sessions.read().expect("locked for reading");
+	let session = sessions
+		.get(session_id)
+		.map(|entry| &entry.session)
+		.filter(|session| !is_expired(session))
+		.ok_or_else(not_found)?;
+
+	Ok((etag != Some(session.etag.as_str())).then(|| session.clone()))
+}
+
+/// Replaces the payload of the session if `etag` is its current version.
+/// Returns None when the session was modified concurrently.
+#[implement(Service)]
+pub fn update(
+	&self,
+	session_id: &str,
+	etag: &str,
+	content_type: &str,
+	data: Bytes,
+) -> Result<Option<Session>> {
+	self.check_enabled()?;
+	self.check_content_length(&data)?;
+
+	let mut sessions = self.sessions.write().expect("locked for writing");
+	let entry = sessions
+		.get_mut(session_id)
+		.filter(|entry| !is_expired(&entry.session))
+		.ok_or_else(not_found)?;
+
+	if entry.session.etag != etag {
+		return Ok(None);
+	}
+
+	entry.session.content_type = content_type.to_owned();
+	entry.session.data = data;
+	entry.session.etag = utils::random_string(ETAG_LENGTH);
+	entry.session.last_modified = SystemTime::now();
+	entry.changed.send_replace(entry.session.etag.clone());
+
+	Ok(Some(entry.session.clone()))
+}
+
+/// Ends the session; waiting pollers receive a 404.
+#[implement(Service)]
+pub fn delete(&self, session_id: &str) -> Result {
+	self.check_enabled()?;
+
+	self.sessions
+		.write()
+		.expect("locked for writing")
+		.remove(session_id)
+		.filter(|entry| !is_expired(&entry.session))
+		.map(|entry| entry.changed.send_replace(String::new()))
+		.ok_or_else(not_found)?;
+
+	Ok(())
+}
+
+#[implement(Service)]
+fn prune(&self) {
+	let mut sessions = self.sessions.write().expect("locked for writing");
+	let before = sessions.len();
+	sessions.retain(|_, entry| !is_expired(&entry.session));
+
+	let pruned = before.saturating_sub(sessions.len());
+	if pruned > 0 {
+		debug!(pruned, "Removed expired rendezvous sessions");
+	}
+}
+
+#[implement(Service)]
+fn check_enabled(&self) -> Result {
+	if !self.services.server.config.allow_rendezvous {
+		return Err!(Request(NotFound("Rendezvous sessions are disabled on this server.")));
+	}
+
+	Ok(())
+}
+
+#[implement(Service)]
+fn check_content_length(&self, data: &Bytes) -> Result {
+	if data.len() > self.services.server.config.rendezvous_max_content_length {
+		return Err!(Request(TooLarge("Rendezvous payload is too large.")));
+	}
+
+	Ok(())
+}
+
+fn is_expired(session: &Session) -> bool { session.expires <= SystemTime::now() }
+
+fn not_found() -> Error { err!(Request(NotFound("Rendezvous session not found."))) }
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use conduwuit::config::Figment;
+use tokio::time::sleep;
+
+use crate::tests::services_with;
+
+const CONTENT_TYPE: &str = "application/octet-stream";
+
+fn config() -> Figment {
+	Figment::new()
+		.merge(("allow_rendezvous", true))
+		.merge(("rendezvous_session_ttl", 1))
+		.merge(("rendezvous_max_sessions", 2))
+		.merge(("rendezvous_max_content_length", 8))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn session_lifecycle() {
+	let services = services_with(config()).await;
+	let rendezvous = &services.rendezvous;
+
+	let (session_id, created) = rendezvous
+		.create(CONTENT_TYPE, Bytes::from_static(b"hello"))
+		.unwrap();
+
+	// Polling without an etag returns the session at once.
+	let polled = rendezvous.poll(&session_id, None).await.unwrap().unwrap();
+	assert_eq!(polled.data, created.data);
+	assert_eq!(polled.etag, created.etag);
+
+	// Updating with a stale etag is refused.
+	assert!(
+		rendezvous
+			.update(&session_id, "stale", CONTENT_TYPE, Bytes::from_static(b"nope"))
+			.unwrap()
+			.is_none()
+	);
+
+	let updated = rendezvous
+		.update(&session_id, &created.etag, CONTENT_TYPE, Bytes::from_static(b"world"))
+		.unwrap()
+		.unwrap();
+	assert_ne!(updated.etag, created.etag);
+
+	// The old etag no longer matches, so the new payload is returned.
+	let polled = rendezvous
+		.poll(&session_id, Some(&created.etag))
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(polled.data, Bytes::from_static(b"world"));
+
+	rendezvous.delete(&session_id).unwrap();
+	assert!(rendezvous.poll(&session_id, None).await.is_err());
+	assert!(rendezvous.delete(&session_id).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn poll_waits_for_update() {
+	let services = services_with(config()).await;
+	let (session_id, created) = services
+		.rendezvous
+		.create(CONTENT_TYPE, Bytes::from_static(b"hello"))
+		.unwrap();
+
+	let poller = {
+		let services = services.clone();
+		let session_id = session_id.clone();
+		let etag = created.etag.clone();
+		tokio::spawn(async move { services.rendezvous.poll(&session_id, Some(&etag)).await })
+	};
+
+	sleep(Duration::from_millis(100)).await;
+	services
+		.rendezvous
+		.update(&session_id, &created.etag, CONTENT_TYPE, Bytes::from_static(b"world"))
+		.unwrap()
+		.unwrap();
+
+	let polled = poller.await.unwrap().unwrap().unwrap();
+	assert_eq!(polled.data, Bytes::from_static(b"world"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sessions_expire() {
+	let services = services_with(config()).await;
+	let rendezvous = &services.rendezvous;
+	let (session_id, created) = rendezvous
+		.create(CONTENT_TYPE, Bytes::from_static(b"hello"))
+		.unwrap();
+
+	// Waiting on the current version ends when the session expires.
+	assert!(
+		rendezvous
+			.poll(&session_id, Some(&created.etag))
+			.await
+			.unwrap()
+			.is_none()
+	);
+
+	sleep(Duration::from_millis(100)).await;
+	assert!(rendezvous.poll(&session_id, None).await.is_err());
+	assert!(
+		rendezvous
+			.update(&session_id, &created.etag, CONTENT_TYPE, Bytes::new())
+			.is_err()
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn limits_are_enforced() {
+	let services = services_with(config()).await;
+	let rendezvous = &services.rendezvous;
+
+	let too_large = Bytes::from_static(b"123456789");
+	assert!(rendezvous.create(CONTENT_TYPE, too_large.clone()).is_err());
+
+	let (session_id, created) = rendezvous.create(CONTENT_TYPE, Bytes::new()).unwrap();
+	assert!(
+		rendezvous
+			.update(&session_id, &created.etag, CONTENT_TYPE, too_large)
+			.is_err()
+	);
+
+	rendezvous.create(CONTENT_TYPE, Bytes::new()).unwrap();
+	assert!(rendezvous.create(CONTENT_TYPE, Bytes::new()).is_err());
+
+	// Ending a session makes room for another.
+	rendezvous.delete(&session_id).unwrap();
+	rendezvous.create(CONTENT_TYPE, Bytes::new()).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn disabled_by_default() {
+	let services = services_with(Figment::new()).await;
+	assert!(
+		services
+			.rendezvous
+			.create(CONTENT_TYPE, Bytes::new())
+			.is_err()
+	);
+}
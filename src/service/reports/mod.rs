@@ -0,0 +1,162 @@
+//! Persistent queue of room and event reports for server admins.
+//!
+//! Reports are kept after they are handled, so that the history of reports
+//! against a room or user remains available to moderators.
+
+use std::sync::Arc;
+
+use conduwuit::{Err, Result, implement, utils, utils::stream::TryIgnore};
+use database::{Deserialized, Ignore, Json, Map};
+use futures::{Stream, StreamExt};
+use ruma::{EventId, Int, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId};
+use serde::{Deserialize, Serialize};
+
+use crate::{Dep, globals};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	reportid_report: Arc<Map>,
+}
+
+struct Services {
+	globals: Dep<globals::Service>,
+}
+
+/// A report of a room, or of an event in a room, made by a local user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Report {
+	pub id: u64,
+	pub reporter: OwnedUserId,
+	pub room_id: OwnedRoomId,
+
+	/// The reported event, or none if the whole room was reported.
+	pub event_id: Option<OwnedEventId>,
+
+	pub reason: Option<String>,
+
+	/// From -100 (most offensive) to 0 (inoffensive), if given.
+	pub score: Option<Int>,
+
+	pub state: ReportState,
+
+	/// Milliseconds since the unix epoch when the report was received.
+	pub received_ts: u64,
+
+	/// The admin who resolved or dismissed the report.
+	pub handled_by: Option<OwnedUserId>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportState {
+	/// Waiting for a moderator.
+	Open,
+
+	/// Action was taken on the report.
+	Resolved,
+
+	/// The report was looked at and no action was needed.
+	Dismissed,
+}
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				reportid_report: args.db["reportid_report"].clone(),
+			},
+			services: Services {
+				globals: args.depend::<globals::Service>("globals"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Stores a new open report, returning it with its assigned ID.
+#[implement(Service)]
+pub fn create(
+	&self,
+	reporter: &UserId,
+	room_id: &RoomId,
+	event_id: Option<&EventId>,
+	reason: Option<&str>,
+	score: Option<Int>,
+) -> Result<Report> {
+	let report = Report {
+		id: self.services.globals.next_count()?,
+		reporter: reporter.to_owned(),
+		room_id: room_id.to_owned(),
+		event_id: event_id.map(ToOwned::to_owned),
+		reason: reason.map(ToOwned::to_owned),
+		score,
+		state: ReportState::Open,
+		received_ts: utils::millis_since_unix_epoch(),
+		handled_by: None,
+	};
+
+	self.put(&report);
+
+	Ok(report)
+}
+
+#[implement(Service)]
+pub async fn get(&self, id: u64) -> Result<Report> {
+	self.db
+		.reportid_report
+		.get(&id.to_be_bytes())
+		.await
+		.deserialized()
+}
+
+/// Iterates over all reports, newest first.
+#[implement(Service)]
+pub fn reports(&self) -> impl Stream<Item = Report> + Send + '_ {
+	self.db
+		.reportid_report
+		.rev_stream()
+		.ignore_err()
+		.map(|(_, report): (Ignore, Report)| report)
+}
+
+/// Marks a report as resolved or dismissed by an admin.
+#[implement(Service)]
+pub async fn set_state(
+	&self,
+	id: u64,
+	state: ReportState,
+	handled_by: &UserId,
+) -> Result<Report> {
+	let Ok(mut report) = self.get(id).await else {
+		return Err!(Request(NotFound("Report #{id} does not exist.")));
+	};
+
+	report.state = state;
+	report.handled_by = (state != ReportState::Open).then(|| handled_by.to_owned());
+	self.put(&report);
+
+	Ok(report)
+}
+
+#[implement(Service)]
+fn put(&self, report: &Report) {
+	self.db
+		.reportid_report
+		.raw_put(report.id.to_be_bytes(), Json(report));
+}
+
+impl ReportState {
+	#[must_use]
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			| Self::Open => "open",
+			| Self::Resolved => "resolved",
+			| Self::Dismissed => "dismissed",
+		}
+	}
+}
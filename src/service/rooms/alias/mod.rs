@@ -212,6 +212,8 @@ async fn user_can_remove_alias(&self, alias: &RoomAliasId, user_id: &UserId) ->
             || self.services.admin.user_is_admin(user_id).await
             // Always allow the server service account to remove the alias, since there may not be an admin room
             || server_user == user_id
+            // Room creators have infinite power in some room versions
+            || self.services.state_accessor.user_is_privileged_creator(&room_id, user_id).await
 		{
 			return Ok(true);
 		}
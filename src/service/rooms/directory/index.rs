@@ -0,0 +1,300 @@
+use std::{
+	cmp::Reverse,
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	ops::Bound::{Excluded, Included, Unbounded},
+};
+
+use conduwuit::{Err, Result, err};
+use ruma::{
+	OwnedRoomId, RoomId, UInt,
+	directory::{Filter, PublicRoomsChunk, RoomTypeFilter},
+};
+
+/// Public rooms with their directory entries, ordered by joined member count.
+#[derive(Default)]
+pub(super) struct Index {
+	rooms: HashMap<OwnedRoomId, Entry>,
+	order: BTreeSet<Key>,
+	tokens: BTreeMap<String, BTreeSet<OwnedRoomId>>,
+}
+
+/// One page of the directory.
+pub struct Page {
+	pub chunk: Vec<PublicRoomsChunk>,
+	pub prev_batch: Option<String>,
+	pub next_batch: Option<String>,
+	pub total: usize,
+}
+
+struct Entry {
+	chunk: PublicRoomsChunk,
+	tokens: BTreeSet<String>,
+}
+
+/// Position of a room in the directory. Pagination tokens encode the key of
+/// the first or last room of a page, so they stay valid as rooms come and go.
+type Key = (Reverse<UInt>, OwnedRoomId);
+
+enum Since {
+	Next(Key),
+	Prev(Key),
+}
+
+const WORD_MAX_LEN: usize = 50;
+
+impl Index {
+	pub(super) fn insert(&mut self, chunk: PublicRoomsChunk) {
+		self.remove(&chunk.room_id);
+
+		let tokens: BTreeSet<String> = chunk_tokens(&chunk).collect();
+		for token in &tokens {
+			self.tokens
+				.entry(token.clone())
+				.or_default()
+				.insert(chunk.room_id.clone());
+		}
+
+		self.order.insert(key(&chunk));
+		self.rooms
+			.insert(chunk.room_id.clone(), Entry { chunk, tokens });
+	}
+
+	pub(super) fn remove(&mut self, room_id: &RoomId) {
+		let Some(Entry { chunk, tokens }) = self.rooms.remove(room_id) else {
+			return;
+		};
+
+		for token in tokens {
+			if let Some(rooms) = self.tokens.get_mut(&token) {
+				rooms.remove(room_id);
+				if rooms.is_empty() {
+					self.tokens.remove(&token);
+				}
+			}
+		}
+
+		self.order.remove(&key(&chunk));
+	}
+
+	#[inline]
+	pub(super) fn len(&self) -> usize { self.rooms.len() }
+
+	/// Pages through the rooms matching the filter. Rooms for which `hidden`
+	/// returns true are left out of the pages and the total.
+	pub(super) fn page<F>(
+		&self,
+		filter: &Filter,
+		since: Option<&str>,
+		limit: usize,
+		hidden: F,
+	) -> Result<Page>
+	where
+		F: Fn(&RoomId) -> bool,
+	{
+		let since = since.map(parse_since).transpose()?.flatten();
+		let candidates = filter
+			.generic_search_term
+			.as_deref()
+			.and_then(|term| self.search(term));
+
+		let matches = |(_, room_id): &&Key| {
+			!hidden(room_id)
+				&& candidates
+					.as_ref()
+					.is_none_or(|candidates| candidates.contains(room_id))
+				&& (filter.room_types.is_empty()
+					|| self.rooms.get(room_id).is_some_and(|entry| {
+						let room_type = RoomTypeFilter::from(entry.chunk.room_type.clone());
+						filter.room_types.contains(&room_type)
+					}))
+		};
+
+		let (keys, prev, next): (Vec<&Key>, bool, bool) = match &since {
+			| None | Some(Since::Next(_)) => {
+				let start = match &since {
+					| Some(Since::Next(key)) => Excluded(key),
+					| _ => Unbounded,
+				};
+
+				let mut keys: Vec<_> = self
+					.order
+					.range((start, Unbounded))
+					.filter(matches)
+					.take(limit.saturating_add(1))
+					.collect();
+
+				let next = keys.len() > limit;
+				keys.truncate(limit);
+
+				let prev = match &since {
+					| Some(Since::Next(key)) => self
+						.order
+						.range((Unbounded, Included(key)))
+						.any(|key| matches(&key)),
+					| _ => false,
+				};
+
+				(keys, prev, next)
+			},
+			| Some(Since::Prev(key)) => {
+				let mut keys: Vec<_> = self
+					.order
+					.range((Unbounded, Excluded(key)))
+					.rev()
+					.filter(matches)
+					.take(limit.saturating_add(1))
+					.collect();
+
+				let prev = keys.len() > limit;
+				keys.truncate(limit);
+				keys.reverse();
+
+				let next = self
+					.order
+					.range((Included(key), Unbounded))
+					.any(|key| matches(&key));
+
+				(keys, prev, next)
+			},
+		};
+
+		Ok(Page {
+			prev_batch: prev
+				.then(|| keys.first().map(|key| make_since('p', key)))
+				.flatten(),
+			next_batch: next
+				.then(|| keys.last().map(|key| make_since('n', key)))
+				.flatten(),
+			chunk: keys
+				.iter()
+				.filter_map(|(_, room_id)| self.rooms.get(room_id))
+				.map(|entry| entry.chunk.clone())
+				.collect(),
+			total: self.total(filter, candidates.as_ref(), &hidden),
+		})
+	}
+
+	/// Number of rooms matching the filter which are not hidden. Only the
+	/// search candidates are walked when searched for.
+	fn total<F>(
+		&self,
+		filter: &Filter,
+		candidates: Option<&HashSet<OwnedRoomId>>,
+		hidden: &F,
+	) -> usize
+	where
+		F: Fn(&RoomId) -> bool,
+	{
+		let room_types: HashSet<Option<String>> = filter
+			.room_types
+			.iter()
+			.map(|room_type| room_type.as_str().map(ToOwned::to_owned))
+			.collect();
+
+		let matches = |(room_id, entry): &(&OwnedRoomId, &Entry)| {
+			!hidden(room_id)
+				&& (room_types.is_empty() || room_types.contains(&room_type(&entry.chunk)))
+		};
+
+		match candidates {
+			| Some(candidates) => candidates
+				.iter()
+				.filter_map(|room_id| self.rooms.get_key_value(room_id))
+				.filter(matches)
+				.count(),
+			| None => self.rooms.iter().filter(matches).count(),
+		}
+	}
+
+	/// Rooms matching every word of the search term by prefix. Returns None
+	/// when the term has no words to search for.
+	fn search(&self, term: &str) -> Option<HashSet<OwnedRoomId>> {
+		let terms: BTreeSet<String> = tokenize(term).collect();
+		let longest = terms.iter().max_by_key(|term| term.len())?;
+
+		let candidates = self
+			.tokens
+			.range::<str, _>((Included(longest.as_str()), Unbounded))
+			.take_while(|(token, _)| token.starts_with(longest.as_str()))
+			.flat_map(|(_, rooms)| rooms.iter())
+			.filter(|room_id| {
+				self.rooms.get(*room_id).is_some_and(|entry| {
+					terms.iter().all(|term| {
+						entry
+							.tokens
+							.iter()
+							.any(|token| token.starts_with(term.as_str()))
+					})
+				})
+			})
+			.cloned()
+			.collect();
+
+		Some(candidates)
+	}
+}
+
+fn room_type(chunk: &PublicRoomsChunk) -> Option<String> {
+	chunk
+		.room_type
+		.as_ref()
+		.map(|room_type| room_type.to_string())
+}
+
+fn key(chunk: &PublicRoomsChunk) -> Key {
+	(Reverse(chunk.num_joined_members), chunk.room_id.clone())
+}
+
+fn make_since(direction: char, (Reverse(members), room_id): &Key) -> String {
+	format!("{direction}{members}_{room_id}")
+}
+
+fn parse_since(since: &str) -> Result<Option<Since>> {
+	let mut characters = since.chars();
+	let direction = characters.next();
+
+	// Tokens from before the index encoded an offset, e.g. `n10`. The rooms they
+	// pointed at are unknown, so those start over at the first page.
+	if matches!(direction, Some('n' | 'p')) && characters.as_str().parse::<u64>().is_ok() {
+		return Ok(None);
+	}
+
+	let Some((members, room_id)) = characters.as_str().split_once('_') else {
+		return Err!(Request(InvalidParam("Invalid `since` token")));
+	};
+
+	let key = (
+		Reverse(
+			members
+				.parse()
+				.map_err(|_| err!(Request(InvalidParam("Invalid `since` token."))))?,
+		),
+		RoomId::parse(room_id)
+			.map_err(|_| err!(Request(InvalidParam("Invalid `since` token."))))?,
+	);
+
+	match direction {
+		| Some('n') => Ok(Some(Since::Next(key))),
+		| Some('p') => Ok(Some(Since::Prev(key))),
+		| _ => Err!(Request(InvalidParam("Invalid `since` token"))),
+	}
+}
+
+fn chunk_tokens(chunk: &PublicRoomsChunk) -> impl Iterator<Item = String> + '_ {
+	let name = chunk.name.as_deref().unwrap_or_default();
+	let topic = chunk.topic.as_deref().unwrap_or_default();
+	let alias = chunk
+		.canonical_alias
+		.as_ref()
+		.map(|alias| alias.as_str())
+		.unwrap_or_default();
+
+	tokenize(name).chain(tokenize(topic)).chain(tokenize(alias))
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+	text.split_terminator(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.filter(|word| word.len() <= WORD_MAX_LEN)
+		.map(str::to_lowercase)
+}
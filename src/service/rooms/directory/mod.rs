@@ -1,35 +1,108 @@
-use std::sync::Arc;
+mod index;
+#[cfg(test)]
+mod tests;
 
-use conduwuit::{Result, implement, utils::stream::TryIgnore};
+use std::{
+	collections::HashSet,
+	fmt::Write,
+	sync::{Arc, Mutex, RwLock},
+};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Result, implement,
+	utils::{TryFutureExtExt, result::FlatOk, stream::TryIgnore},
+};
 use database::Map;
-use futures::Stream;
-use ruma::{RoomId, api::client::room::Visibility};
+use futures::{
+	FutureExt, Stream, StreamExt, TryFutureExt,
+	future::{join, join4, join5},
+};
+use ruma::{
+	OwnedRoomId, RoomId,
+	api::client::room::Visibility,
+	directory::{Filter, PublicRoomJoinRule, PublicRoomsChunk},
+	events::{
+		StateEventType,
+		room::join_rules::{JoinRule, RoomJoinRulesEventContent},
+	},
+	uint,
+};
+use tokio::sync::OnceCell;
+
+use self::index::Index;
+pub use self::index::Page;
+use crate::{Dep, rooms};
 
 pub struct Service {
 	db: Data,
+	services: Services,
+	index: OnceCell<RwLock<Index>>,
+	pending: Mutex<Option<HashSet<OwnedRoomId>>>,
 }
 
 struct Data {
 	publicroomids: Arc<Map>,
 }
 
+struct Services {
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+}
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			db: Data {
 				publicroomids: args.db["publicroomids"].clone(),
 			},
+			services: Services {
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+			},
+			index: OnceCell::new(),
+			pending: Mutex::new(Some(HashSet::new())),
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result<()> {
+		self.index().await;
+
+		Ok(())
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let index = self
+			.index
+			.get()
+			.map_or(0, |index| index.read().expect("locked for reading").len());
+
+		writeln!(out, "public_rooms_index: {index}")?;
+
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 #[implement(Service)]
-pub fn set_public(&self, room_id: &RoomId) { self.db.publicroomids.insert(room_id, []); }
+pub async fn set_public(&self, room_id: &RoomId) {
+	self.db.publicroomids.insert(room_id, []);
+	self.update_room(room_id).await;
+}
 
 #[implement(Service)]
-pub fn set_not_public(&self, room_id: &RoomId) { self.db.publicroomids.remove(room_id); }
+pub fn set_not_public(&self, room_id: &RoomId) {
+	self.db.publicroomids.remove(room_id);
+
+	if let Some(pending) = self.pending.lock().expect("locked").as_mut() {
+		pending.insert(room_id.to_owned());
+	} else if let Some(index) = self.index.get() {
+		index.write().expect("locked for writing").remove(room_id);
+	}
+}
 
 #[implement(Service)]
 pub fn public_rooms(&self) -> impl Stream<Item = &RoomId> + Send {
@@ -49,3 +122,135 @@ pub async fn visibility(&self, room_id: &RoomId) -> Visibility {
 		Visibility::Private
 	}
 }
+
+/// Returns one page of the public room directory, ordered by the number of
+/// joined members. `since` is a pagination token from a previous page; rooms
+/// for which `hidden` returns true are left out.
+#[implement(Service)]
+pub async fn public_rooms_page<F>(
+	&self,
+	filter: &Filter,
+	since: Option<&str>,
+	limit: usize,
+	hidden: F,
+) -> Result<Page>
+where
+	F: Fn(&RoomId) -> bool,
+{
+	self.index()
+		.await
+		.read()
+		.expect("locked for reading")
+		.page(filter, since, limit, hidden)
+}
+
+/// Refreshes the directory entry of a room after its state or visibility
+/// changed. While the index is still being built the room is queued and
+/// refreshed once the build finished.
+#[implement(Service)]
+pub async fn update_room(&self, room_id: &RoomId) {
+	if let Some(pending) = self.pending.lock().expect("locked").as_mut() {
+		pending.insert(room_id.to_owned());
+		return;
+	}
+
+	if let Some(index) = self.index.get() {
+		self.refresh_room(index, room_id).await;
+	}
+}
+
+/// The index is built on first use from the published rooms; afterwards it is
+/// kept up to date by `update_room()` and the visibility setters.
+#[implement(Service)]
+async fn index(&self) -> &RwLock<Index> {
+	let index = self
+		.index
+		.get_or_init(|| async {
+			let mut index = Index::default();
+			let room_ids: Vec<OwnedRoomId> =
+				self.public_rooms().map(ToOwned::to_owned).collect().await;
+
+			for room_id in room_ids {
+				index.insert(self.public_rooms_chunk(room_id).await);
+			}
+
+			RwLock::new(index)
+		})
+		.await;
+
+	// Rooms updated during the build may have been indexed with their old state.
+	let pending = self.pending.lock().expect("locked").take();
+	for room_id in pending.into_iter().flatten() {
+		self.refresh_room(index, &room_id).await;
+	}
+
+	index
+}
+
+#[implement(Service)]
+async fn refresh_room(&self, index: &RwLock<Index>, room_id: &RoomId) {
+	if !self.is_public_room(room_id).await {
+		index.write().expect("locked for writing").remove(room_id);
+		return;
+	}
+
+	let chunk = self.public_rooms_chunk(room_id.to_owned()).await;
+	index.write().expect("locked for writing").insert(chunk);
+}
+
+#[implement(Service)]
+async fn public_rooms_chunk(&self, room_id: OwnedRoomId) -> PublicRoomsChunk {
+	let state_accessor = &self.services.state_accessor;
+
+	let name = state_accessor.get_name(&room_id).ok();
+
+	let room_type = state_accessor.get_room_type(&room_id).ok();
+
+	let canonical_alias = state_accessor.get_canonical_alias(&room_id).ok();
+
+	let avatar_url = state_accessor.get_avatar(&room_id);
+
+	let topic = state_accessor.get_room_topic(&room_id).ok();
+
+	let world_readable = state_accessor.is_world_readable(&room_id);
+
+	let join_rule = state_accessor
+		.room_state_get_content(&room_id, &StateEventType::RoomJoinRules, "")
+		.map_ok(|c: RoomJoinRulesEventContent| match c.join_rule {
+			| JoinRule::Public => PublicRoomJoinRule::Public,
+			| JoinRule::Knock => "knock".into(),
+			| JoinRule::KnockRestricted(_) => "knock_restricted".into(),
+			| _ => "invite".into(),
+		});
+
+	let guest_can_join = state_accessor.guest_can_join(&room_id);
+
+	let num_joined_members = self.services.state_cache.room_joined_count(&room_id);
+
+	let (
+		(avatar_url, canonical_alias, guest_can_join, join_rule, name),
+		(num_joined_members, room_type, topic, world_readable),
+	) = join(
+		join5(avatar_url, canonical_alias, guest_can_join, join_rule, name),
+		join4(num_joined_members, room_type, topic, world_readable),
+	)
+	.boxed()
+	.await;
+
+	PublicRoomsChunk {
+		avatar_url: avatar_url.into_option().unwrap_or_default().url,
+		canonical_alias,
+		guest_can_join,
+		join_rule: join_rule.unwrap_or_default(),
+		name,
+		num_joined_members: num_joined_members
+			.map(TryInto::try_into)
+			.map(Result::ok)
+			.flat_ok()
+			.unwrap_or_else(|| uint!(0)),
+		room_id,
+		room_type,
+		topic,
+		world_readable,
+	}
+}
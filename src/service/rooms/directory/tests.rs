@@ -0,0 +1,193 @@
+use ruma::{
+	OwnedRoomId, RoomId,
+	directory::{Filter, PublicRoomJoinRule, PublicRoomsChunk, RoomTypeFilter},
+	room::RoomType,
+};
+
+use super::{Page, index::Index};
+
+fn chunk(
+	room_id: &str,
+	members: u32,
+	name: &str,
+	room_type: Option<RoomType>,
+) -> PublicRoomsChunk {
+	PublicRoomsChunk {
+		avatar_url: None,
+		canonical_alias: None,
+		guest_can_join: false,
+		join_rule: PublicRoomJoinRule::Public,
+		name: Some(name.to_owned()),
+		num_joined_members: members.into(),
+		room_id: OwnedRoomId::try_from(room_id).unwrap(),
+		room_type,
+		topic: None,
+		world_readable: false,
+	}
+}
+
+fn index() -> Index {
+	let mut index = Index::default();
+	index.insert(chunk("!a:example.com", 50, "Rust programming", None));
+	index.insert(chunk("!b:example.com", 40, "Rust games", None));
+	index.insert(chunk("!c:example.com", 30, "Cooking", None));
+	index.insert(chunk("!d:example.com", 20, "Rust spaces", Some(RoomType::Space)));
+	index.insert(chunk("!e:example.com", 10, "Gardening", None));
+	index
+}
+
+fn room_ids(page: &Page) -> Vec<&str> {
+	page.chunk
+		.iter()
+		.map(|chunk| chunk.room_id.as_str())
+		.collect()
+}
+
+fn search(term: &str, room_types: Vec<RoomTypeFilter>) -> Filter {
+	let mut filter = Filter::new();
+	filter.generic_search_term = Some(term.to_owned()).filter(|term| !term.is_empty());
+	filter.room_types = room_types;
+	filter
+}
+
+#[test]
+fn pages_forward_and_backward() {
+	let index = index();
+	let filter = Filter::new();
+
+	let first = index.page(&filter, None, 2, |_| false).unwrap();
+	assert_eq!(room_ids(&first), ["!a:example.com", "!b:example.com"]);
+	assert!(first.prev_batch.is_none());
+	assert_eq!(first.total, 5);
+
+	let second = index
+		.page(&filter, first.next_batch.as_deref(), 2, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&second), ["!c:example.com", "!d:example.com"]);
+
+	let last = index
+		.page(&filter, second.next_batch.as_deref(), 2, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&last), ["!e:example.com"]);
+	assert!(last.next_batch.is_none());
+
+	let back = index
+		.page(&filter, last.prev_batch.as_deref(), 2, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&back), ["!c:example.com", "!d:example.com"]);
+	assert!(back.next_batch.is_some());
+
+	let back = index
+		.page(&filter, back.prev_batch.as_deref(), 2, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&back), ["!a:example.com", "!b:example.com"]);
+	assert!(back.prev_batch.is_none());
+}
+
+#[test]
+fn tokens_survive_changes() {
+	let mut index = index();
+	let filter = Filter::new();
+	let first = index.page(&filter, None, 2, |_| false).unwrap();
+
+	// Rooms added before or removed from the position of a token do not shift
+	// the pages after it.
+	index.insert(chunk("!f:example.com", 100, "Popular", None));
+	index.remove(&OwnedRoomId::try_from("!b:example.com").unwrap());
+
+	let second = index
+		.page(&filter, first.next_batch.as_deref(), 2, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&second), ["!c:example.com", "!d:example.com"]);
+	assert_eq!(second.total, 5);
+}
+
+#[test]
+fn filters_and_counts() {
+	let index = index();
+
+	let page = index
+		.page(&search("rust", vec![]), None, 10, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&page), ["!a:example.com", "!b:example.com", "!d:example.com"]);
+	assert_eq!(page.total, 3);
+
+	// Every word must match a word of the room by prefix.
+	let page = index
+		.page(&search("ru prog", vec![]), None, 10, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&page), ["!a:example.com"]);
+	assert_eq!(page.total, 1);
+
+	let page = index
+		.page(&search("rust", vec![RoomTypeFilter::Space]), None, 10, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&page), ["!d:example.com"]);
+	assert_eq!(page.total, 1);
+
+	let page = index
+		.page(&search("", vec![RoomTypeFilter::Default]), None, 10, |_| false)
+		.unwrap();
+	assert_eq!(page.total, 4);
+	assert!(!room_ids(&page).contains(&"!d:example.com"));
+
+	// Pages of a search only hold matching rooms.
+	let filter = search("rust", vec![]);
+	let first = index.page(&filter, None, 1, |_| false).unwrap();
+	assert_eq!(room_ids(&first), ["!a:example.com"]);
+
+	let second = index
+		.page(&filter, first.next_batch.as_deref(), 1, |_| false)
+		.unwrap();
+	assert_eq!(room_ids(&second), ["!b:example.com"]);
+	assert_eq!(second.total, 3);
+
+	let page = index
+		.page(&search("baking", vec![]), None, 10, |_| false)
+		.unwrap();
+	assert!(page.chunk.is_empty());
+	assert!(page.next_batch.is_none());
+	assert_eq!(page.total, 0);
+}
+
+#[test]
+fn legacy_tokens_start_over() {
+	let index = index();
+	let filter = Filter::new();
+
+	let page = index.page(&filter, Some("n10"), 2, |_| false).unwrap();
+	assert_eq!(room_ids(&page), ["!a:example.com", "!b:example.com"]);
+
+	let page = index.page(&filter, Some("p4"), 2, |_| false).unwrap();
+	assert_eq!(room_ids(&page), ["!a:example.com", "!b:example.com"]);
+
+	assert!(index.page(&filter, Some("x10"), 2, |_| false).is_err());
+	assert!(
+		index
+			.page(&filter, Some("n10_invalid"), 2, |_| false)
+			.is_err()
+	);
+}
+
+#[test]
+fn hidden_rooms_are_left_out() {
+	let index = index();
+	let filter = Filter::new();
+	let hidden = |room_id: &RoomId| room_id.as_str().starts_with("!b");
+
+	let first = index.page(&filter, None, 2, hidden).unwrap();
+	assert_eq!(room_ids(&first), ["!a:example.com", "!c:example.com"]);
+	assert_eq!(first.total, 4);
+
+	let second = index
+		.page(&filter, first.next_batch.as_deref(), 2, hidden)
+		.unwrap();
+	assert_eq!(room_ids(&second), ["!d:example.com", "!e:example.com"]);
+	assert!(second.next_batch.is_none());
+
+	let page = index
+		.page(&search("rust", vec![]), None, 10, hidden)
+		.unwrap();
+	assert_eq!(room_ids(&page), ["!a:example.com", "!d:example.com"]);
+	assert_eq!(page.total, 2);
+}
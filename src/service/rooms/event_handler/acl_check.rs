@@ -1,6 +1,6 @@
 use conduwuit::{Err, Result, debug, implement, trace, warn};
 use ruma::{
-	RoomId, ServerName,
+	RoomId, ServerName, UserId,
 	events::{StateEventType, room::server_acl::RoomServerAclEventContent},
 };
 
@@ -40,3 +40,14 @@ pub async fn acl_check(&self, server_name: &ServerName, room_id: &RoomId) -> Res
 		Err!(Request(Forbidden("Server was denied by room ACL")))
 	}
 }
+
+/// Returns true if the sender or their server is banned by a policy list.
+#[implement(super::Service)]
+#[tracing::instrument(skip_all, level = "debug")]
+pub async fn policy_check(&self, sender: &UserId) -> bool {
+	self.services
+		.policy
+		.check_user(sender, "PDU")
+		.await
+		.is_err()
+}
@@ -1,7 +1,10 @@
 use std::collections::{BTreeMap, HashMap, hash_map};
 
 use conduwuit::{
-	Err, Error, PduEvent, Result, debug, debug_info, err, implement, state_res, trace, warn,
+	Err, Error, PduEvent, Result, debug, debug_info, err, implement,
+	info::room_version::event_rules_version,
+	matrix::{StateKey, pdu::add_create_room_id},
+	state_res, trace, warn,
 };
 use futures::future::ready;
 use ruma::{
@@ -40,7 +43,9 @@ pub(super) async fn handle_outlier_pdu<'a>(
 		| Ok(ruma::signatures::Verified::Signatures) => {
 			// Redact
 			debug_info!("Calculated hash does not match (redaction): {event_id}");
-			let Ok(obj) = ruma::canonical_json::redact(value, &room_version_id, None) else {
+			let Ok(obj) =
+				ruma::canonical_json::redact(value, event_rules_version(&room_version_id), None)
+			else {
 				return Err!(Request(InvalidParam("Redaction failed")));
 			};
 
@@ -62,6 +67,7 @@ pub(super) async fn handle_outlier_pdu<'a>(
 
 	// Now that we have checked the signature and hashes we can add the eventID and
 	// convert to our PduEvent type
+	add_create_room_id(&mut val, event_id, &room_version_id)?;
 	val.insert("event_id".to_owned(), CanonicalJsonValue::String(event_id.as_str().to_owned()));
 	let incoming_pdu = serde_json::from_value::<PduEvent>(
 		serde_json::to_value(&val).expect("CanonicalJsonObj is a valid JsonValue"),
@@ -118,6 +124,13 @@ pub(super) async fn handle_outlier_pdu<'a>(
 		}
 	}
 
+	let room_version = to_room_version(&room_version_id);
+
+	// The create event is implied by the room ID where it is not listed
+	if room_version.room_ids_as_hashes {
+		auth_events.insert((StateEventType::RoomCreate, StateKey::new()), create_event.clone());
+	}
+
 	// The original create event must be in the auth events
 	if !matches!(
 		auth_events.get(&(StateEventType::RoomCreate, String::new().into())),
@@ -132,7 +145,7 @@ pub(super) async fn handle_outlier_pdu<'a>(
 	};
 
 	let auth_check = state_res::event_auth::auth_check(
-		&to_room_version(&room_version_id),
+		&room_version,
 		&incoming_pdu,
 		None, // TODO: third party invite
 		state_fetch,
@@ -76,7 +76,7 @@ pub(super) async fn handle_prev_pdu<'a>(
 			.remove(room_id);
 	}};
 
-	self.upgrade_outlier_to_timeline_pdu(pdu, json, create_event, origin, room_id)
+	self.upgrade_outlier_to_timeline_pdu(pdu, json, create_event, origin, room_id, true)
 		.await?;
 
 	debug!(
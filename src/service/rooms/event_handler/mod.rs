@@ -24,7 +24,7 @@
 	events::room::create::RoomCreateEventContent,
 };
 
-use crate::{Dep, globals, rooms, sending, server_keys};
+use crate::{Dep, globals, policy, rooms, sending, server_keys};
 
 pub struct Service {
 	pub mutex_federation: RoomMutexMap,
@@ -34,6 +34,7 @@ pub struct Service {
 
 struct Services {
 	globals: Dep<globals::Service>,
+	policy: Dep<policy::Service>,
 	sending: Dep<sending::Service>,
 	auth_chain: Dep<rooms::auth_chain::Service>,
 	metadata: Dep<rooms::metadata::Service>,
@@ -41,6 +42,7 @@ struct Services {
 	pdu_metadata: Dep<rooms::pdu_metadata::Service>,
 	server_keys: Dep<server_keys::Service>,
 	short: Dep<rooms::short::Service>,
+	spam_check: Dep<rooms::spam_check::Service>,
 	state: Dep<rooms::state::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	state_compressor: Dep<rooms::state_compressor::Service>,
@@ -59,6 +61,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			federation_handletime: HandleTimeMap::new().into(),
 			services: Services {
 				globals: args.depend::<globals::Service>("globals"),
+				policy: args.depend::<policy::Service>("policy"),
 				sending: args.depend::<sending::Service>("sending"),
 				auth_chain: args.depend::<rooms::auth_chain::Service>("rooms::auth_chain"),
 				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
@@ -66,6 +69,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
 				pdu_metadata: args.depend::<rooms::pdu_metadata::Service>("rooms::pdu_metadata"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
+				spam_check: args.depend::<rooms::spam_check::Service>("rooms::spam_check"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
@@ -12,10 +12,14 @@
 
 use super::{get_room_version_id, to_room_version};
 use crate::rooms::{
+	spam_check::Verdict,
 	state_compressor::{CompressedState, HashSetCompressStateEvent},
 	timeline::RawPduId,
 };
 
+/// Checks and appends an outlier to the timeline. `is_prev` is set for the
+/// missing prev events of the PDU being handled, which are only fetched to
+/// complete the room's history.
 #[implement(super::Service)]
 pub(super) async fn upgrade_outlier_to_timeline_pdu(
 	&self,
@@ -24,6 +28,7 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 	create_event: &PduEvent,
 	origin: &ServerName,
 	room_id: &RoomId,
+	is_prev: bool,
 ) -> Result<Option<RawPduId>> {
 	// Skip the PDU if we already have it as a timeline event
 	if let Ok(pduid) = self
@@ -133,6 +138,21 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 				.await?,
 	};
 
+	// Soft fail the PDU itself if its sender is banned by a policy list; its prev
+	// events are part of the room's history regardless.
+	let soft_fail = soft_fail || (!is_prev && self.policy_check(&incoming_pdu.sender).await);
+
+	// Ask the room's policy server about the PDU itself if it passed the checks
+	// above. Events it rejects are soft failed like the others, so they are
+	// kept out of the timeline without failing the transaction.
+	let soft_fail = soft_fail
+		|| (!is_prev
+			&& self
+				.services
+				.spam_check
+				.check_pdu(&incoming_pdu, &val)
+				.await != Verdict::Allow);
+
 	// 13. Use state resolution to find new room state
 
 	// We start looking at current room state now, so lets lock the room
@@ -10,6 +10,7 @@
 pub mod search;
 pub mod short;
 pub mod spaces;
+pub mod spam_check;
 pub mod state;
 pub mod state_accessor;
 pub mod state_cache;
@@ -17,6 +18,7 @@
 pub mod threads;
 pub mod timeline;
 pub mod typing;
+pub mod unredacted;
 pub mod user;
 
 use std::sync::Arc;
@@ -34,6 +36,7 @@ pub struct Service {
 	pub search: Arc<search::Service>,
 	pub short: Arc<short::Service>,
 	pub spaces: Arc<spaces::Service>,
+	pub spam_check: Arc<spam_check::Service>,
 	pub state: Arc<state::Service>,
 	pub state_accessor: Arc<state_accessor::Service>,
 	pub state_cache: Arc<state_cache::Service>,
@@ -41,5 +44,6 @@ pub struct Service {
 	pub threads: Arc<threads::Service>,
 	pub timeline: Arc<timeline::Service>,
 	pub typing: Arc<typing::Service>,
+	pub unredacted: Arc<unredacted::Service>,
 	pub user: Arc<user::Service>,
 }
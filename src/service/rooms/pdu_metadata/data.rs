@@ -10,7 +10,7 @@
 		u64_from_u8,
 	},
 };
-use database::Map;
+use database::{Batch, Database, Map};
 use futures::{Stream, StreamExt};
 use ruma::{EventId, RoomId, UserId, api::Direction};
 
@@ -26,6 +26,7 @@ pub(super) struct Data {
 	tofrom_relation: Arc<Map>,
 	referencedevents: Arc<Map>,
 	softfailedeventids: Arc<Map>,
+	pub(super) db: Arc<Database>,
 	services: Services,
 }
 
@@ -42,6 +43,7 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			tofrom_relation: db["tofrom_relation"].clone(),
 			referencedevents: db["referencedevents"].clone(),
 			softfailedeventids: db["softfailedeventids"].clone(),
+			db: args.db.clone(),
 			services: Services {
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
 			},
@@ -89,13 +91,17 @@ pub(super) fn get_relations<'a>(
 	}
 
 	#[inline]
-	pub(super) fn mark_as_referenced<'a, I>(&self, room_id: &RoomId, event_ids: I)
-	where
-		I: Iterator<Item = &'a EventId>,
+	pub(super) fn mark_as_referenced<'a, 'b, I>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		event_ids: I,
+	) where
+		I: Iterator<Item = &'b EventId>,
 	{
 		for prev in event_ids {
 			let key = (room_id, prev);
-			self.referencedevents.put_raw(key, []);
+			batch.put_raw(&self.referencedevents, key, []);
 		}
 	}
 
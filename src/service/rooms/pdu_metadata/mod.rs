@@ -2,6 +2,7 @@
 use std::sync::Arc;
 
 use conduwuit::{PduCount, Result};
+use database::Batch;
 use futures::{StreamExt, future::try_join};
 use ruma::{EventId, RoomId, UserId, api::Direction};
 
@@ -113,7 +114,23 @@ pub fn mark_as_referenced<'a, I>(&self, room_id: &RoomId, event_ids: I)
 	where
 		I: Iterator<Item = &'a EventId>,
 	{
-		self.db.mark_as_referenced(room_id, event_ids);
+		let mut batch = self.db.db.batch();
+		self.db.mark_as_referenced(&mut batch, room_id, event_ids);
+		batch.commit();
+	}
+
+	/// Stages marking events as referenced into a database batch, so it can be
+	/// committed atomically with other writes.
+	#[tracing::instrument(skip_all, level = "debug")]
+	pub fn stage_referenced<'a, 'b, I>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		event_ids: I,
+	) where
+		I: Iterator<Item = &'b EventId>,
+	{
+		self.db.mark_as_referenced(batch, room_id, event_ids);
 	}
 
 	#[inline]
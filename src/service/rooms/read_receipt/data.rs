@@ -4,7 +4,7 @@
 	Result,
 	utils::{ReadyExt, stream::TryIgnore},
 };
-use database::{Deserialized, Json, Map};
+use database::{Batch, Database, Deserialized, Json, Map};
 use futures::{Stream, StreamExt};
 use ruma::{
 	CanonicalJsonObject, RoomId, UserId,
@@ -19,6 +19,7 @@ pub(super) struct Data {
 	roomuserid_lastprivatereadupdate: Arc<Map>,
 	services: Services,
 	readreceiptid_readreceipt: Arc<Map>,
+	pub(super) db: Arc<Database>,
 }
 
 struct Services {
@@ -34,6 +35,7 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			roomuserid_privateread: db["roomuserid_privateread"].clone(),
 			roomuserid_lastprivatereadupdate: db["roomuserid_lastprivatereadupdate"].clone(),
 			readreceiptid_readreceipt: db["readreceiptid_readreceipt"].clone(),
+			db: args.db.clone(),
 			services: Services {
 				globals: args.depend::<globals::Service>("globals"),
 			},
@@ -86,12 +88,18 @@ pub(super) fn readreceipts_since<'a>(
 			.ignore_err()
 	}
 
-	pub(super) fn private_read_set(&self, room_id: &RoomId, user_id: &UserId, pdu_count: u64) {
+	pub(super) fn private_read_set<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		user_id: &UserId,
+		pdu_count: u64,
+	) {
 		let key = (room_id, user_id);
 		let next_count = self.services.globals.next_count().unwrap();
 
-		self.roomuserid_privateread.put(key, pdu_count);
-		self.roomuserid_lastprivatereadupdate.put(key, next_count);
+		batch.put(&self.roomuserid_privateread, key, pdu_count);
+		batch.put(&self.roomuserid_lastprivatereadupdate, key, next_count);
 	}
 
 	pub(super) async fn private_read_get_count(
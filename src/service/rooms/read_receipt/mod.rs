@@ -7,6 +7,7 @@
 	matrix::pdu::{PduCount, PduId, RawPduId},
 	warn,
 };
+use database::Batch;
 use futures::{Stream, TryFutureExt, try_join};
 use ruma::{
 	OwnedEventId, OwnedUserId, RoomId, UserId,
@@ -118,7 +119,24 @@ pub fn readreceipts_since<'a>(
 	#[inline]
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub fn private_read_set(&self, room_id: &RoomId, user_id: &UserId, count: u64) {
-		self.db.private_read_set(room_id, user_id, count);
+		let mut batch = self.db.db.batch();
+		self.db
+			.private_read_set(&mut batch, room_id, user_id, count);
+		batch.commit();
+	}
+
+	/// Stages setting a private read marker into a database batch, so it can be
+	/// committed atomically with other writes.
+	#[inline]
+	#[tracing::instrument(skip(self, batch), level = "debug")]
+	pub fn stage_private_read_set<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		user_id: &UserId,
+		count: u64,
+	) {
+		self.db.private_read_set(batch, room_id, user_id, count);
 	}
 
 	/// Returns the private read marker PDU count.
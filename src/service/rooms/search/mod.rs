@@ -9,7 +9,7 @@
 		stream::{TryIgnore, WidebandExt},
 	},
 };
-use database::{Map, keyval::Val};
+use database::{Batch, Map, keyval::Val};
 use futures::{Stream, StreamExt};
 use ruma::{RoomId, UserId, api::client::search::search_events::v3::Criteria};
 
@@ -84,6 +84,22 @@ pub fn index_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_bod
 		.insert_batch(batch.iter().map(|k| (k.as_slice(), &[])));
 }
 
+/// Stages the search tokens of a message into a database batch, for callers
+/// which must index a PDU atomically with inserting it.
+#[implement(Service)]
+pub fn stage_index_pdu<'a>(
+	&'a self,
+	batch: &mut Batch<'a>,
+	shortroomid: ShortRoomId,
+	pdu_id: &RawPduId,
+	message_body: &str,
+) {
+	for word in tokenize(message_body) {
+		let key = make_tokenid(shortroomid, &word, pdu_id);
+		batch.insert(&self.db.tokenids, &key, b"");
+	}
+}
+
 #[implement(Service)]
 pub fn deindex_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_body: &str) {
 	let batch = tokenize(message_body).map(|word| {
@@ -122,7 +138,7 @@ pub async fn search_pdus<'a>(
 		.wide_filter_map(move |pdu| async move {
 			self.services
 				.state_accessor
-				.user_can_see_event(query.user_id?, &pdu.room_id, &pdu.event_id)
+				.user_can_see_event(query.user_id?, &pdu)
 				.await
 				.then_some(pdu)
 		})
@@ -0,0 +1,206 @@
+//! Spam checking of events through a policy server (MSC4284).
+//!
+//! Rooms opt in with an `org.matrix.msc4284.policy` state event naming their
+//! policy server. Non-state events in those rooms are sent to the policy
+//! server, or to the configured local webhook, before they are accepted.
+
+mod policy_server;
+#[cfg(test)]
+mod tests;
+
+use std::{sync::Arc, time::Duration};
+
+use conduwuit::{Err, PduEvent, Result, Server, debug, err, implement, warn};
+use http::header::CONTENT_TYPE;
+use ruma::{
+	CanonicalJsonObject, EventId, OwnedServerName, RoomId, ServerName, events::StateEventType,
+};
+use serde::Deserialize;
+use serde_json::{json, value::to_raw_value};
+use tokio::time::timeout;
+use url::Url;
+
+use crate::{Dep, client, rooms, sending};
+
+pub struct Service {
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	client: Dep<client::Service>,
+	sending: Dep<sending::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+}
+
+/// Outcome of a spam check.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+	Allow,
+	SoftFail,
+	Reject,
+}
+
+#[derive(Deserialize)]
+struct PolicyServerContent {
+	via: OwnedServerName,
+}
+
+#[derive(Deserialize)]
+struct WebhookResponse {
+	action: Verdict,
+}
+
+const POLICY_EVENT_TYPE: &str = "org.matrix.msc4284.policy";
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				server: args.server.clone(),
+				client: args.depend::<client::Service>("client"),
+				sending: args.depend::<sending::Service>("sending"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Checks an event with the room's policy server. Events are allowed when
+/// spam checking is disabled, the room did not opt in, or the event is a state
+/// event or was sent by the policy server itself.
+#[implement(Service)]
+#[tracing::instrument(skip_all, fields(event_id = %pdu.event_id), level = "debug")]
+pub async fn check_pdu(&self, pdu: &PduEvent, pdu_json: &CanonicalJsonObject) -> Verdict {
+	let config = &self.services.server.config;
+	if !config.spam_check_policy_servers && config.spam_check_webhook.is_none() {
+		return Verdict::Allow;
+	}
+
+	if pdu.state_key.is_some() {
+		return Verdict::Allow;
+	}
+
+	let Some(via) = self.policy_server(&pdu.room_id).await else {
+		return Verdict::Allow;
+	};
+
+	if pdu.sender.server_name() == via {
+		return Verdict::Allow;
+	}
+
+	let result = match &config.spam_check_webhook {
+		| Some(url) => self.check_webhook(url, &pdu.event_id, pdu_json).await,
+		| None =>
+			self.check_policy_server(&via, &pdu.event_id, pdu_json)
+				.await,
+	};
+
+	match result {
+		| Ok(verdict) => {
+			debug!(?verdict, "Spam check completed");
+			verdict
+		},
+		| Err(e) if config.spam_check_fail_open => {
+			warn!("Spam check failed, allowing event: {e}");
+			Verdict::Allow
+		},
+		| Err(e) => {
+			warn!("Spam check failed, soft-failing event: {e}");
+			Verdict::SoftFail
+		},
+	}
+}
+
+/// The policy server a room opted in to, if any.
+#[implement(Service)]
+pub async fn policy_server(&self, room_id: &RoomId) -> Option<OwnedServerName> {
+	self.services
+		.state_accessor
+		.room_state_get_content(room_id, &StateEventType::from(POLICY_EVENT_TYPE), "")
+		.await
+		.map(|content: PolicyServerContent| content.via)
+		.ok()
+}
+
+#[implement(Service)]
+async fn check_policy_server(
+	&self,
+	via: &ServerName,
+	event_id: &EventId,
+	pdu_json: &CanonicalJsonObject,
+) -> Result<Verdict> {
+	let request = policy_server::Request {
+		event_id: event_id.to_owned(),
+		pdu: to_raw_value(pdu_json)?,
+	};
+
+	let response =
+		timeout(self.timeout(), self.services.sending.send_federation_request(via, request))
+			.await
+			.map_err(|_| err!(BadServerResponse("Policy server {via} timed out")))??;
+
+	recommendation_verdict(&response.recommendation)
+		.ok_or_else(|| err!(BadServerResponse("Unknown recommendation from {via}")))
+}
+
+fn recommendation_verdict(recommendation: &str) -> Option<Verdict> {
+	match recommendation {
+		| "ok" => Some(Verdict::Allow),
+		| "spam" => Some(Verdict::SoftFail),
+		| _ => None,
+	}
+}
+
+#[implement(Service)]
+async fn check_webhook(
+	&self,
+	url: &Url,
+	event_id: &EventId,
+	pdu_json: &CanonicalJsonObject,
+) -> Result<Verdict> {
+	let body = json!({
+		"event_id": event_id,
+		"pdu": pdu_json,
+	});
+
+	let response: WebhookResponse = self.post(url.as_str(), &body).await?;
+
+	Ok(response.action)
+}
+
+#[implement(Service)]
+async fn post<T, B>(&self, url: &str, body: &B) -> Result<T>
+where
+	T: for<'de> Deserialize<'de>,
+	B: serde::Serialize,
+{
+	let response = self
+		.services
+		.client
+		.default
+		.post(url)
+		.header(CONTENT_TYPE, "application/json")
+		.body(serde_json::to_vec(body)?)
+		.timeout(self.timeout())
+		.send()
+		.await?;
+
+	let status = response.status();
+	if !status.is_success() {
+		return Err!(BadServerResponse("Spam checker responded with {status}"));
+	}
+
+	let body = response.bytes().await?;
+	serde_json::from_slice(&body)
+		.map_err(|e| err!(BadServerResponse("Invalid response from spam checker: {e}")))
+}
+
+#[implement(Service)]
+fn timeout(&self) -> Duration {
+	Duration::from_secs(self.services.server.config.spam_check_timeout)
+}
@@ -0,0 +1,37 @@
+//! `POST /_matrix/policy/unstable/org.matrix.msc4284/event/{eventId}/check`
+//!
+//! Asks a policy server whether an event is spam. The endpoint is not part of
+//! ruma yet; it is authenticated with server signatures like any federation
+//! request.
+
+use ruma::{
+	OwnedEventId,
+	api::{Metadata, metadata, request, response},
+};
+use serde_json::value::RawValue as RawJsonValue;
+
+const METADATA: Metadata = metadata! {
+	method: POST,
+	rate_limited: false,
+	authentication: ServerSignatures,
+	history: {
+		unstable => "/_matrix/policy/unstable/org.matrix.msc4284/event/:event_id/check",
+	}
+};
+
+#[request]
+pub struct Request {
+	/// The event to check.
+	#[ruma_api(path)]
+	pub event_id: OwnedEventId,
+
+	/// The PDU of the event.
+	#[ruma_api(body)]
+	pub pdu: Box<RawJsonValue>,
+}
+
+#[response]
+pub struct Response {
+	/// `ok`, or `spam` if the event should not be accepted.
+	pub recommendation: String,
+}
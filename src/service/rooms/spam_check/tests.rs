@@ -0,0 +1,83 @@
+use ruma::{
+	CanonicalJsonObject, MatrixVersion, OwnedEventId,
+	api::{OutgoingRequest, SendAccessToken},
+	event_id,
+};
+use serde_json::json;
+
+use super::{Verdict, policy_server, recommendation_verdict};
+use crate::tests::{mock_server, services};
+
+fn pdu_json() -> CanonicalJsonObject {
+	serde_json::from_value(json!({
+		"type": "m.room.message",
+		"sender": "@alice:example.com",
+		"content": { "body": "hello" },
+	}))
+	.unwrap()
+}
+
+#[test]
+fn policy_server_request() {
+	let event_id: OwnedEventId = event_id!("$event:example.com").to_owned();
+	let request = policy_server::Request {
+		event_id,
+		pdu: serde_json::value::to_raw_value(&pdu_json()).unwrap(),
+	};
+
+	let request = request
+		.try_into_http_request::<Vec<u8>>("https://policy.example.org", SendAccessToken::None, &[
+			MatrixVersion::V1_11,
+		])
+		.unwrap();
+
+	assert_eq!(request.method(), http::Method::POST);
+	let path = request.uri().path();
+	assert!(path.starts_with("/_matrix/policy/unstable/org.matrix.msc4284/event/"));
+	assert!(path.ends_with("/check"));
+
+	let body: CanonicalJsonObject = serde_json::from_slice(request.body()).unwrap();
+	assert_eq!(body, pdu_json());
+}
+
+#[test]
+fn policy_server_recommendations() {
+	assert_eq!(recommendation_verdict("ok"), Some(Verdict::Allow));
+	assert_eq!(recommendation_verdict("spam"), Some(Verdict::SoftFail));
+	assert_eq!(recommendation_verdict("maybe"), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhook_verdict() {
+	let services = services().await;
+	let event_id = event_id!("$event:example.com");
+	let (url, server) = mock_server("/check", "200 OK", r#"{"action":"reject"}"#).await;
+
+	let verdict = services
+		.rooms
+		.spam_check
+		.check_webhook(&url, event_id, &pdu_json())
+		.await
+		.unwrap();
+
+	assert_eq!(verdict, Verdict::Reject);
+
+	let request = server.await.unwrap();
+	assert!(request.starts_with("POST /check "));
+	assert!(request.contains(r#""event_id":"$event:example.com""#));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhook_error_status() {
+	let services = services().await;
+	let event_id = event_id!("$event:example.com");
+	let (url, _server) = mock_server("/check", "500 Internal Server Error", "{}").await;
+
+	let result = services
+		.rooms
+		.spam_check
+		.check_webhook(&url, event_id, &pdu_json())
+		.await;
+
+	assert!(result.is_err());
+}
@@ -11,7 +11,7 @@
 	},
 	warn,
 };
-use database::{Deserialized, Ignore, Interfix, Map};
+use database::{Batch, Database, Deserialized, Ignore, Interfix, Map};
 use futures::{
 	FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt, future::join_all, pin_mut,
 };
@@ -52,6 +52,7 @@ struct Data {
 	shorteventid_shortstatehash: Arc<Map>,
 	roomid_shortstatehash: Arc<Map>,
 	roomid_pduleaves: Arc<Map>,
+	db: Arc<Database>,
 }
 
 type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
@@ -77,6 +78,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				shorteventid_shortstatehash: args.db["shorteventid_shortstatehash"].clone(),
 				roomid_shortstatehash: args.db["roomid_shortstatehash"].clone(),
 				roomid_pduleaves: args.db["roomid_pduleaves"].clone(),
+				db: args.db.clone(),
 			},
 		}))
 	}
@@ -92,7 +94,8 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
-	/// Set the room to the given statehash and update caches.
+	/// Set the room to the given statehash and update caches. The membership
+	/// caches and the room state are written atomically.
 	pub async fn force_state(
 		&self,
 		room_id: &RoomId,
@@ -112,6 +115,9 @@ pub async fn force_state(
 			})
 			.ignore_err();
 
+		let mut batch = self.db.db.batch();
+		let mut updated = Vec::new();
+
 		pin_mut!(event_ids);
 		while let Some(event_id) = event_ids.next().await {
 			let Ok(pdu) = self.services.timeline.get_pdu(&event_id).await else {
@@ -129,18 +135,22 @@ pub async fn force_state(
 						continue;
 					};
 
-					self.services
+					if self
+						.services
 						.state_cache
-						.update_membership(
+						.stage_membership(
+							&mut batch,
 							room_id,
 							user_id,
-							membership_event,
+							&membership_event,
 							&pdu.sender,
 							None,
 							None,
-							false,
 						)
-						.await?;
+						.await?
+					{
+						updated.push((user_id.to_owned(), membership_event));
+					}
 				},
 				| TimelineEventType::SpaceChild => {
 					self.services
@@ -154,9 +164,17 @@ pub async fn force_state(
 			}
 		}
 
-		self.services.state_cache.update_joined_count(room_id).await;
+		self.stage_room_state(&mut batch, room_id, shortstatehash, state_lock);
+		batch.commit();
 
-		self.set_room_state(room_id, shortstatehash, state_lock);
+		for (user_id, membership_event) in &updated {
+			self.services
+				.state_cache
+				.membership_updated(room_id, user_id, membership_event, false)
+				.await;
+		}
+
+		self.services.state_cache.update_joined_count(room_id).await;
 
 		Ok(())
 	}
@@ -347,19 +365,30 @@ pub async fn summary_stripped(&self, event: &PduEvent) -> Vec<Raw<AnyStrippedSta
 	}
 
 	/// Set the state hash to a new version, but does not update state_cache.
-	#[tracing::instrument(skip(self, _mutex_lock), level = "debug")]
+	#[tracing::instrument(skip(self, mutex_lock), level = "debug")]
 	pub fn set_room_state(
 		&self,
 		room_id: &RoomId,
 		shortstatehash: u64,
-		_mutex_lock: &RoomMutexGuard, /* Take mutex guard to make sure users get the room
-		                               * state mutex */
+		mutex_lock: &RoomMutexGuard, /* Take mutex guard to make sure users get the room
+		                              * state mutex */
 	) {
-		const BUFSIZE: usize = size_of::<u64>();
+		let mut batch = self.db.db.batch();
+		self.stage_room_state(&mut batch, room_id, shortstatehash, mutex_lock);
+		batch.commit();
+	}
 
-		self.db
-			.roomid_shortstatehash
-			.raw_aput::<BUFSIZE, _, _>(room_id, shortstatehash);
+	/// Stages setting the state hash to a new version into a database batch,
+	/// so it can be committed atomically with other writes. Does not update
+	/// state_cache.
+	pub fn stage_room_state<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		shortstatehash: ShortStateHash,
+		_mutex_lock: &RoomMutexGuard,
+	) {
+		batch.raw_put(&self.db.roomid_shortstatehash, room_id, shortstatehash);
 	}
 
 	/// Returns the room's version.
@@ -398,21 +427,45 @@ pub async fn set_forward_extremities<'a, I>(
 		&'a self,
 		room_id: &'a RoomId,
 		event_ids: I,
-		_state_lock: &'a RoomMutexGuard,
+		state_lock: &'a RoomMutexGuard,
 	) where
 		I: Iterator<Item = &'a EventId> + Send + 'a,
+	{
+		let mut batch = self.db.db.batch();
+		self.stage_forward_extremities(&mut batch, room_id, event_ids, state_lock)
+			.await;
+
+		batch.commit();
+	}
+
+	/// Stages replacing the forward extremities of a room into a database
+	/// batch, so they can be committed atomically with other writes.
+	pub async fn stage_forward_extremities<'a, 'b, I>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &'b RoomId,
+		event_ids: I,
+		_state_lock: &'b RoomMutexGuard,
+	) where
+		I: Iterator<Item = &'b EventId> + Send + 'b,
 	{
 		let prefix = (room_id, Interfix);
-		self.db
+		let leaves: Vec<_> = self
+			.db
 			.roomid_pduleaves
 			.keys_prefix_raw(&prefix)
 			.ignore_err()
-			.ready_for_each(|key| self.db.roomid_pduleaves.remove(key))
+			.map(<[u8]>::to_vec)
+			.collect()
 			.await;
 
+		for key in &leaves {
+			batch.remove(&self.db.roomid_pduleaves, key);
+		}
+
 		for event_id in event_ids {
 			let key = (room_id, event_id);
-			self.db.roomid_pduleaves.put_raw(key, event_id);
+			batch.put_raw(&self.db.roomid_pduleaves, key, event_id);
 		}
 	}
 
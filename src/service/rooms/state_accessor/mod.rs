@@ -28,7 +28,7 @@
 	room::RoomType,
 };
 
-use crate::{Dep, rooms};
+use crate::{Dep, rooms, users};
 
 pub struct Service {
 	services: Services,
@@ -41,6 +41,7 @@ struct Services {
 	state_compressor: Dep<rooms::state_compressor::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
 	timeline: Dep<rooms::timeline::Service>,
+	users: Dep<users::Service>,
 }
 
 struct Data {
@@ -58,6 +59,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_compressor: args
 					.depend::<rooms::state_compressor::Service>("rooms::state_compressor"),
+				users: args.depend::<users::Service>("users"),
 			},
 			db: Data {
 				shorteventid_shortstatehash: args.db["shorteventid_shortstatehash"].clone(),
@@ -1,9 +1,10 @@
-use conduwuit::{Err, Result, implement, pdu::PduBuilder};
+use conduwuit::{Err, PduEvent, Result, RoomVersion, implement, pdu::PduBuilder, state_res};
 use ruma::{
 	EventId, RoomId, UserId,
 	events::{
 		StateEventType, TimelineEventType,
 		room::{
+			create::RoomCreateEventContent,
 			history_visibility::{HistoryVisibility, RoomHistoryVisibilityEventContent},
 			member::{MembershipState, RoomMemberEventContent},
 			power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
@@ -11,7 +12,30 @@
 	},
 };
 
-use crate::rooms::state::RoomMutexGuard;
+use crate::rooms::{short::ShortStateHash, state::RoomMutexGuard};
+
+/// Whether the user created a room of a version in which creators have
+/// infinite power, so that they are not listed in the power levels.
+#[implement(super::Service)]
+pub async fn user_is_privileged_creator(&self, room_id: &RoomId, user_id: &UserId) -> bool {
+	let Ok(create_event) = self
+		.room_state_get(room_id, &StateEventType::RoomCreate, "")
+		.await
+	else {
+		return false;
+	};
+
+	let Some(room_version) = create_event
+		.get_content::<RoomCreateEventContent>()
+		.ok()
+		.and_then(|content| RoomVersion::new(&content.room_version).ok())
+	else {
+		return false;
+	};
+
+	state_res::event_auth::room_creators(&room_version, &create_event)
+		.is_ok_and(|creators| creators.contains(user_id))
+}
 
 /// Checks if a given user can redact a given event
 ///
@@ -44,6 +68,10 @@ pub async fn user_can_redact(
 		)));
 	}
 
+	if self.user_is_privileged_creator(room_id, sender).await {
+		return Ok(true);
+	}
+
 	match self
 		.room_state_get_content::<RoomPowerLevelsEventContent>(
 			room_id,
@@ -88,13 +116,9 @@ pub async fn user_can_redact(
 /// the room's history_visibility at that event's state.
 #[implement(super::Service)]
 #[tracing::instrument(skip_all, level = "trace")]
-pub async fn user_can_see_event(
-	&self,
-	user_id: &UserId,
-	room_id: &RoomId,
-	event_id: &EventId,
-) -> bool {
-	let Ok(shortstatehash) = self.pdu_shortstatehash(event_id).await else {
+pub async fn user_can_see_event(&self, user_id: &UserId, pdu: &PduEvent) -> bool {
+	let room_id = &pdu.room_id;
+	let Ok(shortstatehash) = self.pdu_shortstatehash(&pdu.event_id).await else {
 		return true;
 	};
 
@@ -116,9 +140,31 @@ pub async fn user_can_see_event(
 			// Allow if any member on requested server was joined, else deny
 			self.user_was_joined(shortstatehash, user_id).await
 		},
-		| HistoryVisibility::WorldReadable => true,
-		| HistoryVisibility::Shared | _ => currently_member,
+		| HistoryVisibility::WorldReadable =>
+			self.user_can_see_erased(shortstatehash, user_id, &pdu.sender)
+				.await,
+		| HistoryVisibility::Shared | _ =>
+			currently_member
+				&& self
+					.user_can_see_erased(shortstatehash, user_id, &pdu.sender)
+					.await,
+	}
+}
+
+/// Events sent by erased users are only visible to users who were joined to
+/// the room when they were sent.
+#[implement(super::Service)]
+async fn user_can_see_erased(
+	&self,
+	shortstatehash: ShortStateHash,
+	user_id: &UserId,
+	sender: &UserId,
+) -> bool {
+	if sender == user_id || !self.services.users.is_erased(sender).await {
+		return true;
 	}
+
+	self.user_was_joined(shortstatehash, user_id).await
 }
 
 /// Whether a user is allowed to see an event, based on
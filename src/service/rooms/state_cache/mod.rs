@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod tests;
+
 use std::{
 	collections::{HashMap, HashSet},
 	sync::{Arc, RwLock},
@@ -9,8 +12,8 @@
 	utils::{ReadyExt, StreamTools, stream::TryIgnore},
 	warn,
 };
-use database::{Deserialized, Ignore, Interfix, Json, Map, serialize_key};
-use futures::{Stream, StreamExt, future::join5, pin_mut, stream::iter};
+use database::{Batch, Database, Deserialized, Ignore, Interfix, Json, Map, serialize_key};
+use futures::{Stream, StreamExt, future::join5, pin_mut};
 use itertools::Itertools;
 use ruma::{
 	OwnedRoomId, OwnedServerName, RoomId, ServerName, UserId,
@@ -28,7 +31,10 @@
 	serde::Raw,
 };
 
-use crate::{Dep, account_data, appservice::RegistrationInfo, config, globals, rooms, users};
+use crate::{
+	Dep, account_data, appservice::RegistrationInfo, config, globals, rooms, user_directory,
+	users,
+};
 
 pub struct Service {
 	appservice_in_room_cache: AppServiceInRoomCache,
@@ -42,10 +48,12 @@ struct Services {
 	globals: Dep<globals::Service>,
 	metadata: Dep<rooms::metadata::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
+	user_directory: Dep<user_directory::Service>,
 	users: Dep<users::Service>,
 }
 
 struct Data {
+	db: Arc<Database>,
 	roomid_invitedcount: Arc<Map>,
 	roomid_inviteviaservers: Arc<Map>,
 	roomid_joinedcount: Arc<Map>,
@@ -77,9 +85,11 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				user_directory: args.depend::<user_directory::Service>("user_directory"),
 				users: args.depend::<users::Service>("users"),
 			},
 			db: Data {
+				db: args.db.clone(),
 				roomid_invitedcount: args.db["roomid_invitedcount"].clone(),
 				roomid_inviteviaservers: args.db["roomid_inviteviaservers"].clone(),
 				roomid_joinedcount: args.db["roomid_joinedcount"].clone(),
@@ -124,7 +134,54 @@ pub async fn update_membership(
 		invite_via: Option<Vec<OwnedServerName>>,
 		update_joined_count: bool,
 	) -> Result<()> {
-		let membership = membership_event.membership;
+		let mut batch = self.db.db.batch();
+		let updated = self
+			.stage_membership(
+				&mut batch,
+				room_id,
+				user_id,
+				&membership_event,
+				sender,
+				last_state,
+				invite_via,
+			)
+			.await?;
+
+		batch.commit();
+		if updated {
+			self.membership_updated(room_id, user_id, &membership_event, update_joined_count)
+				.await;
+		}
+
+		Ok(())
+	}
+
+	/// Stages the membership caches for a new membership event into a database
+	/// batch, so they can be committed atomically with the event itself.
+	/// Returns false if the membership is ignored; otherwise
+	/// `membership_updated` must be called once the batch was committed.
+	#[tracing::instrument(
+		level = "debug",
+		skip_all,
+		fields(
+			%room_id,
+			%user_id,
+			%sender,
+			?membership_event,
+		),
+	)]
+	#[allow(clippy::too_many_arguments)]
+	pub async fn stage_membership<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		user_id: &UserId,
+		membership_event: &RoomMemberEventContent,
+		sender: &UserId,
+		last_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
+		invite_via: Option<Vec<OwnedServerName>>,
+	) -> Result<bool> {
+		let membership = &membership_event.membership;
 
 		// Keep track what remote users exist by adding them as "deactivated" users
 		//
@@ -161,12 +218,12 @@ pub async fn update_membership(
 			*/
 		}
 
-		match &membership {
+		match membership {
 			| MembershipState::Join => {
 				// Check if the user never joined this room
 				if !self.once_joined(user_id, room_id).await {
 					// Add the user ID to the join list then
-					self.mark_as_once_joined(user_id, room_id);
+					self.stage_once_joined(batch, user_id, room_id);
 
 					// Check if the room has a predecessor
 					if let Ok(Some(predecessor)) = self
@@ -214,13 +271,13 @@ pub async fn update_membership(
 						{
 							self.services
 								.account_data
-								.update(
+								.stage_update(
+									batch,
 									Some(room_id),
 									user_id,
 									RoomAccountDataEventType::Tag,
 									&tag_event,
 								)
-								.await
 								.ok();
 						}
 
@@ -243,51 +300,70 @@ pub async fn update_membership(
 							}
 
 							if room_ids_updated {
-								self.services
-									.account_data
-									.update(
-										None,
-										user_id,
-										GlobalAccountDataEventType::Direct.to_string().into(),
-										&serde_json::to_value(&direct_event)
-											.expect("to json always works"),
-									)
-									.await?;
+								self.services.account_data.stage_update(
+									batch,
+									None,
+									user_id,
+									GlobalAccountDataEventType::Direct.to_string().into(),
+									&serde_json::to_value(&direct_event)
+										.expect("to json always works"),
+								)?;
 							}
 						}
 					}
 				}
 
-				self.mark_as_joined(user_id, room_id);
+				self.stage_joined(batch, user_id, room_id);
 			},
 			| MembershipState::Invite => {
 				// We want to know if the sender is ignored by the receiver
 				if self.services.users.user_is_ignored(sender, user_id).await {
-					return Ok(());
+					return Ok(false);
 				}
 
-				self.mark_as_invited(user_id, room_id, last_state, invite_via)
+				self.stage_invited(batch, user_id, room_id, last_state, invite_via)
 					.await;
 			},
 			| MembershipState::Leave | MembershipState::Ban => {
-				self.mark_as_left(user_id, room_id);
+				self.stage_left(batch, user_id, room_id);
 
 				if self.services.globals.user_is_local(user_id)
 					&& (self.services.config.forget_forced_upon_leave
 						|| self.services.metadata.is_banned(room_id).await
 						|| self.services.metadata.is_disabled(room_id).await)
 				{
-					self.forget(room_id, user_id);
+					self.stage_forget(batch, room_id, user_id);
 				}
 			},
 			| _ => {},
 		}
 
+		Ok(true)
+	}
+
+	/// Updates what depends on the membership caches, once the batch from
+	/// `stage_membership` was committed.
+	pub async fn membership_updated(
+		&self,
+		room_id: &RoomId,
+		user_id: &UserId,
+		membership_event: &RoomMemberEventContent,
+		update_joined_count: bool,
+	) {
+		let membership = &membership_event.membership;
 		if update_joined_count {
 			self.update_joined_count(room_id).await;
 		}
 
-		Ok(())
+		if matches!(
+			membership,
+			MembershipState::Join | MembershipState::Leave | MembershipState::Ban
+		) {
+			self.services
+				.user_directory
+				.update_membership(user_id, membership_event.displayname.as_deref())
+				.await;
+		}
 	}
 
 	#[tracing::instrument(level = "trace", skip_all)]
@@ -337,25 +413,31 @@ pub async fn appservice_in_room(
 	/// `update_membership` instead
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub fn mark_as_joined(&self, user_id: &UserId, room_id: &RoomId) {
+		let mut batch = self.db.db.batch();
+		self.stage_joined(&mut batch, user_id, room_id);
+		batch.commit();
+	}
+
+	fn stage_joined<'a>(&'a self, batch: &mut Batch<'a>, user_id: &UserId, room_id: &RoomId) {
 		let userroom_id = (user_id, room_id);
 		let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
 
 		let roomuser_id = (room_id, user_id);
 		let roomuser_id = serialize_key(roomuser_id).expect("failed to serialize roomuser_id");
 
-		self.db.userroomid_joined.insert(&userroom_id, []);
-		self.db.roomuserid_joined.insert(&roomuser_id, []);
+		batch.insert(&self.db.userroomid_joined, &userroom_id, []);
+		batch.insert(&self.db.roomuserid_joined, &roomuser_id, []);
 
-		self.db.userroomid_invitestate.remove(&userroom_id);
-		self.db.roomuserid_invitecount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_invitestate, &userroom_id);
+		batch.remove(&self.db.roomuserid_invitecount, &roomuser_id);
 
-		self.db.userroomid_leftstate.remove(&userroom_id);
-		self.db.roomuserid_leftcount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_leftstate, &userroom_id);
+		batch.remove(&self.db.roomuserid_leftcount, &roomuser_id);
 
-		self.db.userroomid_knockedstate.remove(&userroom_id);
-		self.db.roomuserid_knockedcount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_knockedstate, &userroom_id);
+		batch.remove(&self.db.roomuserid_knockedcount, &roomuser_id);
 
-		self.db.roomid_inviteviaservers.remove(room_id);
+		batch.remove(&self.db.roomid_inviteviaservers, room_id);
 	}
 
 	/// Direct DB function to directly mark a user as left. It is not
@@ -363,6 +445,12 @@ pub fn mark_as_joined(&self, user_id: &UserId, room_id: &RoomId) {
 	/// `update_membership` instead
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) {
+		let mut batch = self.db.db.batch();
+		self.stage_left(&mut batch, user_id, room_id);
+		batch.commit();
+	}
+
+	fn stage_left<'a>(&'a self, batch: &mut Batch<'a>, user_id: &UserId, room_id: &RoomId) {
 		let userroom_id = (user_id, room_id);
 		let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
 
@@ -372,23 +460,23 @@ pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) {
 		// (timo) TODO
 		let leftstate = Vec::<Raw<AnySyncStateEvent>>::new();
 
-		self.db
-			.userroomid_leftstate
-			.raw_put(&userroom_id, Json(leftstate));
-		self.db
-			.roomuserid_leftcount
-			.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+		batch.raw_put(&self.db.userroomid_leftstate, &userroom_id, Json(leftstate));
+		batch.raw_put(
+			&self.db.roomuserid_leftcount,
+			&roomuser_id,
+			self.services.globals.next_count().unwrap(),
+		);
 
-		self.db.userroomid_joined.remove(&userroom_id);
-		self.db.roomuserid_joined.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_joined, &userroom_id);
+		batch.remove(&self.db.roomuserid_joined, &roomuser_id);
 
-		self.db.userroomid_invitestate.remove(&userroom_id);
-		self.db.roomuserid_invitecount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_invitestate, &userroom_id);
+		batch.remove(&self.db.roomuserid_invitecount, &roomuser_id);
 
-		self.db.userroomid_knockedstate.remove(&userroom_id);
-		self.db.roomuserid_knockedcount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_knockedstate, &userroom_id);
+		batch.remove(&self.db.roomuserid_knockedcount, &roomuser_id);
 
-		self.db.roomid_inviteviaservers.remove(room_id);
+		batch.remove(&self.db.roomid_inviteviaservers, room_id);
 	}
 
 	/// Direct DB function to directly mark a user as knocked. It is not
@@ -400,6 +488,18 @@ pub fn mark_as_knocked(
 		user_id: &UserId,
 		room_id: &RoomId,
 		knocked_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
+	) {
+		let mut batch = self.db.db.batch();
+		self.stage_knocked(&mut batch, user_id, room_id, knocked_state);
+		batch.commit();
+	}
+
+	fn stage_knocked<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		user_id: &UserId,
+		room_id: &RoomId,
+		knocked_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
 	) {
 		let userroom_id = (user_id, room_id);
 		let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
@@ -407,33 +507,43 @@ pub fn mark_as_knocked(
 		let roomuser_id = (room_id, user_id);
 		let roomuser_id = serialize_key(roomuser_id).expect("failed to serialize roomuser_id");
 
-		self.db
-			.userroomid_knockedstate
-			.raw_put(&userroom_id, Json(knocked_state.unwrap_or_default()));
-		self.db
-			.roomuserid_knockedcount
-			.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+		batch.raw_put(
+			&self.db.userroomid_knockedstate,
+			&userroom_id,
+			Json(knocked_state.unwrap_or_default()),
+		);
+		batch.raw_put(
+			&self.db.roomuserid_knockedcount,
+			&roomuser_id,
+			self.services.globals.next_count().unwrap(),
+		);
 
-		self.db.userroomid_joined.remove(&userroom_id);
-		self.db.roomuserid_joined.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_joined, &userroom_id);
+		batch.remove(&self.db.roomuserid_joined, &roomuser_id);
 
-		self.db.userroomid_invitestate.remove(&userroom_id);
-		self.db.roomuserid_invitecount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_invitestate, &userroom_id);
+		batch.remove(&self.db.roomuserid_invitecount, &roomuser_id);
 
-		self.db.userroomid_leftstate.remove(&userroom_id);
-		self.db.roomuserid_leftcount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_leftstate, &userroom_id);
+		batch.remove(&self.db.roomuserid_leftcount, &roomuser_id);
 
-		self.db.roomid_inviteviaservers.remove(room_id);
+		batch.remove(&self.db.roomid_inviteviaservers, room_id);
 	}
 
 	/// Makes a user forget a room.
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub fn forget(&self, room_id: &RoomId, user_id: &UserId) {
+		let mut batch = self.db.db.batch();
+		self.stage_forget(&mut batch, room_id, user_id);
+		batch.commit();
+	}
+
+	fn stage_forget<'a>(&'a self, batch: &mut Batch<'a>, room_id: &RoomId, user_id: &UserId) {
 		let userroom_id = (user_id, room_id);
 		let roomuser_id = (room_id, user_id);
 
-		self.db.userroomid_leftstate.del(userroom_id);
-		self.db.roomuserid_leftcount.del(roomuser_id);
+		batch.del(&self.db.userroomid_leftstate, userroom_id);
+		batch.del(&self.db.roomuserid_leftcount, roomuser_id);
 	}
 
 	/// Returns an iterator of all servers participating in this room.
@@ -936,9 +1046,14 @@ pub async fn update_joined_count(&self, room_id: &RoomId) {
 	}
 
 	#[tracing::instrument(level = "debug", skip(self))]
-	fn mark_as_once_joined(&self, user_id: &UserId, room_id: &RoomId) {
+	fn stage_once_joined<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		user_id: &UserId,
+		room_id: &RoomId,
+	) {
 		let key = (user_id, room_id);
-		self.db.roomuseroncejoinedids.put_raw(key, []);
+		batch.put_raw(&self.db.roomuseroncejoinedids, key, []);
 	}
 
 	#[tracing::instrument(level = "debug", skip(self, last_state, invite_via))]
@@ -948,6 +1063,21 @@ pub async fn mark_as_invited(
 		room_id: &RoomId,
 		last_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
 		invite_via: Option<Vec<OwnedServerName>>,
+	) {
+		let mut batch = self.db.db.batch();
+		self.stage_invited(&mut batch, user_id, room_id, last_state, invite_via)
+			.await;
+
+		batch.commit();
+	}
+
+	async fn stage_invited<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		user_id: &UserId,
+		room_id: &RoomId,
+		last_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
+		invite_via: Option<Vec<OwnedServerName>>,
 	) {
 		let roomuser_id = (room_id, user_id);
 		let roomuser_id = serialize_key(roomuser_id).expect("failed to serialize roomuser_id");
@@ -955,35 +1085,56 @@ pub async fn mark_as_invited(
 		let userroom_id = (user_id, room_id);
 		let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
 
-		self.db
-			.userroomid_invitestate
-			.raw_put(&userroom_id, Json(last_state.unwrap_or_default()));
-		self.db
-			.roomuserid_invitecount
-			.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+		batch.raw_put(
+			&self.db.userroomid_invitestate,
+			&userroom_id,
+			Json(last_state.unwrap_or_default()),
+		);
+		batch.raw_put(
+			&self.db.roomuserid_invitecount,
+			&roomuser_id,
+			self.services.globals.next_count().unwrap(),
+		);
 
-		self.db.userroomid_joined.remove(&userroom_id);
-		self.db.roomuserid_joined.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_joined, &userroom_id);
+		batch.remove(&self.db.roomuserid_joined, &roomuser_id);
 
-		self.db.userroomid_leftstate.remove(&userroom_id);
-		self.db.roomuserid_leftcount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_leftstate, &userroom_id);
+		batch.remove(&self.db.roomuserid_leftcount, &roomuser_id);
 
-		self.db.userroomid_knockedstate.remove(&userroom_id);
-		self.db.roomuserid_knockedcount.remove(&roomuser_id);
+		batch.remove(&self.db.userroomid_knockedstate, &userroom_id);
+		batch.remove(&self.db.roomuserid_knockedcount, &roomuser_id);
 
 		if let Some(servers) = invite_via.filter(is_not_empty!()) {
-			self.add_servers_invite_via(room_id, servers).await;
+			self.stage_servers_invite_via(batch, room_id, servers);
 		}
 	}
 
 	#[tracing::instrument(level = "debug", skip(self, servers))]
 	pub async fn add_servers_invite_via(&self, room_id: &RoomId, servers: Vec<OwnedServerName>) {
-		let mut servers: Vec<_> = self
-			.servers_invite_via(room_id)
-			.map(ToOwned::to_owned)
-			.chain(iter(servers.into_iter()))
-			.collect()
-			.await;
+		let mut batch = self.db.db.batch();
+		self.stage_servers_invite_via(&mut batch, room_id, servers);
+
+		batch.commit();
+	}
+
+	fn stage_servers_invite_via<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		room_id: &RoomId,
+		servers: Vec<OwnedServerName>,
+	) {
+		// Read through the batch so servers staged earlier in it are kept.
+		let staged = batch
+			.get(&self.db.roomid_inviteviaservers, room_id.as_bytes())
+			.unwrap_or_default();
+
+		let mut servers: Vec<_> = staged
+			.split(|&byte| byte == 0xFF)
+			.filter_map(|server| str::from_utf8(server).ok())
+			.filter_map(|server| ServerName::parse(server).ok())
+			.chain(servers)
+			.collect();
 
 		servers.sort_unstable();
 		servers.dedup();
@@ -994,8 +1145,6 @@ pub async fn add_servers_invite_via(&self, room_id: &RoomId, servers: Vec<OwnedS
 			.collect_vec()
 			.join(&[0xFF][..]);
 
-		self.db
-			.roomid_inviteviaservers
-			.insert(room_id.as_bytes(), &servers);
+		batch.insert(&self.db.roomid_inviteviaservers, room_id.as_bytes(), &servers);
 	}
 }
@@ -0,0 +1,97 @@
+use ruma::{
+	events::room::member::{MembershipState, RoomMemberEventContent},
+	owned_server_name, room_id, user_id,
+};
+
+use crate::tests::services;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staged_membership_is_written_on_commit() {
+	let services = services().await;
+	let state_cache = &services.rooms.state_cache;
+	let room_id = room_id!("!room:example.com");
+	let alice = user_id!("@alice:example.com");
+	let content = RoomMemberEventContent::new(MembershipState::Join);
+
+	// A batch dropped without being committed writes nothing.
+	let mut batch = services.db.batch();
+	assert!(
+		state_cache
+			.stage_membership(&mut batch, room_id, alice, &content, alice, None, None)
+			.await
+			.unwrap()
+	);
+	drop(batch);
+	assert!(!state_cache.is_joined(alice, room_id).await);
+
+	let mut batch = services.db.batch();
+	state_cache
+		.stage_membership(&mut batch, room_id, alice, &content, alice, None, None)
+		.await
+		.unwrap();
+
+	assert!(!state_cache.is_joined(alice, room_id).await);
+	batch.commit();
+	assert!(state_cache.is_joined(alice, room_id).await);
+	assert!(state_cache.once_joined(alice, room_id).await);
+
+	state_cache
+		.membership_updated(room_id, alice, &content, true)
+		.await;
+	assert_eq!(state_cache.room_joined_count(room_id).await.unwrap(), 1);
+
+	// Leaving replaces the joined entries in the same write.
+	let content = RoomMemberEventContent::new(MembershipState::Leave);
+	let mut batch = services.db.batch();
+	state_cache
+		.stage_membership(&mut batch, room_id, alice, &content, alice, None, None)
+		.await
+		.unwrap();
+
+	batch.commit();
+	assert!(!state_cache.is_joined(alice, room_id).await);
+	assert!(state_cache.is_left(alice, room_id).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staged_invites_read_through_batch() {
+	let services = services().await;
+	let state_cache = &services.rooms.state_cache;
+	let room_id = room_id!("!room:example.com");
+	let alice = user_id!("@alice:example.com");
+	let bob = user_id!("@bob:example.com");
+	let content = RoomMemberEventContent::new(MembershipState::Invite);
+
+	// The second invite must see the servers staged by the first one.
+	let mut batch = services.db.batch();
+	for (user_id, server) in [
+		(alice, owned_server_name!("a.example.com")),
+		(bob, owned_server_name!("b.example.com")),
+	] {
+		state_cache
+			.stage_membership(
+				&mut batch,
+				room_id,
+				user_id,
+				&content,
+				alice,
+				None,
+				Some(vec![server]),
+			)
+			.await
+			.unwrap();
+	}
+
+	batch.commit();
+	assert!(state_cache.is_invited(alice, room_id).await);
+	assert!(state_cache.is_invited(bob, room_id).await);
+
+	let servers = state_cache
+		.db
+		.roomid_inviteviaservers
+		.get(room_id)
+		.await
+		.unwrap();
+
+	assert_eq!(&*servers, b"a.example.com\xFFb.example.com");
+}
@@ -6,7 +6,7 @@
 	utils,
 	utils::stream::TryReadyExt,
 };
-use database::{Database, Deserialized, Json, KeyVal, Map};
+use database::{Batch, Database, Deserialized, Json, KeyVal, Map};
 use futures::{FutureExt, Stream, TryFutureExt, TryStreamExt, future::select_ok, pin_mut};
 use ruma::{CanonicalJsonObject, EventId, OwnedUserId, RoomId, UserId, api::Direction};
 
@@ -177,8 +177,9 @@ pub(super) async fn get_pdu_json_from_id(
 		self.pduid_pdu.get(pdu_id).await.deserialized()
 	}
 
-	pub(super) async fn append_pdu(
-		&self,
+	pub(super) fn append_pdu<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
 		pdu_id: &RawPduId,
 		pdu: &PduEvent,
 		json: &CanonicalJsonObject,
@@ -186,9 +187,9 @@ pub(super) async fn append_pdu(
 	) {
 		debug_assert!(matches!(count, PduCount::Normal(_)), "PduCount not Normal");
 
-		self.pduid_pdu.raw_put(pdu_id, Json(json));
-		self.eventid_pduid.insert(pdu.event_id.as_bytes(), pdu_id);
-		self.eventid_outlierpdu.remove(pdu.event_id.as_bytes());
+		batch.raw_put(&self.pduid_pdu, pdu_id, Json(json));
+		batch.insert(&self.eventid_pduid, pdu.event_id.as_bytes(), pdu_id);
+		batch.remove(&self.eventid_outlierpdu, pdu.event_id.as_bytes());
 	}
 
 	pub(super) fn prepend_backfill_pdu(
@@ -269,26 +270,25 @@ fn each_pdu((pdu_id, pdu): KeyVal<'_>, user_id: Option<&UserId>) -> Result<PdusI
 		Ok((pdu_id.pdu_count(), pdu))
 	}
 
-	pub(super) fn increment_notification_counts(
-		&self,
+	pub(super) fn increment_notification_counts<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
 		room_id: &RoomId,
-		notifies: Vec<OwnedUserId>,
-		highlights: Vec<OwnedUserId>,
+		notifies: &[OwnedUserId],
+		highlights: &[OwnedUserId],
 	) {
-		let _cork = self.db.cork();
-
 		for user in notifies {
 			let mut userroom_id = user.as_bytes().to_vec();
 			userroom_id.push(0xFF);
 			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_notificationcount, &userroom_id);
+			increment(batch, &self.userroomid_notificationcount, &userroom_id);
 		}
 
 		for user in highlights {
 			let mut userroom_id = user.as_bytes().to_vec();
 			userroom_id.push(0xFF);
 			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_highlightcount, &userroom_id);
+			increment(batch, &self.userroomid_highlightcount, &userroom_id);
 		}
 	}
 
@@ -316,8 +316,8 @@ async fn count_to_id(
 }
 
 //TODO: this is an ABA
-fn increment(db: &Arc<Map>, key: &[u8]) {
-	let old = db.get_blocking(key);
+fn increment<'a>(batch: &mut Batch<'a>, db: &'a Arc<Map>, key: &[u8]) {
+	let old = batch.get(db, key);
 	let new = utils::increment(old.ok().as_deref());
-	db.insert(key, new);
+	batch.insert(db, key, new);
 }
@@ -15,7 +15,9 @@
 	Err, Error, Result, Server, at, debug, debug_warn, err, error, implement, info,
 	matrix::{
 		Event,
-		pdu::{EventHash, PduBuilder, PduCount, PduEvent, gen_event_id},
+		pdu::{
+			EventHash, PduBuilder, PduCount, PduEvent, gen_event_id, room_id_from_create_event_id,
+		},
 		state_res::{self, RoomVersion},
 	},
 	utils::{
@@ -53,9 +55,13 @@
 use crate::{
 	Dep, account_data, admin, appservice,
 	appservice::NamespaceRegex,
-	globals, pusher, rooms,
-	rooms::{short::ShortRoomId, state_compressor::CompressedState},
-	sending, server_keys, users,
+	globals, policy, pusher, rooms,
+	rooms::{
+		short::{ShortRoomId, ShortStateHash},
+		spam_check::Verdict,
+		state_compressor::CompressedState,
+	},
+	sending, server_keys, user_directory, users,
 };
 
 // Update Relationships
@@ -92,6 +98,7 @@ struct Services {
 	appservice: Dep<appservice::Service>,
 	admin: Dep<admin::Service>,
 	alias: Dep<rooms::alias::Service>,
+	directory: Dep<rooms::directory::Service>,
 	globals: Dep<globals::Service>,
 	short: Dep<rooms::short::Service>,
 	state: Dep<rooms::state::Service>,
@@ -102,14 +109,30 @@ struct Services {
 	sending: Dep<sending::Service>,
 	server_keys: Dep<server_keys::Service>,
 	user: Dep<rooms::user::Service>,
+	user_directory: Dep<user_directory::Service>,
 	users: Dep<users::Service>,
+	policy: Dep<policy::Service>,
 	pusher: Dep<pusher::Service>,
 	threads: Dep<rooms::threads::Service>,
 	search: Dep<rooms::search::Service>,
 	spaces: Dep<rooms::spaces::Service>,
+	spam_check: Dep<rooms::spam_check::Service>,
 	event_handler: Dep<rooms::event_handler::Service>,
+	unredacted: Dep<rooms::unredacted::Service>,
 }
 
+/// State events which change a room's entry in the public room directory.
+const DIRECTORY_EVENT_TYPES: &[TimelineEventType] = &[
+	TimelineEventType::RoomAvatar,
+	TimelineEventType::RoomCanonicalAlias,
+	TimelineEventType::RoomGuestAccess,
+	TimelineEventType::RoomHistoryVisibility,
+	TimelineEventType::RoomJoinRules,
+	TimelineEventType::RoomMember,
+	TimelineEventType::RoomName,
+	TimelineEventType::RoomTopic,
+];
+
 type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
 pub type RoomMutexGuard = MutexMapGuard<OwnedRoomId, ()>;
 
@@ -123,6 +146,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				appservice: args.depend::<appservice::Service>("appservice"),
 				admin: args.depend::<admin::Service>("admin"),
 				alias: args.depend::<rooms::alias::Service>("rooms::alias"),
+				directory: args.depend::<rooms::directory::Service>("rooms::directory"),
 				globals: args.depend::<globals::Service>("globals"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
@@ -134,13 +158,17 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				sending: args.depend::<sending::Service>("sending"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
 				user: args.depend::<rooms::user::Service>("rooms::user"),
+				user_directory: args.depend::<user_directory::Service>("user_directory"),
 				users: args.depend::<users::Service>("users"),
+				policy: args.depend::<policy::Service>("policy"),
 				pusher: args.depend::<pusher::Service>("pusher"),
 				threads: args.depend::<rooms::threads::Service>("rooms::threads"),
 				search: args.depend::<rooms::search::Service>("rooms::search"),
 				spaces: args.depend::<rooms::spaces::Service>("rooms::spaces"),
+				spam_check: args.depend::<rooms::spam_check::Service>("rooms::spam_check"),
 				event_handler: args
 					.depend::<rooms::event_handler::Service>("rooms::event_handler"),
+				unredacted: args.depend::<rooms::unredacted::Service>("rooms::unredacted"),
 			},
 			db: Data::new(&args),
 			mutex_insert: RoomMutexMap::new(),
@@ -266,12 +294,32 @@ pub async fn replace_pdu(
 	/// happens in `append_pdu`.
 	///
 	/// Returns pdu id
-	#[tracing::instrument(level = "debug", skip_all)]
+	#[inline]
 	pub async fn append_pdu<'a, Leafs>(
+		&'a self,
+		pdu: &'a PduEvent,
+		pdu_json: CanonicalJsonObject,
+		leafs: Leafs,
+		state_lock: &'a RoomMutexGuard,
+	) -> Result<RawPduId>
+	where
+		Leafs: Iterator<Item = &'a EventId> + Send + 'a,
+	{
+		self.append_pdu_with_state(pdu, pdu_json, leafs, None, state_lock)
+			.await
+	}
+
+	/// Like `append_pdu`, additionally setting the room state to
+	/// `shortstatehash` in the same atomic write as the pdu, so that there is
+	/// never a moment in time where the pdu exists without its state or the
+	/// room state references a missing event.
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn append_pdu_with_state<'a, Leafs>(
 		&'a self,
 		pdu: &'a PduEvent,
 		mut pdu_json: CanonicalJsonObject,
 		leafs: Leafs,
+		shortstatehash: Option<ShortStateHash>,
 		state_lock: &'a RoomMutexGuard,
 	) -> Result<RawPduId>
 	where
@@ -337,35 +385,29 @@ pub async fn append_pdu<'a, Leafs>(
 			}
 		}
 
-		// We must keep track of all events that have been referenced.
-		self.services
-			.pdu_metadata
-			.mark_as_referenced(&pdu.room_id, pdu.prev_events.iter().map(AsRef::as_ref));
-
-		self.services
-			.state
-			.set_forward_extremities(&pdu.room_id, leafs, state_lock)
-			.await;
-
-		let insert_lock = self.mutex_insert.lock(&pdu.room_id).await;
-
-		let count1 = self.services.globals.next_count().unwrap();
-		// Mark as read first so the sending client doesn't get a notification even if
-		// appending fails
-		self.services
-			.read_receipt
-			.private_read_set(&pdu.room_id, &pdu.sender, count1);
-		self.services
-			.user
-			.reset_notification_counts(&pdu.sender, &pdu.room_id);
+		// Parse everything which can fail before anything is written.
+		let message_body = match pdu.kind {
+			| TimelineEventType::RoomMessage => pdu.get_content::<ExtractBody>()?.body,
+			| _ => None,
+		};
 
-		let count2 = PduCount::Normal(self.services.globals.next_count().unwrap());
-		let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count2 }.into();
+		let membership = match (&pdu.kind, &pdu.state_key) {
+			| (TimelineEventType::RoomMember, Some(state_key)) => {
+				// if the state_key fails
+				let target_user_id =
+					UserId::parse(state_key).expect("This state_key was previously validated");
 
-		// Insert pdu
-		self.db.append_pdu(&pdu_id, pdu, &pdu_json, count2).await;
+				let content: RoomMemberEventContent = pdu.get_content()?;
+				let stripped_state = match content.membership {
+					| MembershipState::Invite | MembershipState::Knock =>
+						self.services.state.summary_stripped(pdu).await.into(),
+					| _ => None,
+				};
 
-		drop(insert_lock);
+				Some((target_user_id, content, stripped_state))
+			},
+			| _ => None,
+		};
 
 		// See if the event matches any known pushers via power level
 		let power_levels: RoomPowerLevelsEventContent = self
@@ -442,7 +484,88 @@ pub async fn append_pdu<'a, Leafs>(
 			if highlight {
 				highlights.push(user.clone());
 			}
+		}
+
+		// Everything the pdu changes in the room is written in a single atomic batch
+		// with the pdu itself.
+		let mut batch = self.db.db.batch();
+
+		// We must keep track of all events that have been referenced.
+		self.services.pdu_metadata.stage_referenced(
+			&mut batch,
+			&pdu.room_id,
+			pdu.prev_events.iter().map(AsRef::as_ref),
+		);
+
+		self.services
+			.state
+			.stage_forward_extremities(&mut batch, &pdu.room_id, leafs, state_lock)
+			.await;
 
+		let insert_lock = self.mutex_insert.lock(&pdu.room_id).await;
+
+		let count1 = self.services.globals.next_count().unwrap();
+		// Mark as read so the sending client doesn't get a notification for its own
+		// event
+		self.services.read_receipt.stage_private_read_set(
+			&mut batch,
+			&pdu.room_id,
+			&pdu.sender,
+			count1,
+		);
+		self.services
+			.user
+			.stage_reset_notification_counts(&mut batch, &pdu.sender, &pdu.room_id);
+
+		let count2 = PduCount::Normal(self.services.globals.next_count().unwrap());
+		let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count2 }.into();
+
+		self.db
+			.append_pdu(&mut batch, &pdu_id, pdu, &pdu_json, count2);
+
+		if let Some(body) = &message_body {
+			self.services
+				.search
+				.stage_index_pdu(&mut batch, shortroomid, &pdu_id, body);
+		}
+
+		if let Some(shortstatehash) = shortstatehash {
+			self.services.state.stage_room_state(
+				&mut batch,
+				&pdu.room_id,
+				shortstatehash,
+				state_lock,
+			);
+		}
+
+		self.db
+			.increment_notification_counts(&mut batch, &pdu.room_id, &notifies, &highlights);
+
+		// Update our membership info, we do this here incase a user is invited or
+		// knocked and immediately leaves we need the DB to record the invite or
+		// knock event for auth
+		let membership = match membership {
+			| Some((target_user_id, content, stripped_state)) => self
+				.services
+				.state_cache
+				.stage_membership(
+					&mut batch,
+					&pdu.room_id,
+					target_user_id,
+					&content,
+					&pdu.sender,
+					stripped_state,
+					None,
+				)
+				.await?
+				.then_some((target_user_id, content)),
+			| None => None,
+		};
+
+		batch.commit();
+		drop(insert_lock);
+
+		for user in &push_target {
 			self.services
 				.pusher
 				.get_pushkeys(user)
@@ -455,9 +578,6 @@ pub async fn append_pdu<'a, Leafs>(
 				.await;
 		}
 
-		self.db
-			.increment_notification_counts(&pdu.room_id, notifies, highlights);
-
 		match pdu.kind {
 			| TimelineEventType::RoomRedaction => {
 				use RoomVersionId::*;
@@ -500,51 +620,38 @@ pub async fn append_pdu<'a, Leafs>(
 						.await
 						.remove(&pdu.room_id);
 				},
-			| TimelineEventType::RoomMember => {
-				if let Some(state_key) = &pdu.state_key {
-					// if the state_key fails
-					let target_user_id = UserId::parse(state_key)
-						.expect("This state_key was previously validated");
-
-					let content: RoomMemberEventContent = pdu.get_content()?;
-					let stripped_state = match content.membership {
-						| MembershipState::Invite | MembershipState::Knock =>
-							self.services.state.summary_stripped(pdu).await.into(),
-						| _ => None,
-					};
-
-					// Update our membership info, we do this here incase a user is invited or
-					// knocked and immediately leaves we need the DB to record the invite or
-					// knock event for auth
+			| TimelineEventType::RoomMember =>
+				if let Some((target_user_id, content)) = &membership {
 					self.services
 						.state_cache
-						.update_membership(
-							&pdu.room_id,
-							target_user_id,
-							content,
-							&pdu.sender,
-							stripped_state,
-							None,
-							true,
-						)
-						.await?;
-				}
+						.membership_updated(&pdu.room_id, target_user_id, content, true)
+						.await;
+				},
+			| TimelineEventType::RoomJoinRules => {
+				self.services.user_directory.update_room(&pdu.room_id).await;
 			},
-			| TimelineEventType::RoomMessage => {
-				let content: ExtractBody = pdu.get_content()?;
-				if let Some(body) = content.body {
-					self.services.search.index_pdu(shortroomid, &pdu_id, &body);
-
+			| TimelineEventType::PolicyRuleUser
+			| TimelineEventType::PolicyRuleRoom
+			| TimelineEventType::PolicyRuleServer
+				if pdu.state_key.is_some() =>
+			{
+				self.services.policy.update_rule(pdu).await;
+			},
+			| TimelineEventType::RoomMessage =>
+				if let Some(body) = message_body {
 					if self.services.admin.is_admin_command(pdu, &body).await {
 						self.services
 							.admin
 							.command(body, Some((*pdu.event_id).into()))?;
 					}
-				}
-			},
+				},
 			| _ => {},
 		}
 
+		if pdu.state_key.is_some() && DIRECTORY_EVENT_TYPES.contains(&pdu.kind) {
+			self.services.directory.update_room(&pdu.room_id).await;
+		}
+
 		if let Ok(content) = pdu.get_content::<ExtractRelatesToEventId>() {
 			if let Ok(related_pducount) = self.get_pdu_count(&content.relates_to.event_id).await {
 				self.services
@@ -680,6 +787,10 @@ pub async fn create_hash_and_sign_event(
 
 		let room_version = RoomVersion::new(&room_version_id).expect("room version is supported");
 
+		// The room ID is derived from the create event in some room versions
+		let hashed_create =
+			room_version.room_ids_as_hashes && event_type == TimelineEventType::RoomCreate;
+
 		let auth_events = self
 			.services
 			.state
@@ -740,6 +851,9 @@ pub async fn create_hash_and_sign_event(
 			depth,
 			auth_events: auth_events
 				.values()
+				.filter(|pdu| {
+					!room_version.room_ids_as_hashes || pdu.kind != TimelineEventType::RoomCreate
+				})
 				.map(|pdu| pdu.event_id.clone())
 				.collect(),
 			redacts,
@@ -752,24 +866,6 @@ pub async fn create_hash_and_sign_event(
 			signatures: None,
 		};
 
-		let auth_fetch = |k: &StateEventType, s: &str| {
-			let key = (k.clone(), s.into());
-			ready(auth_events.get(&key))
-		};
-
-		let auth_check = state_res::auth_check(
-			&room_version,
-			&pdu,
-			None, // TODO: third_party_invite
-			auth_fetch,
-		)
-		.await
-		.map_err(|e| err!(Request(Forbidden(warn!("Auth check failed: {e:?}")))))?;
-
-		if !auth_check {
-			return Err!(Request(Forbidden("Event is not authorized.")));
-		}
-
 		// Hash and sign
 		let mut pdu_json = utils::to_canonical_object(&pdu).map_err(|e| {
 			err!(Request(BadJson(warn!("Failed to convert PDU to canonical JSON: {e}"))))
@@ -783,6 +879,10 @@ pub async fn create_hash_and_sign_event(
 			},
 		}
 
+		if hashed_create {
+			pdu_json.remove("room_id");
+		}
+
 		// Add origin because synapse likes that (and it's required in the spec)
 		pdu_json.insert(
 			"origin".to_owned(),
@@ -809,6 +909,31 @@ pub async fn create_hash_and_sign_event(
 		pdu_json
 			.insert("event_id".into(), CanonicalJsonValue::String(pdu.event_id.clone().into()));
 
+		if hashed_create {
+			pdu.room_id = room_id_from_create_event_id(&pdu.event_id)?;
+			pdu_json
+				.insert("room_id".into(), CanonicalJsonValue::String(pdu.room_id.clone().into()));
+		}
+
+		// Checked once the event ID is known, which the room ID may be derived from
+		let auth_fetch = |k: &StateEventType, s: &str| {
+			let key = (k.clone(), s.into());
+			ready(auth_events.get(&key))
+		};
+
+		let auth_check = state_res::auth_check(
+			&room_version,
+			&pdu,
+			None, // TODO: third_party_invite
+			auth_fetch,
+		)
+		.await
+		.map_err(|e| err!(Request(Forbidden(warn!("Auth check failed: {e:?}")))))?;
+
+		if !auth_check {
+			return Err!(Request(Forbidden("Event is not authorized.")));
+		}
+
 		// Generate short event id
 		let _shorteventid = self
 			.services
@@ -819,6 +944,28 @@ pub async fn create_hash_and_sign_event(
 		Ok((pdu, pdu_json))
 	}
 
+	/// The ID of a new room of a version where room IDs are the reference hash
+	/// of the `m.room.create` event. The event has to have a timestamp, so that
+	/// it hashes the same when it is appended to the room later.
+	pub async fn create_room_id(
+		&self,
+		create_event: &PduBuilder,
+		sender: &UserId,
+	) -> Result<OwnedRoomId> {
+		if create_event.timestamp.is_none() {
+			return Err!("The m.room.create event of a room with hashed ID needs a timestamp.");
+		}
+
+		// Not yet known, the event is built against a placeholder
+		let room_id = RoomId::new(self.services.globals.server_name());
+		let state_lock = self.services.state.mutex.lock(&room_id).await;
+		let (pdu, _) = self
+			.create_hash_and_sign_event(create_event.clone(), sender, &room_id, &state_lock)
+			.await?;
+
+		Ok(pdu.room_id)
+	}
+
 	/// Creates a new persisted data unit and adds it to a room. This function
 	/// takes a roomid_mutex_state, meaning that only this function is able to
 	/// mutate the room state.
@@ -838,6 +985,10 @@ pub async fn build_and_append_pdu(
 			self.check_pdu_for_admin_room(&pdu, sender).boxed().await?;
 		}
 
+		if self.services.spam_check.check_pdu(&pdu, &pdu_json).await != Verdict::Allow {
+			return Err!(Request(Forbidden("Event was rejected by the room's policy server.")));
+		}
+
 		// If redaction event is not authorized, do not append it to the timeline
 		if pdu.kind == TimelineEventType::RoomRedaction {
 			use RoomVersionId::*;
@@ -898,24 +1049,21 @@ pub async fn build_and_append_pdu(
 		// fail.
 		let statehashid = self.services.state.append_to_state(&pdu).await?;
 
+		// The room state is set in the same write as the pdu, so that we never have a
+		// moment in time where events in the current room state do not exist
 		let pdu_id = self
-			.append_pdu(
+			.append_pdu_with_state(
 				&pdu,
 				pdu_json,
 				// Since this PDU references all pdu_leaves we can update the leaves
 				// of the room
 				once(pdu.event_id.borrow()),
+				Some(statehashid),
 				state_lock,
 			)
 			.boxed()
 			.await?;
 
-		// We set the room state after inserting the pdu, so that we never have a moment
-		// in time where events in the current room state do not exist
-		self.services
-			.state
-			.set_room_state(&pdu.room_id, statehashid, state_lock);
-
 		let mut servers: HashSet<OwnedServerName> = self
 			.services
 			.state_cache
@@ -972,15 +1120,19 @@ pub async fn append_incoming_pdu<'a, Leafs>(
 			.await?;
 
 		if soft_fail {
-			self.services
-				.pdu_metadata
-				.mark_as_referenced(&pdu.room_id, pdu.prev_events.iter().map(AsRef::as_ref));
+			let mut batch = self.db.db.batch();
+			self.services.pdu_metadata.stage_referenced(
+				&mut batch,
+				&pdu.room_id,
+				pdu.prev_events.iter().map(AsRef::as_ref),
+			);
 
 			self.services
 				.state
-				.set_forward_extremities(&pdu.room_id, new_room_leafs, state_lock)
+				.stage_forward_extremities(&mut batch, &pdu.room_id, new_room_leafs, state_lock)
 				.await;
 
+			batch.commit();
 			return Ok(None);
 		}
 
@@ -1054,6 +1206,7 @@ pub async fn redact_pdu(
 
 		let room_version_id = self.services.state.get_room_version(&pdu.room_id).await?;
 
+		self.services.unredacted.store(&pdu);
 		pdu.redact(&room_version_id, reason)?;
 
 		let obj = utils::to_canonical_object(&pdu).map_err(|e| {
@@ -1063,6 +1216,63 @@ pub async fn redact_pdu(
 		self.replace_pdu(&pdu_id, &obj, &pdu).await
 	}
 
+	/// Locally redacts every message event sent by the user in the room,
+	/// returning the number of events redacted. State events are kept, as
+	/// redacting them would change the state of the room.
+	pub async fn redact_sender(
+		&self,
+		user_id: &UserId,
+		room_id: &RoomId,
+		reason: &str,
+	) -> Result<usize> {
+		let shortroomid = self.services.short.get_shortroomid(room_id).await?;
+
+		let reason = PduEvent {
+			event_id: EventId::new(self.services.globals.server_name()),
+			room_id: room_id.to_owned(),
+			sender: user_id.to_owned(),
+			origin: None,
+			origin_server_ts: utils::millis_since_unix_epoch()
+				.try_into()
+				.expect("Timestamp is valid js_int value"),
+			kind: TimelineEventType::RoomRedaction,
+			content: to_raw_value(
+				&RoomRedactionEventContent::new_v1().with_reason(reason.to_owned()),
+			)
+			.expect("this is valid JSON"),
+			state_key: None,
+			prev_events: vec![],
+			depth: uint!(0),
+			auth_events: vec![],
+			redacts: None,
+			unsigned: None,
+			hashes: EventHash { sha256: String::new() },
+			signatures: None,
+		};
+
+		let events: Vec<(OwnedEventId, bool)> = self
+			.pdus(None, room_id, None)
+			.ignore_err()
+			.ready_filter(|(_, pdu)| pdu.sender == user_id && pdu.state_key.is_none())
+			.map(|(_, pdu)| (pdu.event_id.clone(), pdu.is_redacted()))
+			.collect()
+			.await;
+
+		let mut redaction_count: usize = 0;
+		for (event_id, redacted) in &events {
+			if !redacted {
+				self.redact_pdu(event_id, &reason, shortroomid).await?;
+				redaction_count = redaction_count.saturating_add(1);
+			}
+
+			// The original content is not kept for moderators once the sender is
+			// erased, including that of events redacted before.
+			self.services.unredacted.remove(event_id);
+		}
+
+		Ok(redaction_count)
+	}
+
 	#[tracing::instrument(name = "backfill", level = "debug", skip(self))]
 	pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Result<()> {
 		if self
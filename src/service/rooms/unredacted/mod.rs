@@ -0,0 +1,159 @@
+//! Original content of redacted events, kept for a limited time so that
+//! moderators can review abuse after it was redacted (MSC2815).
+
+#[cfg(test)]
+mod tests;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, Result, Server, debug, implement, info,
+	matrix::pdu::PduEvent,
+	utils::{self, ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Json, Map};
+use futures::StreamExt;
+use ruma::{EventId, OwnedEventId};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue as RawJsonValue;
+use tokio::{
+	sync::Notify,
+	time::{MissedTickBehavior, interval},
+};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+	interrupt: Notify,
+}
+
+struct Data {
+	eventid_unredacted: Arc<Map>,
+}
+
+struct Services {
+	server: Arc<Server>,
+}
+
+/// The content of an event as it was before the event was redacted.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Unredacted {
+	pub content: Box<RawJsonValue>,
+
+	/// Milliseconds since the unix epoch when the event was redacted.
+	pub redacted_ts: u64,
+}
+
+/// Interval at which expired content is purged.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				eventid_unredacted: args.db["eventid_unredacted"].clone(),
+			},
+			services: Services { server: args.server.clone() },
+			interrupt: Notify::new(),
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let mut i = interval(PURGE_INTERVAL);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		loop {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			let purged = self.purge_expired().await;
+			if purged > 0 {
+				info!("Purged the original content of {purged} redacted events");
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Keeps the content of an event which is about to be redacted. Does nothing
+/// if the event was already redacted or retention is disabled.
+#[implement(Service)]
+pub fn store(&self, pdu: &PduEvent) {
+	if self.retention().is_zero() || pdu.is_redacted() {
+		return;
+	}
+
+	let unredacted = Unredacted {
+		content: pdu.content.clone(),
+		redacted_ts: utils::millis_since_unix_epoch(),
+	};
+
+	self.db
+		.eventid_unredacted
+		.raw_put(&pdu.event_id, Json(unredacted));
+}
+
+/// Returns the original content of a redacted event, unless it has expired.
+#[implement(Service)]
+pub async fn get(&self, event_id: &EventId) -> Result<Unredacted> {
+	let unredacted: Unredacted = self
+		.db
+		.eventid_unredacted
+		.get(event_id)
+		.await
+		.deserialized()?;
+
+	if self.is_expired(&unredacted) {
+		return Err!(Request(NotFound("Original content has been purged.")));
+	}
+
+	Ok(unredacted)
+}
+
+/// Forgets the original content of an event, such as when its sender is
+/// erased.
+#[implement(Service)]
+pub fn remove(&self, event_id: &EventId) { self.db.eventid_unredacted.remove(event_id); }
+
+/// Removes all content kept for longer than the retention period, returning
+/// the number of events purged.
+#[implement(Service)]
+pub async fn purge_expired(&self) -> usize {
+	let expired: Vec<OwnedEventId> = self
+		.db
+		.eventid_unredacted
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|(event_id, unredacted): (&EventId, Unredacted)| {
+			self.is_expired(&unredacted).then(|| event_id.to_owned())
+		})
+		.collect()
+		.await;
+
+	for event_id in &expired {
+		debug!(%event_id, "Purging original content of redacted event");
+		self.db.eventid_unredacted.remove(event_id);
+	}
+
+	expired.len()
+}
+
+#[implement(Service)]
+fn is_expired(&self, unredacted: &Unredacted) -> bool {
+	let retention: u64 = self.retention().as_millis().try_into().unwrap_or(u64::MAX);
+
+	utils::millis_since_unix_epoch().saturating_sub(unredacted.redacted_ts) >= retention
+}
+
+#[implement(Service)]
+fn retention(&self) -> Duration {
+	Duration::from_secs(self.services.server.config.redacted_content_retention)
+}
@@ -0,0 +1,42 @@
+use conduwuit::{
+	matrix::pdu::{EventHash, PduEvent},
+	utils,
+};
+use ruma::{event_id, events::TimelineEventType, owned_room_id, owned_user_id, uint};
+use serde_json::{json, value::to_raw_value};
+
+use crate::tests::services;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn removed_content_is_not_kept() {
+	let services = services().await;
+	let unredacted = &services.rooms.unredacted;
+	let event_id = event_id!("$message:example.com");
+
+	let pdu = PduEvent {
+		event_id: event_id.to_owned(),
+		room_id: owned_room_id!("!room:example.com"),
+		sender: owned_user_id!("@alice:example.com"),
+		origin: None,
+		origin_server_ts: utils::millis_since_unix_epoch().try_into().unwrap(),
+		kind: TimelineEventType::RoomMessage,
+		content: to_raw_value(&json!({ "msgtype": "m.text", "body": "abuse" })).unwrap(),
+		state_key: None,
+		prev_events: vec![],
+		depth: uint!(0),
+		auth_events: vec![],
+		redacts: None,
+		unsigned: None,
+		hashes: EventHash { sha256: String::new() },
+		signatures: None,
+	};
+
+	unredacted.store(&pdu);
+	let kept = unredacted.get(event_id).await.unwrap();
+	assert_eq!(kept.content.get(), pdu.content.get());
+
+	// Erasing the sender forgets the content before it expires.
+	unredacted.remove(event_id);
+	assert!(unredacted.get(event_id).await.is_err());
+	assert_eq!(unredacted.purge_expired().await, 0);
+}
@@ -11,6 +11,7 @@
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use conduwuit::{
 	Error, Result, debug, err, error,
+	matrix::pdu::room_ids_as_hashes,
 	result::LogErr,
 	trace,
 	utils::{
@@ -27,8 +28,8 @@
 	stream::FuturesUnordered,
 };
 use ruma::{
-	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedRoomId, OwnedServerName, OwnedUserId,
-	RoomId, RoomVersionId, ServerName, UInt,
+	CanonicalJsonObject, CanonicalJsonValue, MilliSecondsSinceUnixEpoch, OwnedRoomId,
+	OwnedServerName, OwnedUserId, RoomId, RoomVersionId, ServerName, UInt,
 	api::{
 		appservice::event::push_events::v1::EphemeralData,
 		federation::transactions::{
@@ -899,9 +900,21 @@ pub async fn convert_to_outgoing_federation_event(
 			.and_then(|val| RoomId::parse(val.as_str()?).ok())
 		{
 			match self.services.state.get_room_version(room_id).await {
-				| Ok(room_version_id) => match room_version_id {
-					| RoomVersionId::V1 | RoomVersionId::V2 => {},
-					| _ => _ = pdu_json.remove("event_id"),
+				| Ok(room_version_id) => {
+					match room_version_id {
+						| RoomVersionId::V1 | RoomVersionId::V2 => {},
+						| _ => _ = pdu_json.remove("event_id"),
+					}
+
+					// the room_id of the create event is its hash in room v12 and above
+					let is_create = pdu_json
+						.get("type")
+						.and_then(CanonicalJsonValue::as_str)
+						.is_some_and(|kind| kind == "m.room.create");
+
+					if is_create && room_ids_as_hashes(&room_version_id) {
+						pdu_json.remove("room_id");
+					}
 				},
 				| Err(_) => _ = pdu_json.remove("event_id"),
 			}
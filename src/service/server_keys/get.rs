@@ -1,6 +1,6 @@
 use std::borrow::Borrow;
 
-use conduwuit::{Err, Result, implement};
+use conduwuit::{Err, Result, implement, info::room_version::event_rules_version};
 use ruma::{
 	CanonicalJsonObject, RoomVersionId, ServerName, ServerSigningKeyId,
 	api::federation::discovery::VerifyKey,
@@ -16,7 +16,7 @@ pub async fn get_event_keys(
 ) -> Result<PubKeyMap> {
 	use ruma::signatures::required_keys;
 
-	let required = match required_keys(object, version) {
+	let required = match required_keys(object, event_rules_version(version)) {
 		| Ok(required) => required,
 		| Err(e) => {
 			return Err!(BadServerResponse("Failed to determine keys required to verify: {e}"));
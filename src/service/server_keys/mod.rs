@@ -9,6 +9,7 @@
 
 use conduwuit::{
 	Result, Server, implement,
+	info::room_version::event_rules_version,
 	utils::{IterStream, timepoint_from_now},
 };
 use database::{Deserialized, Json, Map};
@@ -119,7 +120,7 @@ pub async fn required_keys_exist(
 ) -> bool {
 	use ruma::signatures::required_keys;
 
-	let Ok(required_keys) = required_keys(object, version) else {
+	let Ok(required_keys) = required_keys(object, event_rules_version(version)) else {
 		return false;
 	};
 
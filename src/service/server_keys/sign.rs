@@ -1,4 +1,4 @@
-use conduwuit::{Result, implement};
+use conduwuit::{Result, implement, info::room_version::event_rules_version};
 use ruma::{CanonicalJsonObject, RoomVersionId};
 
 #[implement(super::Service)]
@@ -18,5 +18,6 @@ pub fn hash_and_sign_event(
 	use ruma::signatures::hash_and_sign_event;
 
 	let server_name = self.services.globals.server_name().as_str();
+	let room_version = event_rules_version(room_version);
 	hash_and_sign_event(server_name, self.keypair(), object, room_version).map_err(Into::into)
 }
@@ -1,4 +1,8 @@
-use conduwuit::{Err, Result, implement, pdu::gen_event_id_canonical_json};
+use conduwuit::{
+	Err, Result, implement,
+	info::room_version::event_rules_version,
+	pdu::{add_create_room_id, gen_event_id_canonical_json},
+};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, RoomVersionId, signatures::Verified,
 };
@@ -17,6 +21,7 @@ pub async fn validate_and_add_event_id(
 		)));
 	}
 
+	add_create_room_id(&mut value, &event_id, room_version)?;
 	value.insert("event_id".into(), CanonicalJsonValue::String(event_id.as_str().into()));
 
 	Ok((event_id, value))
@@ -41,6 +46,7 @@ pub async fn validate_and_add_event_id_no_fetch(
 		)));
 	}
 
+	add_create_room_id(&mut value, &event_id, room_version)?;
 	value.insert("event_id".into(), CanonicalJsonValue::String(event_id.as_str().into()));
 
 	Ok((event_id, value))
@@ -52,7 +58,7 @@ pub async fn verify_event(
 	event: &CanonicalJsonObject,
 	room_version: Option<&RoomVersionId>,
 ) -> Result<Verified> {
-	let room_version = room_version.unwrap_or(&RoomVersionId::V11);
+	let room_version = room_version.map_or(&RoomVersionId::V11, event_rules_version);
 	let keys = self.get_event_keys(event, room_version).await?;
 	ruma::signatures::verify_event(&keys, event, room_version).map_err(Into::into)
 }
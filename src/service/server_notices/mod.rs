@@ -0,0 +1,326 @@
+//! Server notices: messages from the server to individual users.
+//!
+//! Every user receiving a notice gets a room of their own with the server
+//! notices system user, tagged `m.server_notice`. The room is created on the
+//! first notice, and again once the user left it.
+
+#[cfg(test)]
+mod tests;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use conduwuit::{
+	Err, Result, RoomVersion, Server, debug_info, implement,
+	pdu::{PduBuilder, room_ids_as_hashes},
+	utils::{MutexMap, ReadyExt},
+};
+use database::{Deserialized, Map};
+use futures::{Stream, StreamExt};
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, RoomVersionId,
+	UserId,
+	events::{
+		RoomAccountDataEventType,
+		room::{
+			create::RoomCreateEventContent,
+			guest_access::{GuestAccess, RoomGuestAccessEventContent},
+			history_visibility::{HistoryVisibility, RoomHistoryVisibilityEventContent},
+			join_rules::{JoinRule, RoomJoinRulesEventContent},
+			member::{MembershipState, RoomMemberEventContent},
+			message::RoomMessageEventContent,
+			name::RoomNameEventContent,
+			power_levels::RoomPowerLevelsEventContent,
+		},
+		tag::{TagEvent, TagEventContent, TagInfo},
+	},
+};
+
+use crate::{Dep, account_data, globals, rooms, users};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+	system_user: OwnedUserId,
+
+	/// Serializes sending per recipient, so concurrent notices to a user
+	/// without a room do not each create one.
+	mutex: MutexMap<OwnedUserId, ()>,
+}
+
+struct Data {
+	global: Arc<Map>,
+	userid_servernoticeroomid: Arc<Map>,
+}
+
+struct Services {
+	server: Arc<Server>,
+	account_data: Dep<account_data::Service>,
+	globals: Dep<globals::Service>,
+	short: Dep<rooms::short::Service>,
+	state: Dep<rooms::state::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+	users: Dep<users::Service>,
+}
+
+const SERVER_NOTICE_TAG: &str = "m.server_notice";
+
+/// Key in the `global` map recording which account was created as the system
+/// user, so an existing account of the same name is never taken over.
+const SYSTEM_USER_KEY: &[u8] = b"server_notices_user";
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let system_user = UserId::parse_with_server_name(
+			config.server_notices_localpart.as_str(),
+			&config.server_name,
+		)?;
+
+		Ok(Arc::new(Self {
+			db: Data {
+				global: args.db["global"].clone(),
+				userid_servernoticeroomid: args.db["userid_servernoticeroomid"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				account_data: args.depend::<account_data::Service>("account_data"),
+				globals: args.depend::<globals::Service>("globals"),
+				short: args.depend::<rooms::short::Service>("rooms::short"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+				users: args.depend::<users::Service>("users"),
+			},
+			system_user,
+			mutex: MutexMap::new(),
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// The user server notices are sent by.
+#[implement(Service)]
+#[inline]
+pub fn system_user(&self) -> &UserId { &self.system_user }
+
+/// Whether `user_id` is the server notices system user. Its name is reserved
+/// and cannot be registered.
+#[implement(Service)]
+#[inline]
+pub fn is_system_user(&self, user_id: &UserId) -> bool { user_id == self.system_user }
+
+/// Creates the system user, or checks that an existing account of its name was
+/// created as the system user.
+#[implement(Service)]
+async fn ensure_system_user(&self) -> Result {
+	let system_user = self.system_user.as_ref();
+	if self.services.users.exists(system_user).await {
+		let created: Result<OwnedUserId> =
+			self.db.global.get(SYSTEM_USER_KEY).await.deserialized();
+
+		if !created.is_ok_and(|created| created == system_user) {
+			return Err!(
+				"Cannot send server notices as {system_user}: the account already exists and is \
+				 not the server notices user. Change `server_notices_localpart`."
+			);
+		}
+
+		return Ok(());
+	}
+
+	self.services.users.create(system_user, None)?;
+	self.services.users.set_displayname(
+		system_user,
+		Some(
+			self.services
+				.server
+				.config
+				.server_notices_displayname
+				.clone(),
+		),
+	);
+
+	self.db
+		.global
+		.insert(SYSTEM_USER_KEY, system_user.as_bytes());
+
+	Ok(())
+}
+
+/// Sends a notice to a local user. Notices about limits or policies should use
+/// the `m.server_notice` msgtype so clients can present them accordingly.
+#[implement(Service)]
+pub async fn send(
+	&self,
+	user_id: &UserId,
+	content: RoomMessageEventContent,
+) -> Result<OwnedEventId> {
+	let user_lock = self.mutex.lock(user_id).await;
+	let room_id = match self.room_id(user_id).await {
+		| Some(room_id) => room_id,
+		| None => self.create_room(user_id).await?,
+	};
+
+	drop(user_lock);
+
+	let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+	self.services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder::timeline(&content),
+			&self.system_user,
+			&room_id,
+			&state_lock,
+		)
+		.await
+}
+
+/// Local users who can receive notices: everyone but deactivated accounts and
+/// the server's own users.
+#[implement(Service)]
+pub fn recipients(&self) -> impl Stream<Item = &UserId> + Send + '_ {
+	self.services
+		.users
+		.list_local_users()
+		.ready_filter(|user_id| {
+			*user_id != self.system_user && *user_id != self.services.globals.server_user
+		})
+		.filter_map(|user_id| async move {
+			self.services
+				.users
+				.is_active_local(user_id)
+				.await
+				.then_some(user_id)
+		})
+}
+
+/// The notices room of a user, unless they left it.
+#[implement(Service)]
+pub async fn room_id(&self, user_id: &UserId) -> Option<OwnedRoomId> {
+	let room_id: OwnedRoomId = self
+		.db
+		.userid_servernoticeroomid
+		.get(user_id)
+		.await
+		.deserialized()
+		.ok()?;
+
+	let state_cache = &self.services.state_cache;
+	let present = state_cache.is_joined(user_id, &room_id).await
+		|| state_cache.is_invited(user_id, &room_id).await;
+
+	present.then_some(room_id)
+}
+
+#[implement(Service)]
+async fn create_room(&self, user_id: &UserId) -> Result<OwnedRoomId> {
+	let config = &self.services.server.config;
+	let system_user = self.system_user.as_ref();
+	self.ensure_system_user().await?;
+
+	let room_version = &config.default_room_version;
+	let create_content = {
+		use RoomVersionId::*;
+		match room_version {
+			| V1 | V2 | V3 | V4 | V5 | V6 | V7 | V8 | V9 | V10 =>
+				RoomCreateEventContent::new_v1(system_user.into()),
+			| _ => RoomCreateEventContent::new_v11(),
+		}
+	};
+
+	let create_event = PduBuilder {
+		timestamp: Some(MilliSecondsSinceUnixEpoch::now()),
+		..PduBuilder::state(String::new(), &RoomCreateEventContent {
+			federate: false,
+			predecessor: None,
+			room_version: room_version.clone(),
+			..create_content
+		})
+	};
+
+	let room_id = if room_ids_as_hashes(room_version) {
+		self.services
+			.timeline
+			.create_room_id(&create_event, system_user)
+			.await?
+	} else {
+		RoomId::new(self.services.globals.server_name())
+	};
+
+	let _short_id = self
+		.services
+		.short
+		.get_or_create_shortroomid(&room_id)
+		.await;
+
+	let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+	// The creator is privileged already in some room versions
+	let mut users = BTreeMap::new();
+	if !RoomVersion::new(room_version)?.explicitly_privilege_room_creators {
+		users.insert(system_user.into(), 100.into());
+	}
+	let member = |membership| RoomMemberEventContent {
+		displayname: Some(config.server_notices_displayname.clone()),
+		..RoomMemberEventContent::new(membership)
+	};
+
+	let events = [
+		create_event,
+		PduBuilder::state(system_user.to_string(), &member(MembershipState::Join)),
+		// Only the system user may post.
+		PduBuilder::state(String::new(), &RoomPowerLevelsEventContent {
+			users,
+			events_default: 100.into(),
+			..Default::default()
+		}),
+		PduBuilder::state(String::new(), &RoomJoinRulesEventContent::new(JoinRule::Invite)),
+		PduBuilder::state(
+			String::new(),
+			&RoomHistoryVisibilityEventContent::new(HistoryVisibility::Shared),
+		),
+		PduBuilder::state(
+			String::new(),
+			&RoomGuestAccessEventContent::new(GuestAccess::Forbidden),
+		),
+		PduBuilder::state(
+			String::new(),
+			&RoomNameEventContent::new(config.server_notices_room_name.clone()),
+		),
+		PduBuilder::state(
+			user_id.to_string(),
+			&RoomMemberEventContent::new(MembershipState::Invite),
+		),
+	];
+
+	for event in events {
+		self.services
+			.timeline
+			.build_and_append_pdu(event, system_user, &room_id, &state_lock)
+			.await?;
+	}
+
+	drop(state_lock);
+
+	let tags = BTreeMap::from_iter([(SERVER_NOTICE_TAG.into(), TagInfo::new())]);
+	self.services
+		.account_data
+		.update(
+			Some(&room_id),
+			user_id,
+			RoomAccountDataEventType::Tag,
+			&serde_json::to_value(TagEvent { content: TagEventContent { tags } })
+				.expect("to json value always works"),
+		)
+		.await?;
+
+	self.db
+		.userid_servernoticeroomid
+		.insert(user_id, room_id.as_bytes());
+
+	debug_info!("Created server notices room {room_id} for {user_id}");
+	Ok(room_id)
+}
@@ -0,0 +1,46 @@
+use futures::{StreamExt, join};
+use ruma::{events::room::message::RoomMessageEventContent, user_id};
+
+use crate::tests::services;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn existing_account_is_not_taken_over() {
+	let services = services().await;
+	let notices = user_id!("@notices:example.com");
+	let alice = user_id!("@alice:example.com");
+
+	assert!(services.server_notices.is_system_user(notices));
+	services.users.create(notices, Some("hunter2")).unwrap();
+	services.users.create(alice, Some("hunter2")).unwrap();
+
+	let content = RoomMessageEventContent::text_plain("hello");
+	assert!(services.server_notices.send(alice, content).await.is_err());
+	assert!(services.server_notices.room_id(alice).await.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_notices_share_a_room() {
+	let services = services().await;
+	let alice = user_id!("@alice:example.com");
+	services.users.create(alice, Some("hunter2")).unwrap();
+
+	let first = RoomMessageEventContent::text_plain("first");
+	let second = RoomMessageEventContent::text_plain("second");
+	let (first, second) = join!(
+		services.server_notices.send(alice, first),
+		services.server_notices.send(alice, second),
+	);
+
+	first.unwrap();
+	second.unwrap();
+	assert!(services.server_notices.room_id(alice).await.is_some());
+	assert_eq!(
+		services
+			.rooms
+			.state_cache
+			.rooms_invited(alice)
+			.count()
+			.await,
+		1
+	);
+}
@@ -10,11 +10,13 @@
 use tokio::sync::Mutex;
 
 use crate::{
-	account_data, admin, appservice, client, config, emergency, federation, globals, key_backups,
+	account_data, admin, appservice, client, config, emergency, export, federation, globals,
+	key_backups,
 	manager::Manager,
-	media, presence, pusher, resolver, rooms, sending, server_keys, service,
+	media, oauth, policy, presence, pusher, ratelimit, rendezvous, reports, resolver, rooms,
+	sending, server_keys, server_notices, service,
 	service::{Args, Map, Service},
-	sync, transaction_ids, uiaa, updates, users,
+	sync, transaction_ids, uiaa, updates, user_directory, users,
 };
 
 pub struct Services {
@@ -24,20 +26,28 @@ pub struct Services {
 	pub config: Arc<config::Service>,
 	pub client: Arc<client::Service>,
 	pub emergency: Arc<emergency::Service>,
+	pub export: Arc<export::Service>,
 	pub globals: Arc<globals::Service>,
 	pub key_backups: Arc<key_backups::Service>,
 	pub media: Arc<media::Service>,
+	pub oauth: Arc<oauth::Service>,
+	pub policy: Arc<policy::Service>,
 	pub presence: Arc<presence::Service>,
 	pub pusher: Arc<pusher::Service>,
+	pub ratelimit: Arc<ratelimit::Service>,
+	pub rendezvous: Arc<rendezvous::Service>,
+	pub reports: Arc<reports::Service>,
 	pub resolver: Arc<resolver::Service>,
 	pub rooms: rooms::Service,
 	pub federation: Arc<federation::Service>,
 	pub sending: Arc<sending::Service>,
 	pub server_keys: Arc<server_keys::Service>,
+	pub server_notices: Arc<server_notices::Service>,
 	pub sync: Arc<sync::Service>,
 	pub transaction_ids: Arc<transaction_ids::Service>,
 	pub uiaa: Arc<uiaa::Service>,
 	pub updates: Arc<updates::Service>,
+	pub user_directory: Arc<user_directory::Service>,
 	pub users: Arc<users::Service>,
 
 	manager: Mutex<Option<Arc<Manager>>>,
@@ -71,11 +81,17 @@ macro_rules! build {
 			client: build!(client::Service),
 			config: build!(config::Service),
 			emergency: build!(emergency::Service),
+			export: build!(export::Service),
 			globals: build!(globals::Service),
 			key_backups: build!(key_backups::Service),
 			media: build!(media::Service),
+			oauth: build!(oauth::Service),
+			policy: build!(policy::Service),
 			presence: build!(presence::Service),
 			pusher: build!(pusher::Service),
+			ratelimit: build!(ratelimit::Service),
+			rendezvous: build!(rendezvous::Service),
+			reports: build!(reports::Service),
 			rooms: rooms::Service {
 				alias: build!(rooms::alias::Service),
 				auth_chain: build!(rooms::auth_chain::Service),
@@ -89,6 +105,7 @@ macro_rules! build {
 				search: build!(rooms::search::Service),
 				short: build!(rooms::short::Service),
 				spaces: build!(rooms::spaces::Service),
+				spam_check: build!(rooms::spam_check::Service),
 				state: build!(rooms::state::Service),
 				state_accessor: build!(rooms::state_accessor::Service),
 				state_cache: build!(rooms::state_cache::Service),
@@ -96,15 +113,18 @@ macro_rules! build {
 				threads: build!(rooms::threads::Service),
 				timeline: build!(rooms::timeline::Service),
 				typing: build!(rooms::typing::Service),
+				unredacted: build!(rooms::unredacted::Service),
 				user: build!(rooms::user::Service),
 			},
 			federation: build!(federation::Service),
 			sending: build!(sending::Service),
 			server_keys: build!(server_keys::Service),
+			server_notices: build!(server_notices::Service),
 			sync: build!(sync::Service),
 			transaction_ids: build!(transaction_ids::Service),
 			uiaa: build!(uiaa::Service),
 			updates: build!(updates::Service),
+			user_directory: build!(user_directory::Service),
 			users: build!(users::Service),
 
 			manager: Mutex::new(None),
@@ -0,0 +1,83 @@
+//! Services backed by the in-memory database engine, for tests which need more
+//! than a single service in isolation.
+
+use std::{env, sync::Arc};
+
+use conduwuit::{
+	Server,
+	config::{Config, Figment},
+	log::{Log, LogLevelReloadHandles, capture},
+};
+use ruma::user_id;
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+	task::JoinHandle,
+};
+use url::Url;
+
+use crate::Services;
+
+/// Builds all services on an empty in-memory database. `config` is merged over
+/// the minimal configuration needed for the services to build.
+pub(crate) async fn services_with(config: Figment) -> Arc<Services> {
+	let database_path = env::temp_dir().join("conduwuit-service-tests");
+	let config = Figment::new()
+		.merge(("server_name", "example.com"))
+		.merge(("database_path", database_path))
+		.merge(("database_backend", "memory"))
+		.merge(config);
+
+	let config = Config::new(&config).expect("valid test configuration");
+	let log = Log {
+		reload: LogLevelReloadHandles::default(),
+		capture: Arc::new(capture::State::new()),
+	};
+
+	let server = Server::new(config, Some(tokio::runtime::Handle::current()), log);
+	Services::build(Arc::new(server))
+		.await
+		.expect("services build on the memory engine")
+}
+
+pub(crate) async fn services() -> Arc<Services> { services_with(Figment::new()).await }
+
+/// Serves a single HTTP response at `path` of a local mock server and returns
+/// the raw request it received.
+pub(crate) async fn mock_server(
+	path: &str,
+	status: &'static str,
+	body: &'static str,
+) -> (Url, JoinHandle<String>) {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}{path}", listener.local_addr().unwrap());
+	let handle = tokio::spawn(async move {
+		let (mut stream, _) = listener.accept().await.unwrap();
+		let mut buf = vec![0_u8; 4096];
+		let len = stream.read(&mut buf).await.unwrap();
+		let response = format!(
+			"HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: \
+			 {}\r\nconnection: close\r\n\r\n{body}",
+			body.len()
+		);
+
+		stream.write_all(response.as_bytes()).await.unwrap();
+		String::from_utf8_lossy(&buf[..len]).into_owned()
+	});
+
+	(url.parse().unwrap(), handle)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn users_on_memory_engine() {
+	let services = services().await;
+	let alice = user_id!("@alice:example.com");
+
+	assert!(!services.users.exists(alice).await);
+	services.users.create(alice, Some("hunter2")).unwrap();
+	assert!(services.users.exists(alice).await);
+	assert!(!services.users.is_deactivated(alice).await.unwrap());
+
+	services.users.deactivate_account(alice).await.unwrap();
+	assert!(services.users.is_deactivated(alice).await.unwrap());
+}
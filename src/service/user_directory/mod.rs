@@ -0,0 +1,345 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::{BTreeSet, HashSet},
+	sync::Arc,
+};
+
+use conduwuit::{
+	Result, Server, debug, implement,
+	utils::{
+		ReadyExt,
+		stream::{BroadbandExt, TryIgnore},
+	},
+};
+use database::{Map, SEP};
+use futures::{FutureExt, StreamExt, future::ready};
+use ruma::{OwnedUserId, RoomId, UserId, events::room::join_rules::JoinRule};
+
+use crate::{Dep, globals, rooms, users};
+
+/// Index of users for the user directory. Every user is indexed by the tokens
+/// of their localpart and display name, so that searches are prefix scans
+/// rather than walks over all users. Server names are not indexed, as nearly
+/// every user on a server would share their tokens; they are only matched
+/// against the candidates found through the other tokens.
+pub struct Service {
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	tokenuserid_directory: Arc<Map>,
+	userid_directory: Arc<Map>,
+	userid_directorypublic: Arc<Map>,
+}
+
+struct Services {
+	server: Arc<Server>,
+	globals: Dep<globals::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	users: Dep<users::Service>,
+}
+
+/// Ordering of search results; lower ranks are listed first.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Rank {
+	Exact,
+	SharesRoom,
+	Other,
+}
+
+/// Maximum number of matching users ranked for one search. Very short search
+/// terms match most of the index; those are narrowed down by typing more.
+const CANDIDATES_MAX: usize = 2048;
+
+const WORD_MAX_LEN: usize = 50;
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				tokenuserid_directory: args.db["tokenuserid_directory"].clone(),
+				userid_directory: args.db["userid_directory"].clone(),
+				userid_directorypublic: args.db["userid_directorypublic"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				globals: args.depend::<globals::Service>("globals"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				users: args.depend::<users::Service>("users"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Indexes the user under their localpart and display name, replacing the
+/// tokens of their previous display name.
+#[implement(Service)]
+pub fn update_profile(&self, user_id: &UserId, displayname: Option<&str>) {
+	let displayname = displayname.unwrap_or_default();
+	let tokens: BTreeSet<String> = user_tokens(user_id, displayname).collect();
+
+	if let Ok(previous) = self.db.userid_directory.get_blocking(user_id) {
+		let previous = String::from_utf8_lossy(&previous);
+		for token in user_tokens(user_id, &previous).filter(|token| !tokens.contains(token)) {
+			debug!(%user_id, ?token, "Removing stale user directory token");
+			self.db
+				.tokenuserid_directory
+				.remove(&token_key(&token, user_id));
+		}
+	}
+
+	self.db.userid_directory.insert(user_id, displayname);
+	for token in &tokens {
+		self.db
+			.tokenuserid_directory
+			.insert(&token_key(token, user_id), []);
+	}
+}
+
+/// Updates the directory after the user's membership in a room changed. Users
+/// seen for the first time are indexed; remote users are indexed under the
+/// display name from their membership event, as we do not otherwise track
+/// their profiles.
+#[implement(Service)]
+pub async fn update_membership(&self, user_id: &UserId, displayname: Option<&str>) {
+	let is_local = self.services.globals.user_is_local(user_id);
+	let indexed: Option<Vec<u8>> = self
+		.db
+		.userid_directory
+		.get(user_id)
+		.await
+		.ok()
+		.map(Into::into);
+
+	match (is_local, indexed) {
+		| (true, None) => {
+			let displayname = self.services.users.displayname(user_id).await.ok();
+			self.update_profile(user_id, displayname.as_deref());
+		},
+		| (false, None) => self.update_profile(user_id, displayname),
+		| (false, Some(name))
+			if displayname.is_some_and(|displayname| displayname.as_bytes() != name) =>
+			self.update_profile(user_id, displayname),
+		| _ => {},
+	}
+
+	self.update_visibility(user_id).await;
+}
+
+/// Updates the visibility of all members of a room, e.g. after its join rules
+/// changed.
+#[implement(Service)]
+pub async fn update_room(&self, room_id: &RoomId) {
+	let members: Vec<OwnedUserId> = self
+		.services
+		.state_cache
+		.room_members(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for user_id in members {
+		self.update_visibility(&user_id).await;
+	}
+}
+
+/// Records whether the user is joined to any public room; such users are
+/// visible in the directory to everyone.
+#[implement(Service)]
+pub async fn update_visibility(&self, user_id: &UserId) {
+	let in_public_room = self
+		.services
+		.state_cache
+		.rooms_joined(user_id)
+		.map(ToOwned::to_owned)
+		.any(|room_id| async move {
+			self.services
+				.state_accessor
+				.get_join_rules(&room_id)
+				.map(|rule| matches!(rule, JoinRule::Public))
+				.await
+		})
+		.await;
+
+	if in_public_room {
+		self.db.userid_directorypublic.insert(user_id, []);
+	} else {
+		self.db.userid_directorypublic.remove(user_id);
+	}
+}
+
+#[implement(Service)]
+pub async fn is_public(&self, user_id: &UserId) -> bool {
+	self.db.userid_directorypublic.exists(user_id).await.is_ok()
+}
+
+/// Indexes all known users from scratch.
+#[implement(Service)]
+pub async fn rebuild(&self) -> usize {
+	let users: Vec<OwnedUserId> = self
+		.services
+		.users
+		.stream()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for user_id in &users {
+		let displayname = self.services.users.displayname(user_id).await.ok();
+		self.update_profile(user_id, displayname.as_deref());
+		self.update_visibility(user_id).await;
+	}
+
+	users.len()
+}
+
+/// Searches the directory on behalf of `sender_user`. Returns up to `limit`
+/// users, ranked by exact matches first, then users sharing a room with the
+/// sender, then everyone else; and whether there were more results.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn search(
+	&self,
+	sender_user: &UserId,
+	search_term: &str,
+	limit: usize,
+) -> (Vec<OwnedUserId>, bool) {
+	// Anything after a colon is taken as (part of) a server name, which is not
+	// indexed and only matched against the candidates' user IDs.
+	let exact = search_term.trim().trim_start_matches('@').to_lowercase();
+	let (name, server) = exact.split_once(':').unwrap_or((exact.as_str(), ""));
+	let terms: BTreeSet<String> = tokenize(name).collect();
+	let server_terms: BTreeSet<String> = tokenize(server).collect();
+
+	// The longest term is the most selective; the others are checked against
+	// each candidate's tokens below.
+	let Some(longest) = terms.iter().max_by_key(|term| term.len()) else {
+		return (Vec::new(), false);
+	};
+
+	// A user is listed once for every token matching the longest term.
+	let mut candidates = HashSet::new();
+	let search_all_users = self.services.server.config.user_directory_search_all_users;
+	let mut ranked: Vec<(Rank, OwnedUserId)> = self
+		.db
+		.tokenuserid_directory
+		.raw_keys_prefix(longest.as_bytes())
+		.ignore_err()
+		.ready_filter_map(parse_token_key)
+		.filter(move |user_id| ready(candidates.insert(user_id.clone())))
+		.broad_filter_map(|user_id| {
+			let (terms, server_terms, exact) = (&terms, &server_terms, &exact);
+			async move {
+				let displayname: String = self
+					.db
+					.userid_directory
+					.get(&user_id)
+					.await
+					.map(|name| String::from_utf8_lossy(&name).into_owned())
+					.unwrap_or_default();
+
+				let tokens: BTreeSet<String> = user_tokens(&user_id, &displayname).collect();
+				let server_tokens: BTreeSet<String> =
+					tokenize(user_id.server_name().as_str()).collect();
+
+				let matches = |terms: &BTreeSet<String>, tokens: &BTreeSet<String>| {
+					terms
+						.iter()
+						.all(|term| tokens.iter().any(|token| token.starts_with(term.as_str())))
+				};
+
+				if !matches(terms, &tokens) || !matches(server_terms, &server_tokens) {
+					return None;
+				}
+
+				let is_local = self.services.globals.user_is_local(&user_id);
+				if is_local
+					&& self
+						.services
+						.users
+						.is_deactivated(&user_id)
+						.await
+						.unwrap_or(true)
+				{
+					return None;
+				}
+
+				let shares_room = self
+					.services
+					.state_cache
+					.user_sees_user(sender_user, &user_id)
+					.await;
+
+				let visible = shares_room
+					|| (is_local && search_all_users)
+					|| self.is_public(&user_id).await;
+
+				if !visible {
+					return None;
+				}
+
+				let is_exact = user_id.localpart().to_lowercase() == *exact
+					|| user_id.as_str().to_lowercase().trim_start_matches('@') == *exact
+					|| displayname.to_lowercase() == *exact;
+
+				let rank = match (is_exact, shares_room) {
+					| (true, _) => Rank::Exact,
+					| (false, true) => Rank::SharesRoom,
+					| (false, false) => Rank::Other,
+				};
+
+				Some((rank, user_id))
+			}
+		})
+		.take(CANDIDATES_MAX.saturating_add(1))
+		.collect()
+		.await;
+
+	ranked.sort_unstable();
+
+	let limit = limit.min(CANDIDATES_MAX);
+	let limited = ranked.len() > limit;
+	let results = ranked
+		.into_iter()
+		.take(limit)
+		.map(|(_, user_id)| user_id)
+		.collect();
+
+	(results, limited)
+}
+
+fn user_tokens<'a>(
+	user_id: &'a UserId,
+	displayname: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+	tokenize(user_id.localpart()).chain(tokenize(displayname))
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + Send + '_ {
+	text.split_terminator(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.filter(|word| word.len() <= WORD_MAX_LEN)
+		.map(str::to_lowercase)
+}
+
+fn token_key(token: &str, user_id: &UserId) -> Vec<u8> {
+	let mut key = token.as_bytes().to_vec();
+	key.push(SEP);
+	key.extend_from_slice(user_id.as_bytes());
+	key
+}
+
+fn parse_token_key(key: &[u8]) -> Option<OwnedUserId> {
+	let pos = key.iter().position(|&byte| byte == SEP)?;
+	let user_id = std::str::from_utf8(&key[pos.saturating_add(1)..]).ok()?;
+
+	UserId::parse(user_id).ok()
+}
@@ -0,0 +1,68 @@
+use conduwuit::config::Figment;
+use ruma::user_id;
+
+use crate::tests::services_with;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_tokens() {
+	let config = Figment::new().merge(("user_directory_search_all_users", true));
+	let services = services_with(config).await;
+	let directory = &services.user_directory;
+	let alice = user_id!("@alice:example.com");
+	let bob = user_id!("@bob:example.com");
+
+	for user_id in [alice, bob] {
+		services.users.create(user_id, Some("hunter2")).unwrap();
+	}
+
+	directory.update_profile(bob, Some("Robert Tables"));
+	assert_eq!(directory.search(alice, "tab", 10).await.0, [bob]);
+	assert_eq!(directory.search(alice, "robert tables", 10).await.0, [bob]);
+
+	// The server name is matched but not indexed.
+	assert_eq!(directory.search(alice, "@bob:example.com", 10).await.0, [bob]);
+	assert!(
+		directory
+			.search(alice, "bob:other.org", 10)
+			.await
+			.0
+			.is_empty()
+	);
+	assert!(directory.search(alice, "example", 10).await.0.is_empty());
+
+	// Renaming removes the tokens of the previous display name.
+	directory.update_profile(bob, Some("Bobby"));
+	assert!(directory.search(alice, "tables", 10).await.0.is_empty());
+	assert_eq!(directory.search(alice, "bobby", 10).await.0, [bob]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_limits_visible_users() {
+	let config = Figment::new().merge(("user_directory_search_all_users", true));
+	let services = services_with(config).await;
+	let directory = &services.user_directory;
+	let alice = user_id!("@alice:example.com");
+	let local = [user_id!("@bob:example.com"), user_id!("@carol:example.com")];
+	let remote = [user_id!("@aaron:other.org"), user_id!("@abby:other.org")];
+
+	services.users.create(alice, Some("hunter2")).unwrap();
+	for user_id in local.iter().chain(&remote) {
+		services
+			.users
+			.create(user_id, services.globals.user_is_local(user_id).then_some("hunter2"))
+			.unwrap();
+
+		directory.update_profile(user_id, Some("Tables"));
+	}
+
+	// Remote users sharing no room are not visible, so they neither take the
+	// place of visible users nor count towards the limit.
+	let (results, limited) = directory.search(alice, "tables", 2).await;
+	assert_eq!(results.len(), 2);
+	assert!(results.iter().all(|user_id| local.contains(&&**user_id)));
+	assert!(!limited);
+
+	let (results, limited) = directory.search(alice, "tables", 1).await;
+	assert_eq!(results.len(), 1);
+	assert!(limited);
+}
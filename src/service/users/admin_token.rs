@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use conduwuit::{
+	Result, debug, implement,
+	utils::{self, ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Ignore, Interfix, Json};
+use futures::StreamExt;
+use ruma::{
+	DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId,
+	api::client::device::Device,
+};
+use serde::{Deserialize, Serialize};
+
+/// Length of the device IDs of admin-issued sessions.
+const DEVICE_ID_LENGTH: usize = 10;
+
+/// Length of the access tokens of admin-issued sessions.
+const TOKEN_LENGTH: usize = 32;
+
+/// Marks a device whose access token was issued by a server admin to act as
+/// the user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AdminIssuedToken {
+	/// The server admin who issued the token.
+	pub issued_by: OwnedUserId,
+
+	/// Time at which the token expires, in milliseconds since the unix epoch.
+	pub expires_at: u64,
+}
+
+impl AdminIssuedToken {
+	#[inline]
+	#[must_use]
+	pub fn is_expired(&self) -> bool { self.expires_at <= utils::millis_since_unix_epoch() }
+}
+
+/// Creates a new device of the user with an access token for a server admin,
+/// valid for `ttl`. Unlike `create_device` this does not change the user's
+/// device list, so their other sessions are not told about a new login.
+/// Returns the device ID and access token.
+#[implement(super::Service)]
+pub async fn issue_admin_token(
+	&self,
+	user_id: &UserId,
+	issued_by: &UserId,
+	ttl: Duration,
+) -> Result<(OwnedDeviceId, String)> {
+	let device_id: OwnedDeviceId = utils::random_string(DEVICE_ID_LENGTH).into();
+	let token = utils::random_string(TOKEN_LENGTH);
+	let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+	let marker = AdminIssuedToken {
+		issued_by: issued_by.to_owned(),
+		expires_at: utils::millis_since_unix_epoch().saturating_add(ttl),
+	};
+
+	let key = (user_id, &*device_id);
+	let device = Device {
+		device_id: device_id.clone(),
+		display_name: Some(format!("Issued by server admin {issued_by}")),
+		last_seen_ip: None,
+		last_seen_ts: Some(MilliSecondsSinceUnixEpoch::now()),
+	};
+
+	self.db.userdeviceid_metadata.put(key, Json(device));
+	self.db.userdeviceid_adminissued.put(key, Json(marker));
+	self.set_token(user_id, &device_id, &token).await?;
+
+	Ok((device_id, token))
+}
+
+/// Returns the marker of a device created by `issue_admin_token`, or an
+/// error if the device was not issued by a server admin.
+#[implement(super::Service)]
+pub async fn admin_issued_token(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> Result<AdminIssuedToken> {
+	let key = (user_id, device_id);
+	self.db
+		.userdeviceid_adminissued
+		.qry(&key)
+		.await
+		.deserialized()
+}
+
+/// Removes all devices of the user which were issued by a server admin,
+/// returning their number.
+#[implement(super::Service)]
+pub async fn revoke_admin_tokens(&self, user_id: &UserId) -> usize {
+	let prefix = (user_id, Interfix);
+	let device_ids: Vec<OwnedDeviceId> = self
+		.db
+		.userdeviceid_adminissued
+		.keys_prefix(&prefix)
+		.ignore_err()
+		.map(|(_, device_id): (Ignore, &DeviceId)| device_id.to_owned())
+		.collect()
+		.await;
+
+	for device_id in &device_ids {
+		self.remove_device(user_id, device_id).await;
+	}
+
+	device_ids.len()
+}
+
+/// Removes the admin-issued devices of all users whose token has expired,
+/// returning their number.
+#[implement(super::Service)]
+pub async fn remove_expired_admin_tokens(&self) -> usize {
+	type KeyVal<'a> = ((&'a UserId, &'a DeviceId), AdminIssuedToken);
+
+	let expired: Vec<(OwnedUserId, OwnedDeviceId)> = self
+		.db
+		.userdeviceid_adminissued
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|((user_id, device_id), marker): KeyVal<'_>| {
+			marker
+				.is_expired()
+				.then(|| (user_id.to_owned(), device_id.to_owned()))
+		})
+		.collect()
+		.await;
+
+	for (user_id, device_id) in &expired {
+		debug!(%user_id, %device_id, "Removing expired admin-issued device");
+		self.remove_device(user_id, device_id).await;
+	}
+
+	expired.len()
+}
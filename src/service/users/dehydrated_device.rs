@@ -0,0 +1,145 @@
+use conduwuit::{
+	Err, Result, err, implement,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Json};
+use futures::{Stream, StreamExt};
+use ruma::{
+	DeviceId, OwnedDeviceId, UserId,
+	api::client::dehydrated_device::{
+		DehydratedDeviceData, put_dehydrated_device::unstable::Request,
+	},
+	events::AnyToDeviceEvent,
+	serde::Raw,
+};
+use serde::{Deserialize, Serialize};
+
+/// The dehydrated device of a user (MSC3814). The device itself is a regular
+/// device without an access token; this record only tracks which one it is
+/// and the opaque data the client needs to rehydrate it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DehydratedDevice {
+	/// Unique ID of the device.
+	pub device_id: OwnedDeviceId,
+
+	/// Opaque data for the client to rehydrate the device.
+	pub device_data: Raw<DehydratedDeviceData>,
+}
+
+/// Creates or replaces the dehydrated device of a user. Any previous
+/// dehydrated device is removed along with its keys and to-device queue.
+#[implement(super::Service)]
+pub async fn set_dehydrated_device(&self, user_id: &UserId, request: Request) -> Result {
+	if !self.exists(user_id).await {
+		return Err!(Request(InvalidParam("User does not exist.")));
+	}
+
+	let device_keys = request.device_keys.deserialize().map_err(|e| {
+		err!(Request(BadJson("Invalid device keys JSON for dehydrated device: {e}")))
+	})?;
+
+	if device_keys.user_id != user_id || device_keys.device_id != request.device_id {
+		return Err!(Request(InvalidParam(
+			"Device keys do not match the dehydrated device's user or device ID."
+		)));
+	}
+
+	let existing = self.get_dehydrated_device_id(user_id).await.ok();
+	if existing.as_deref() != Some(&*request.device_id)
+		&& self
+			.get_device_metadata(user_id, &request.device_id)
+			.await
+			.is_ok()
+	{
+		return Err!(Request(InvalidParam("Device ID is already in use by another device.")));
+	}
+
+	if let Some(existing) = existing {
+		self.remove_device(user_id, &existing).await;
+	}
+
+	self.create_device_metadata(
+		user_id,
+		&request.device_id,
+		request.initial_device_display_name.clone(),
+		None,
+	);
+
+	self.add_device_keys(user_id, &request.device_id, &request.device_keys)
+		.await;
+
+	for (key_id, key) in &request.one_time_keys {
+		self.add_one_time_key(user_id, &request.device_id, key_id, key)
+			.await?;
+	}
+
+	// Fallback keys keep the device reachable once its one-time keys run out
+	// while it is offline.
+	for (key_id, key) in &request.fallback_keys {
+		self.add_fallback_key(user_id, &request.device_id, key_id, key)
+			.await?;
+	}
+
+	let dehydrated_device = DehydratedDevice {
+		device_id: request.device_id,
+		device_data: request.device_data,
+	};
+
+	self.db
+		.userid_dehydrateddevice
+		.raw_put(user_id, Json(dehydrated_device));
+
+	Ok(())
+}
+
+/// Removes the dehydrated device of a user, returning its device ID.
+#[implement(super::Service)]
+pub async fn remove_dehydrated_device(&self, user_id: &UserId) -> Result<OwnedDeviceId> {
+	let device_id = self.get_dehydrated_device_id(user_id).await?;
+
+	self.remove_device(user_id, &device_id).await;
+	self.db.userid_dehydrateddevice.remove(user_id);
+
+	Ok(device_id)
+}
+
+/// Returns the device ID of the dehydrated device of a user.
+#[implement(super::Service)]
+pub async fn get_dehydrated_device_id(&self, user_id: &UserId) -> Result<OwnedDeviceId> {
+	self.get_dehydrated_device(user_id)
+		.await
+		.map(|device| device.device_id)
+}
+
+/// Returns the dehydrated device of a user.
+#[implement(super::Service)]
+pub async fn get_dehydrated_device(&self, user_id: &UserId) -> Result<DehydratedDevice> {
+	self.db
+		.userid_dehydrateddevice
+		.get(user_id)
+		.await
+		.deserialized()
+}
+
+/// Returns the to-device events queued for a device after `since`, along
+/// with the count each one was stored at for use as a pagination token.
+#[implement(super::Service)]
+pub fn get_to_device_events_with_count<'a>(
+	&'a self,
+	user_id: &'a UserId,
+	device_id: &'a DeviceId,
+	since: Option<u64>,
+) -> impl Stream<Item = (u64, Raw<AnyToDeviceEvent>)> + Send + 'a {
+	type Key<'a> = (&'a UserId, &'a DeviceId, u64);
+
+	let from = (user_id, device_id, since.map_or(0, |since| since.saturating_add(1)));
+
+	self.db
+		.todeviceid_events
+		.stream_from(&from)
+		.ignore_err()
+		.ready_take_while(move |((user_id_, device_id_, _), _): &(Key<'_>, _)| {
+			user_id == *user_id_ && device_id == *device_id_
+		})
+		.map(|((_, _, count), event): (Key<'_>, _)| (count, event))
+}
@@ -0,0 +1,24 @@
+use conduwuit::{Result, implement, utils};
+use database::Deserialized;
+use ruma::UserId;
+
+/// Marks a user as erased. The events of erased users are hidden from
+/// anyone who was not joined to the room when they were sent.
+#[implement(super::Service)]
+pub fn mark_erased(&self, user_id: &UserId) {
+	self.db
+		.userid_erased
+		.raw_put(user_id, utils::millis_since_unix_epoch());
+}
+
+#[implement(super::Service)]
+pub async fn is_erased(&self, user_id: &UserId) -> bool {
+	self.db.userid_erased.get(user_id).await.is_ok()
+}
+
+/// Returns the time at which the user was erased, in milliseconds since the
+/// unix epoch.
+#[implement(super::Service)]
+pub async fn erased_since(&self, user_id: &UserId) -> Result<u64> {
+	self.db.userid_erased.get(user_id).await.deserialized()
+}
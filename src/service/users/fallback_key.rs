@@ -0,0 +1,122 @@
+use conduwuit::{
+	Err, Result, err, error, implement,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Ignore, Interfix, Json};
+use futures::StreamExt;
+use ruma::{
+	DeviceId, OneTimeKeyAlgorithm, OneTimeKeyName, OwnedKeyId, UserId, encryption::OneTimeKey,
+	serde::Raw,
+};
+use serde::{Deserialize, Serialize};
+
+/// The fallback key of a device for one algorithm. Unlike one-time keys it is
+/// handed out again whenever a device has run out of one-time keys; it is only
+/// flagged as used so the client knows to upload a new one.
+#[derive(Deserialize, Serialize)]
+struct FallbackKey {
+	key_id: OwnedKeyId<OneTimeKeyAlgorithm, OneTimeKeyName>,
+	key: Raw<OneTimeKey>,
+	used: bool,
+}
+
+/// Stores the fallback key of a device, replacing any previous fallback key
+/// for the same algorithm.
+#[implement(super::Service)]
+pub async fn add_fallback_key(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	key_id: &OwnedKeyId<OneTimeKeyAlgorithm, OneTimeKeyName>,
+	key: &Raw<OneTimeKey>,
+) -> Result {
+	if self
+		.db
+		.userdeviceid_metadata
+		.qry(&(user_id, device_id))
+		.await
+		.is_err()
+	{
+		return Err!(Database(error!(
+			?user_id,
+			?device_id,
+			"User does not exist or device has no metadata."
+		)));
+	}
+
+	let fallback_key = FallbackKey {
+		key_id: key_id.clone(),
+		key: key.clone(),
+		used: false,
+	};
+
+	let algorithm = key_id.algorithm();
+	self.db
+		.fallbackkeyid_fallbackkey
+		.put((user_id, device_id, algorithm.as_str()), Json(fallback_key));
+
+	Ok(())
+}
+
+/// Returns the fallback key of a device for an algorithm and marks it as used.
+/// The key stays available for later claims until it is replaced.
+#[implement(super::Service)]
+pub async fn take_fallback_key(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	algorithm: &OneTimeKeyAlgorithm,
+) -> Result<(OwnedKeyId<OneTimeKeyAlgorithm, OneTimeKeyName>, Raw<OneTimeKey>)> {
+	let key = (user_id, device_id, algorithm.as_str());
+	let mut fallback_key: FallbackKey = self
+		.db
+		.fallbackkeyid_fallbackkey
+		.qry(&key)
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(NotFound("No fallback key found"))))?;
+
+	if !fallback_key.used {
+		fallback_key.used = true;
+		self.db
+			.fallbackkeyid_fallbackkey
+			.put(key, Json(&fallback_key));
+
+		let count = self.services.globals.next_count()?;
+		self.db.userid_lastonetimekeyupdate.raw_put(user_id, count);
+	}
+
+	Ok((fallback_key.key_id, fallback_key.key))
+}
+
+/// Algorithms for which a device has a fallback key which was not claimed yet.
+#[implement(super::Service)]
+pub async fn unused_fallback_key_types(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> Vec<OneTimeKeyAlgorithm> {
+	type KeyVal<'a> = ((Ignore, Ignore, &'a str), FallbackKey);
+
+	self.db
+		.fallbackkeyid_fallbackkey
+		.stream_prefix(&(user_id, device_id, Interfix))
+		.ignore_err()
+		.ready_filter_map(|((Ignore, Ignore, algorithm), fallback_key): KeyVal<'_>| {
+			(!fallback_key.used).then(|| algorithm.into())
+		})
+		.collect()
+		.await
+}
+
+/// Removes all fallback keys of a device.
+#[implement(super::Service)]
+pub(super) async fn remove_fallback_keys(&self, user_id: &UserId, device_id: &DeviceId) {
+	let prefix = (user_id, device_id, Interfix);
+	self.db
+		.fallbackkeyid_fallbackkey
+		.keys_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| self.db.fallbackkeyid_fallbackkey.remove(key))
+		.await;
+}
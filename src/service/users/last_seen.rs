@@ -0,0 +1,132 @@
+use std::{
+	collections::HashMap,
+	net::IpAddr,
+	time::{Duration, Instant},
+};
+
+use conduwuit::{
+	debug, implement,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use database::Json;
+use futures::StreamExt;
+use ruma::{
+	DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId,
+	api::client::device::Device,
+};
+
+/// When and from where the last-seen information of each device was last
+/// written, so requests within the update interval skip the database.
+pub(super) type LastSeenMap = HashMap<(OwnedUserId, OwnedDeviceId), (Instant, Option<IpAddr>)>;
+
+/// Records that a device was just used from the given address. Writes are
+/// rate-limited by `device_last_seen_update_interval` unless the address
+/// changed. Unlike `update_device_metadata` this does not bump the device
+/// list version, as last-seen information is not part of device lists.
+#[implement(super::Service)]
+pub async fn update_device_last_seen(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	client_ip: Option<IpAddr>,
+) {
+	let interval =
+		Duration::from_secs(self.services.server.config.device_last_seen_update_interval);
+
+	let key = (user_id.to_owned(), device_id.to_owned());
+	{
+		let mut last_seen = self.last_seen.lock().expect("locked");
+		let last_ip = last_seen.get(&key).and_then(|(_, ip)| *ip);
+		if let Some((written, ip)) = last_seen.get(&key) {
+			let ip_changed = client_ip.is_some() && client_ip != *ip;
+			if !ip_changed && written.elapsed() < interval {
+				return;
+			}
+		}
+
+		// Claim the write before reaching the database so concurrent requests
+		// of the same device don't all write.
+		last_seen.insert(key, (Instant::now(), client_ip.or(last_ip)));
+	}
+
+	// The device is read under the lock, so that it is not written back after a
+	// concurrent removal and concurrent changes of its metadata are kept.
+	let _lock = self.lock_device(user_id, device_id).await;
+	let Ok(mut device) = self.get_device_metadata(user_id, device_id).await else {
+		self.forget_device_last_seen(user_id, device_id);
+		return;
+	};
+
+	device.last_seen_ts = Some(MilliSecondsSinceUnixEpoch::now());
+	if let Some(client_ip) = client_ip {
+		device.last_seen_ip = Some(client_ip.to_string());
+	}
+
+	self.db
+		.userdeviceid_metadata
+		.put((user_id, device_id), Json(device));
+}
+
+#[implement(super::Service)]
+pub(super) fn forget_device_last_seen(&self, user_id: &UserId, device_id: &DeviceId) {
+	self.last_seen
+		.lock()
+		.expect("locked")
+		.remove(&(user_id.to_owned(), device_id.to_owned()));
+}
+
+/// Drops in-memory write times older than the update interval; those devices
+/// are written to again on their next request anyway.
+#[implement(super::Service)]
+pub(super) fn forget_expired_last_seen(&self) {
+	let interval =
+		Duration::from_secs(self.services.server.config.device_last_seen_update_interval);
+
+	self.last_seen
+		.lock()
+		.expect("locked")
+		.retain(|_, (written, _)| written.elapsed() < interval);
+}
+
+/// Deletes all devices which have not been seen for longer than `max_age`,
+/// returning the number of devices removed. Devices of appservice users and
+/// devices without an access token are skipped.
+#[implement(super::Service)]
+pub async fn prune_stale_devices(&self, max_age: Duration) -> usize {
+	type KeyVal<'a> = ((&'a UserId, &'a DeviceId), Device);
+
+	let max_age: u64 = max_age.as_millis().try_into().unwrap_or(u64::MAX);
+	let now: u64 = MilliSecondsSinceUnixEpoch::now().get().into();
+
+	let stale: Vec<(OwnedUserId, OwnedDeviceId)> = self
+		.db
+		.userdeviceid_metadata
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|((user_id, device_id), device): KeyVal<'_>| {
+			let last_seen: u64 = device.last_seen_ts?.get().into();
+			(now.saturating_sub(last_seen) > max_age)
+				.then(|| (user_id.to_owned(), device_id.to_owned()))
+		})
+		.collect()
+		.await;
+
+	let mut pruned: usize = 0;
+	for (user_id, device_id) in stale {
+		if !self.services.globals.user_is_local(&user_id)
+			|| self
+				.services
+				.appservice
+				.is_exclusive_user_id(&user_id)
+				.await || self.get_token(&user_id, &device_id).await.is_err()
+		{
+			continue;
+		}
+
+		debug!(%user_id, %device_id, "Pruning stale device");
+		self.remove_device(&user_id, &device_id).await;
+		pruned = pruned.saturating_add(1);
+	}
+
+	pruned
+}
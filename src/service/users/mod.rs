@@ -1,8 +1,23 @@
-use std::{collections::BTreeMap, mem, sync::Arc};
+mod admin_token;
+mod dehydrated_device;
+mod erasure;
+mod fallback_key;
+mod last_seen;
+mod suspension;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	mem,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
+use async_trait::async_trait;
 use conduwuit::{
-	Err, Error, Result, Server, at, debug_warn, err, trace,
-	utils::{self, ReadyExt, stream::TryIgnore, string::Unquoted},
+	Err, Error, Result, Server, at, debug, debug_warn, err, info, trace,
+	utils::{self, MutexMap, MutexMapGuard, ReadyExt, stream::TryIgnore, string::Unquoted},
 };
 use database::{Deserialized, Ignore, Interfix, Json, Map};
 use futures::{Stream, StreamExt, TryFutureExt};
@@ -17,24 +32,39 @@
 	serde::Raw,
 };
 use serde_json::json;
+use tokio::{
+	sync::Notify,
+	time::{MissedTickBehavior, interval},
+};
 
-use crate::{Dep, account_data, admin, globals, rooms};
+pub use self::{
+	admin_token::AdminIssuedToken, dehydrated_device::DehydratedDevice, suspension::Restrictions,
+};
+use crate::{Dep, account_data, admin, appservice, globals, oauth, rooms, user_directory};
 
 pub struct Service {
 	services: Services,
 	db: Data,
+	interrupt: Notify,
+	last_seen: Mutex<last_seen::LastSeenMap>,
+	device_mutex: MutexMap<String, ()>,
+	restrictions: Mutex<suspension::RestrictionsCache>,
 }
 
 struct Services {
 	server: Arc<Server>,
 	account_data: Dep<account_data::Service>,
 	admin: Dep<admin::Service>,
+	appservice: Dep<appservice::Service>,
 	globals: Dep<globals::Service>,
+	oauth: Dep<oauth::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
+	user_directory: Dep<user_directory::Service>,
 }
 
 struct Data {
+	fallbackkeyid_fallbackkey: Arc<Map>,
 	keychangeid_userid: Arc<Map>,
 	keyid_key: Arc<Map>,
 	onetimekeyid_onetimekeys: Arc<Map>,
@@ -42,21 +72,28 @@ struct Data {
 	logintoken_expiresatuserid: Arc<Map>,
 	todeviceid_events: Arc<Map>,
 	token_userdeviceid: Arc<Map>,
+	userdeviceid_adminissued: Arc<Map>,
 	userdeviceid_metadata: Arc<Map>,
 	userdeviceid_token: Arc<Map>,
 	userfilterid_filter: Arc<Map>,
 	userid_avatarurl: Arc<Map>,
 	userid_blurhash: Arc<Map>,
+	userid_dehydrateddevice: Arc<Map>,
 	userid_devicelistversion: Arc<Map>,
 	userid_displayname: Arc<Map>,
+	userid_erased: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
+	userid_locked: Arc<Map>,
 	userid_masterkeyid: Arc<Map>,
 	userid_password: Arc<Map>,
 	userid_selfsigningkeyid: Arc<Map>,
+	userid_shadowbanned: Arc<Map>,
+	userid_suspended: Arc<Map>,
 	userid_usersigningkeyid: Arc<Map>,
 	useridprofilekey_value: Arc<Map>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -64,12 +101,16 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				server: args.server.clone(),
 				account_data: args.depend::<account_data::Service>("account_data"),
 				admin: args.depend::<admin::Service>("admin"),
+				appservice: args.depend::<appservice::Service>("appservice"),
 				globals: args.depend::<globals::Service>("globals"),
+				oauth: args.depend::<oauth::Service>("oauth"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				user_directory: args.depend::<user_directory::Service>("user_directory"),
 			},
 			db: Data {
+				fallbackkeyid_fallbackkey: args.db["fallbackkeyid_fallbackkey"].clone(),
 				keychangeid_userid: args.db["keychangeid_userid"].clone(),
 				keyid_key: args.db["keyid_key"].clone(),
 				onetimekeyid_onetimekeys: args.db["onetimekeyid_onetimekeys"].clone(),
@@ -77,23 +118,74 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				logintoken_expiresatuserid: args.db["logintoken_expiresatuserid"].clone(),
 				todeviceid_events: args.db["todeviceid_events"].clone(),
 				token_userdeviceid: args.db["token_userdeviceid"].clone(),
+				userdeviceid_adminissued: args.db["userdeviceid_adminissued"].clone(),
 				userdeviceid_metadata: args.db["userdeviceid_metadata"].clone(),
 				userdeviceid_token: args.db["userdeviceid_token"].clone(),
 				userfilterid_filter: args.db["userfilterid_filter"].clone(),
 				userid_avatarurl: args.db["userid_avatarurl"].clone(),
 				userid_blurhash: args.db["userid_blurhash"].clone(),
+				userid_dehydrateddevice: args.db["userid_dehydrateddevice"].clone(),
 				userid_devicelistversion: args.db["userid_devicelistversion"].clone(),
 				userid_displayname: args.db["userid_displayname"].clone(),
+				userid_erased: args.db["userid_erased"].clone(),
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
+				userid_locked: args.db["userid_locked"].clone(),
 				userid_masterkeyid: args.db["userid_masterkeyid"].clone(),
 				userid_password: args.db["userid_password"].clone(),
 				userid_selfsigningkeyid: args.db["userid_selfsigningkeyid"].clone(),
+				userid_shadowbanned: args.db["userid_shadowbanned"].clone(),
+				userid_suspended: args.db["userid_suspended"].clone(),
 				userid_usersigningkeyid: args.db["userid_usersigningkeyid"].clone(),
 				useridprofilekey_value: args.db["useridprofilekey_value"].clone(),
 			},
+			interrupt: Notify::new(),
+			last_seen: Mutex::default(),
+			device_mutex: MutexMap::new(),
+			restrictions: Mutex::default(),
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result<()> {
+		let config = &self.services.server.config;
+		let max_age = (config.prune_stale_devices_after > 0)
+			.then(|| Duration::from_secs(config.prune_stale_devices_after));
+
+		if max_age.is_none() {
+			debug!("Disabling stale device pruning");
+		}
+
+		let period = Duration::from_secs(config.prune_stale_devices_interval.max(1));
+
+		let mut i = interval(period);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		loop {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			self.forget_expired_last_seen();
+
+			let expired = self.remove_expired_admin_tokens().await;
+			if expired > 0 {
+				info!("Removed {expired} expired admin-issued devices");
+			}
+
+			if let Some(max_age) = max_age {
+				let pruned = self.prune_stale_devices(max_age).await;
+				if pruned > 0 {
+					info!("Pruned {pruned} stale devices");
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	async fn clear_cache(&self) { self.clear_restrictions_cache(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -139,6 +231,9 @@ pub async fn deactivate_account(&self, user_id: &UserId) -> Result<()> {
 		// account is deactivated.
 		self.set_password(user_id, None)?;
 
+		// Introspected tokens of the user must not keep authenticating from cache.
+		self.services.oauth.invalidate_sessions(user_id);
+
 		// TODO: Unhook 3PID
 		Ok(())
 	}
@@ -231,6 +326,10 @@ pub async fn displayname(&self, user_id: &UserId) -> Result<String> {
 	/// Sets a new displayname or removes it if displayname is None. You still
 	/// need to nofify all rooms of this change.
 	pub fn set_displayname(&self, user_id: &UserId, displayname: Option<String>) {
+		self.services
+			.user_directory
+			.update_profile(user_id, displayname.as_deref());
+
 		if let Some(displayname) = displayname {
 			self.db.userid_displayname.insert(user_id, displayname);
 		} else {
@@ -284,6 +383,19 @@ pub async fn create_device(
 			))));
 		}
 
+		self.create_device_metadata(user_id, device_id, initial_device_display_name, client_ip);
+		self.set_token(user_id, device_id, token).await
+	}
+
+	/// Adds the metadata of a new device to a user without assigning it an
+	/// access token.
+	fn create_device_metadata(
+		&self,
+		user_id: &UserId,
+		device_id: &DeviceId,
+		initial_device_display_name: Option<String>,
+		client_ip: Option<String>,
+	) {
 		let key = (user_id, device_id);
 		let val = Device {
 			device_id: device_id.into(),
@@ -294,11 +406,11 @@ pub async fn create_device(
 
 		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
 		self.db.userdeviceid_metadata.put(key, Json(val));
-		self.set_token(user_id, device_id, token).await
 	}
 
 	/// Removes a device from a user.
 	pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
+		let _lock = self.lock_device(user_id, device_id).await;
 		let userdeviceid = (user_id, device_id);
 
 		// Remove tokens
@@ -306,6 +418,8 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 			self.db.userdeviceid_token.del(userdeviceid);
 			self.db.token_userdeviceid.remove(&old_token);
 		}
+		self.db.userdeviceid_adminissued.del(userdeviceid);
+		self.forget_device_last_seen(user_id, device_id);
 
 		// Remove todevice events
 		let prefix = (user_id, device_id, Interfix);
@@ -316,7 +430,19 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 			.ready_for_each(|key| self.db.todeviceid_events.remove(key))
 			.await;
 
-		// TODO: Remove onetimekeys
+		// Remove onetimekeys
+		let prefix = (user_id, device_id, Interfix);
+		self.db
+			.onetimekeyid_onetimekeys
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.onetimekeyid_onetimekeys.remove(key))
+			.await;
+
+		self.remove_fallback_keys(user_id, device_id).await;
+
+		// Remove device keys
+		self.db.keyid_key.del(userdeviceid);
 
 		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
 
@@ -831,6 +957,7 @@ pub async fn update_device_metadata(
 		device_id: &DeviceId,
 		device: &Device,
 	) -> Result<()> {
+		let _lock = self.lock_device(user_id, device_id).await;
 		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
 
 		let key = (user_id, device_id);
@@ -839,6 +966,18 @@ pub async fn update_device_metadata(
 		Ok(())
 	}
 
+	/// Serializes writes to the metadata of a device, so that a device being
+	/// removed is not written back by a concurrent update.
+	async fn lock_device(
+		&self,
+		user_id: &UserId,
+		device_id: &DeviceId,
+	) -> MutexMapGuard<String, ()> {
+		self.device_mutex
+			.lock(&format!("{user_id}\0{device_id}"))
+			.await
+	}
+
 	/// Get device metadata.
 	pub async fn get_device_metadata(
 		&self,
@@ -0,0 +1,150 @@
+use conduwuit::{Result, implement, utils};
+use database::Deserialized;
+use futures::future::join;
+use lru_cache::LruCache;
+use ruma::{OwnedUserId, UserId};
+
+/// Whether a user is locked or suspended, which is checked on every
+/// authenticated request.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Restrictions {
+	pub locked: bool,
+	pub suspended: bool,
+}
+
+/// Restrictions of recently active users. The version is bumped by every
+/// change, so that a lookup racing with a change does not cache stale state.
+pub(super) struct RestrictionsCache {
+	version: u64,
+	users: LruCache<OwnedUserId, Restrictions>,
+}
+
+const RESTRICTIONS_CACHE_CAPACITY: usize = 4096;
+
+impl Default for RestrictionsCache {
+	fn default() -> Self {
+		Self {
+			version: 0,
+			users: LruCache::new(RESTRICTIONS_CACHE_CAPACITY),
+		}
+	}
+}
+
+/// Returns whether the user is locked or suspended, from the cache when
+/// possible.
+#[implement(super::Service)]
+pub async fn restrictions(&self, user_id: &UserId) -> Restrictions {
+	let version = {
+		let mut cache = self.restrictions.lock().expect("locked");
+		if let Some(restrictions) = cache.users.get_mut(user_id) {
+			return *restrictions;
+		}
+
+		cache.version
+	};
+
+	let (locked, suspended) =
+		join(self.db.userid_locked.get(user_id), self.db.userid_suspended.get(user_id)).await;
+
+	let restrictions = Restrictions {
+		locked: locked.is_ok(),
+		suspended: suspended.is_ok(),
+	};
+
+	let mut cache = self.restrictions.lock().expect("locked");
+	if cache.version == version {
+		cache.users.insert(user_id.to_owned(), restrictions);
+	}
+
+	restrictions
+}
+
+#[implement(super::Service)]
+fn restrictions_changed(&self, user_id: &UserId) {
+	let mut cache = self.restrictions.lock().expect("locked");
+	cache.version = cache.version.wrapping_add(1);
+	cache.users.remove(user_id);
+}
+
+#[implement(super::Service)]
+pub(super) fn clear_restrictions_cache(&self) {
+	self.restrictions.lock().expect("locked").users.clear();
+}
+
+/// Suspends a user (MSC3823). Suspended users may still read but cannot
+/// send events, join rooms or otherwise change state.
+#[implement(super::Service)]
+pub fn suspend(&self, user_id: &UserId) {
+	self.db
+		.userid_suspended
+		.raw_put(user_id, utils::millis_since_unix_epoch());
+
+	self.restrictions_changed(user_id);
+}
+
+/// Lifts the suspension of a user.
+#[implement(super::Service)]
+pub fn unsuspend(&self, user_id: &UserId) {
+	self.db.userid_suspended.remove(user_id);
+	self.restrictions_changed(user_id);
+}
+
+#[implement(super::Service)]
+pub async fn is_suspended(&self, user_id: &UserId) -> bool {
+	self.restrictions(user_id).await.suspended
+}
+
+/// Returns the time at which the user was suspended, in milliseconds since
+/// the unix epoch.
+#[implement(super::Service)]
+pub async fn suspended_since(&self, user_id: &UserId) -> Result<u64> {
+	self.db.userid_suspended.get(user_id).await.deserialized()
+}
+
+/// Locks a user (MSC3939). Locked users are rejected on every authenticated
+/// request until unlocked.
+#[implement(super::Service)]
+pub fn lock(&self, user_id: &UserId) {
+	self.db
+		.userid_locked
+		.raw_put(user_id, utils::millis_since_unix_epoch());
+
+	self.restrictions_changed(user_id);
+}
+
+/// Unlocks a user.
+#[implement(super::Service)]
+pub fn unlock(&self, user_id: &UserId) {
+	self.db.userid_locked.remove(user_id);
+	self.restrictions_changed(user_id);
+}
+
+#[implement(super::Service)]
+pub async fn is_locked(&self, user_id: &UserId) -> bool {
+	self.restrictions(user_id).await.locked
+}
+
+/// Returns the time at which the user was locked, in milliseconds since the
+/// unix epoch.
+#[implement(super::Service)]
+pub async fn locked_since(&self, user_id: &UserId) -> Result<u64> {
+	self.db.userid_locked.get(user_id).await.deserialized()
+}
+
+/// Shadow-bans a user. Requests of shadow-banned users which would send
+/// events appear to succeed, but nothing is actually sent.
+#[implement(super::Service)]
+pub fn shadow_ban(&self, user_id: &UserId) {
+	self.db
+		.userid_shadowbanned
+		.raw_put(user_id, utils::millis_since_unix_epoch());
+}
+
+/// Lifts the shadow-ban of a user.
+#[implement(super::Service)]
+pub fn unshadow_ban(&self, user_id: &UserId) { self.db.userid_shadowbanned.remove(user_id); }
+
+#[implement(super::Service)]
+pub async fn is_shadow_banned(&self, user_id: &UserId) -> bool {
+	self.db.userid_shadowbanned.get(user_id).await.is_ok()
+}
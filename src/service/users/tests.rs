@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use ruma::{OneTimeKeyAlgorithm, OwnedOneTimeKeyId, device_id, serde::Raw, user_id};
+use serde_json::{json, value::to_raw_value};
+
+use super::Restrictions;
+use crate::tests::services;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fallback_key_is_reused() {
+	let services = services().await;
+	let users = &services.users;
+	let alice = user_id!("@alice:example.com");
+	let device_id = device_id!("ALICEDEV");
+	let algorithm = OneTimeKeyAlgorithm::SignedCurve25519;
+
+	users.create(alice, Some("hunter2")).unwrap();
+	users
+		.create_device(alice, device_id, "token", None, None)
+		.await
+		.unwrap();
+
+	let key_id: OwnedOneTimeKeyId = "signed_curve25519:AAAAHQ".try_into().unwrap();
+	let key = Raw::from_json(to_raw_value(&json!({ "key": "fallback" })).unwrap());
+	users
+		.add_fallback_key(alice, device_id, &key_id, &key)
+		.await
+		.unwrap();
+
+	assert_eq!(users.unused_fallback_key_types(alice, device_id).await, [algorithm.clone()]);
+
+	// Claiming marks the key as used but keeps handing it out.
+	for _ in 0..2 {
+		let (claimed_id, _) = users
+			.take_fallback_key(alice, device_id, &algorithm)
+			.await
+			.unwrap();
+		assert_eq!(claimed_id, key_id);
+	}
+
+	assert!(
+		users
+			.unused_fallback_key_types(alice, device_id)
+			.await
+			.is_empty()
+	);
+	assert!(
+		users
+			.take_one_time_key(alice, device_id, &algorithm)
+			.await
+			.is_err()
+	);
+
+	users.remove_device(alice, device_id).await;
+	assert!(
+		users
+			.take_fallback_key(alice, device_id, &algorithm)
+			.await
+			.is_err()
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn expired_admin_tokens_are_removed() {
+	let services = services().await;
+	let users = &services.users;
+	let alice = user_id!("@alice:example.com");
+	let admin = user_id!("@admin:example.com");
+
+	users.create(alice, Some("hunter2")).unwrap();
+	let (expired, _) = users
+		.issue_admin_token(alice, admin, Duration::ZERO)
+		.await
+		.unwrap();
+	let (valid, _) = users
+		.issue_admin_token(alice, admin, Duration::from_secs(3600))
+		.await
+		.unwrap();
+
+	assert_eq!(users.remove_expired_admin_tokens().await, 1);
+	assert!(users.admin_issued_token(alice, &expired).await.is_err());
+	assert!(users.admin_issued_token(alice, &valid).await.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn last_seen_does_not_resurrect_removed_device() {
+	let services = services().await;
+	let users = &services.users;
+	let alice = user_id!("@alice:example.com");
+	let device_id = device_id!("ALICEDEV");
+
+	users.create(alice, Some("hunter2")).unwrap();
+	users
+		.create_device(alice, device_id, "token", None, None)
+		.await
+		.unwrap();
+
+	tokio::join!(
+		users.update_device_last_seen(alice, device_id, "192.0.2.1".parse().ok()),
+		users.remove_device(alice, device_id),
+		users.update_device_last_seen(alice, device_id, "192.0.2.2".parse().ok()),
+	);
+
+	assert!(users.get_device_metadata(alice, device_id).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restrictions_follow_changes() {
+	let services = services().await;
+	let users = &services.users;
+	let alice = user_id!("@alice:example.com");
+
+	users.create(alice, Some("hunter2")).unwrap();
+	assert_eq!(users.restrictions(alice).await, Restrictions::default());
+
+	// Cached lookups see every change.
+	users.suspend(alice);
+	users.lock(alice);
+	assert_eq!(users.restrictions(alice).await, Restrictions { locked: true, suspended: true });
+
+	users.unlock(alice);
+	assert!(!users.is_locked(alice).await);
+	assert!(users.is_suspended(alice).await);
+
+	users.unsuspend(alice);
+	assert_eq!(users.restrictions(alice).await, Restrictions::default());
+}